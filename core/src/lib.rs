@@ -0,0 +1,484 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The payload and QR-Code of a WIFI network, shared by the `wifiqr` command line tool, its library and the
+//! WebAssembly bindings, so all of them escape the same way. [`WifiCredentials`] assembles the `WIFI:` payload,
+//! [`WifiQrBuilder`] checks the network and encodes it, and [`render`] draws the matrix of modules as text.
+//! [`DppUri`] assembles the `DPP:` URI of Wi-Fi Easy Connect, which onboards devices by their public key instead.
+//!
+//! The crate is `no_std` with `alloc`. Encoding, and with it the builder, is the default `encode` feature, as the
+//! encoder uses `std`; without it the payload and the drawing of matrices are left, for targets without `std`.
+//! The `clap` feature derives clap's `ArgEnum` for [`Security`], for command lines taking it as a value.
+//!
+//! ```
+//! use wifi_qr_core::{Security, WifiQrBuilder};
+//!
+//! let code = WifiQrBuilder::new("Home").password("hunter22").security(Security::Wpa2).build().unwrap();
+//! assert_eq!(code.payload(), "WIFI:T:WPA;S:Home;P:hunter22;;");
+//! print!("{}", code.to_blocks());
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "clap")]
+extern crate std;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "encode")]
+pub use qrcode_generator::{QRCodeError, QrCodeEcc};
+
+pub mod render;
+
+/// Security standard of a network with a shared password.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "clap", derive(clap::ArgEnum))]
+pub enum Security{
+    Wep,
+    Wpa,
+    Wpa2,
+    Wpa3,
+    /// WPA3 networks still accepting WPA2 devices
+    Wpa2Wpa3
+}
+impl Security {
+    pub const ALL: [Security; 5] = [Security::Wep, Security::Wpa, Security::Wpa2, Security::Wpa3, Security::Wpa2Wpa3];
+    /// The name `wifiqr` gives the standard, e.g. `wpa2-wpa3`, on the command line and in file names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Security::Wep => "wep",
+            Security::Wpa => "wpa",
+            Security::Wpa2 => "wpa2",
+            Security::Wpa3 => "wpa3",
+            Security::Wpa2Wpa3 => "wpa2-wpa3"
+        }
+    }
+    /// The token of the `T:` field readers expect. WPA covers WPA and WPA2, and SAE is WPA3. Transition-mode
+    /// networks are written as WPA, as the WPA3 specification has devices use SAE with them if they support it.
+    pub fn payload_token(self) -> &'static str {
+        match self {
+            Security::Wep => "WEP",
+            Security::Wpa | Security::Wpa2 | Security::Wpa2Wpa3 => "WPA",
+            Security::Wpa3 => "SAE"
+        }
+    }
+}
+impl core::str::FromStr for Security {
+    type Err = String;
+    /// Reads the names `wifiqr` gives the standards, `wep`, `wpa`, `wpa2`, `wpa3` and `wpa2-wpa3`, in any case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Security::ALL.into_iter().find(|x| x.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown security standard {}, expected wep, wpa, wpa2, wpa3 or wpa2-wpa3", name))
+    }
+}
+/// The standard as told to people, e.g. `Wpa2/Wpa3`.
+impl fmt::Display for Security {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Security::Wpa2Wpa3 => write!(f, "Wpa2/Wpa3"),
+            _ => write!(f, "{:?}", self)
+        }
+    }
+}
+
+/// The ECC-Level of the name `wifiqr` gives it, `low`, `medium`, `quartile` or `high`, in any case.
+#[cfg(feature = "encode")]
+pub fn ecc_level(name: &str) -> Result<QrCodeEcc, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "low" => Ok(QrCodeEcc::Low),
+        "medium" => Ok(QrCodeEcc::Medium),
+        "quartile" => Ok(QrCodeEcc::Quartile),
+        "high" => Ok(QrCodeEcc::High),
+        _ => Err(format!("unknown ECC-Level {}, expected low, medium, quartile or high", name))
+    }
+}
+
+/// Characters with a meaning in `WIFI:` payloads, which have to be escaped with a backslash in values.
+pub const SPECIAL_CHARACTERS: [char; 5] = ['\\', ';', ',', ':', '"'];
+/// Escapes the special characters of a value of a `WIFI:` payload, e.g. `My;Home` as `My\;Home`.
+pub fn escape(value: &str) -> String {
+    let mut escaped:String = String::with_capacity(value.len());
+    for character in value.chars() {
+        if SPECIAL_CHARACTERS.contains(&character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+/// The bytes as hex digits, two per byte, e.g. for an SSID written as `S:436166C3A9` instead of `S:Café`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02X}", x)).collect()
+}
+/// Whether a value consists of hex digits alone, e.g. `DEADBEEF`, which readers may decode as the bytes they spell.
+pub fn is_hex_like(value: &str) -> bool {
+    !value.is_empty() && value.len().is_multiple_of(2) && value.chars().all(|x| x.is_ascii_hexdigit())
+}
+/// Escapes a value of a `WIFI:` payload and wraps hex-like ones in double quotes, which readers take as text, e.g.
+/// `DEADBEEF` as `"DEADBEEF"`.
+pub fn quote(value: &str) -> String {
+    match is_hex_like(value) {
+        true => format!("\"{}\"", value),
+        false => escape(value)
+    }
+}
+
+/// Whether the password is a raw key, which readers decode from its hex digits: 10 or 26 of them for WEP, and 64 for
+/// the pre-shared key of WPA and SAE.
+pub fn is_raw_key(password: &str, security: Option<Security>) -> bool {
+    let hexadecimal:bool = password.chars().all(|x| x.is_ascii_hexdigit());
+    match security {
+        Some(Security::Wep) => hexadecimal && [10, 26].contains(&password.len()),
+        Some(_) => hexadecimal && password.len() == 64,
+        None => false
+    }
+}
+/// The value of `P:`: quoted as [`quote`] does, except for raw keys, which readers would take for a passphrase in
+/// quotes.
+pub fn quote_password(password: &str, security: Option<Security>) -> String {
+    match is_raw_key(password, security) {
+        true => String::from(password),
+        false => quote(password)
+    }
+}
+
+/// How the `WIFI:` payload of a network is written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PayloadStyle{
+    /// [`WifiCredentials::payload`]
+    #[default]
+    Standard,
+    /// [`WifiCredentials::compat_payload`]
+    Compat,
+    /// [`WifiCredentials::compact_payload`]
+    Compact
+}
+
+/// A network with a shared password, or an open one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WifiCredentials{
+    pub ssid: String,
+    pub password: Option<String>,
+    /// `None` for open networks, or for WPA2 if a password is given.
+    pub security: Option<Security>,
+    pub hidden: bool,
+    /// The bytes of the SSID, written as hex digits in place of `ssid`, for SSIDs that aren't UTF-8 or readers that
+    /// garble non-ASCII ones.
+    pub ssid_bytes: Option<Vec<u8>>,
+    /// Tells devices to no longer fall back to WPA2 once they joined with WPA3, only for [`Security::Wpa3`].
+    pub transition_disable: bool
+}
+impl WifiCredentials {
+    /// The security standard, WPA2 if only a password was given.
+    pub fn security(&self) -> Option<Security> {
+        let has_password:bool = self.password.as_ref().is_some_and(|x| !x.is_empty());
+        self.security.or(has_password.then_some(Security::Wpa2))
+    }
+    /// The `WIFI:` payload of the network, with the values escaped and hex-like ones quoted. Open networks are
+    /// written as nopass, as some readers don't take an empty type for one. `H:` is only written for hidden
+    /// networks, as the format has it `true` or left out and some readers reject `H:false`.
+    pub fn payload(&self) -> String {
+        self.payload_with(if self.hidden {"H:true;"}else{""})
+    }
+    /// The payload as written before `H:` was left out of visible networks, with `H:false`, for readers that
+    /// expect the field.
+    pub fn compat_payload(&self) -> String {
+        self.payload_with(&format!("H:{};", self.hidden))
+    }
+    /// The payload written in the style.
+    pub fn styled_payload(&self, style: PayloadStyle) -> String {
+        match style {
+            PayloadStyle::Standard => self.payload(),
+            PayloadStyle::Compat => self.compat_payload(),
+            PayloadStyle::Compact => self.compact_payload()
+        }
+    }
+    /// The value of `S:`.
+    fn ssid_value(&self) -> String {
+        self.ssid_bytes.as_deref().map_or_else(|| quote(&self.ssid), hex)
+    }
+    /// The value of `P:`.
+    fn password_value(&self) -> String {
+        quote_password(self.password.as_deref().unwrap_or_default(), self.security())
+    }
+    fn payload_with(&self, hidden: &str) -> String {
+        let security:&str = self.security().map_or("nopass", Security::payload_token);
+        let transition_disable:&str = if self.transition_disable {"R:1;"}else{""};
+        format!("WIFI:T:{};S:{};P:{};{}{};", security, self.ssid_value(), self.password_value(), transition_disable, hidden)
+    }
+    /// The shortest payload of the network, for codes with little room: the type and password of open networks and
+    /// `H:` of visible ones are left out, as readers assume them when missing.
+    pub fn compact_payload(&self) -> String {
+        let mut payload:String = String::from("WIFI:");
+        if let Some(security) = self.security() {
+            payload += &format!("T:{};S:{};P:{};", security.payload_token(), self.ssid_value(), self.password_value());
+        }else{
+            payload += &format!("S:{};", self.ssid_value());
+        }
+        if self.transition_disable {
+            payload += "R:1;";
+        }
+        if self.hidden {
+            payload += "H:true;";
+        }
+        payload + ";"
+    }
+}
+
+/// The bootstrapping information of a device or access point for Wi-Fi Easy Connect (DPP), whose `DPP:` URI a
+/// configurator such as Android 10 and later scans to provision it without a password.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DppUri{
+    /// The public bootstrapping key, the base64 of its DER SubjectPublicKeyInfo.
+    pub key: String,
+    /// The channels it listens on, as the global operating class and the channel, e.g. `(81, 1)` for channel 1 of
+    /// 2.4 GHz.
+    pub channels: Vec<(u8, u8)>,
+    /// The MAC address it listens on.
+    pub mac: Option<[u8; 6]>,
+    /// Information such as a serial number, printable ASCII without `;`.
+    pub info: Option<String>
+}
+impl DppUri {
+    /// The `DPP:` URI, with the fields in the order of the specification and the key last, e.g.
+    /// `DPP:C:81/1,115/36;M:5254005828e5;K:MDkw...;;`.
+    pub fn uri(&self) -> String {
+        let mut uri:String = String::from("DPP:");
+        if !self.channels.is_empty() {
+            let channels:Vec<String> = self.channels.iter().map(|(class, channel)| format!("{}/{}", class, channel)).collect();
+            uri += &format!("C:{};", channels.join(","));
+        }
+        if let Some(mac) = self.mac {
+            uri += &format!("M:{};", hex(&mac).to_lowercase());
+        }
+        if let Some(info) = &self.info {
+            uri += &format!("I:{};", info);
+        }
+        uri + &format!("K:{};;", self.key)
+    }
+}
+
+/// Checks a network and encodes it as a QR-Code, at the lowest ECC-Level unless set.
+#[cfg(feature = "encode")]
+#[derive(Clone, Debug)]
+pub struct WifiQrBuilder{
+    credentials: WifiCredentials,
+    style: PayloadStyle,
+    ecc: QrCodeEcc
+}
+#[cfg(feature = "encode")]
+impl WifiQrBuilder {
+    pub fn new(ssid: impl Into<String>) -> Self {
+        WifiQrBuilder{ credentials: WifiCredentials{ ssid: ssid.into(), ..WifiCredentials::default() }, style: PayloadStyle::Standard, ecc: QrCodeEcc::Low }
+    }
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.credentials.password = Some(password.into());
+        self
+    }
+    pub fn security(mut self, security: Security) -> Self {
+        self.credentials.security = Some(security);
+        self
+    }
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.credentials.hidden = hidden;
+        self
+    }
+    pub fn transition_disable(mut self, transition_disable: bool) -> Self {
+        self.credentials.transition_disable = transition_disable;
+        self
+    }
+    /// Writes the SSID as the hex digits of these bytes, see [`WifiCredentials::ssid_bytes`].
+    pub fn ssid_bytes(mut self, ssid_bytes: impl Into<Vec<u8>>) -> Self {
+        self.credentials.ssid_bytes = Some(ssid_bytes.into());
+        self
+    }
+    pub fn style(mut self, style: PayloadStyle) -> Self {
+        self.style = style;
+        self
+    }
+    pub fn ecc(mut self, ecc: QrCodeEcc) -> Self {
+        self.ecc = ecc;
+        self
+    }
+    pub fn credentials(&self) -> &WifiCredentials {
+        &self.credentials
+    }
+    /// Fails for networks devices can't join, as [`WifiQrBuilder::build`] does before encoding them.
+    pub fn check(&self) -> Result<(), BuildError> {
+        let credentials:&WifiCredentials = &self.credentials;
+        if credentials.ssid.is_empty() && credentials.ssid_bytes.as_ref().is_none_or(|x| x.is_empty()) {
+            return Err(BuildError::EmptySsid);
+        }
+        match credentials.security {
+            Some(security) if credentials.password.as_ref().is_none_or(|x| x.is_empty()) => Err(BuildError::MissingPassword(security)),
+            security if credentials.transition_disable && security != Some(Security::Wpa3) => Err(BuildError::TransitionDisable),
+            _ => Ok(())
+        }
+    }
+    /// The payload of the network in the style, without checking it.
+    pub fn payload(&self) -> String {
+        self.credentials.styled_payload(self.style)
+    }
+    /// Encodes the network, failing for networks devices can't join and payloads too long for a QR-Code.
+    pub fn build(self) -> Result<WifiQr, BuildError> {
+        self.check()?;
+        let payload:String = self.payload();
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(&payload, self.ecc).map_err(BuildError::QrCode)?;
+        Ok(WifiQr{ credentials: self.credentials, payload, matrix })
+    }
+}
+
+/// The QR-Code of a network.
+#[cfg(feature = "encode")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WifiQr{
+    credentials: WifiCredentials,
+    payload: String,
+    matrix: Vec<Vec<bool>>
+}
+#[cfg(feature = "encode")]
+impl WifiQr {
+    pub fn credentials(&self) -> &WifiCredentials {
+        &self.credentials
+    }
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+    /// The modules of the symbol, without the quiet zone.
+    pub fn matrix(&self) -> &[Vec<bool>] {
+        &self.matrix
+    }
+    /// The symbol with its quiet zone drawn with blocks, two characters per module.
+    pub fn to_blocks(&self) -> String {
+        render::blocks(&render::with_quiet_zone(&self.matrix), "██")
+    }
+    /// The symbol with its quiet zone drawn with half blocks, for terminals too narrow for [`WifiQr::to_blocks`].
+    pub fn to_half_blocks(&self) -> String {
+        render::half_blocks(&render::with_quiet_zone(&self.matrix))
+    }
+}
+
+#[cfg(feature = "encode")]
+#[derive(Debug)]
+pub enum BuildError{
+    EmptySsid,
+    /// A security standard was given without a password.
+    MissingPassword(Security),
+    /// The transition was disabled for another security standard than WPA3.
+    TransitionDisable,
+    QrCode(QRCodeError)
+}
+#[cfg(feature = "encode")]
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::EmptySsid => write!(f, "the SSID is empty"),
+            BuildError::MissingPassword(security) => write!(f, "{:?} networks need a password", security),
+            BuildError::TransitionDisable => write!(f, "only WPA3 networks can disable the transition"),
+            BuildError::QrCode(QRCodeError::DataTooLong) => write!(f, "the payload is too long for a QR-Code"),
+            BuildError::QrCode(err) => write!(f, "{}", err)
+        }
+    }
+}
+#[cfg(feature = "encode")]
+impl core::error::Error for BuildError {}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_payload() {
+        let mut credentials = WifiCredentials{ ssid: String::from("My;Home"), password: Some(String::from("a\"b")), ..WifiCredentials::default() };
+        assert_eq!(credentials.payload(), "WIFI:T:WPA;S:My\\;Home;P:a\\\"b;;");
+        assert_eq!(credentials.compat_payload(), "WIFI:T:WPA;S:My\\;Home;P:a\\\"b;H:false;;");
+        credentials.password = None;
+        assert_eq!(credentials.payload(), "WIFI:T:nopass;S:My\\;Home;P:;;");
+        assert_eq!(credentials.compact_payload(), "WIFI:S:My\\;Home;;");
+        credentials.hidden = true;
+        credentials.password = Some(String::from("pw"));
+        assert_eq!(credentials.compact_payload(), "WIFI:T:WPA;S:My\\;Home;P:pw;H:true;;");
+        assert_eq!(credentials.payload(), "WIFI:T:WPA;S:My\\;Home;P:pw;H:true;;");
+        assert_eq!(credentials.compat_payload(), credentials.payload());
+    }
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("DEADBEEF"), "\"DEADBEEF\"");
+        assert_eq!(quote("cafe42"), "\"cafe42\"");
+        // Odd numbers of hex digits don't spell bytes.
+        assert_eq!(quote("ABC"), "ABC");
+        assert_eq!(quote("CAFE;"), "CAFE\\;");
+        assert_eq!(quote(""), "");
+        let credentials = WifiCredentials{ ssid: String::from("DEADBEEF"), password: Some(String::from("0123456789")), ..WifiCredentials::default() };
+        assert_eq!(credentials.payload(), "WIFI:T:WPA;S:\"DEADBEEF\";P:\"0123456789\";;");
+        assert_eq!(credentials.compact_payload(), "WIFI:T:WPA;S:\"DEADBEEF\";P:\"0123456789\";;");
+        let credentials = WifiCredentials{ ssid: String::from("Café"), ssid_bytes: Some(String::from("Café").into_bytes()), ..WifiCredentials::default() };
+        assert_eq!(credentials.payload(), "WIFI:T:nopass;S:436166C3A9;P:;;");
+    }
+    #[test]
+    fn test_raw_keys() {
+        // Hex WEP keys of 10 and 26 digits are keys, not passphrases in quotes.
+        let mut credentials = WifiCredentials{ ssid: String::from("Old"), password: Some(String::from("0123456789")), security: Some(Security::Wep), ..WifiCredentials::default() };
+        assert_eq!(credentials.payload(), "WIFI:T:WEP;S:Old;P:0123456789;;");
+        credentials.password = Some(String::from("0123456789abcdef0123456789"));
+        assert_eq!(credentials.compact_payload(), "WIFI:T:WEP;S:Old;P:0123456789abcdef0123456789;;");
+        // 64 hex digits are the pre-shared key of WPA and SAE, and a passphrase like any other for WEP.
+        let psk:String = "0123456789abcdef".repeat(4);
+        credentials.password = Some(psk.clone());
+        assert_eq!(credentials.payload(), format!("WIFI:T:WEP;S:Old;P:\"{}\";;", psk));
+        for security in [Security::Wpa2, Security::Wpa3] {
+            credentials.security = Some(security);
+            assert_eq!(credentials.payload(), format!("WIFI:T:{};S:Old;P:{};;", security.payload_token(), psk));
+        }
+        // Other numbers of hex digits are still quoted.
+        credentials.password = Some(String::from("0123456789"));
+        assert_eq!(credentials.payload(), "WIFI:T:SAE;S:Old;P:\"0123456789\";;");
+    }
+    #[test]
+    fn test_dpp_uri() {
+        let mut dpp = DppUri{ key: String::from("MDkw"), ..DppUri::default() };
+        assert_eq!(dpp.uri(), "DPP:K:MDkw;;");
+        dpp.channels = alloc::vec![(81, 1), (115, 36)];
+        dpp.mac = Some([0x52, 0x54, 0x00, 0x58, 0x28, 0xe5]);
+        dpp.info = Some(String::from("SN=4774LH2b4044"));
+        assert_eq!(dpp.uri(), "DPP:C:81/1,115/36;M:5254005828e5;I:SN=4774LH2b4044;K:MDkw;;");
+    }
+    #[test]
+    fn test_names() {
+        assert_eq!("WPA2-WPA3".parse(), Ok(Security::Wpa2Wpa3));
+        assert!("wpa4".parse::<Security>().is_err());
+        assert!(Security::ALL.into_iter().all(|x| x.name().parse() == Ok(x)));
+        assert_eq!(alloc::format!("{} {}", Security::Wpa3, Security::Wpa2Wpa3), "Wpa3 Wpa2/Wpa3");
+        #[cfg(feature = "encode")]
+        assert_eq!((ecc_level("Quartile"), ecc_level("max").is_err()), (Ok(QrCodeEcc::Quartile), true));
+    }
+    #[cfg(feature = "encode")]
+    #[test]
+    fn test_builder() {
+        let code:WifiQr = WifiQrBuilder::new("Lab").password("hunter22").security(Security::Wpa3).transition_disable(true).hidden(true).ecc(QrCodeEcc::High).build().unwrap();
+        assert_eq!(code.payload(), "WIFI:T:SAE;S:Lab;P:hunter22;R:1;H:true;;");
+        assert_eq!(code.to_blocks().lines().count(), code.matrix().len() + 2 * render::QUIET_ZONE);
+        assert!(matches!(WifiQrBuilder::new("Lab").security(Security::Wpa2).build(), Err(BuildError::MissingPassword(Security::Wpa2))));
+        assert!(matches!(WifiQrBuilder::new("Lab").password("hunter22").transition_disable(true).build(), Err(BuildError::TransitionDisable)));
+        assert!(matches!(WifiQrBuilder::new("").build(), Err(BuildError::EmptySsid)));
+        let builder:WifiQrBuilder = WifiQrBuilder::new("").ssid_bytes(String::from("Café")).style(PayloadStyle::Compact);
+        assert_eq!((builder.check().is_ok(), builder.payload()), (true, String::from("WIFI:S:436166C3A9;;")));
+        assert!(matches!(WifiQrBuilder::new("x".repeat(3000)).build(), Err(BuildError::QrCode(QRCodeError::DataTooLong))));
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Drawing the matrix of a QR-Code as text, and the borders and scaling applied before drawing it in any form.
+//! Matrices are rows of modules, `true` being dark.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Quiet zone around the symbol in modules, as required by the QR-Code spec.
+pub const QUIET_ZONE: usize = 4;
+
+/// Adds the quiet zone to the matrix, so the symbol can be placed directly on any background.
+pub fn with_quiet_zone(matrix: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    with_border(matrix, QUIET_ZONE)
+}
+/// Adds a light border of `border` modules around the matrix.
+pub fn with_border(matrix: &[Vec<bool>], border: usize) -> Vec<Vec<bool>> {
+    let size:usize = matrix.len() + 2 * border;
+    let mut output:Vec<Vec<bool>> = vec![vec![false; size]; size];
+    for (y, row) in matrix.iter().enumerate() {
+        output[y + border][border..border + row.len()].copy_from_slice(row);
+    }
+    output
+}
+/// Repeats every module `factor` times in both directions.
+pub fn scaled(matrix: &[Vec<bool>], factor: usize) -> Vec<Vec<bool>> {
+    matrix.iter().flat_map(|row| {
+        let row:Vec<bool> = row.iter().flat_map(|x| core::iter::repeat_n(*x, factor)).collect();
+        core::iter::repeat_n(row, factor)
+    }).collect()
+}
+/// Draws two characters per module, `dark_module` for the dark ones (e.g. `██`) and spaces for the light ones, so
+/// the modules come out roughly square in the terminal.
+pub fn blocks(matrix: &[Vec<bool>], dark_module: &str) -> String {
+    let mut output:String = String::new();
+    for row in matrix {
+        for module in row {
+            output += if *module {dark_module}else{"  "};
+        }
+        output += "\n";
+    }
+    output
+}
+/// Draws one character per module and two rows per line, so the code is half as wide and tall as with
+/// [`blocks`].
+pub fn half_blocks(matrix: &[Vec<bool>]) -> String {
+    let mut output:String = String::new();
+    for rows in matrix.chunks(2) {
+        for (x, top) in rows[0].iter().enumerate() {
+            let bottom:bool = rows.get(1).is_some_and(|row| row[x]);
+            output.push(match (*top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' '
+            });
+        }
+        output += "\n";
+    }
+    output
+}
+/// Raised dots of a braille pattern by the column and row of the module, as laid out in Unicode.
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+/// Draws a braille pattern per two by four modules, with a dot for each dark module.
+pub fn braille(matrix: &[Vec<bool>]) -> String {
+    let mut output:String = String::new();
+    for rows in matrix.chunks(4) {
+        for x in (0..rows[0].len()).step_by(2) {
+            let mut pattern:u32 = 0x2800;
+            for (row, modules) in rows.iter().enumerate() {
+                for (column, dots) in BRAILLE_DOTS.iter().enumerate() {
+                    if modules.get(x + column).copied().unwrap_or(false) {
+                        pattern |= dots[row];
+                    }
+                }
+            }
+            output.push(char::from_u32(pattern).unwrap());
+        }
+        output += "\n";
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_quiet_zone() {
+        let matrix = with_quiet_zone(&[vec![true]]);
+        assert_eq!(matrix.len(), 2 * QUIET_ZONE + 1);
+        assert!(matrix[QUIET_ZONE][QUIET_ZONE]);
+        assert_eq!(matrix.iter().flatten().filter(|x| **x).count(), 1);
+    }
+    #[test]
+    fn test_scaled() {
+        assert_eq!(scaled(&[vec![true, false]], 2), vec![vec![true, true, false, false], vec![true, true, false, false]]);
+    }
+    #[test]
+    fn test_blocks() {
+        assert_eq!(blocks(&[vec![true, false], vec![false, true]], "##"), "##  \n  ##\n");
+    }
+    #[test]
+    fn test_half_blocks() {
+        let test_matrix: Vec<Vec<bool>> = vec![vec![true, false, true], vec![true, true, false], vec![false, true, true]];
+        assert_eq!(half_blocks(&test_matrix), "█▄▀\n ▀▀\n");
+    }
+    #[test]
+    fn test_braille() {
+        let test_matrix: Vec<Vec<bool>> = vec![vec![true, false, true], vec![false, true, false], vec![false, false, false], vec![true, true, false], vec![false, false, true]];
+        assert_eq!(braille(&test_matrix), "⣑⠁\n\u{2800}⠁\n");
+    }
+}
@@ -0,0 +1,40 @@
+#ifndef WIFI_QR_H
+#define WIFI_QR_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit it by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// The code of a network, or why it couldn't be encoded.
+typedef struct WifiQrResult {
+  // The `WIFI:` payload, or null if the network couldn't be encoded.
+  char *payload;
+  // The modules with the quiet zone, `size` times `size` bytes row after row, 1 being dark, or null.
+  uint8_t *modules;
+  // The width and height of the code in modules.
+  size_t size;
+  // What went wrong, or null.
+  char *error;
+} WifiQrResult;
+
+// Encodes the network as `wifiqr` does. `psw`, `security` (`wep`, `wpa`, `wpa2`, `wpa3` or `wpa2-wpa3`) and
+// `ecc` (`low` unless given, `medium`, `quartile` or `high`) may be null. The result is never null and has to be
+// released with [`wifi_qr_free`].
+//
+// # Safety
+// The pointers have to be null or point to null-terminated strings.
+struct WifiQrResult *wifi_qr_generate(const char *ssid,
+                                      const char *psw,
+                                      const char *security,
+                                      bool hidden,
+                                      const char *ecc);
+
+// Releases a result of [`wifi_qr_generate`]. Null is ignored.
+//
+// # Safety
+// The result has to be one returned by [`wifi_qr_generate`] and not be released already.
+void wifi_qr_free(struct WifiQrResult *result);
+
+#endif  /* WIFI_QR_H */
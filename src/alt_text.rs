@@ -0,0 +1,93 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Text alternatives for the image outputs, describing the network in words so that web embeds of the QR-Code
+//! remain accessible to screen-reader users.
+
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+use crate::{raster, resolve_security, Args};
+
+/// PNG keyword for a description of the image, as registered in the PNG specification.
+const PNG_KEYWORD: &str = "Description";
+
+/// Describes what scanning the code does, including the password, so the network can also be joined by hand.
+pub fn describe(args: &Args) -> String {
+    let mut description:String = match resolve_security(args) {
+        Some(security) => format!("QR-Code for joining the Wi-Fi network \"{}\", secured with {} (password: \"{}\").",
+            args.ssid, security.to_string().to_uppercase(), args.psw.clone().unwrap_or_default()),
+        None => format!("QR-Code for joining the open Wi-Fi network \"{}\".", args.ssid)
+    };
+    if args.hidden {
+        description += " The network is hidden, so its name has to be entered when joining by hand.";
+    }
+    description
+}
+
+/// Path of the alt-text file accompanying an image, e.g. `Home-wallpaper.alt.txt` for `Home-wallpaper.png`.
+pub fn alt_text_path(image: &Path) -> PathBuf {
+    image.with_extension("alt.txt")
+}
+
+/// Saves the image, its extension picking the format, TIFFs optionally in CMYK. With a description, PNGs carry it
+/// in an iTXt chunk and the alt-text file is written next to the image.
+pub fn save_image(image: &RgbaImage, path: &Path, description: Option<&str>, cmyk: bool) -> image::ImageResult<()> {
+    let Some(description) = description else {
+        return raster::save(image, path, cmyk);
+    };
+    std::fs::write(alt_text_path(path), format!("{}\n", description))?;
+    if cmyk || !path.extension().is_some_and(|x| x.eq_ignore_ascii_case("png")) {
+        return raster::save(image, path, cmyk);
+    }
+    let encoding_error = |err: png::EncodingError| image::ImageError::Encoding(image::error::EncodingError::new(image::ImageFormat::Png.into(), err));
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_itxt_chunk(PNG_KEYWORD.to_string(), description.to_string()).map_err(encoding_error)?;
+    encoder.write_header().and_then(|mut writer| writer.write_image_data(image.as_raw())).map_err(encoding_error)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn test_args() -> Args {
+        crate::tests::network("Martin Router King", Some("password"), Some(Security::Wpa2))
+    }
+    #[test]
+    fn test_describe() {
+        let mut args = test_args();
+        assert_eq!(describe(&args), "QR-Code for joining the Wi-Fi network \"Martin Router King\", secured with WPA2 (password: \"password\").");
+        args.psw = None;
+        args.sec = None;
+        args.hidden = true;
+        assert!(describe(&args).starts_with("QR-Code for joining the open Wi-Fi network \"Martin Router King\". The network is hidden"));
+    }
+    #[test]
+    fn test_png_description() {
+        let path:PathBuf = std::env::temp_dir().join(format!("wifiqr-alt-text-{}.png", std::process::id()));
+        save_image(&RgbaImage::new(2, 2), &path, Some("Wi-Fi \"Café\""), false).unwrap();
+        assert_eq!(std::fs::read_to_string(alt_text_path(&path)).unwrap(), "Wi-Fi \"Café\"\n");
+        let reader = png::Decoder::new(std::fs::File::open(&path).unwrap()).read_info().unwrap();
+        let chunk = &reader.info().utf8_text[0];
+        assert_eq!((chunk.keyword.as_str(), chunk.get_text().unwrap().as_str()), (PNG_KEYWORD, "Wi-Fi \"Café\""));
+        let _ = std::fs::remove_file(alt_text_path(&path));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,193 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Reading the networks an Android phone knew from its `WifiConfigStore.xml`, which rooted devices keep in
+//! `/data/misc/apexdata/com.android.wifi/` (`/data/misc/wifi/` before Android 11) and backups contain, and writing
+//! networks as the `openNetworkConfiguration` of Android Management API policies, which MDMs push to devices.
+
+use std::fmt;
+
+use roxmltree::{Document, Node};
+use serde_json::{json, Value};
+
+use crate::batch::Network;
+use crate::{profile, Args};
+
+#[derive(Debug)]
+pub enum AndroidError{
+    Xml(roxmltree::Error),
+    /// The document isn't a `WifiConfigStoreData`.
+    NotAConfigStore
+}
+impl fmt::Display for AndroidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AndroidError::Xml(err) => write!(f, "{}", err),
+            AndroidError::NotAConfigStore => write!(f, "the file isn't a WifiConfigStore.xml")
+        }
+    }
+}
+impl From<roxmltree::Error> for AndroidError {
+    fn from(err: roxmltree::Error) -> Self {
+        AndroidError::Xml(err)
+    }
+}
+
+/// The text of the `<string>` named `name`.
+fn string<'a>(configuration: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    configuration.children().find(|x| x.has_tag_name("string") && x.attribute("name") == Some(name)).map(|x| x.text().unwrap_or_default())
+}
+/// The `value` of the element named `name`, such as `<boolean name="HiddenSSID" value="false" />`.
+fn value<'a>(configuration: Node<'a, '_>, tag: &str, name: &str) -> Option<&'a str> {
+    configuration.children().find(|x| x.has_tag_name(tag) && x.attribute("name") == Some(name)).and_then(|x| x.attribute("value"))
+}
+
+/// Android stores SSIDs and passphrases in quotes, and SSIDs that aren't UTF-8 and raw keys as hex digits.
+fn unquote(value: &str) -> Result<String, String> {
+    if let Some(text) = value.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        return Ok(String::from(text));
+    }
+    let bytes:Vec<u8> = (0..value.len()).step_by(2).map(|i| value.get(i..i + 2).and_then(|x| u8::from_str_radix(x, 16).ok()))
+        .collect::<Option<Vec<u8>>>().ok_or_else(|| format!("'{}' is neither quoted nor hexadecimal", value))?;
+    String::from_utf8(bytes).map_err(|_| format!("{} isn't UTF-8", value))
+}
+
+/// The network of a `<WifiConfiguration>`.
+fn network(configuration: Node) -> Result<Network, String> {
+    let ssid:String = unquote(string(configuration, "SSID").ok_or("the SSID is missing")?)?;
+    let name = |err: String| format!("{}: {}", ssid, err);
+    // The ConfigKey ends in the key management, e.g. "Home"WPA_PSK.
+    let key_management:&str = string(configuration, "ConfigKey").and_then(|x| x.rsplit('"').next()).unwrap_or_default();
+    let hidden:bool = value(configuration, "boolean", "HiddenSSID") == Some("true");
+    let (security, password):(Option<&str>, Option<String>) = match key_management {
+        "NONE" => (None, None),
+        "WPA_PSK" | "SAE" => {
+            // Raw 64-digit keys are stored without quotes, and encoded like that too.
+            let key:&str = string(configuration, "PreSharedKey").ok_or_else(|| name(String::from("the pre-shared key is missing")))?;
+            let password:String = match key.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+                Some(passphrase) => String::from(passphrase),
+                None => String::from(key)
+            };
+            (Some(if key_management == "SAE" {"wpa3"}else{"wpa2"}), Some(password))
+        },
+        "WEP" => {
+            let index:usize = value(configuration, "int", "WEPTxKeyIndex").and_then(|x| x.parse().ok()).unwrap_or_default();
+            let keys:Vec<&str> = configuration.children().find(|x| x.has_tag_name("string-array") && x.attribute("name") == Some("WEPKeys"))
+                .map(|x| x.children().filter_map(|x| x.attribute("value")).collect()).unwrap_or_default();
+            let key:&str = keys.get(index).ok_or_else(|| name(String::from("the WEP key is missing")))?;
+            (Some("wep"), Some(key.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(key).to_string()))
+        },
+        "WPA_EAP" | "IEEE8021X" => return Err(name(String::from("enterprise networks can't be encoded yet"))),
+        "OWE" => return Err(name(String::from("enhanced open networks can't be encoded yet"))),
+        other => return Err(name(format!("unknown key management '{}'", other)))
+    };
+    Ok(Network{ ssid, password, security: security.map(String::from), hidden })
+}
+
+/// Reads the saved networks, returning them and why the others were skipped.
+pub fn read_networks(xml: &str) -> Result<(Vec<Network>, Vec<String>), AndroidError> {
+    let document = Document::parse(xml)?;
+    if !document.root_element().has_tag_name("WifiConfigStoreData") {
+        return Err(AndroidError::NotAConfigStore);
+    }
+    let mut networks:Vec<Network> = Vec::new();
+    let mut skipped:Vec<String> = Vec::new();
+    for configuration in document.descendants().filter(|x| x.has_tag_name("WifiConfiguration")) {
+        match network(configuration) {
+            Ok(network) if !networks.contains(&network) => networks.push(network),
+            Ok(_) => {},
+            Err(err) => skipped.push(err)
+        }
+    }
+    Ok((networks, skipped))
+}
+
+/// The policy fragment configuring the network.
+pub fn policy(args: &Args) -> Value {
+    json!({
+        "openNetworkConfiguration": {
+            "NetworkConfigurations": [profile::onc_configuration(args)]
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    const STORE: &str = r#"<?xml version='1.0' encoding='utf-8' standalone='yes' ?>
+<WifiConfigStoreData>
+<int name="Version" value="3" />
+<NetworkList>
+<Network>
+<WifiConfiguration>
+<string name="ConfigKey">&quot;Home &amp; Garden&quot;WPA_PSK</string>
+<string name="SSID">&quot;Home &amp; Garden&quot;</string>
+<null name="BSSID" />
+<string name="PreSharedKey">&quot;hunter2222&quot;</string>
+<null name="WEPKeys" />
+<boolean name="HiddenSSID" value="true" />
+</WifiConfiguration>
+<NetworkStatus />
+</Network>
+<Network>
+<WifiConfiguration>
+<string name="ConfigKey">&quot;Caf&#233;&quot;NONE</string>
+<string name="SSID">436166C3A9</string>
+<boolean name="HiddenSSID" value="false" />
+</WifiConfiguration>
+</Network>
+<Network>
+<WifiConfiguration>
+<string name="ConfigKey">&quot;Legacy&quot;WEP</string>
+<string name="SSID">&quot;Legacy&quot;</string>
+<string-array name="WEPKeys" num="4"><item value="" /><item value="&quot;abcde&quot;" /><item value="" /><item value="" /></string-array>
+<int name="WEPTxKeyIndex" value="1" />
+</WifiConfiguration>
+</Network>
+<Network>
+<WifiConfiguration>
+<string name="ConfigKey">&quot;Office&quot;WPA_EAP</string>
+<string name="SSID">&quot;Office&quot;</string>
+</WifiConfiguration>
+</Network>
+</NetworkList>
+</WifiConfigStoreData>"#;
+    #[test]
+    fn test_policy() {
+        use crate::Security;
+        let args = Args{ hidden: true, ..crate::tests::network("Guest", Some("hunter2222"), Some(Security::Wpa3)) };
+        assert_eq!(policy(&args), json!({"openNetworkConfiguration": {"NetworkConfigurations": [{
+            "GUID": "wifiqr-Guest",
+            "Name": "Guest",
+            "Type": "WiFi",
+            "WiFi": {"SSID": "Guest", "Security": "WPA-PSK", "Passphrase": "hunter2222", "HiddenSSID": true, "AutoConnect": true}
+        }]}}));
+        let open = Args{ psw: None, sec: None, ..args };
+        assert_eq!(policy(&open)["openNetworkConfiguration"]["NetworkConfigurations"][0]["WiFi"],
+            json!({"SSID": "Guest", "Security": "None", "HiddenSSID": true, "AutoConnect": true}));
+    }
+    #[test]
+    fn test_read_networks() {
+        let (networks, skipped) = read_networks(STORE).unwrap();
+        assert_eq!(networks, vec![
+            Network{ ssid: String::from("Home & Garden"), password: Some(String::from("hunter2222")), security: Some(String::from("wpa2")), hidden: true },
+            Network{ ssid: String::from("Café"), password: None, security: None, hidden: false },
+            Network{ ssid: String::from("Legacy"), password: Some(String::from("abcde")), security: Some(String::from("wep")), hidden: false }
+        ]);
+        assert_eq!(skipped, vec!["Office: enterprise networks can't be encoded yet"]);
+        assert!(matches!(read_networks("<resources />"), Err(AndroidError::NotAConfigStore)));
+    }
+}
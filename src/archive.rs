@@ -0,0 +1,171 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! age-encrypted tarballs bundling every artifact wifiqr generates for a network, plus a manifest.
+
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::branding::Branding;
+use crate::error::Error;
+use crate::output::{self, OutputFormat};
+use crate::{assemble_qr_string, get_bool_matrix_as_string, matrix, ndef, resolve_security, Args};
+
+/// A generated file, stored in the archive under `name`.
+pub struct Artifact{
+    pub name: String,
+    pub contents: Vec<u8>
+}
+
+#[derive(Debug)]
+pub enum ArchiveError{
+    Io(std::io::Error),
+    Encrypt(age::EncryptError)
+}
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveError::Io(err) => write!(f, "{}", err),
+            ArchiveError::Encrypt(err) => write!(f, "{}", err)
+        }
+    }
+}
+impl From<std::io::Error> for ArchiveError {
+    fn from(err: std::io::Error) -> Self {
+        ArchiveError::Io(err)
+    }
+}
+impl From<age::EncryptError> for ArchiveError {
+    fn from(err: age::EncryptError) -> Self {
+        ArchiveError::Encrypt(err)
+    }
+}
+
+/// Renders every output format for the network, drawn with the output options, but the NFC record of enterprise
+/// networks, which can't carry their credentials.
+pub fn generated_artifacts(args: &Args, branding: &Branding) -> Result<Vec<Artifact>, Error> {
+    let payload:String = assemble_qr_string(args);
+    let matrix:Vec<Vec<bool>> = matrix(args, &payload)?;
+    let mut artifacts:Vec<Artifact> = vec![
+        Artifact{ name: String::from("qr.png"), contents: output::rendered(args, branding, OutputFormat::Png, matrix.clone(), Path::new("qr.png"))
+            .map_err(|err| Error::new(err.kind(), format!("Failed to draw the QR-Code: {}", err)))? },
+        Artifact{ name: String::from("qr.txt"), contents: get_bool_matrix_as_string(matrix).into_bytes() }
+    ];
+    if args.enterprise.eap.is_none() {
+        artifacts.push(Artifact{ name: String::from("wifi.ndef"), contents: ndef::wifi_message(args) });
+    }
+    Ok(artifacts)
+}
+
+/// Describes the networks and files in the archive. Passwords and payloads are left out unless
+/// `include_secrets` is set, so the manifest can be forwarded on its own.
+pub fn manifest(networks: &[&Args], artifacts: &[Artifact], include_secrets: bool) -> Value {
+    let networks:Vec<Value> = networks.iter().map(|args| {
+        let mut network:Value = json!({
+            "ssid": args.ssid,
+            "security": resolve_security(args).map(|x| x.name()),
+            "hidden": args.hidden
+        });
+        if include_secrets {
+            network["password"] = json!(args.psw);
+            network["payload"] = json!(assemble_qr_string(args));
+        }
+        network
+    }).collect();
+    json!({
+        "generator": format!("wifiqr {}", env!("CARGO_PKG_VERSION")),
+        "includes_secrets": include_secrets,
+        "networks": networks,
+        "files": artifacts.iter().map(|x| json!({"name": x.name, "size": x.contents.len()})).collect::<Vec<Value>>()
+    })
+}
+
+/// Packs the artifacts and their manifest into a tarball.
+pub fn tarball(artifacts: &[Artifact], manifest: &Value) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let manifest:Vec<u8> = serde_json::to_vec_pretty(manifest).unwrap();
+    for (name, contents) in std::iter::once(("manifest.json", &manifest[..])).chain(artifacts.iter().map(|x| (&x.name[..], &x.contents[..]))) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o600);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents)?;
+    }
+    builder.into_inner()
+}
+
+/// Encrypts `data` so that any of the recipients can decrypt it.
+pub fn encrypt(data: &[u8], recipients: &[age::x25519::Recipient]) -> Result<Vec<u8>, ArchiveError> {
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|x| x as &dyn age::Recipient))?;
+    let mut output:Vec<u8> = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut output)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn test_args() -> Args {
+        crate::tests::network("Martin Router King", Some("password"), Some(Security::Wpa2))
+    }
+    #[test]
+    fn test_manifest_secrets() {
+        let args = test_args();
+        let artifacts = generated_artifacts(&args, &Branding::default()).unwrap();
+        let without_secrets = manifest(&[&args], &artifacts, false);
+        assert_eq!(without_secrets["networks"][0]["ssid"], "Martin Router King");
+        assert!(without_secrets["networks"][0].get("password").is_none());
+        let with_secrets = manifest(&[&args], &artifacts, true);
+        assert_eq!(with_secrets["networks"][0]["password"], "password");
+        assert_eq!(with_secrets["files"].as_array().unwrap().len(), artifacts.len());
+        let transition:Args = Args{ sec: Some(Security::Wpa2Wpa3), ..test_args() };
+        assert_eq!(manifest(&[&transition], &artifacts, false)["networks"][0]["security"], "wpa2-wpa3");
+    }
+    #[test]
+    fn test_encrypted_archive_round_trip() {
+        let args = test_args();
+        let artifacts = generated_artifacts(&args, &Branding::default()).unwrap();
+        let tarball = tarball(&artifacts, &manifest(&[&args], &artifacts, false)).unwrap();
+        let identity = age::x25519::Identity::generate();
+        let encrypted = encrypt(&tarball, &[identity.to_public()]).unwrap();
+        assert_eq!(age::decrypt(&identity, &encrypted).unwrap(), tarball);
+
+        let mut archive = tar::Archive::new(&tarball[..]);
+        let names: Vec<String> = archive.entries().unwrap().map(|x| x.unwrap().path().unwrap().display().to_string()).collect();
+        assert_eq!(names, vec!["manifest.json", "qr.png", "qr.txt", "wifi.ndef"]);
+    }
+    #[test]
+    fn test_artifact_options() {
+        let mut args = test_args();
+        (args.output.border, args.output.module_size) = (0, Some(8));
+        let artifacts = generated_artifacts(&args, &Branding::default()).unwrap();
+        // 29 modules of version 3, without a border.
+        assert_eq!(image::load_from_memory(&artifacts[0].contents).unwrap().width(), 232);
+    }
+    #[test]
+    fn test_enterprise_artifacts() {
+        let mut args = test_args();
+        args.enterprise = crate::eap::Enterprise{ eap: Some(crate::eap::EapMethod::Peap), identity: Some(String::from("alice")), ..crate::eap::Enterprise::default() };
+        let names:Vec<String> = generated_artifacts(&args, &Branding::default()).unwrap().into_iter().map(|x| x.name).collect();
+        assert_eq!(names, vec!["qr.png", "qr.txt"]);
+    }
+}
@@ -0,0 +1,721 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Generating the outputs for a fleet of networks read from a CSV file, laid out in per-network directories by
+//! path templates such as `{ssid}/qr.png`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::branding::Branding;
+use crate::error::ErrorKind;
+use crate::output::{self, OutputError, OutputFormat};
+use crate::script::{Hooks, ScriptError};
+use crate::{assemble_qr_string, capacity, get_bool_matrix_as_string, html, layout, ndef, pdf, verify, Args, Security};
+
+/// Paths written for every network if no `--output` is given.
+pub const DEFAULT_OUTPUTS: [&str; 3] = ["{ssid}/qr.png", "{ssid}/qr.svg", "{ssid}/poster.pdf"];
+
+/// A row of the CSV file, or a `--network` of the command line. Only the SSID is required.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Network{
+    pub ssid: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub security: Option<String>,
+    #[serde(default)]
+    pub hidden: bool
+}
+
+/// What is written to an output, picked by its extension.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format{
+    Png,
+    /// An LZW-compressed RGB TIFF.
+    Tiff,
+    Gif,
+    Svg,
+    Eps,
+    Pbm,
+    Text,
+    Ndef,
+    /// A table tent on A4, which doubles as a poster.
+    Pdf,
+    /// A self-contained page showing the code full-screen.
+    Html
+}
+impl Format {
+    fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "tif" | "tiff" => Some(Format::Tiff),
+            "gif" => Some(Format::Gif),
+            "svg" => Some(Format::Svg),
+            "eps" => Some(Format::Eps),
+            "pbm" => Some(Format::Pbm),
+            "txt" => Some(Format::Text),
+            "ndef" => Some(Format::Ndef),
+            "pdf" => Some(Format::Pdf),
+            "html" | "htm" => Some(Format::Html),
+            _ => None
+        }
+    }
+    /// Whether the output is laid out with the branding. These are rendered on the thread that loaded it, as its fonts
+    /// collect the glyphs of their subsets.
+    fn uses_branding(self) -> bool {
+        matches!(self, Format::Pdf | Format::Html)
+    }
+}
+
+#[derive(Debug)]
+pub enum BatchError{
+    Io(PathBuf, std::io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    /// A row of the CSV file, counting the header as row 1, and what is wrong with it.
+    InvalidNetwork(usize, String),
+    UnsupportedFormat(String),
+    /// Two networks would be written to the same path.
+    Collision(PathBuf),
+    /// An NDEF record of the template was asked for enterprise networks, which it can't carry the credentials of.
+    EnterpriseRecord(String),
+    /// An image the output options can't be drawn with, such as a logo that can't be read.
+    Output(OutputError),
+    Script(ScriptError)
+}
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            BatchError::Csv(err) => write!(f, "{}", err),
+            BatchError::Json(err) => write!(f, "{}", err),
+            BatchError::InvalidNetwork(row, message) => write!(f, "row {}: {}", row, message),
+            BatchError::UnsupportedFormat(template) => write!(f, "can't tell what to write to '{}', use one of .png, .tiff, .gif, .svg, .eps, .pbm, .txt, .ndef, .pdf or .html", template),
+            BatchError::Collision(path) => write!(f, "several networks would be written to {}, add {{index}} to the path", path.display()),
+            BatchError::EnterpriseRecord(template) => write!(f, "NFC records can't carry the credentials of enterprise networks, leave out '{}' or --eap", template),
+            BatchError::Output(err) => write!(f, "{}", err),
+            BatchError::Script(err) => write!(f, "{}", err)
+        }
+    }
+}
+impl BatchError {
+    /// What kind of failure this is, for the exit code.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            BatchError::Io(path, _) if path == Path::new("stdin") => ErrorKind::Input,
+            BatchError::Io(..) => ErrorKind::Output,
+            BatchError::Csv(_) | BatchError::Json(_) | BatchError::InvalidNetwork(..) | BatchError::Script(_) => ErrorKind::Input,
+            BatchError::UnsupportedFormat(_) | BatchError::Collision(_) | BatchError::EnterpriseRecord(_) => ErrorKind::Usage,
+            BatchError::Output(err) => err.kind()
+        }
+    }
+}
+impl From<csv::Error> for BatchError {
+    fn from(err: csv::Error) -> Self {
+        BatchError::Csv(err)
+    }
+}
+impl From<OutputError> for BatchError {
+    fn from(err: OutputError) -> Self {
+        match err {
+            OutputError::Io(path, err) => BatchError::Io(path, err),
+            err => BatchError::Output(err)
+        }
+    }
+}
+impl From<ScriptError> for BatchError {
+    fn from(err: ScriptError) -> Self {
+        BatchError::Script(err)
+    }
+}
+
+/// Parses `key=value` pairs separated by commas, such as `ssid=Staff,psw=secret,sec=wpa3,hidden`. `hidden` may be
+/// given without a value. Commas and backslashes within values are escaped with a backslash.
+impl FromStr for Network {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut network = Network{ ssid: String::new(), password: None, security: None, hidden: false };
+        let mut pairs:Vec<String> = vec![String::new()];
+        let mut characters = s.chars();
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => pairs.last_mut().unwrap().extend(characters.next()),
+                ',' => pairs.push(String::new()),
+                _ => pairs.last_mut().unwrap().push(character)
+            }
+        }
+        for pair in pairs {
+            let (key, value) = pair.split_once('=').unwrap_or((&pair, ""));
+            match key {
+                "ssid" => network.ssid = value.to_string(),
+                "psw" | "password" => network.password = Some(value.to_string()),
+                "sec" | "security" => network.security = Some(value.to_string()),
+                "hidden" => network.hidden = value.is_empty() || value.parse().map_err(|_| format!("'{}' is not true or false", value))?,
+                _ => return Err(format!("unknown key '{}', expected ssid, psw, sec or hidden", key))
+            }
+        }
+        if network.ssid.is_empty() {
+            return Err(String::from("the SSID is missing"));
+        }
+        network.sec()?;
+        Ok(network)
+    }
+}
+
+/// Reads the networks from a JSON array of objects keyed like the columns of a CSV file. Rows are counted from 1.
+pub fn read_json_networks(json: impl std::io::Read) -> Result<Vec<Network>, BatchError> {
+    let networks:Vec<Network> = serde_json::from_reader(json).map_err(BatchError::Json)?;
+    if let Some(index) = networks.iter().position(|x| x.ssid.is_empty()) {
+        return Err(BatchError::InvalidNetwork(index + 1, String::from("the SSID is empty")));
+    }
+    Ok(networks)
+}
+
+/// Reads the networks from a CSV file with a header row.
+pub fn read_networks(csv: impl std::io::Read) -> Result<Vec<Network>, BatchError> {
+    let mut networks:Vec<Network> = Vec::new();
+    for (index, network) in csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(csv).deserialize().enumerate() {
+        let network:Network = network?;
+        if network.ssid.is_empty() {
+            return Err(BatchError::InvalidNetwork(index + 2, String::from("the SSID is empty")));
+        }
+        networks.push(network);
+    }
+    Ok(networks)
+}
+
+impl Network {
+    /// The security standard, checking that it is known and comes with a password.
+    pub fn sec(&self) -> Result<Option<Security>, String> {
+        let sec:Option<Security> = match self.security.as_deref().filter(|x| !x.is_empty()) {
+            Some(name) => Some(name.parse::<Security>().map_err(|_| format!("'{}' is not a security standard", name))?),
+            None => None
+        };
+        if sec.is_some() && self.password.as_ref().is_none_or(|x| x.is_empty()) {
+            return Err(String::from("a security standard was given without a password"));
+        }
+        Ok(sec)
+    }
+}
+
+/// The arguments for a single network, keeping the global options. `row` is reported in errors.
+pub fn network_args(args: &Args, network: &Network, row: usize) -> Result<Args, BatchError> {
+    let sec:Option<Security> = network.sec().map_err(|err| BatchError::InvalidNetwork(row, err))?;
+    Ok(with_network(args, network, sec))
+}
+
+/// The arguments for a single network secured with `sec` rather than its `security`, keeping the global options.
+pub fn with_network(args: &Args, network: &Network, sec: Option<Security>) -> Args {
+    Args{
+        ssid: network.ssid.clone(),
+        hex_ssid: crate::hex_ssid::Options{ ssid_hex: None, write_ssid_hex: args.hex_ssid.write_ssid_hex },
+        psw: network.password.clone(),
+        interactive: false,
+        psw_source: crate::credentials::PasswordSource::default(),
+        generator: crate::passphrase::Generator::default(),
+        saved: crate::saved::Source::default(),
+        sec,
+        hidden: network.hidden,
+        compat: args.compat,
+        // Only WPA3 networks can disable the transition, the others of a batch are left as they are.
+        transition_disable: args.transition_disable && sec == Some(Security::Wpa3),
+        ecc: args.ecc,
+        symbol: args.symbol.clone(),
+        branding: args.branding.clone(),
+        font: args.font.clone(),
+        align: args.align,
+        frame: args.frame,
+        palette: args.palette,
+        scan_distance: args.scan_distance,
+        shred: args.shred,
+        downgrade_ecc: args.downgrade_ecc,
+        force: args.force,
+        wep: args.wep.clone(),
+        info_url: args.info_url.clone(),
+        explain: args.explain,
+        verify: args.verify,
+        output: args.output.clone(),
+        config: args.config.clone(),
+        enterprise: args.enterprise.clone(),
+        networks: Vec::new(),
+        command: None
+    }
+}
+
+/// Makes the SSID usable as a single path component on every platform, replacing separators, the characters
+/// Windows reserves, control characters, leading dots and trailing dots and spaces, which Windows drops.
+pub fn sanitize(ssid: &str) -> String {
+    let mut sanitized:String = ssid.chars().map(|x| if matches!(x, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || x.is_control() {'_'}else{x}).collect();
+    let trailing:usize = sanitized.len() - sanitized.trim_end_matches(['.', ' ']).len();
+    sanitized.truncate(sanitized.len() - trailing);
+    sanitized.push_str(&"_".repeat(trailing));
+    match sanitized.strip_prefix('.') {
+        Some(rest) => format!("_{}", rest),
+        None => sanitized
+    }
+}
+
+/// Fills in `{ssid}`, `{security}` and `{index}` (the position of the network in the file, starting at 1).
+pub fn expand(template: &str, args: &Args, index: usize) -> String {
+    let security:String = crate::resolve_security(args).map_or(String::from("open"), |x| String::from(x.name()));
+    template.replace("{ssid}", &sanitize(&args.ssid)).replace("{security}", &security).replace("{index}", &index.to_string())
+}
+
+/// What is written for every network.
+pub struct Outputs<'a>{
+    /// Paths below the output directory, their placeholders filled in for every network.
+    pub templates: &'a [String],
+    /// The sheet the shared documents tile the cards of the networks on, instead of a table tent per network.
+    pub sheet: Option<layout::Sheet>
+}
+
+/// A network with what the hooks of the script made of it.
+struct Job{
+    args: Args,
+    payload: String,
+    /// The QR-Code of the payload.
+    matrix: Vec<Vec<bool>>,
+    /// The footer the `caption` hook gave the layouts, instead of the one of the branding.
+    footer: Option<String>
+}
+
+/// Renders an output that isn't laid out with the branding, drawing the images like the single network of the command
+/// line with the output options.
+fn render_plain(format: Format, args: &Args, matrix: &[Vec<bool>], branding: &Branding, path: &Path) -> Result<Vec<u8>, OutputError> {
+    let format:OutputFormat = match format {
+        Format::Png => OutputFormat::Png,
+        Format::Tiff => OutputFormat::Tiff,
+        Format::Gif => OutputFormat::Gif,
+        Format::Svg => OutputFormat::Svg,
+        Format::Eps => OutputFormat::Eps,
+        Format::Pbm => OutputFormat::Pbm,
+        Format::Text => return Ok(get_bool_matrix_as_string(matrix.to_vec()).into_bytes()),
+        Format::Ndef => return Ok(ndef::wifi_message(args)),
+        Format::Pdf | Format::Html => unreachable!("{:?} is laid out with the branding", format)
+    };
+    output::rendered(args, branding, format, matrix.to_vec(), path)
+}
+
+/// The branding with the footer the `caption` hook gave the network, if it gave one.
+fn captioned<'a>(job: &Job, branding: &'a Branding) -> Cow<'a, Branding> {
+    match &job.footer {
+        Some(footer) => {
+            let mut captioned:Branding = branding.clone();
+            captioned.footer = Some(footer.clone());
+            Cow::Owned(captioned)
+        },
+        None => Cow::Borrowed(branding)
+    }
+}
+
+/// The pages of the table tent of a network.
+fn table_tent(job: &Job, branding: &Branding) -> Vec<pdf::Page> {
+    layout::table_tent(&job.args, &job.matrix, &captioned(job, branding), layout::PaperSize::A4)
+}
+
+fn render(format: Format, job: &Job, branding: &Branding, path: &Path) -> Result<Vec<u8>, OutputError> {
+    match format {
+        Format::Pdf => Ok(pdf::write_document(&table_tent(job, branding))),
+        Format::Html => Ok(html::page(&job.args, &job.matrix, &captioned(job, branding)).into_bytes()),
+        _ => render_plain(format, &job.args, &job.matrix, branding, path)
+    }
+}
+
+/// The network with its payload and footer as the hooks made them, checked to fit into a QR-Code.
+fn prepare(args: &Args, network: &Network, row: usize, index: usize, hooks: Option<&Hooks>) -> Result<Job, BatchError> {
+    let mut args:Args = network_args(args, network, row)?;
+    crate::checked_credentials(&mut args).map_err(|err| BatchError::InvalidNetwork(row, err.message))?;
+    let mut payload:String = assemble_qr_string(&args);
+    let mut footer:Option<String> = None;
+    if let Some(hooks) = hooks {
+        payload = hooks.payload(&args, index, payload)?;
+        footer = hooks.caption(&args, index)?;
+    }
+    capacity::fit(&mut args, &payload).map_err(|err| BatchError::InvalidNetwork(row, err.to_string()))?;
+    let matrix:Vec<Vec<bool>> = crate::matrix(&args, &payload).map_err(|err| BatchError::InvalidNetwork(row, err.message))?;
+    let job = Job{ args, payload, matrix, footer };
+    crate::diagnostics::network(&job.args, &job.payload, || Ok(job.matrix.clone()));
+    if job.args.verify {
+        verify::verify(&job.args, &job.payload, &job.matrix).map_err(|err| BatchError::InvalidNetwork(row, format!("failed to verify the QR-Code: {}", err)))?;
+    }
+    Ok(job)
+}
+
+/// A `.pdf` output without placeholders is a single document, with the table tents of every network, or their cards
+/// tiled on the sheets of `--sheet`, which also tiles them on a single `.html` page.
+fn shared(template: &str, format: Format, sheet: Option<layout::Sheet>) -> bool {
+    (format == Format::Pdf || format == Format::Html && sheet.is_some()) && !template.contains('{')
+}
+
+/// The paths of the outputs of a network, which must not have been taken by another network. The shared documents
+/// are marked as taken by none.
+fn paths(args: &Args, index: usize, out_dir: &Path, outputs: &Outputs, formats: &[Format], hooks: Option<&Hooks>, seen: &mut HashMap<PathBuf, Option<usize>>) -> Result<Vec<PathBuf>, BatchError> {
+    let mut paths:Vec<PathBuf> = Vec::with_capacity(outputs.templates.len());
+    for (template, format) in outputs.templates.iter().zip(formats) {
+        let owner:Option<usize> = if shared(template, *format, outputs.sheet) {None}else{Some(index)};
+        let mut path:String = expand(template, args, index);
+        if let (Some(hooks), Some(_)) = (hooks, owner) {
+            path = hooks.filename(args, index, path)?;
+        }
+        let path:PathBuf = out_dir.join(path);
+        if seen.insert(path.clone(), owner).is_some_and(|x| x != owner) {
+            return Err(BatchError::Collision(path));
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Failing to render or write a file, which unlike the errors of scripts can be reported from other threads.
+type WriteError = OutputError;
+
+fn create_parent(path: &Path) -> Result<(), WriteError> {
+    match path.parent() {
+        Some(directory) => std::fs::create_dir_all(directory).map_err(|err| OutputError::Io(directory.to_path_buf(), err)),
+        None => Ok(())
+    }
+}
+
+fn write(path: &Path, contents: Result<Vec<u8>, OutputError>) -> Result<(), WriteError> {
+    let contents:Vec<u8> = contents?;
+    create_parent(path)?;
+    std::fs::write(path, contents).map_err(|err| OutputError::Io(path.to_path_buf(), err))
+}
+
+/// The formats of the outputs. The networks of a batch share `--eap`, so none of them gets an NDEF record with it.
+fn formats(args: &Args, outputs: &[String]) -> Result<Vec<Format>, BatchError> {
+    outputs.iter().map(|x| match Format::from_path(x) {
+        Some(Format::Ndef) if args.enterprise.eap.is_some() => Err(BatchError::EnterpriseRecord(x.clone())),
+        Some(format) => Ok(format),
+        None => Err(BatchError::UnsupportedFormat(x.clone()))
+    }).collect()
+}
+
+/// A shared document being written.
+enum Document{
+    /// A PDF, with the page of cards being filled if they are tiled on sheets.
+    Pdf(Box<pdf::DocumentWriter<std::io::BufWriter<std::fs::File>>>, Option<layout::CardSheet>),
+    /// An HTML page of cards tiled on sheets, with the number of cards written.
+    Html(std::io::BufWriter<std::fs::File>, usize)
+}
+
+/// The shared documents being written, each getting the pages or cards of a network as it is rendered.
+struct Documents{
+    sheet: Option<layout::Sheet>,
+    open: HashMap<PathBuf, Document>
+}
+impl Documents {
+    fn new(sheet: Option<layout::Sheet>) -> Self {
+        Documents{ sheet, open: HashMap::new() }
+    }
+    fn add(&mut self, path: &Path, format: Format, job: &Job, branding: &Branding) -> Result<(), WriteError> {
+        use std::io::Write;
+        let error = |err| OutputError::Io(path.to_path_buf(), err);
+        if !self.open.contains_key(path) {
+            create_parent(path)?;
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(error)?);
+            let document:Document = match (format, self.sheet) {
+                (Format::Html, Some(sheet)) => {
+                    file.write_all(html::sheet_start(branding, sheet, job.args.output.lang).as_bytes()).map_err(error)?;
+                    Document::Html(file, 0)
+                },
+                _ => Document::Pdf(Box::new(pdf::DocumentWriter::new(file).map_err(error)?), self.sheet.map(layout::CardSheet::new))
+            };
+            self.open.insert(path.to_path_buf(), document);
+        }
+        match self.open.get_mut(path).unwrap() {
+            Document::Pdf(document, Some(cards)) => {
+                cards.add(&job.args, &job.matrix, &captioned(job, branding)).map_or(Ok(()), |page| document.add_page(&page).map_err(error))
+            },
+            Document::Pdf(document, None) => {
+                for page in table_tent(job, branding) {
+                    document.add_page(&page).map_err(error)?;
+                }
+                Ok(())
+            },
+            Document::Html(file, cards) => {
+                let per_page:usize = self.sheet.map_or(1, |x| x.cards_per_page());
+                *cards += 1;
+                file.write_all(html::sheet_card(&job.args, &job.matrix, &captioned(job, branding), *cards - 1, per_page).as_bytes()).map_err(error)
+            }
+        }
+    }
+    fn finish(self) -> Result<(), WriteError> {
+        use std::io::Write;
+        for (path, document) in self.open {
+            let finished:std::io::Result<()> = match document {
+                Document::Pdf(mut document, cards) => match cards.and_then(layout::CardSheet::finish) {
+                    Some(page) => document.add_page(&page).and_then(|_| document.finish()).map(drop),
+                    None => document.finish().map(drop)
+                },
+                Document::Html(mut file, _) => file.write_all(html::SHEET_END.as_bytes()).and_then(|_| file.flush())
+            };
+            finished.map_err(|err| OutputError::Io(path, err))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the outputs of a network on this thread.
+fn write_job(job: &Job, paths: &[PathBuf], outputs: &[String], formats: &[Format], branding: &Branding, documents: &mut Documents) -> Result<(), BatchError> {
+    for ((path, template), format) in paths.iter().zip(outputs).zip(formats) {
+        if shared(template, *format, documents.sheet) {
+            documents.add(path, *format, job, branding)?;
+        }else{
+            write(path, render(*format, job, branding, path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes every output for every network below `out_dir`, creating directories as needed, and returns the paths
+/// written. The paths are checked before anything is written, so a bad template leaves no partial run behind. The
+/// `hooks` of a script may change the payload, the footer of the layouts and the paths of each network. The NDEF
+/// records are written from the network itself.
+///
+/// Only the paths of the networks are kept while they are checked. Each network is then prepared as its outputs are
+/// handed to `workers` threads besides this one, which renders the layouts, so a network that doesn't fit into a
+/// QR-Code stops the run there. At most a few networks are waiting for a thread at a time, so the memory needed
+/// apart from the paths doesn't grow with the number of networks, and the pages of shared documents are written as
+/// they are laid out.
+pub fn run(args: &Args, branding: &Branding, networks: &[Network], out_dir: &Path, outputs: &Outputs, hooks: Option<&Hooks>, workers: usize) -> Result<Vec<PathBuf>, BatchError> {
+    let formats:Vec<Format> = formats(args, outputs.templates)?;
+    let mut network_paths:Vec<Vec<PathBuf>> = Vec::with_capacity(networks.len());
+    let mut seen:HashMap<PathBuf, Option<usize>> = HashMap::new();
+    for (index, network) in networks.iter().enumerate() {
+        let network_args:Args = network_args(args, network, index + 2)?;
+        network_paths.push(paths(&network_args, index + 1, out_dir, outputs, &formats, hooks, &mut seen)?);
+    }
+    let failure:Mutex<Option<WriteError>> = Mutex::new(None);
+    let fail = |err: WriteError| {failure.lock().unwrap().get_or_insert(err);};
+    let mut invalid:Option<BatchError> = None;
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<(&Path, Format, Arc<Job>)>(workers);
+    let receiver = Mutex::new(receiver);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                // Only the captions of labels are set in the fonts of the branding, which stays on this thread.
+                let plain:Branding = Branding::default();
+                loop {
+                    // The lock is released once a file is received, while it is rendered.
+                    let received = receiver.lock().unwrap().recv();
+                    let Ok((path, format, job)) = received else { break; };
+                    if failure.lock().unwrap().is_none() {
+                        write(path, render_plain(format, &job.args, &job.matrix, &plain, path)).unwrap_or_else(fail);
+                    }
+                }
+            });
+        }
+        let mut documents = Documents::new(outputs.sheet);
+        for (index, (network, paths)) in networks.iter().zip(&network_paths).enumerate() {
+            if failure.lock().unwrap().is_some() {
+                break;
+            }
+            let job:Arc<Job> = match prepare(args, network, index + 2, index + 1, hooks) {
+                Ok(job) => Arc::new(job),
+                Err(err) => {
+                    invalid = Some(err);
+                    break;
+                }
+            };
+            for ((path, template), format) in paths.iter().zip(outputs.templates).zip(&formats) {
+                let written:Result<(), WriteError> = if shared(template, *format, outputs.sheet) {
+                    documents.add(path, *format, &job, branding)
+                }else if format.uses_branding() || job.args.output.label.is_some() || workers == 0 {
+                    write(path, render(*format, &job, branding, path))
+                }else{
+                    sender.send((path, *format, Arc::clone(&job))).unwrap();
+                    Ok(())
+                };
+                written.unwrap_or_else(fail);
+            }
+        }
+        drop(sender);
+        documents.finish().unwrap_or_else(fail);
+    });
+    if let Some(err) = invalid {
+        return Err(err);
+    }
+    if let Some(err) = failure.into_inner().unwrap() {
+        return Err(err.into());
+    }
+    // Shared documents are listed once, where the first network was added.
+    Ok(network_paths.iter().flatten().filter(|path| match seen.get(*path) {
+        Some(Some(_)) => true,
+        Some(None) => seen.remove(*path).is_some(),
+        None => false
+    }).cloned().collect())
+}
+
+/// Like [`run`], but for networks arriving as newline-delimited JSON records with the columns of the CSV file as
+/// keys, e.g. `{"ssid": "Staff", "password": "hunter22", "security": "wpa3"}`. Each network is written as soon as
+/// its record is read, and followed by a JSON record on `results`: `{"line": 1, "ssid": "Staff", "files": [...]}`, or
+/// `{"line": 1, "error": "..."}` if the record is invalid, in which case the stream carries on. Blank lines are
+/// skipped. Shared documents are complete once the stream ends. Returns the number of networks written.
+pub fn stream(args: &Args, branding: &Branding, records: impl std::io::BufRead, mut results: impl std::io::Write, out_dir: &Path, outputs: &Outputs, hooks: Option<&Hooks>) -> Result<usize, BatchError> {
+    let formats:Vec<Format> = formats(args, outputs.templates)?;
+    let mut seen:HashMap<PathBuf, Option<usize>> = HashMap::new();
+    let mut documents = Documents::new(outputs.sheet);
+    let mut written:usize = 0;
+    for (index, record) in records.lines().enumerate() {
+        let record:String = record.map_err(|err| BatchError::Io(PathBuf::from("stdin"), err))?;
+        if record.trim().is_empty() {
+            continue;
+        }
+        let network:Result<Network, BatchError> = serde_json::from_str(&record).map_err(|err| BatchError::InvalidNetwork(index + 1, err.to_string()));
+        let job:Result<(Job, Vec<PathBuf>), BatchError> = network.and_then(|network| {
+            if network.ssid.is_empty() {
+                return Err(BatchError::InvalidNetwork(index + 1, String::from("the SSID is empty")));
+            }
+            let job:Job = prepare(args, &network, index + 1, index + 1, hooks)?;
+            let paths:Vec<PathBuf> = paths(&job.args, index + 1, out_dir, outputs, &formats, hooks, &mut seen)?;
+            Ok((job, paths))
+        });
+        let result:serde_json::Value = match job {
+            Ok((job, paths)) => {
+                write_job(&job, &paths, outputs.templates, &formats, branding, &mut documents)?;
+                written += 1;
+                serde_json::json!({"line": index + 1, "ssid": job.args.ssid, "files": paths})
+            },
+            Err(BatchError::InvalidNetwork(_, message)) => serde_json::json!({"line": index + 1, "error": message}),
+            Err(err @ (BatchError::Collision(_) | BatchError::Script(_))) => serde_json::json!({"line": index + 1, "error": err.to_string()}),
+            Err(err) => return Err(err)
+        };
+        writeln!(results, "{}", result).and_then(|_| results.flush()).map_err(|err| BatchError::Io(PathBuf::from("stdout"), err))?;
+    }
+    documents.finish()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::ECCLevel;
+    fn test_args() -> Args {
+        Args{ ecc: ECCLevel::Medium, ..crate::tests::network("", None, None) }
+    }
+    const CSV: &str = "ssid,password,security,hidden\nGuest,,,false\nStaff/5G, hunter22 ,wpa3,true\n";
+    #[test]
+    fn test_read_networks() {
+        let networks = read_networks(CSV.as_bytes()).unwrap();
+        assert_eq!(networks[1], Network{ ssid: String::from("Staff/5G"), password: Some(String::from("hunter22")), security: Some(String::from("wpa3")), hidden: true });
+        assert!(matches!(read_networks("ssid\n\"\"\n".as_bytes()), Err(BatchError::InvalidNetwork(2, _))));
+        let args = network_args(&test_args(), &networks[1], 3).unwrap();
+        assert_eq!((args.sec, args.ecc), (Some(Security::Wpa3), ECCLevel::Medium));
+        let invalid = Network{ ssid: String::from("Guest"), password: None, security: Some(String::from("wpa2")), hidden: false };
+        assert!(matches!(network_args(&test_args(), &invalid, 2), Err(BatchError::InvalidNetwork(2, _))));
+    }
+    #[test]
+    fn test_read_json_networks() {
+        let networks = read_json_networks("[{\"ssid\": \"Guest\"}, {\"ssid\": \"Staff\", \"password\": \"hunter22\", \"security\": \"wpa3\", \"hidden\": true}]".as_bytes()).unwrap();
+        assert_eq!(networks[1], Network{ ssid: String::from("Staff"), password: Some(String::from("hunter22")), security: Some(String::from("wpa3")), hidden: true });
+        assert!(matches!(read_json_networks("[{\"ssid\": \"Guest\"}, {\"ssid\": \"\"}]".as_bytes()), Err(BatchError::InvalidNetwork(2, _))));
+        assert!(matches!(read_json_networks("{\"ssid\": \"Guest\"}".as_bytes()), Err(BatchError::Json(_))));
+    }
+    #[test]
+    fn test_parse_network() {
+        let network:Network = "ssid=Staff\\, 5G,psw=a\\\\b,sec=wpa3,hidden".parse().unwrap();
+        assert_eq!(network, Network{ ssid: String::from("Staff, 5G"), password: Some(String::from("a\\b")), security: Some(String::from("wpa3")), hidden: true });
+        assert!(!"ssid=Guest,hidden=false".parse::<Network>().unwrap().hidden);
+        assert!("psw=secret".parse::<Network>().is_err());
+        assert!("ssid=Guest,sec=wpa2".parse::<Network>().is_err());
+        assert!("ssid=Guest,channel=6".parse::<Network>().is_err());
+    }
+    #[test]
+    fn test_expand() {
+        let networks = read_networks(CSV.as_bytes()).unwrap();
+        let args = network_args(&test_args(), &networks[1], 3).unwrap();
+        assert_eq!(expand("{ssid}/{security}-{index}.png", &args, 2), "Staff_5G/wpa3-2.png");
+        let transition:Args = Args{ sec: Some(Security::Wpa2Wpa3), ..args };
+        assert_eq!(expand("{ssid}/{security}.txt", &transition, 2), "Staff_5G/wpa2-wpa3.txt");
+        assert_eq!(sanitize(".."), "__");
+        assert_eq!(sanitize("Cafe: Guest?"), "Cafe_ Guest_");
+        assert_eq!(sanitize("<Lobby> \"5G\"|*"), "_Lobby_ _5G___");
+        assert_eq!(sanitize(".hidden. "), "_hidden__");
+    }
+    #[test]
+    fn test_run() {
+        let out_dir:PathBuf = std::env::temp_dir().join(format!("wifiqr-batch-{}", std::process::id()));
+        let networks = read_networks(CSV.as_bytes()).unwrap();
+        let outputs = vec![String::from("{ssid}/qr.txt"), String::from("{ssid}/wifi.ndef")];
+        let written = run(&test_args(), &Branding::default(), &networks, &out_dir, &Outputs{ templates: &outputs, sheet: None }, None, 2).unwrap();
+        assert_eq!(written, vec![out_dir.join("Guest/qr.txt"), out_dir.join("Guest/wifi.ndef"), out_dir.join("Staff_5G/qr.txt"), out_dir.join("Staff_5G/wifi.ndef")]);
+        assert!(std::fs::read_to_string(out_dir.join("Staff_5G/qr.txt")).unwrap().contains("██"));
+        assert!(matches!(run(&test_args(), &Branding::default(), &networks, &out_dir, &Outputs{ templates: &[String::from("qr.png")], sheet: None }, None, 2), Err(BatchError::Collision(_))));
+        assert!(matches!(run(&test_args(), &Branding::default(), &networks, &out_dir, &Outputs{ templates: &[String::from("{ssid}/profile.xml")], sheet: None }, None, 2), Err(BatchError::UnsupportedFormat(_))));
+        let mut enterprise:Args = test_args();
+        enterprise.enterprise = crate::eap::Enterprise{ eap: Some(crate::eap::EapMethod::Peap), identity: Some(String::from("alice")), ..crate::eap::Enterprise::default() };
+        assert!(matches!(run(&enterprise, &Branding::default(), &networks, &out_dir, &Outputs{ templates: &outputs, sheet: None }, None, 2), Err(BatchError::EnterpriseRecord(_))));
+        let written = run(&test_args(), &Branding::default(), &networks, &out_dir, &Outputs{ templates: &[String::from("tents.pdf"), String::from("{index}.svg")], sheet: None }, None, 0).unwrap();
+        assert_eq!(written, vec![out_dir.join("tents.pdf"), out_dir.join("1.svg"), out_dir.join("2.svg")]);
+        let tents = std::fs::read(out_dir.join("tents.pdf")).unwrap();
+        assert_eq!(String::from_utf8_lossy(&tents).matches("/Type /Page ").count(), 2 * layout::table_tent(&test_args(), &[vec![true]], &Branding::default(), layout::PaperSize::A4).len());
+        // A sheet of a single card gets a page for each network.
+        let sheet = layout::Sheet{ paper: layout::PaperSize::Letter, columns: 1, rows: 1 };
+        let written = run(&test_args(), &Branding::default(), &networks, &out_dir, &Outputs{ templates: &[String::from("cards.pdf"), String::from("cards.html")], sheet: Some(sheet) }, None, 0).unwrap();
+        assert_eq!(written, vec![out_dir.join("cards.pdf"), out_dir.join("cards.html")]);
+        assert_eq!(String::from_utf8_lossy(&std::fs::read(out_dir.join("cards.pdf")).unwrap()).matches("/Type /Page ").count(), 2);
+        let cards:String = std::fs::read_to_string(out_dir.join("cards.html")).unwrap();
+        assert!(cards.matches("<section class=\"sheet\">").count() == 2 && cards.ends_with("</html>\n"));
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+    #[test]
+    fn test_output_options() {
+        let out_dir:PathBuf = std::env::temp_dir().join(format!("wifiqr-batch-options-{}", std::process::id()));
+        let networks = read_networks(CSV.as_bytes()).unwrap();
+        let mut args:Args = test_args();
+        (args.output.border, args.output.module_size) = (0, Some(8));
+        let outputs = vec![String::from("{index}.png"), String::from("{index}.pbm")];
+        run(&args, &Branding::default(), &networks, &out_dir, &Outputs{ templates: &outputs, sheet: None }, None, 2).unwrap();
+        // The 25 modules of version 2, without a border.
+        assert_eq!(image::image_dimensions(out_dir.join("1.png")).unwrap(), (200, 200));
+        assert!(std::fs::read(out_dir.join("1.pbm")).unwrap().starts_with(b"P4\n200 200\n"));
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+    #[test]
+    fn test_prepare_checks_credentials() {
+        let networks = read_networks("ssid,password,security\nCafe,abc,wpa2\nOld,01:23:45:67:89,wep\n".as_bytes()).unwrap();
+        match prepare(&test_args(), &networks[0], 2, 1, None) {
+            Err(BatchError::InvalidNetwork(2, message)) => assert!(message.starts_with("Devices won't accept Cafe")),
+            _ => panic!("the short passphrase was encoded")
+        }
+        let forced:Args = Args{ force: true, ..test_args() };
+        assert_eq!(prepare(&forced, &networks[0], 2, 1, None).unwrap().args.psw.as_deref(), Some("abc"));
+        assert_eq!(prepare(&test_args(), &networks[1], 3, 2, None).unwrap().args.psw.as_deref(), Some("0123456789"));
+    }
+    #[test]
+    fn test_stream() {
+        let out_dir:PathBuf = std::env::temp_dir().join(format!("wifiqr-stream-{}", std::process::id()));
+        let records = "{\"ssid\": \"Guest\"}\n\n{\"ssid\": \"Staff\", \"security\": \"wpa3\"}\nnot json\n{\"ssid\": \"Guest\"}\n";
+        let mut results:Vec<u8> = Vec::new();
+        let written = stream(&test_args(), &Branding::default(), records.as_bytes(), &mut results, &out_dir, &Outputs{ templates: &[String::from("{ssid}.txt")], sheet: None }, None).unwrap();
+        assert_eq!(written, 1);
+        let results:Vec<serde_json::Value> = String::from_utf8(results).unwrap().lines().map(|x| serde_json::from_str(x).unwrap()).collect();
+        assert_eq!(results[0], serde_json::json!({"line": 1, "ssid": "Guest", "files": [out_dir.join("Guest.txt")]}));
+        assert_eq!(results[1], serde_json::json!({"line": 3, "error": "a security standard was given without a password"}));
+        assert_eq!(results[2]["line"], 4);
+        assert!(results[3]["error"].as_str().unwrap().starts_with("several networks would be written to"));
+        assert!(out_dir.join("Guest.txt").exists());
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+}
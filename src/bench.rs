@@ -0,0 +1,149 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Measuring how fast payloads are built, QR-Codes generated and each output rendered, over a batch of synthetic
+//! networks, with the allocations each network costs.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::batch::{self, Network};
+use crate::branding::Branding;
+use crate::{assemble_qr_string, get_bool_matrix_as_string, layout, ndef, pdf, raster, svg, wallpaper, Args};
+
+/// The system allocator, counting allocations so the benchmark can report them.
+pub struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+// SAFETY: Every call is forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// The allocations and allocated bytes so far.
+fn allocations() -> (u64, u64) {
+    (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+/// The result of running a stage once per network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement{
+    pub stage: &'static str,
+    pub networks: usize,
+    pub elapsed: Duration,
+    pub allocations: u64,
+    pub allocated_bytes: u64
+}
+impl Measurement {
+    pub fn per_second(&self) -> f64 {
+        self.networks as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Networks with varied SSIDs, passwords and security standards, like a fleet would have.
+pub fn synthetic_networks(args: &Args, count: usize) -> Vec<Args> {
+    let security:[Option<&str>; 4] = [Some("wpa2"), Some("wpa3"), None, Some("wpa")];
+    (0..count).map(|index| {
+        let sec:Option<&str> = security[index % security.len()];
+        let network = Network{
+            ssid: format!("Site {} Floor {}", index / 8, index % 8),
+            password: sec.map(|_| format!("correct-horse-{:08x}", (index as u32).wrapping_mul(2654435761))),
+            security: sec.map(String::from),
+            hidden: index % 5 == 0
+        };
+        batch::network_args(args, &network, index).unwrap()
+    }).collect()
+}
+
+/// Renders a network, with its matrix, into an output and returns its size.
+type Renderer<'a> = &'a dyn Fn(&Args, &[Vec<bool>]) -> usize;
+
+fn measure<T>(stage: &'static str, items: &[T], mut run: impl FnMut(&T)) -> Measurement {
+    let (allocations_before, bytes_before) = allocations();
+    let start:Instant = Instant::now();
+    for item in items {
+        run(item);
+    }
+    let elapsed:Duration = start.elapsed();
+    let (allocations_after, bytes_after) = allocations();
+    Measurement{ stage, networks: items.len(), elapsed, allocations: allocations_after - allocations_before, allocated_bytes: bytes_after - bytes_before }
+}
+
+/// Runs every stage over the networks. The renderers get the matrix, so their measurements don't include the
+/// generation of the QR-Code.
+pub fn run(networks: &[Args], branding: &Branding) -> Vec<Measurement> {
+    let matrix = |network: &Args| qrcode_generator::to_matrix(assemble_qr_string(network), network.ecc.into()).unwrap();
+    let mut measurements:Vec<Measurement> = vec![
+        measure("payload", networks, |x| {black_box(assemble_qr_string(x));}),
+        measure("qr-code", networks, |x| {black_box(matrix(x));})
+    ];
+    let matrices:Vec<(&Args, Vec<Vec<bool>>)> = networks.iter().map(|x| (x, matrix(x))).collect();
+    let renderers:[(&'static str, Renderer); 6] = [
+        ("text", &|_, matrix| get_bool_matrix_as_string(matrix.to_vec()).len()),
+        ("svg", &|network, matrix| svg::qr_code(matrix, &network.ssid).len()),
+        ("png", &|network, _| qrcode_generator::to_png_to_vec(assemble_qr_string(network), network.ecc.into(), 512).unwrap().len()),
+        ("tiff", &|_, matrix| raster::encode_tiff(&wallpaper::qr_image(matrix, 512), false).unwrap().len()),
+        ("ndef", &|network, _| ndef::wifi_message(network).len()),
+        ("pdf", &|network, matrix| pdf::write_document(&layout::table_tent(network, matrix, branding, layout::PaperSize::A4)).len())
+    ];
+    for (stage, render) in renderers {
+        measurements.push(measure(stage, &matrices, |(network, matrix)| {black_box(render(network, matrix));}));
+    }
+    measurements
+}
+
+/// The measurements as a table, with the time and allocations per network.
+pub fn report(measurements: &[Measurement]) -> String {
+    let mut table:String = String::from("Stage      Networks/s   Time/network   Allocations/network   Bytes/network\n");
+    for measurement in measurements {
+        let networks:f64 = measurement.networks.max(1) as f64;
+        table += &format!("{:<9}  {:>10.0}   {:>9.1} µs   {:>19.1}   {:>13.0}\n", measurement.stage, measurement.per_second(),
+            measurement.elapsed.as_secs_f64() * 1e6 / networks, measurement.allocations as f64 / networks, measurement.allocated_bytes as f64 / networks);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_run() {
+        let args = crate::tests::network("", None, None);
+        let networks = synthetic_networks(&args, 4);
+        assert_eq!(networks[1].ssid, "Site 0 Floor 1");
+        assert!(networks[2].psw.is_none() && networks[0].hidden);
+        let measurements = run(&networks, &Branding::default());
+        assert_eq!(measurements.iter().map(|x| x.stage).collect::<Vec<_>>(), vec!["payload", "qr-code", "text", "svg", "png", "tiff", "ndef", "pdf"]);
+        assert!(measurements.iter().all(|x| x.networks == 4));
+        assert_eq!(report(&measurements).lines().count(), 9);
+    }
+}
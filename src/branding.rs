@@ -0,0 +1,354 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Organization branding (logo, palette, fonts and footer), read from a `branding.toml` and applied to every
+//! rendered output.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use serde::Deserialize;
+
+use crate::color::{self, Color, Deficiency};
+use crate::font::{self, EmbeddedFont, FontError};
+use crate::pdf::Font;
+
+/// Name of the file picked up from the working directory if no branding is passed explicitly.
+pub const DEFAULT_FILE_NAME: &str = "branding.toml";
+
+/// Longest side logos are scaled down to before they are embedded.
+const MAX_LOGO_SIZE: u32 = 512;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Palette{
+    /// Body text.
+    pub text: Color,
+    /// Headings and labels.
+    pub accent: Color,
+    pub background: Color
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Palette{ text: Color::BLACK, accent: Color::BLACK, background: Color::WHITE }
+    }
+}
+
+/// Palettes whose text and accent colors keep the recommended contrast to the background with protanopia,
+/// deuteranopia and tritanopia too, so accessible outputs don't take picking colors by hand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum PalettePreset{
+    /// Black text on white, the default.
+    HighContrast,
+    /// The blue of the Okabe-Ito palette, on white.
+    OkabeIto,
+    /// The orange of the Okabe-Ito palette with white text, on black.
+    OkabeItoDark,
+    /// The wine and indigo of Paul Tol's muted palette, on white.
+    Tol
+}
+impl PalettePreset {
+    pub fn palette(self) -> Palette {
+        let (text, accent, background) = match self {
+            PalettePreset::HighContrast => (Color::BLACK, Color::BLACK, Color::WHITE),
+            PalettePreset::OkabeIto => (Color::BLACK, Color::new(0x00, 0x72, 0xb2), Color::WHITE),
+            PalettePreset::OkabeItoDark => (Color::WHITE, Color::new(0xe6, 0x9f, 0x00), Color::BLACK),
+            PalettePreset::Tol => (Color::new(0x33, 0x22, 0x88), Color::new(0x88, 0x22, 0x55), Color::WHITE)
+        };
+        Palette{ text, accent, background }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fonts{
+    pub heading: Font,
+    pub body: Font
+}
+impl Default for Fonts {
+    fn default() -> Self {
+        Fonts{ heading: Font::HelveticaBold, body: Font::Helvetica }
+    }
+}
+
+/// Horizontal alignment of caption lines.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Align{
+    /// Keep the alignment of the layout, but align right-to-left lines to the right where they'd be on the left.
+    #[default]
+    Auto,
+    Left,
+    Center,
+    Right
+}
+impl Align {
+    /// Alignment of a line of `text` in a place of the layout that is aligned `natural` by default.
+    pub fn resolve(self, natural: Align, text: &str) -> Align {
+        match self {
+            Align::Auto if natural == Align::Left && font::is_rtl(text) => Align::Right,
+            Align::Auto => natural,
+            align => align
+        }
+    }
+    /// Start of a line `width` wide, aligned within `span` starting at `x`.
+    pub fn position(self, x: f32, span: f32, width: f32) -> f32 {
+        match self {
+            Align::Auto | Align::Left => x,
+            Align::Center => x + (span - width) / 2.0,
+            Align::Right => x + span - width
+        }
+    }
+}
+
+/// Designs of the frame wrapping the QR-Code in vector outputs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FrameStyle{
+    /// A solid border with the call to action in a band below the code.
+    ScanMe,
+    /// The code on the screen of a phone, with the call to action below it.
+    Phone,
+    /// A thin border with the call to action above it, and an arrow pointing at the code.
+    Arrow
+}
+impl FrameStyle {
+    pub fn default_text(&self) -> &'static str {
+        match self {
+            FrameStyle::ScanMe => "SCAN ME",
+            FrameStyle::Phone => "Scan to join",
+            FrameStyle::Arrow => "Scan me"
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Frame{
+    pub style: FrameStyle,
+    /// Call to action, defaulting to the one of the style.
+    pub text: Option<String>,
+    /// Color of the frame, defaulting to the accent color.
+    pub color: Option<Color>,
+    /// Color of the call to action where it is set on the frame.
+    #[serde(default = "Frame::default_text_color")]
+    pub text_color: Color
+}
+impl Frame {
+    pub fn new(style: FrameStyle) -> Self {
+        Frame{ style, text: None, color: None, text_color: Frame::default_text_color() }
+    }
+    fn default_text_color() -> Color {
+        Color::WHITE
+    }
+    pub fn text(&self) -> &str {
+        self.text.as_deref().unwrap_or(self.style.default_text())
+    }
+}
+
+/// The fonts as written in the file: names of standard fonts, or paths of TrueType/OpenType fonts relative to it.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FontNames{
+    heading: Option<String>,
+    body: Option<String>
+}
+
+/// Resolves a font name from the branding file or the command line: a standard font, the path of a font file or
+/// the family name of an installed font.
+pub fn resolve_font(name: &str, directory: &Path) -> Result<Font, FontError> {
+    if let Some(font) = Font::from_name(name) {
+        return Ok(font);
+    }
+    let path:PathBuf = directory.join(name);
+    if path.exists() {
+        Ok(Font::Embedded(EmbeddedFont::load(&path)?))
+    }else{
+        Ok(Font::Embedded(EmbeddedFont::find(name)?))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Branding{
+    /// Path of the logo, relative to the branding file.
+    pub logo: Option<PathBuf>,
+    pub palette: Palette,
+    #[serde(rename = "fonts")]
+    font_names: FontNames,
+    #[serde(skip)]
+    pub fonts: Fonts,
+    /// Line printed at the bottom of the layouts, e.g. the name of the venue.
+    pub footer: Option<String>,
+    pub align: Align,
+    pub frame: Option<Frame>,
+    #[serde(skip)]
+    pub logo_image: Option<RgbaImage>
+}
+
+#[derive(Debug)]
+pub enum BrandingError{
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Logo(PathBuf, image::ImageError),
+    Font(FontError),
+    Contrast(PathBuf, &'static str, f32)
+}
+impl fmt::Display for BrandingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BrandingError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            BrandingError::Parse(path, err) => write!(f, "invalid branding in {}: {}", path.display(), err),
+            BrandingError::Logo(path, err) => write!(f, "failed to load the logo {}: {}", path.display(), err),
+            BrandingError::Font(err) => write!(f, "{}", err),
+            BrandingError::Contrast(path, name, contrast) => write!(f, "the {} color in {} has a contrast of only {:.1}:1 to what it's drawn onto, at least {}:1 is needed",
+                name, path.display(), contrast, color::MIN_CONTRAST)
+        }
+    }
+}
+
+impl From<FontError> for BrandingError {
+    fn from(err: FontError) -> Self {
+        BrandingError::Font(err)
+    }
+}
+
+impl Branding {
+    /// Color of the frame around the QR-Code.
+    pub fn frame_color(&self, frame: &Frame) -> Color {
+        frame.color.unwrap_or(self.palette.accent)
+    }
+    /// The colors of text and what they're drawn onto.
+    fn color_pairs(&self) -> Vec<(&'static str, Color, Color)> {
+        let mut pairs:Vec<(&'static str, Color, Color)> = vec![("text", self.palette.text, self.palette.background), ("accent", self.palette.accent, self.palette.background)];
+        match &self.frame {
+            Some(frame) if frame.style == FrameStyle::Arrow => pairs.push(("frame", self.frame_color(frame), self.palette.background)),
+            Some(frame) => pairs.push(("frame text", frame.text_color, self.frame_color(frame))),
+            None => ()
+        }
+        pairs
+    }
+    /// The colors of text whose contrast to what it's drawn onto is below [`color::RECOMMENDED_CONTRAST`].
+    pub fn low_contrast(&self) -> Vec<(&'static str, f32)> {
+        self.color_pairs().into_iter()
+            .map(|(name, x, background)| (name, x.contrast(background)))
+            .filter(|(_, contrast)| *contrast < color::RECOMMENDED_CONTRAST)
+            .collect()
+    }
+    /// The colors of text that have enough contrast, but fall below [`color::RECOMMENDED_CONTRAST`] with a
+    /// color-vision deficiency, with the lowest contrast among the deficiencies.
+    pub fn low_contrast_with_deficiency(&self) -> Vec<(&'static str, Deficiency, f32)> {
+        self.color_pairs().into_iter()
+            .filter(|(_, x, background)| x.contrast(*background) >= color::RECOMMENDED_CONTRAST)
+            .filter_map(|(name, x, background)| Deficiency::ALL.into_iter()
+                .map(|deficiency| (name, deficiency, x.simulate(deficiency).contrast(background.simulate(deficiency))))
+                .min_by(|a, b| a.2.total_cmp(&b.2)))
+            .filter(|(_, _, contrast)| *contrast < color::RECOMMENDED_CONTRAST)
+            .collect()
+    }
+    /// Parses the branding, resolving the logo and fonts relative to `directory`.
+    pub fn parse(source: &str, directory: &Path, path: &Path) -> Result<Self, BrandingError> {
+        let mut branding:Branding = toml::from_str(source).map_err(|err| BrandingError::Parse(path.to_path_buf(), err))?;
+        if let Some((name, contrast)) = branding.low_contrast().into_iter().find(|(_, x)| *x < color::MIN_CONTRAST) {
+            return Err(BrandingError::Contrast(path.to_path_buf(), name, contrast));
+        }
+        if let Some(logo) = &branding.logo {
+            let logo:PathBuf = directory.join(logo);
+            let image = image::open(&logo).map_err(|err| BrandingError::Logo(logo.clone(), err))?;
+            let image = if image.width().max(image.height()) > MAX_LOGO_SIZE {image.thumbnail(MAX_LOGO_SIZE, MAX_LOGO_SIZE)}else{image};
+            branding.logo_image = Some(image.to_rgba8());
+            branding.logo = Some(logo);
+        }
+        if let Some(heading) = &branding.font_names.heading {
+            branding.fonts.heading = resolve_font(heading, directory)?;
+        }
+        if let Some(body) = &branding.font_names.body {
+            branding.fonts.body = resolve_font(body, directory)?;
+        }
+        Ok(branding)
+    }
+    /// Loads the branding from `path`, or from `branding.toml` in the working directory if it exists.
+    /// Without either the default look is used.
+    pub fn load(path: Option<&Path>) -> Result<Self, BrandingError> {
+        let path:PathBuf = match path {
+            Some(path) => path.to_path_buf(),
+            None if Path::new(DEFAULT_FILE_NAME).is_file() => PathBuf::from(DEFAULT_FILE_NAME),
+            None => return Ok(Branding::default())
+        };
+        let source:String = std::fs::read_to_string(&path).map_err(|err| BrandingError::Io(path.clone(), err))?;
+        Branding::parse(&source, path.parent().unwrap_or(Path::new("")), &path)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_parse_branding() {
+        let source = "footer = \"Hotel Example\"\n[palette]\naccent = \"#1e3c8c\"\n[fonts]\nbody = \"helvetica-bold\"\n";
+        let branding = Branding::parse(source, Path::new(""), Path::new("branding.toml")).unwrap();
+        assert_eq!(branding.footer.as_deref(), Some("Hotel Example"));
+        assert_eq!(branding.palette.accent, Color::new(0x1e, 0x3c, 0x8c));
+        assert_eq!(branding.palette.text, Color::BLACK);
+        assert_eq!(branding.fonts.body, Font::HelveticaBold);
+        assert_eq!(branding.fonts.heading, Font::HelveticaBold);
+        assert_eq!(branding.align, Align::Auto);
+        assert_eq!(branding.frame, None);
+    }
+    #[test]
+    fn test_parse_frame() {
+        let branding = Branding::parse("[frame]\nstyle = \"phone\"\ncolor = \"#1e3c8c\"\n", Path::new(""), Path::new("b.toml")).unwrap();
+        let frame = branding.frame.as_ref().unwrap();
+        assert_eq!((frame.style, frame.text(), frame.text_color), (FrameStyle::Phone, "Scan to join", Color::WHITE));
+        assert_eq!(branding.frame_color(frame), Color::new(0x1e, 0x3c, 0x8c));
+        // White text on the default black accent is fine, but not on a yellow frame.
+        assert!(Branding::parse("[frame]\nstyle = \"scan-me\"\n", Path::new(""), Path::new("b.toml")).is_ok());
+        assert!(matches!(Branding::parse("[frame]\nstyle = \"scan-me\"\ncolor = \"#ffff00\"\n", Path::new(""), Path::new("b.toml")), Err(BrandingError::Contrast(_, "frame text", _))));
+    }
+    #[test]
+    fn test_align() {
+        assert_eq!(Align::Auto.resolve(Align::Left, "שלום Wi-Fi"), Align::Right);
+        assert_eq!(Align::Auto.resolve(Align::Left, "Wi-Fi שלום"), Align::Left);
+        assert_eq!(Align::Auto.resolve(Align::Center, "שלום"), Align::Center);
+        assert_eq!(Align::Left.resolve(Align::Center, "שלום"), Align::Left);
+        assert_eq!(Align::Right.position(10.0, 100.0, 30.0), 80.0);
+        assert_eq!(Align::Center.position(10.0, 100.0, 30.0), 45.0);
+    }
+    #[test]
+    fn test_parse_branding_errors() {
+        assert!(matches!(Branding::parse("[palette]\ntext = \"red\"\n", Path::new(""), Path::new("b.toml")), Err(BrandingError::Parse(..))));
+        assert!(matches!(Branding::parse("colour = 1\n", Path::new(""), Path::new("b.toml")), Err(BrandingError::Parse(..))));
+        assert!(matches!(Branding::parse("logo = \"missing.png\"\n", Path::new("/nonexistent"), Path::new("b.toml")), Err(BrandingError::Logo(..))));
+        assert!(matches!(Branding::parse("[fonts]\nbody = \"missing.ttf\"\n", Path::new("/nonexistent"), Path::new("b.toml")), Err(BrandingError::Font(..))));
+        assert!(matches!(Branding::parse("[palette]\naccent = \"#ffff00\"\n", Path::new(""), Path::new("b.toml")), Err(BrandingError::Contrast(_, "accent", _))));
+    }
+    #[test]
+    fn test_palette_presets() {
+        use clap::ArgEnum;
+        for preset in PalettePreset::value_variants() {
+            let branding = Branding{ palette: preset.palette(), frame: Some(Frame::new(FrameStyle::Arrow)), ..Branding::default() };
+            assert_eq!((branding.low_contrast(), branding.low_contrast_with_deficiency()), (vec![], vec![]), "{:?}", preset);
+        }
+        // A pink that reads fine on white loses contrast without green cones.
+        let branding = Branding{ palette: Palette{ accent: Color::new(0xd8, 0x1b, 0x60), ..Palette::default() }, ..Branding::default() };
+        assert_eq!(branding.low_contrast_with_deficiency().iter().map(|x| (x.0, x.1)).collect::<Vec<_>>(), vec![("accent", Deficiency::Deuteranopia)]);
+    }
+    #[test]
+    fn test_low_contrast_warning() {
+        let branding = Branding::parse("[palette]\ntext = \"#ff0000\"\n", Path::new(""), Path::new("b.toml")).unwrap();
+        assert_eq!(branding.low_contrast().iter().map(|x| x.0).collect::<Vec<&str>>(), vec!["text"]);
+    }
+}
@@ -0,0 +1,130 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Checking that the payload fits into a QR-Code before anything is generated, explaining what to change if it
+//! doesn't. Splitting the payload with structured append isn't among the suggestions, as phones only join networks
+//! described by a single code.
+
+use std::fmt;
+
+use crate::{Args, ECCLevel};
+
+/// Bytes a version 40 code holds in byte mode, which `WIFI:` payloads always use as `;` isn't alphanumeric.
+pub const fn byte_capacity(ecc: ECCLevel) -> usize {
+    match ecc {
+        ECCLevel::Low => 2953,
+        ECCLevel::Medium => 2331,
+        ECCLevel::Quartile => 1663,
+        ECCLevel::High => 1273
+    }
+}
+
+/// A payload too long for the ECC-Level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overflow{
+    pub length: usize,
+    pub ecc: ECCLevel,
+    pub suggestions: Vec<String>
+}
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the payload has {} bytes, but a QR-Code with ECC-Level {:?} holds at most {}.", self.length, self.ecc, byte_capacity(self.ecc))?;
+        for suggestion in &self.suggestions {
+            write!(f, "\n  - {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// The most robust ECC-Level below `ecc` that `length` bytes fit at.
+fn lower_ecc(ecc: ECCLevel, length: usize) -> Option<ECCLevel> {
+    [ECCLevel::Quartile, ECCLevel::Medium, ECCLevel::Low].into_iter().find(|&x| x < ecc && length <= byte_capacity(x))
+}
+
+/// Checks that `payload`, the one encoded for `args`, fits at the ECC-Level of `args`.
+pub fn check(args: &Args, payload: &str) -> Result<(), Overflow> {
+    let length:usize = payload.len();
+    if length <= byte_capacity(args.ecc) {
+        return Ok(());
+    }
+    let mut suggestions:Vec<String> = Vec::new();
+    if let Some(ecc) = lower_ecc(args.ecc, length) {
+        suggestions.push(format!("Lower the ECC-Level with --ecc {}, which holds {} bytes.", format!("{:?}", ecc).to_lowercase(), byte_capacity(ecc)));
+    }else{
+        let excess:usize = length - byte_capacity(args.ecc);
+        let password:usize = args.psw.as_ref().map_or(0, String::len);
+        suggestions.push(format!("Shorten the SSID ({} bytes) or the password ({} bytes) by {} bytes in total.", args.ssid.len(), password, excess));
+        if args.ssid.len() > 32 {
+            suggestions.push(String::from("SSIDs have at most 32 bytes, so this one won't match any network anyway."));
+        }
+        if password > 63 {
+            suggestions.push(String::from("WPA passphrases have at most 63 characters, so this password won't be accepted anyway."));
+        }
+    }
+    Err(Overflow{ length, ecc: args.ecc, suggestions })
+}
+
+/// Like [`check`], but with `--downgrade-ecc` lowers the ECC-Level of `args` until the payload fits.
+pub fn fit(args: &mut Args, payload: &str) -> Result<(), Overflow> {
+    let overflow:Overflow = match check(args, payload) {
+        Ok(()) => return Ok(()),
+        Err(overflow) => overflow
+    };
+    match lower_ecc(args.ecc, payload.len()).filter(|_| args.downgrade_ecc) {
+        Some(ecc) => {
+            crate::diagnostics::warn("ecc-downgraded", format!("the payload of {} doesn't fit at ECC-Level {:?}, using {:?}", args.ssid, args.ecc, ecc));
+            args.ecc = ecc;
+            Ok(())
+        },
+        None => Err(overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    fn test_args(ssid: String, ecc: ECCLevel) -> Args {
+        Args{ ecc, ..crate::tests::network(&ssid, Some("password"), None) }
+    }
+    #[test]
+    fn test_byte_capacity() {
+        for ecc in [ECCLevel::Low, ECCLevel::Medium, ECCLevel::Quartile, ECCLevel::High] {
+            assert!(qrcode_generator::to_matrix("a;".repeat(byte_capacity(ecc) / 2), ecc.into()).is_ok());
+            assert!(qrcode_generator::to_matrix("a;".repeat(byte_capacity(ecc) / 2 + 1), ecc.into()).is_err());
+        }
+    }
+    #[test]
+    fn test_check() {
+        let args = test_args(String::from("Home"), ECCLevel::High);
+        assert_eq!(check(&args, "WIFI:S:Home;;"), Ok(()));
+        let overflow = check(&args, &"x".repeat(2000)).unwrap_err();
+        assert_eq!(overflow.suggestions, vec!["Lower the ECC-Level with --ecc medium, which holds 2331 bytes."]);
+        let args = test_args("x".repeat(3000), ECCLevel::Medium);
+        let overflow = check(&args, &"x".repeat(3020)).unwrap_err();
+        assert_eq!(overflow.to_string(), "the payload has 3020 bytes, but a QR-Code with ECC-Level Medium holds at most 2331.\n  \
+            - Shorten the SSID (3000 bytes) or the password (8 bytes) by 689 bytes in total.\n  \
+            - SSIDs have at most 32 bytes, so this one won't match any network anyway.");
+    }
+    #[test]
+    fn test_fit() {
+        let mut args = test_args(String::from("Home"), ECCLevel::High);
+        assert!(fit(&mut args, &"x".repeat(2000)).is_err());
+        args.downgrade_ecc = true;
+        assert_eq!(fit(&mut args, &"x".repeat(2000)), Ok(()));
+        assert_eq!(args.ecc, ECCLevel::Medium);
+        assert!(fit(&mut args, &"x".repeat(3000)).is_err());
+    }
+}
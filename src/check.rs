@@ -0,0 +1,212 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Validating the networks of batch and profile files without generating anything, for `--check`, so repositories of
+//! network configurations can check them before changes are merged.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::batch::{self, BatchError, Network};
+use crate::inspect::{Finding, Severity};
+use crate::{assemble_qr_string, capacity, Args, Security};
+
+/// An entry of a file with what is wrong with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry{
+    /// Where the entry is, e.g. `row 3` or `line 2`, or `file` for the file as a whole.
+    pub location: String,
+    pub ssid: Option<String>,
+    pub findings: Vec<Finding>
+}
+impl Entry {
+    /// The file couldn't be read at all, e.g. as it isn't valid XML.
+    pub fn unreadable(err: impl fmt::Display) -> Self {
+        Entry{ location: String::from("file"), ssid: None, findings: vec![error(err.to_string())] }
+    }
+}
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location:String = match &self.ssid {
+            Some(ssid) => format!("{} ({})", self.location, ssid),
+            None => self.location.clone()
+        };
+        for finding in &self.findings {
+            writeln!(f, "{}: {}", location, finding)?;
+        }
+        Ok(())
+    }
+}
+
+fn error(message: String) -> Finding {
+    Finding{ severity: Severity::Error, message }
+}
+fn warning(message: String) -> Finding {
+    Finding{ severity: Severity::Warning, message }
+}
+
+/// What is wrong with a network, given the global options. `row` is only used to build its arguments.
+pub fn network(args: &Args, network: &Network, row: usize) -> Vec<Finding> {
+    let mut args:Args = match batch::network_args(args, network, row) {
+        Ok(args) => args,
+        Err(BatchError::InvalidNetwork(_, message)) => return vec![error(message)],
+        Err(err) => return vec![error(err.to_string())]
+    };
+    let mut findings:Vec<Finding> = credentials(&args);
+    let payload:String = assemble_qr_string(&args);
+    if let Err(overflow) = capacity::fit(&mut args, &payload) {
+        findings.push(error(overflow.to_string()));
+    }
+    findings
+}
+
+/// What devices won't accept of the SSID and password of a network: SSIDs over 32 bytes, WPA passphrases outside
+/// 8 to 63 characters and WEP keys of another length than those of 64 and 128 bit keys. Also checked before
+/// encoding a network given on the command line.
+pub fn credentials(args: &Args) -> Vec<Finding> {
+    let mut findings:Vec<Finding> = Vec::new();
+    let ssid:usize = crate::hex_ssid::bytes(args).len();
+    if ssid > 32 {
+        findings.push(error(format!("SSIDs have at most 32 bytes, this one has {}", ssid)));
+    }
+    let psw:&str = args.psw.as_deref().unwrap_or_default();
+    let length:usize = psw.chars().count();
+    let hexadecimal:bool = psw.chars().all(|x| x.is_ascii_hexdigit());
+    // 64 hex digits are the pre-shared key itself.
+    let valid_passphrase:bool = (8..=63).contains(&length) || (length == 64 && hexadecimal);
+    let valid_key:bool = [5, 13].contains(&length) || ([10, 26].contains(&length) && hexadecimal);
+    match crate::resolve_security(args) {
+        Some(Security::Wpa | Security::Wpa2 | Security::Wpa3 | Security::Wpa2Wpa3) if !valid_passphrase =>
+            findings.push(error(format!("WPA passphrases have 8 to 63 characters, this one has {}", length))),
+        Some(Security::Wep) if !valid_key =>
+            findings.push(error(format!("WEP keys have 5 or 13 characters or 10 or 26 hex digits, this one has {}", length))),
+        _ => {}
+    }
+    findings
+}
+
+/// Checks networks read from a file as a whole, such as a profile, warning about those that were skipped.
+pub fn networks(args: &Args, networks: &[Network], skipped: &[String]) -> Vec<Entry> {
+    let mut entries:Vec<Entry> = skipped.iter().map(|x| Entry{ location: String::from("file"), ssid: None, findings: vec![warning(format!("skipping {}", x))] }).collect();
+    entries.extend(networks.iter().enumerate().map(|(index, x)| Entry{ location: format!("network {}", index + 1), ssid: Some(x.ssid.clone()), findings: network(args, x, index + 1) }));
+    duplicates(entries)
+}
+
+/// Checks every row of a CSV file with a header row, as read by `batch`.
+pub fn csv(args: &Args, csv: impl std::io::Read) -> Vec<Entry> {
+    let mut entries:Vec<Entry> = Vec::new();
+    for (index, network) in csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(csv).deserialize().enumerate() {
+        let location:String = format!("row {}", index + 2);
+        entries.push(match network {
+            Ok(network) => entry(args, location, network, index + 2),
+            Err(err) => Entry{ location, ssid: None, findings: vec![error(err.to_string())] }
+        });
+    }
+    duplicates(entries)
+}
+
+/// Checks every line of newline-delimited JSON records, skipping blank lines.
+pub fn jsonl(args: &Args, records: &str) -> Vec<Entry> {
+    let mut entries:Vec<Entry> = Vec::new();
+    for (index, record) in records.lines().enumerate().filter(|(_, x)| !x.trim().is_empty()) {
+        let location:String = format!("line {}", index + 1);
+        entries.push(match serde_json::from_str::<Network>(record) {
+            Ok(network) => entry(args, location, network, index + 1),
+            Err(err) => Entry{ location, ssid: None, findings: vec![error(err.to_string())] }
+        });
+    }
+    duplicates(entries)
+}
+
+/// Checks every object of a JSON array.
+pub fn json(args: &Args, json: &str) -> Vec<Entry> {
+    let records:Vec<serde_json::Value> = match serde_json::from_str(json) {
+        Ok(records) => records,
+        Err(err) => return vec![Entry::unreadable(err)]
+    };
+    let entries:Vec<Entry> = records.into_iter().enumerate().map(|(index, record)| {
+        let location:String = format!("network {}", index + 1);
+        match serde_json::from_value::<Network>(record) {
+            Ok(network) => entry(args, location, network, index + 1),
+            Err(err) => Entry{ location, ssid: None, findings: vec![error(err.to_string())] }
+        }
+    }).collect();
+    duplicates(entries)
+}
+
+fn entry(args: &Args, location: String, network: Network, row: usize) -> Entry {
+    let findings:Vec<Finding> = if network.ssid.is_empty() {
+        vec![error(String::from("the SSID is empty"))]
+    }else{
+        self::network(args, &network, row)
+    };
+    Entry{ location, ssid: Some(network.ssid).filter(|x| !x.is_empty()), findings }
+}
+
+/// Warns about SSIDs given more than once, whose outputs would overwrite each other.
+fn duplicates(mut entries: Vec<Entry>) -> Vec<Entry> {
+    let mut seen:HashMap<String, String> = HashMap::new();
+    for entry in &mut entries {
+        let Some(ssid) = &entry.ssid else { continue; };
+        match seen.get(ssid) {
+            Some(first) => entry.findings.push(warning(format!("the SSID is also given in {}", first))),
+            None => {seen.insert(ssid.clone(), entry.location.clone());}
+        }
+    }
+    entries
+}
+
+/// The findings of the entries, followed by a summary, and whether any of them is an error.
+pub fn report(entries: &[Entry]) -> (String, bool) {
+    let mut report:String = entries.iter().map(|x| x.to_string()).collect();
+    let count = |severity: Severity| entries.iter().flat_map(|x| &x.findings).filter(|x| x.severity == severity).count();
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    let networks:usize = entries.iter().filter(|x| x.location != "file").count();
+    let plural = |count: usize, noun: &str| format!("{} {}{}", count, noun, if count == 1 {""}else{"s"});
+    report += &format!("Checked {}: {}, {}.\n", plural(networks, "network"), plural(errors, "error"), plural(warnings, "warning"));
+    (report, errors > 0)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::ECCLevel;
+    fn test_args() -> Args {
+        Args{ ecc: ECCLevel::High, ..crate::tests::network("", None, None) }
+    }
+    #[test]
+    fn test_csv() {
+        let long:String = "x".repeat(1300);
+        let rows = format!("ssid,password,security,hidden\nGuest,,,false\nStaff,short,wpa2,false\nLab,secret,wep,false\n,,,false\nGuest,,,true\nLong,{},wpa3,false\n", long);
+        let entries = csv(&test_args(), rows.as_bytes());
+        assert!(entries[0].findings.is_empty());
+        assert_eq!(entries[1].to_string(), "row 3 (Staff): error: WPA passphrases have 8 to 63 characters, this one has 5\n");
+        assert_eq!(entries[2].findings, vec![error(String::from("WEP keys have 5 or 13 characters or 10 or 26 hex digits, this one has 6"))]);
+        assert_eq!(entries[3].to_string(), "row 5: error: the SSID is empty\n");
+        assert_eq!(entries[4].findings, vec![warning(String::from("the SSID is also given in row 2"))]);
+        assert!(entries[5].findings.iter().any(|x| x.message.starts_with("the payload has")));
+        let (report, failed) = report(&entries);
+        assert!(failed && report.ends_with("Checked 6 networks: 5 errors, 1 warning.\n"));
+    }
+    #[test]
+    fn test_jsonl() {
+        let entries = jsonl(&test_args(), "{\"ssid\": \"Guest\"}\n\n{\"ssid\": \"Guest\", \"password\": \"hunter22\"}\n{\n");
+        assert_eq!(entries.iter().map(|x| x.location.as_str()).collect::<Vec<_>>(), vec!["line 1", "line 3", "line 4"]);
+        assert_eq!(entries[1].findings, vec![warning(String::from("the SSID is also given in line 1"))]);
+        assert_eq!(entries[2].findings[0].severity, Severity::Error);
+        assert!(!report(&entries[..2]).1);
+    }
+}
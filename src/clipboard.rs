@@ -0,0 +1,152 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Copying the QR-Code or its payload to the clipboard, for `--clipboard`, to be pasted into a chat or a document.
+//! The clipboards of Windows and macOS keep what was copied, while on X11, and on Wayland through XWayland, it is
+//! served by the program that copied it, so a child process is left in the background to do that until something
+//! else is copied.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use image::RgbaImage;
+
+/// What `--clipboard` copies.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Content{
+    /// The QR-Code as an image, as it would be written to a PNG.
+    Image,
+    /// The payload as text, e.g. `WIFI:T:WPA;S:Home;P:hunter22;;`.
+    Payload
+}
+
+#[derive(Debug)]
+pub enum ClipboardError{
+    Clipboard(arboard::Error),
+    /// The background process serving the clipboard couldn't be started.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Fork(std::io::Error)
+}
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClipboardError::Clipboard(err) => write!(f, "{}", err),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            ClipboardError::Fork(err) => write!(f, "failed to start the process serving the clipboard: {}", err)
+        }
+    }
+}
+
+/// What is copied to the clipboard.
+pub enum Contents{
+    Text(String),
+    Image(RgbaImage)
+}
+
+/// The pixels of the image as the clipboard takes them.
+fn image_data(image: &RgbaImage) -> arboard::ImageData<'_> {
+    arboard::ImageData{ width: image.width() as usize, height: image.height() as usize, bytes: Cow::Borrowed(image.as_raw()) }
+}
+
+/// Places the contents on the clipboard.
+fn set(clipboard: &mut arboard::Clipboard, contents: &Contents) -> Result<(), arboard::Error> {
+    match contents {
+        Contents::Text(text) => clipboard.set_text(text.as_str()),
+        Contents::Image(image) => clipboard.set_image(image_data(image))
+    }
+}
+
+/// Copies the contents to the clipboard.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn copy(contents: &Contents) -> Result<(), ClipboardError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(ClipboardError::Clipboard)?;
+    set(&mut clipboard, contents).map_err(ClipboardError::Clipboard)
+}
+/// Copies the contents to the clipboard, which a forked child serves until something else is copied. The child
+/// connects to the display first and tells through a pipe whether it could, so that failing is reported here.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn copy(contents: &Contents) -> Result<(), ClipboardError> {
+    use arboard::SetExtLinux;
+    let mut fds:[libc::c_int; 2] = [0; 2];
+    // SAFETY: The pipe writes its two descriptors into the array. The child only uses its end of the pipe and the
+    // clipboard before it exits, without returning into the rest of the program.
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(ClipboardError::Fork(std::io::Error::last_os_error()));
+        }
+        match libc::fork() {
+            -1 => {
+                let err = std::io::Error::last_os_error();
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+                Err(ClipboardError::Fork(err))
+            },
+            0 => {
+                libc::close(fds[0]);
+                // Detached from the terminal and its output, which e.g. `$(wifiqr ...)` would wait to be closed.
+                libc::setsid();
+                let null:libc::c_int = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+                if null >= 0 {
+                    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+                        libc::dup2(null, fd);
+                    }
+                }
+                let clipboard = arboard::Clipboard::new();
+                let connected:u8 = clipboard.is_ok() as u8;
+                libc::write(fds[1], (&connected as *const u8).cast(), 1);
+                libc::close(fds[1]);
+                if let Ok(mut clipboard) = clipboard {
+                    let _ = match contents {
+                        Contents::Text(text) => clipboard.set().wait().text(text.as_str()),
+                        Contents::Image(image) => clipboard.set().wait().image(image_data(image))
+                    };
+                }
+                libc::_exit(0)
+            },
+            _ => {
+                libc::close(fds[1]);
+                let mut connected:u8 = 0;
+                let read:isize = libc::read(fds[0], (&mut connected as *mut u8).cast(), 1);
+                libc::close(fds[0]);
+                if read == 1 && connected == 1 {
+                    return Ok(());
+                }
+                // The child couldn't connect, so the error is found out again here.
+                let mut clipboard = arboard::Clipboard::new().map_err(ClipboardError::Clipboard)?;
+                set(&mut clipboard, contents).map_err(ClipboardError::Clipboard)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Args;
+    use clap::Parser;
+    #[test]
+    fn test_content() {
+        assert_eq!(Args::parse_from(["wifiqr", "--ssid", "Home", "--clipboard"]).output.clipboard, Some(Content::Image));
+        assert_eq!(Args::parse_from(["wifiqr", "--ssid", "Home", "--clipboard=payload"]).output.clipboard, Some(Content::Payload));
+        assert!(Args::try_parse_from(["wifiqr", "--ssid", "Home", "--clipboard", "--output", "wifi.png"]).is_err());
+    }
+    #[test]
+    fn test_image_data() {
+        let image:RgbaImage = RgbaImage::new(3, 2);
+        let data = image_data(&image);
+        assert_eq!((data.width, data.height, data.bytes.len()), (3, 2, 24));
+    }
+}
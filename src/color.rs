@@ -0,0 +1,154 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Colors for the rendered outputs, parsed from hex notation on the command line, their contrast, and how they
+//! appear with color-vision deficiencies.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Contrast ratio below which colors are rejected, as readers and scanners can't reliably tell them apart.
+pub const MIN_CONTRAST: f32 = 3.0;
+/// Contrast ratio WCAG asks of body text. Colors below it are accepted with a warning.
+pub const RECOMMENDED_CONTRAST: f32 = 4.5;
+
+/// Color-vision deficiencies, in their complete form, that colors are checked against.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Deficiency{
+    /// No red cones.
+    Protanopia,
+    /// No green cones, the most common.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia
+}
+impl Deficiency {
+    pub const ALL: [Deficiency; 3] = [Deficiency::Protanopia, Deficiency::Deuteranopia, Deficiency::Tritanopia];
+    /// Matrix converting linear RGB to what is seen, from Machado, Oliveira and Fernandes (2009) at full severity.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [[0.152286, 1.052583, -0.204868], [0.114503, 0.786281, 0.099216], [-0.003882, -0.048116, 1.051998]],
+            Deficiency::Deuteranopia => [[0.367322, 0.860646, -0.227968], [0.280085, 0.672501, 0.047413], [-0.011820, 0.042940, 0.968881]],
+            Deficiency::Tritanopia => [[1.255528, -0.076749, -0.178779], [-0.078411, 0.930809, 0.147602], [0.004733, 0.691367, 0.303900]]
+        }
+    }
+}
+
+fn to_linear(channel: u8) -> f32 {
+    let channel:f32 = channel as f32 / 255.0;
+    if channel <= 0.04045 {channel / 12.92}else{((channel + 0.055) / 1.055).powf(2.4)}
+}
+fn from_linear(channel: f32) -> u8 {
+    let channel:f32 = channel.clamp(0.0, 1.0);
+    let channel:f32 = if channel <= 0.0031308 {channel * 12.92}else{1.055 * channel.powf(1.0 / 2.4) - 0.055};
+    (channel * 255.0).round() as u8
+}
+
+/// An sRGB color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color{
+    pub r: u8,
+    pub g: u8,
+    pub b: u8
+}
+impl Color {
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const WHITE: Color = Color::new(255, 255, 255);
+    pub const GRAY: Color = Color::new(160, 160, 160);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color{ r, g, b }
+    }
+    pub fn to_rgba(self) -> image::Rgba<u8> {
+        image::Rgba([self.r, self.g, self.b, 255])
+    }
+    /// Relative luminance as defined by WCAG 2, from 0 for black to 1 for white.
+    pub fn luminance(self) -> f32 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+    /// The color as it appears with `deficiency`.
+    pub fn simulate(self, deficiency: Deficiency) -> Color {
+        let linear:[f32; 3] = [to_linear(self.r), to_linear(self.g), to_linear(self.b)];
+        let [r, g, b] = deficiency.matrix().map(|row| from_linear(row.iter().zip(linear).map(|(x, y)| x * y).sum()));
+        Color::new(r, g, b)
+    }
+    /// Contrast ratio as defined by WCAG 2, from 1 for equal colors to 21 for black on white.
+    pub fn contrast(self, other: Color) -> f32 {
+        let (lighter, darker) = if self.luminance() > other.luminance() {(self, other)}else{(other, self)};
+        (lighter.luminance() + 0.05) / (darker.luminance() + 0.05)
+    }
+}
+/// Parses `#RRGGBB` or `#RGB`, the `#` being optional.
+impl FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex:&str = s.strip_prefix('#').unwrap_or(s);
+        let invalid = || format!("'{}' is not a color of the form #RRGGBB", s);
+        if !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let channel = |x: &str| u8::from_str_radix(x, 16).map_err(|_| invalid());
+        match hex.len() {
+            6 => Ok(Color::new(channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+            // Each digit of the short form is repeated, so #fa0 is #ffaa00.
+            3 => Ok(Color::new(channel(&hex[0..1])? * 17, channel(&hex[1..2])? * 17, channel(&hex[2..3])? * 17)),
+            _ => Err(invalid())
+        }
+    }
+}
+/// Colors are written as strings in configuration files, using the same notation as on the command line.
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_parse_color() {
+        assert_eq!("#ff8000".parse::<Color>().unwrap(), Color::new(255, 128, 0));
+        assert_eq!("FF8000".parse::<Color>().unwrap(), Color::new(255, 128, 0));
+        assert_eq!("#fa0".parse::<Color>().unwrap(), Color::new(255, 170, 0));
+        assert!("#ff80".parse::<Color>().is_err());
+        assert!("#gg0000".parse::<Color>().is_err());
+        assert!("#ä0000".parse::<Color>().is_err());
+        assert_eq!(Color::new(255, 128, 0).to_string(), "#ff8000");
+    }
+    #[test]
+    fn test_contrast() {
+        assert!((Color::BLACK.contrast(Color::WHITE) - 21.0).abs() < 0.001);
+        assert_eq!(Color::GRAY.contrast(Color::GRAY), 1.0);
+        // Pure red on white falls just short of the recommended contrast.
+        assert!((Color::new(255, 0, 0).contrast(Color::WHITE) - 4.0).abs() < 0.01);
+    }
+    #[test]
+    fn test_simulate() {
+        for deficiency in Deficiency::ALL {
+            assert_eq!(Color::BLACK.simulate(deficiency), Color::BLACK);
+            assert_eq!(Color::WHITE.simulate(deficiency), Color::WHITE);
+        }
+        // Red and green, told apart by hue only, become the same dull yellow without green cones.
+        let (red, green) = (Color::new(200, 60, 40).simulate(Deficiency::Deuteranopia), Color::new(90, 140, 40).simulate(Deficiency::Deuteranopia));
+        assert!(red.contrast(green) < 1.2);
+    }
+}
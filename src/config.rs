@@ -0,0 +1,164 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Defaults for the options from a `config.toml`, so preferences such as the ECC-Level or the colors in the terminal
+//! don't have to be repeated on every run. Options given on the command line win over their `WIFIQR_*` environment
+//! variables, which win over the config.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::{ArgMatches, ValueSource};
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::console::{Invert, Render};
+use crate::i18n::Language;
+use crate::layout::CardFormat;
+use crate::output::OutputFormat;
+use crate::{Args, ECCLevel};
+
+/// Name of the file in the `wifi-qr` directory of the user's config directory.
+pub const DEFAULT_FILE_NAME: &str = "config.toml";
+
+/// The options that can be set in the config, named as on the command line.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config{
+    pub ecc: Option<ECCLevel>,
+    pub render: Option<Render>,
+    pub invert: Option<Invert>,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub format: Option<OutputFormat>,
+    pub size: Option<usize>,
+    pub border: Option<usize>,
+    pub module_size: Option<u32>,
+    pub card: Option<CardFormat>,
+    pub lang: Option<Language>
+}
+
+#[derive(Debug)]
+pub enum ConfigError{
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error)
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "invalid config in {}: {}", path.display(), err)
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/wifi-qr/config.toml`, or `~/.config/wifi-qr/config.toml` without it.
+#[cfg(not(windows))]
+pub fn default_path() -> Option<PathBuf> {
+    let directory:PathBuf = match std::env::var_os("XDG_CONFIG_HOME").filter(|x| !x.is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from(std::env::var_os("HOME").filter(|x| !x.is_empty())?).join(".config")
+    };
+    Some(directory.join("wifi-qr").join(DEFAULT_FILE_NAME))
+}
+/// `%APPDATA%\wifi-qr\config.toml`.
+#[cfg(windows)]
+pub fn default_path() -> Option<PathBuf> {
+    Some(PathBuf::from(std::env::var_os("APPDATA")?).join("wifi-qr").join(DEFAULT_FILE_NAME))
+}
+
+impl Config {
+    pub fn parse(source: &str, path: &Path) -> Result<Self, ConfigError> {
+        toml::from_str(source).map_err(|err| ConfigError::Parse(path.to_path_buf(), err))
+    }
+    /// Loads the config from `path`, or from [`default_path`] if it exists. Without either nothing is changed.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path:PathBuf = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default())
+            }
+        };
+        let source:String = std::fs::read_to_string(&path).map_err(|err| ConfigError::Io(path.clone(), err))?;
+        Config::parse(&source, &path)
+    }
+    /// Sets the options of the config that were neither given on the command line nor by their environment variables.
+    pub fn apply(&self, args: &mut Args, matches: &ArgMatches) {
+        let unset = |id: &str| !matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable));
+        fn set<T: Copy>(option: &mut T, value: Option<T>, unset: bool) {
+            if let (Some(value), true) = (value, unset) {
+                *option = value;
+            }
+        }
+        set(&mut args.ecc, self.ecc, unset("ecc"));
+        set(&mut args.output.render, self.render.map(Some), unset("render"));
+        set(&mut args.output.invert, self.invert, unset("invert"));
+        set(&mut args.output.fg, self.fg.map(Some), unset("fg"));
+        set(&mut args.output.bg, self.bg.map(Some), unset("bg"));
+        // Defaults of the format only pick the format of `--output`, while `--format` itself writes to stdout without it.
+        if args.output.path.is_none() && matches.value_source("format") != Some(ValueSource::CommandLine) {
+            args.output.format = None;
+        }else{
+            set(&mut args.output.format, self.format.map(Some), unset("format"));
+        }
+        set(&mut args.output.size, self.size, unset("size"));
+        set(&mut args.output.border, self.border, unset("border"));
+        set(&mut args.output.module_size, self.module_size.map(Some), unset("module-size"));
+        set(&mut args.output.card, self.card, unset("card"));
+        set(&mut args.output.lang, self.lang, unset("lang"));
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+    fn layered(arguments: &[&str], config: &str) -> Args {
+        let matches:ArgMatches = Args::command().get_matches_from(arguments);
+        let mut args:Args = Args::from_arg_matches(&matches).unwrap();
+        Config::parse(config, Path::new("config.toml")).unwrap().apply(&mut args, &matches);
+        args
+    }
+    #[test]
+    fn test_layering() {
+        let config:&str = "ecc = \"high\"\nrender = \"half-blocks\"\nfg = \"#1e3c8c\"\nborder = 2\ncard = \"a5\"\n";
+        let args:Args = layered(&["wifiqr", "--ssid", "Home"], config);
+        assert_eq!((args.ecc, args.output.render, args.output.fg, args.output.border, args.output.card),
+            (ECCLevel::High, Some(Render::HalfBlocks), Some(Color::new(0x1e, 0x3c, 0x8c)), 2, CardFormat::A5));
+        assert_eq!((args.output.size, args.output.bg), (512, None));
+        let args:Args = layered(&["wifiqr", "--ssid", "Home", "--ecc", "low", "--border", "4"], config);
+        assert_eq!((args.ecc, args.output.border, args.output.card), (ECCLevel::Low, 4, CardFormat::A5));
+        // Only this test sets the variable, so the other tests don't pick it up.
+        std::env::set_var("WIFIQR_MODULE_SIZE", "6");
+        let from_env:Args = layered(&["wifiqr", "--ssid", "Home"], "module-size = 9\n");
+        let from_cli:Args = layered(&["wifiqr", "--ssid", "Home", "--module-size", "3"], "module-size = 9\n");
+        std::env::remove_var("WIFIQR_MODULE_SIZE");
+        assert_eq!((from_env.output.module_size, from_cli.output.module_size), (Some(6), Some(3)));
+    }
+    #[test]
+    fn test_default_format() {
+        assert_eq!(layered(&["wifiqr", "--ssid", "Home"], "format = \"svg\"\n").output.format, None);
+        assert_eq!(layered(&["wifiqr", "--ssid", "Home", "-o", "wifi"], "format = \"svg\"\n").output.format, Some(OutputFormat::Svg));
+        assert_eq!(layered(&["wifiqr", "--ssid", "Home", "--format", "pbm"], "format = \"svg\"\n").output.format, Some(OutputFormat::Pbm));
+    }
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(Config::parse("", Path::new("config.toml")).unwrap(), Config::default());
+        assert!(matches!(Config::parse("ecc = \"ultra\"\n", Path::new("config.toml")), Err(ConfigError::Parse(..))));
+        assert!(matches!(Config::parse("ssid = \"Home\"\n", Path::new("config.toml")), Err(ConfigError::Parse(..))));
+    }
+}
@@ -0,0 +1,129 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Joining the network of a decoded code, for `connect`: NetworkManager gets a connection named after the SSID
+//! through `nmcli`, Windows a WLAN profile through the WLAN API, replacing one of the same name, and the network is
+//! joined right away.
+
+use crate::saved::SavedError;
+use crate::Args;
+#[cfg(not(windows))]
+use crate::{resolve_security, Security};
+
+/// The settings of the NetworkManager connection of the network, as `nmcli connection add` takes them. The key is
+/// left out, [`nm_secrets`] hands it over as the connection is brought up.
+#[cfg(not(windows))]
+fn nm_settings(args: &Args) -> Vec<String> {
+    let mut settings:Vec<String> = ["type", "wifi", "con-name", &args.ssid, "ssid", &args.ssid].map(String::from).to_vec();
+    if args.hidden {
+        settings.extend(["802-11-wireless.hidden", "yes"].map(String::from));
+    }
+    let security:Vec<String> = match resolve_security(args) {
+        None => Vec::new(),
+        Some(Security::Wep) => ["none", "wifi-sec.wep-key-type", "1", "wifi-sec.wep-tx-keyidx", &args.wep.key_index().to_string()].map(String::from).to_vec(),
+        Some(Security::Wpa) => ["wpa-psk", "wifi-sec.proto", "wpa"].map(String::from).to_vec(),
+        // NetworkManager picks SAE for wpa-psk connections by itself if the access point offers it.
+        Some(Security::Wpa2 | Security::Wpa2Wpa3) => vec![String::from("wpa-psk")],
+        Some(Security::Wpa3) => vec![String::from("sae")]
+    };
+    if !security.is_empty() {
+        settings.push(String::from("wifi-sec.key-mgmt"));
+        settings.extend(security);
+    }
+    settings
+}
+
+/// The secrets of the connection of the network, which are handed over as it's brought up to keep them out of the
+/// arguments of `nmcli`.
+#[cfg(not(windows))]
+fn nm_secrets(args: &Args) -> Option<String> {
+    let password:&str = args.psw.as_deref().unwrap_or_default();
+    match resolve_security(args) {
+        None => None,
+        Some(Security::Wep) => Some(format!("802-11-wireless-security.wep-key{}:{}\n", args.wep.key_index(), password)),
+        Some(_) => Some(format!("802-11-wireless-security.psk:{}\n", password))
+    }
+}
+
+/// Saves the network and joins it.
+#[cfg(not(windows))]
+pub fn connect(args: &Args) -> Result<(), SavedError> {
+    let names:String = crate::saved::nmcli(&["--get-values", "NAME", "connection", "show"])?;
+    if names.lines().any(|x| x == args.ssid) {
+        crate::saved::nmcli(&["connection", "delete", "id", &args.ssid])?;
+    }
+    let settings:Vec<String> = nm_settings(args);
+    crate::saved::nmcli(&[&["connection", "add"], settings.iter().map(String::as_str).collect::<Vec<&str>>().as_slice()].concat())?;
+    crate::saved::nmcli_up(&args.ssid, nm_secrets(args).as_deref(), args.shred)
+}
+/// Saves the network as a profile of the first Wi-Fi adapter taking it, and joins it.
+#[cfg(windows)]
+pub fn connect(args: &Args) -> Result<(), SavedError> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::NetworkManagement::WiFi::*;
+    let wlan:crate::saved::Wlan = crate::saved::Wlan::open()?;
+    let wide = |text: &str| text.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let xml:Vec<u16> = wide(&crate::profile::write_netsh(args));
+    let name:Vec<u16> = wide(&args.ssid);
+    let mut result:Result<(), SavedError> = Err(SavedError::Wlan("WlanEnumInterfaces", windows_sys::Win32::Foundation::ERROR_NOT_FOUND));
+    for interface in wlan.interfaces() {
+        let mut reason:u32 = 0;
+        // SAFETY: The strings are null-terminated and outlive the calls, which copy what they need.
+        unsafe {
+            let code:u32 = WlanSetProfile(wlan.handle, &interface.InterfaceGuid, 0, xml.as_ptr(), null(), 1, null(), &mut reason);
+            if code != ERROR_SUCCESS {
+                result = Err(SavedError::Wlan("WlanSetProfile", code));
+                continue;
+            }
+            let parameters = WLAN_CONNECTION_PARAMETERS{
+                wlanConnectionMode: wlan_connection_mode_profile,
+                strProfile: name.as_ptr(),
+                pDot11Ssid: null_mut(),
+                pDesiredBssidList: null_mut(),
+                dot11BssType: dot11_BSS_type_infrastructure,
+                dwFlags: 0
+            };
+            let code:u32 = WlanConnect(wlan.handle, &interface.InterfaceGuid, &parameters, null());
+            result = if code == ERROR_SUCCESS {Ok(())}else{Err(SavedError::Wlan("WlanConnect", code))};
+        }
+        break;
+    }
+    result
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests{
+    use super::*;
+    fn test_args() -> Args {
+        Args{ hidden: true, ..crate::tests::network("Home", Some("hunter22"), Some(Security::Wpa3)) }
+    }
+    #[test]
+    fn test_nm_settings() {
+        let mut args:Args = test_args();
+        assert_eq!(nm_settings(&args).join(" "), "type wifi con-name Home ssid Home 802-11-wireless.hidden yes wifi-sec.key-mgmt sae");
+        assert_eq!(nm_secrets(&args).unwrap(), "802-11-wireless-security.psk:hunter22\n");
+        args.sec = Some(Security::Wep);
+        args.psw = Some(String::from("0123456789"));
+        assert_eq!(nm_settings(&args).join(" "), "type wifi con-name Home ssid Home 802-11-wireless.hidden yes wifi-sec.key-mgmt none wifi-sec.wep-key-type 1 wifi-sec.wep-tx-keyidx 0");
+        assert_eq!(nm_secrets(&args).unwrap(), "802-11-wireless-security.wep-key0:0123456789\n");
+        args.psw = None;
+        args.sec = None;
+        args.hidden = false;
+        assert_eq!(nm_settings(&args).join(" "), "type wifi con-name Home ssid Home");
+        assert_eq!(nm_secrets(&args), None);
+    }
+}
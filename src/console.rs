@@ -0,0 +1,269 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Picking characters for the QR-Code the terminal can show. Legacy Windows consoles with raster fonts can only
+//! show the characters of their code page, and mangle `█` in most that aren't derived from DOS. Codes too wide or too
+//! high for the terminal are drawn with half blocks, two rows per line, or braille, and codes that don't fit even so
+//! are drawn with a warning, as their lines wrap. Terminals whose locale isn't UTF-8, such as
+//! serial consoles, get `#` likewise.
+
+use std::io::IsTerminal;
+
+use crate::color::Color;
+
+/// Characters the modules of the QR-Code are drawn with in the terminal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Charset{
+    /// Full blocks, `██` per dark module.
+    Blocks,
+    /// `##` per dark module, for consoles that can't show blocks.
+    Ascii
+}
+impl Charset {
+    /// The two characters of a dark module. Light modules are two spaces.
+    pub fn dark_module(self) -> &'static str {
+        match self {
+            Charset::Blocks => "██",
+            Charset::Ascii => "##"
+        }
+    }
+}
+
+/// How the modules are packed into the characters of the terminal.
+#[derive(clap::ArgEnum, Copy, Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Render{
+    /// Two characters per module, the largest.
+    Blocks,
+    /// `▀`, `▄` and `█`, one character per module and two rows per line. Needs a terminal that can show blocks.
+    HalfBlocks,
+    /// Braille patterns, each holding two by four modules, for small panes. Needs a font with braille.
+    Braille,
+    /// `##` and spaces, two characters per module, for serial consoles, log files and fonts without blocks.
+    Ascii,
+    /// An image, with the Sixel graphics of xterm, foot, mlterm and others.
+    Sixel,
+    /// An image, with the graphics protocol of Kitty, Ghostty and others.
+    Kitty,
+    /// An image, with the inline images of iTerm2 and WezTerm.
+    Iterm
+}
+impl Render {
+    /// The columns and lines a code `modules` wide takes drawn this way, `None` for images.
+    pub fn cells(self, modules: usize) -> Option<(usize, usize)> {
+        match self {
+            Render::Blocks | Render::Ascii => Some((2 * modules, modules)),
+            Render::HalfBlocks => Some((modules, modules.div_ceil(2))),
+            Render::Braille => Some((modules.div_ceil(2), modules.div_ceil(4))),
+            Render::Sixel | Render::Kitty | Render::Iterm => None
+        }
+    }
+}
+
+/// What the terminal the code is drawn in can show: its size, if it is known, and its characters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Terminal{
+    pub columns: Option<usize>,
+    pub lines: Option<usize>,
+    pub charset: Charset,
+    /// Whether the output goes to the terminal, rather than to a file or pipe, where nothing wraps.
+    pub interactive: bool
+}
+impl Terminal {
+    pub fn detect() -> Self {
+        Terminal{ columns: width(), lines: height(), charset: detect(), interactive: std::io::stdout().is_terminal() }
+    }
+    /// Whether a code of the columns and lines fits, leaving a line for the prompt beneath it.
+    fn fits(&self, (columns, lines): (usize, usize)) -> bool {
+        self.columns.is_none_or(|x| columns <= x) && self.lines.is_none_or(|x| lines < x)
+    }
+    /// The largest rendering of a code `modules` wide that fits into the terminal, and braille if none does.
+    /// Terminals that can't show blocks only get the two characters per module of their charset.
+    pub fn fitting(&self, modules: usize) -> Render {
+        if self.charset != Charset::Blocks {
+            return Render::Blocks;
+        }
+        [Render::Blocks, Render::HalfBlocks].into_iter().find(|x| self.fits(x.cells(modules).expect("characters take cells"))).unwrap_or(Render::Braille)
+    }
+    /// Why a code `modules` wide drawn with the rendering can't be scanned in the terminal, if it doesn't fit.
+    pub fn overflow(&self, render: Render, modules: usize) -> Option<String> {
+        let (columns, lines) = render.cells(modules).filter(|_| self.interactive)?;
+        let advice:&str = "write it to a file with --output instead, e.g. -o wifi.png";
+        match (self.columns, self.lines) {
+            (Some(width), _) if columns > width =>
+                Some(format!("the QR-Code is {} columns wide, but the terminal only {}, so its lines wrap and it can't be scanned; widen the terminal or {}", columns, width, advice)),
+            (_, Some(height)) if lines >= height =>
+                Some(format!("the QR-Code is {} lines high, but the terminal only {}, so its top scrolls out of view; enlarge the terminal or {}", lines, height, advice)),
+            _ => None
+        }
+    }
+}
+
+/// Whether the modules drawn are swapped, so light modules are drawn with characters and dark ones left blank.
+/// Characters are light in dark terminals, which would draw the code inverted, which many readers can't scan.
+#[derive(clap::ArgEnum, Copy, Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Invert{
+    /// If the terminal says its background is dark.
+    Auto,
+    Always,
+    Never
+}
+
+/// Whether the background of the terminal is dark, by `$COLORFGBG`, which rxvt, Konsole and others set to the
+/// palette indices of the foreground and background, e.g. `15;0`.
+pub fn dark_background() -> Option<bool> {
+    let colors:String = std::env::var("COLORFGBG").ok()?;
+    let background:u8 = colors.rsplit(';').next()?.parse().ok()?;
+    // The dark colors of the palette, plus dark gray.
+    Some(background < 7 || background == 8)
+}
+
+/// Draws the lines of `text` in the foreground color on the background color, with 24-bit color sequences.
+pub fn paint(text: &str, foreground: Color, background: Color) -> String {
+    text.lines().map(|line| format!("\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m{}\x1b[0m\n",
+        foreground.r, foreground.g, foreground.b, background.r, background.g, background.b, line)).collect()
+}
+
+/// Width of the terminal in columns, if the output goes to one, and otherwise `$COLUMNS`, if that's set.
+pub fn width() -> Option<usize> {
+    terminal_size().map(|(columns, _)| columns).or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+}
+
+/// Height of the terminal in lines, if the output goes to one, and otherwise `$LINES`, if that's set.
+pub fn height() -> Option<usize> {
+    terminal_size().map(|(_, lines)| lines).filter(|x| *x > 0).or_else(|| std::env::var("LINES").ok()?.parse().ok())
+}
+
+/// Columns and lines of the terminal.
+#[cfg(unix)]
+fn terminal_size() -> Option<(usize, usize)> {
+    let mut size = libc::winsize{ ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+    // SAFETY: TIOCGWINSZ only writes the winsize passed, and fails for outputs that aren't terminals.
+    let result:libc::c_int = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some((size.ws_col as usize, size.ws_row as usize))
+}
+
+/// Columns and lines of the window of the console, not of its buffer.
+#[cfg(windows)]
+fn terminal_size() -> Option<(usize, usize)> {
+    use windows_sys::Win32::System::Console::{GetConsoleScreenBufferInfo, GetStdHandle, CONSOLE_SCREEN_BUFFER_INFO, STD_OUTPUT_HANDLE};
+    // SAFETY: The info is plain data the call fills in, and the call fails for handles that aren't consoles.
+    unsafe {
+        let mut info:CONSOLE_SCREEN_BUFFER_INFO = std::mem::zeroed();
+        if GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) == 0 {
+            return None;
+        }
+        Some(((info.srWindow.Right - info.srWindow.Left + 1) as usize, (info.srWindow.Bottom - info.srWindow.Top + 1) as usize))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminal_size() -> Option<(usize, usize)> {
+    None
+}
+
+/// OEM code pages with `█` at 0xDB, which raster fonts can show.
+#[cfg(windows)]
+const BLOCK_CODE_PAGES: [u32; 15] = [437, 737, 775, 850, 852, 855, 857, 858, 860, 861, 862, 863, 865, 866, 869];
+#[cfg(windows)]
+const CP_UTF8: u32 = 65001;
+
+/// Consoles that accept virtual terminal sequences are the Unicode-capable ones of Windows 10 and later, including
+/// Windows Terminal. Older consoles are trusted with blocks only if their code page has them.
+#[cfg(windows)]
+pub fn detect() -> Charset {
+    use windows_sys::Win32::System::Console::{GetConsoleMode, GetConsoleOutputCP, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_OUTPUT_HANDLE};
+    // SAFETY: The handle is only passed back to the console functions, which fail gracefully on invalid handles.
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode:u32 = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            // Not a console, so the output is written as UTF-8 to a file or pipe.
+            return Charset::Blocks;
+        }
+        if SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0 {
+            SetConsoleMode(handle, mode);
+            return Charset::Blocks;
+        }
+        let code_page:u32 = GetConsoleOutputCP();
+        if code_page == CP_UTF8 || BLOCK_CODE_PAGES.contains(&code_page) {Charset::Blocks}else{Charset::Ascii}
+    }
+}
+
+/// Terminals are trusted with blocks unless their locale says they use another encoding than UTF-8. Without a
+/// locale, and for files and pipes, the output is written as UTF-8.
+#[cfg(unix)]
+pub fn detect() -> Charset {
+    // SAFETY: isatty only looks at the descriptor.
+    let terminal:bool = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
+    let locale:Option<String> = ["LC_ALL", "LC_CTYPE", "LANG"].iter().filter_map(|x| std::env::var(x).ok()).find(|x| !x.is_empty());
+    match locale {
+        Some(locale) if terminal && !utf8(&locale) => Charset::Ascii,
+        _ => Charset::Blocks
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn detect() -> Charset {
+    Charset::Blocks
+}
+
+/// Whether the locale, e.g. `en_US.UTF-8`, encodes characters in UTF-8.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn utf8(locale: &str) -> bool {
+    let codeset:String = locale.split('@').next().unwrap_or_default().rsplit('.').next().unwrap_or_default().to_lowercase();
+    locale.contains('.') && (codeset == "utf-8" || codeset == "utf8")
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("██\n  \n", Color::BLACK, Color::new(255, 255, 0)), "\x1b[38;2;0;0;0m\x1b[48;2;255;255;0m██\x1b[0m\n\x1b[38;2;0;0;0m\x1b[48;2;255;255;0m  \x1b[0m\n");
+    }
+    #[test]
+    fn test_fitting() {
+        let terminal = |columns: Option<usize>, lines: Option<usize>| Terminal{ columns, lines, charset: Charset::Blocks, interactive: true };
+        assert_eq!(terminal(Some(80), None).fitting(45), Render::HalfBlocks);
+        assert_eq!(terminal(Some(80), None).fitting(40), Render::Blocks);
+        assert_eq!(terminal(Some(50), None).fitting(57), Render::Braille);
+        // Pipes and files get the full size.
+        assert_eq!(terminal(None, None).fitting(177), Render::Blocks);
+        // 37 lines of blocks don't fit into 24, 19 of half blocks do.
+        assert_eq!(terminal(Some(200), Some(24)).fitting(37), Render::HalfBlocks);
+        assert_eq!(terminal(Some(200), Some(15)).fitting(37), Render::Braille);
+        assert_eq!(Terminal{ charset: Charset::Ascii, ..terminal(Some(50), None) }.fitting(57), Render::Blocks);
+    }
+    #[test]
+    fn test_overflow() {
+        let terminal = Terminal{ columns: Some(80), lines: Some(24), charset: Charset::Blocks, interactive: true };
+        assert_eq!(terminal.overflow(Render::Braille, 41), None);
+        assert!(terminal.overflow(Render::Blocks, 41).unwrap().starts_with("the QR-Code is 82 columns wide, but the terminal only 80"));
+        assert!(terminal.overflow(Render::HalfBlocks, 57).unwrap().starts_with("the QR-Code is 29 lines high"));
+        assert_eq!(terminal.overflow(Render::Kitty, 41), None);
+        assert_eq!(Terminal{ interactive: false, ..terminal }.overflow(Render::Blocks, 41), None);
+    }
+    #[test]
+    fn test_utf8() {
+        assert!(utf8("en_US.UTF-8"));
+        assert!(utf8("de_DE.utf8@euro"));
+        assert!(!utf8("C"));
+        assert!(!utf8("POSIX"));
+        assert!(!utf8("en_US.ISO-8859-1"));
+    }
+}
@@ -0,0 +1,112 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Reading the password from stdin, a file or an environment variable in place of `--psw`, so automation doesn't
+//! have to put it into the arguments of the process, which other users can list.
+
+use std::fmt;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Where to read the password from. At most one of them is given, and not together with `--psw`.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PasswordSource{
+    /// Read the password from stdin, e.g. piped from a secret store.
+    #[clap(long, action, conflicts_with_all = &["psw", "interactive", "psw-file", "psw-env"])]
+    pub psw_stdin: bool,
+
+    /// Read the password from the file, e.g. a mounted secret.
+    #[clap(long, value_parser, value_name = "PATH", conflicts_with_all = &["psw", "interactive", "psw-env"])]
+    pub psw_file: Option<PathBuf>,
+
+    /// Read the password from the environment variable.
+    #[clap(long, value_parser, value_name = "VAR", conflicts_with_all = &["psw", "interactive"])]
+    pub psw_env: Option<String>
+}
+impl PasswordSource {
+    /// The password of the source given, if any. The line break ending files and piped input is removed, anything
+    /// else is kept, as spaces are valid in passwords.
+    pub fn read(&self) -> Result<Option<String>, CredentialError> {
+        let (source, contents):(String, String) = if self.psw_stdin {
+            let mut contents:String = String::new();
+            std::io::stdin().read_to_string(&mut contents).map_err(|err| CredentialError::Io(String::from("stdin"), err))?;
+            (String::from("stdin"), contents)
+        }else if let Some(path) = &self.psw_file {
+            let source:String = path.display().to_string();
+            let contents:String = std::fs::read_to_string(path).map_err(|err| CredentialError::Io(source.clone(), err))?;
+            (source, contents)
+        }else if let Some(name) = &self.psw_env {
+            let contents:String = std::env::var(name).map_err(|err| CredentialError::Env(name.clone(), err))?;
+            (format!("${}", name), contents)
+        }else{
+            return Ok(None);
+        };
+        validate(&source, &contents).map(Some)
+    }
+}
+
+/// The password in the contents: a single line, which may end with a line break, and isn't empty.
+fn validate(source: &str, contents: &str) -> Result<String, CredentialError> {
+    let password:&str = contents.strip_suffix('\n').map_or(contents, |x| x.strip_suffix('\r').unwrap_or(x));
+    if password.is_empty() {
+        return Err(CredentialError::Empty(String::from(source)));
+    }
+    if password.contains(['\n', '\r']) {
+        return Err(CredentialError::SeveralLines(String::from(source)));
+    }
+    Ok(String::from(password))
+}
+
+#[derive(Debug)]
+pub enum CredentialError{
+    Io(String, std::io::Error),
+    Env(String, std::env::VarError),
+    Empty(String),
+    SeveralLines(String)
+}
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CredentialError::Io(source, err) => write!(f, "{}: {}", source, err),
+            CredentialError::Env(name, std::env::VarError::NotPresent) => write!(f, "${} is not set", name),
+            CredentialError::Env(name, std::env::VarError::NotUnicode(_)) => write!(f, "${} is not valid UTF-8", name),
+            CredentialError::Empty(source) => write!(f, "{} is empty, leave out the password for open networks", source),
+            CredentialError::SeveralLines(source) => write!(f, "{} has more than one line, but passwords have one", source)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("stdin", "hunter22\r\n").unwrap(), "hunter22");
+        assert_eq!(validate("stdin", " pass word ").unwrap(), " pass word ");
+        assert!(matches!(validate("stdin", "\n"), Err(CredentialError::Empty(_))));
+        assert!(matches!(validate("stdin", "hunter22\nhunter23\n"), Err(CredentialError::SeveralLines(_))));
+    }
+    #[test]
+    fn test_read() {
+        let path:PathBuf = std::env::temp_dir().join(format!("wifiqr-psw-{}", std::process::id()));
+        std::fs::write(&path, "hunter22\n").unwrap();
+        let source = PasswordSource{ psw_file: Some(path.clone()), ..PasswordSource::default() };
+        assert_eq!(source.read().unwrap().as_deref(), Some("hunter22"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(source.read(), Err(CredentialError::Io(..))));
+        assert_eq!(PasswordSource::default().read().unwrap(), None);
+    }
+}
@@ -0,0 +1,220 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The diagnostics on stderr: errors and warnings, and with `-v` what was built for each network, the payload and
+//! the version and ECC-Level of its QR-Code. `--log-format json` writes them as a JSON object per line, for the logs
+//! of batch runs and servers to be collected and searched.
+
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::{resolve_security, written_password, Args, Security, SPECIAL_CHARACTERS};
+
+/// How the diagnostics are written.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat{
+    /// Lines such as `Warning: ...`.
+    #[default]
+    Text,
+    /// A JSON object per line, e.g. `{"level":"warning","event":"weak-password","message":"...","ssid":"Home"}`.
+    Json
+}
+
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options{
+    /// Report what was built for each network on stderr: the payload, with the password masked unless repeated, and
+    /// the version and ECC-Level of the QR-Code.
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only report errors, leaving out the warnings.
+    #[clap(short, long, action, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Format of the diagnostics on stderr, `json` for a JSON object per line.
+    #[clap(arg_enum, long, value_parser, default_value_t = LogFormat::Text, env = "WIFIQR_LOG_FORMAT")]
+    pub log_format: LogFormat
+}
+
+/// How much is reported, from errors only to the payloads with their passwords.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level{
+    Error,
+    Warning,
+    Info,
+    Debug
+}
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug"
+        }
+    }
+}
+
+/// The level and format set by [`init`], which the threads of a batch share.
+static SETTINGS: OnceLock<(Level, LogFormat)> = OnceLock::new();
+
+/// Sets what is reported, once at the start.
+pub fn init(options: &Options) {
+    let level:Level = match (options.quiet, options.verbose) {
+        (true, _) => Level::Error,
+        (false, 0) => Level::Warning,
+        (false, 1) => Level::Info,
+        (false, _) => Level::Debug
+    };
+    let _ = SETTINGS.set((level, options.log_format));
+}
+fn settings() -> (Level, LogFormat) {
+    SETTINGS.get().copied().unwrap_or((Level::Warning, LogFormat::Text))
+}
+
+/// Whether diagnostics of the level are reported.
+pub fn enabled(level: Level) -> bool {
+    level <= settings().0
+}
+
+/// The line reporting an event, with the fields of a JSON object in the JSON format.
+fn line(format: LogFormat, level: Level, event: &str, message: &str, fields: Value) -> String {
+    match format {
+        LogFormat::Text => match level {
+            Level::Error => format!("Error: {}", message),
+            Level::Warning => format!("Warning: {}", message),
+            Level::Info | Level::Debug => String::from(message)
+        },
+        LogFormat::Json => {
+            let mut object = serde_json::Map::new();
+            object.insert(String::from("level"), json!(level.name()));
+            object.insert(String::from("event"), json!(event));
+            object.insert(String::from("message"), json!(message));
+            if let Value::Object(fields) = fields {
+                object.extend(fields);
+            }
+            Value::Object(object).to_string()
+        }
+    }
+}
+
+/// Reports the event if its level is, as `message` or as a JSON object with the fields in addition.
+pub fn report(level: Level, event: &str, message: &str, fields: Value) {
+    let (enabled, format) = settings();
+    if level <= enabled {
+        eprintln!("{}", line(format, level, event, message, fields));
+    }
+}
+
+/// Reports a warning, which is a sentence without its final period, e.g. `the border is narrower ...`.
+pub fn warn(event: &str, message: impl AsRef<str>) {
+    report(Level::Warning, event, &format!("{}.", message.as_ref().trim_end_matches('.')), Value::Null);
+}
+
+/// Reports the error the program exits with, with its exit code in JSON.
+pub fn error(err: &Error) {
+    report(Level::Error, "error", &err.message, json!({"exit_code": err.kind.exit_code()}));
+}
+
+/// Logs a request answered by a server: on stdout as before in the text format, on stderr along with the other
+/// diagnostics in JSON.
+pub fn request(request: &str, status: u16) {
+    match settings() {
+        (Level::Error, _) => {},
+        (_, LogFormat::Text) => println!("{} {}", request, status),
+        (_, LogFormat::Json) => eprintln!("{}", line(LogFormat::Json, Level::Info, "request", &format!("{} {}", request, status), json!({"request": request, "status": status})))
+    }
+}
+
+/// The payload with the password masked, to be shown in logs.
+fn masked(args: &Args, payload: &str) -> String {
+    match args.psw.as_deref().filter(|x| !x.is_empty()) {
+        Some(psw) => payload.replacen(&format!("P:{};", written_password(args)), &format!("P:{};", "*".repeat(psw.chars().count())), 1),
+        None => String::from(payload)
+    }
+}
+
+/// Why the password of the network is easy to guess, if it is.
+fn weakness(args: &Args) -> Option<String> {
+    let psw:&str = args.psw.as_deref().unwrap_or_default();
+    match resolve_security(args) {
+        Some(Security::Wep) => Some(String::from("WEP is cracked within minutes, whatever the key")),
+        Some(_) if args.enterprise.eap.is_none() && psw.chars().count() < 12 => Some(format!("it has only {} characters, while 12 or more resist guessing", psw.chars().count())),
+        Some(_) if args.enterprise.eap.is_none() && (psw.chars().all(|x| x.is_ascii_digit()) || psw.chars().all(|x| x.is_alphabetic() && x.is_lowercase())) =>
+            Some(String::from("it only has digits or lowercase letters, while mixing them resists guessing")),
+        _ => None
+    }
+}
+
+/// Reports what was built for the network: with `-v` the payload, the QR-Code `matrix` makes of it and what readers
+/// may trip over, and weak passwords.
+pub fn network(args: &Args, payload: &str, matrix: impl FnOnce() -> Result<Vec<Vec<bool>>, Error>) {
+    let ssid:&str = &args.ssid;
+    if enabled(Level::Info) {
+        let shown:String = if enabled(Level::Debug) {String::from(payload)}else{masked(args, payload)};
+        report(Level::Info, "payload", &format!("Payload of {}: {} ({} bytes)", ssid, shown, payload.len()), json!({"ssid": ssid, "payload": shown, "bytes": payload.len()}));
+        if let Ok(matrix) = matrix() {
+            let (version, micro) = match args.output.micro {
+                true => ((matrix.len() - 9) / 2, "M"),
+                false => ((matrix.len() - 17) / 4, "")
+            };
+            report(Level::Info, "symbol", &format!("QR-Code of {}: version {}{} ({}x{} modules), ECC-Level {:?}", ssid, micro, version, matrix.len(), matrix.len(), args.ecc),
+                json!({"ssid": ssid, "version": format!("{}{}", micro, version), "modules": matrix.len(), "ecc": format!("{:?}", args.ecc).to_lowercase()}));
+        }
+        for (field, value) in [("SSID", ssid), ("password", args.psw.as_deref().unwrap_or_default())] {
+            let escaped:Vec<String> = SPECIAL_CHARACTERS.iter().filter(|x| value.contains(**x)).map(|x| x.to_string()).collect();
+            if !escaped.is_empty() {
+                let listed:Vec<String> = escaped.iter().map(|x| format!("'{}'", x)).collect();
+                report(Level::Info, "escaped", &format!("The {} of {} has {} escaped with a backslash, which a few older readers keep", field, ssid, listed.join(", ")),
+                    json!({"ssid": ssid, "field": field.to_lowercase(), "characters": escaped}));
+            }
+        }
+    }
+    if let Some(weakness) = weakness(args) {
+        report(Level::Warning, "weak-password", &format!("the password of {} is weak: {}.", ssid, weakness), json!({"ssid": ssid}));
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    #[test]
+    fn test_line() {
+        assert_eq!(line(LogFormat::Text, Level::Warning, "weak-password", "the password is weak.", json!({"ssid": "Home"})), "Warning: the password is weak.");
+        assert_eq!(line(LogFormat::Json, Level::Info, "symbol", "QR-Code", json!({"version": "3"})), r#"{"event":"symbol","level":"info","message":"QR-Code","version":"3"}"#);
+        assert_eq!(line(LogFormat::Json, Level::Error, "error", "Failed", Value::Null), r#"{"event":"error","level":"error","message":"Failed"}"#);
+    }
+    #[test]
+    fn test_masked_and_weakness() {
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Home", "--psw", "hunter;22"]);
+        assert_eq!(masked(&args, "WIFI:T:WPA;S:Home;P:hunter\\;22;;"), "WIFI:T:WPA;S:Home;P:*********;;");
+        assert!(weakness(&args).unwrap().contains("only 9 characters"));
+        assert!(weakness(&Args::parse_from(["wifiqr", "--ssid", "Home", "--psw", "correcthorsebattery"])).unwrap().contains("lowercase"));
+        assert_eq!(weakness(&Args::parse_from(["wifiqr", "--ssid", "Home", "--psw", "Correct-Horse-7"])), None);
+        assert_eq!(weakness(&Args::parse_from(["wifiqr", "--ssid", "Cafe"])), None);
+    }
+    #[test]
+    fn test_options() {
+        assert_eq!(Args::parse_from(["wifiqr", "--ssid", "Home", "-vv"]).output.log.verbose, 2);
+        assert_eq!(Args::parse_from(["wifiqr", "--ssid", "Home", "--log-format", "json"]).output.log.log_format, LogFormat::Json);
+        assert!(Args::try_parse_from(["wifiqr", "--ssid", "Home", "-v", "-q"]).is_err());
+    }
+}
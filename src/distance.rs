@@ -0,0 +1,146 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Adapting the outputs to the distance they're scanned from, for lobby banners and stage screens.
+
+use std::fmt;
+
+use crate::branding::{Branding, Palette};
+use crate::pdf::{Page, MM};
+use crate::{ECCLevel, QUIET_ZONE};
+
+/// The usual rule of thumb scans a code from ten times its width. For a code of 25 modules, the smallest there is
+/// for most networks, that makes each module 1/250 of the distance.
+pub const DISTANCE_PER_MODULE: f32 = 250.0;
+
+/// Printer dots each module should span at least, so dot gain doesn't blur its edges.
+const DOTS_PER_MODULE: f32 = 4.0;
+
+/// Printed dimensions of a code, as reported by `size-calc`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintSize{
+    /// Modules per side, without the quiet zone.
+    pub modules: usize,
+    /// Module size in millimeters.
+    pub module_size: f32,
+    /// Distance in meters the code can be scanned from.
+    pub distance: f32
+}
+impl PrintSize {
+    /// Size needed to scan a code of `modules` from `meters` away.
+    pub fn for_distance(modules: usize, meters: f32) -> Self {
+        PrintSize{ modules, module_size: min_module_size(meters) / MM, distance: meters }
+    }
+    /// Distance a code of `modules` printed `width` millimeters wide, including the quiet zone, scans from.
+    pub fn for_width(modules: usize, width: f32) -> Self {
+        let module_size:f32 = width / (modules + 2 * QUIET_ZONE) as f32;
+        PrintSize{ modules, module_size, distance: module_size * DISTANCE_PER_MODULE / 1000.0 }
+    }
+    /// Width in millimeters including the quiet zone.
+    pub fn width(&self) -> f32 {
+        self.module_size * (self.modules + 2 * QUIET_ZONE) as f32
+    }
+    /// Lowest printer resolution that gives every module [`DOTS_PER_MODULE`] dots.
+    pub fn min_dpi(&self) -> u32 {
+        (DOTS_PER_MODULE * 25.4 / self.module_size).ceil() as u32
+    }
+}
+impl fmt::Display for PrintSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "QR-Code:       version {}, {} x {} modules plus a quiet zone of {}", (self.modules - 17) / 4, self.modules, self.modules, QUIET_ZONE)?;
+        writeln!(f, "Scan distance: {:.2} m", self.distance)?;
+        writeln!(f, "Module size:   {:.2} mm", self.module_size)?;
+        writeln!(f, "Printed size:  {:.1} x {:.1} mm including the quiet zone", self.width(), self.width())?;
+        writeln!(f, "Resolution:    at least {} DPI, for {} dots per module", self.min_dpi(), DOTS_PER_MODULE)
+    }
+}
+
+/// A table of the sizes of a payload at each ECC-Level, `None` where it doesn't fit.
+pub fn comparison_table(rows: &[(ECCLevel, Option<PrintSize>)]) -> String {
+    let mut table:String = String::from("ECC-Level  Version  Modules  Module size  Printed size  Scan distance\n");
+    for (ecc, size) in rows {
+        let level:String = format!("{:?}", ecc);
+        table += &match size {
+            Some(size) => format!("{:<9}  {:>7}  {:>7}  {:>8.2} mm  {:>9.1} mm  {:>11.2} m\n",
+                level, (size.modules - 17) / 4, size.modules, size.module_size, size.width(), size.distance),
+            None => format!("{:<9}  doesn't fit into a QR-Code\n", level)
+        };
+    }
+    table
+}
+
+/// Smallest module size in points that can be scanned from `meters` away.
+pub fn min_module_size(meters: f32) -> f32 {
+    meters * 1000.0 * MM / DISTANCE_PER_MODULE
+}
+
+/// Glare, blur and the angle of view take their toll from afar, so more of the code is spent on error correction.
+pub fn min_ecc(meters: f32) -> ECCLevel {
+    if meters >= 5.0 {
+        ECCLevel::Quartile
+    }else if meters >= 1.0 {
+        ECCLevel::Medium
+    }else{
+        ECCLevel::Low
+    }
+}
+
+/// Drops the logo, frame and colors of the branding, leaving black captions on white for the most contrast.
+pub fn simplify(branding: &mut Branding) {
+    branding.logo_image = None;
+    branding.frame = None;
+    branding.palette = Palette::default();
+}
+
+/// The smallest module of the QR-Codes on the pages if it's too small to be scanned from `meters` away.
+pub fn too_small_module(pages: &[Page], meters: f32) -> Option<f32> {
+    pages.iter().flat_map(|x| x.module_sizes.iter().copied()).reduce(f32::min).filter(|x| *x < min_module_size(meters))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_min_module_size() {
+        // From ten meters, each module has to be 4 cm wide.
+        assert!((min_module_size(10.0) - 40.0 * MM).abs() < 0.001);
+        assert_eq!(min_ecc(0.5), ECCLevel::Low);
+        assert_eq!(min_ecc(10.0), ECCLevel::Quartile);
+        let mut page = Page::new(100.0, 100.0);
+        page.qr_code(&vec![vec![true; 10]; 10], crate::pdf::Rect::new(0.0, 0.0, 50.0, 50.0));
+        assert_eq!(too_small_module(std::slice::from_ref(&page), 0.4), None);
+        assert_eq!(too_small_module(&[page], 2.0), Some(5.0));
+    }
+    #[test]
+    fn test_print_size() {
+        let size = PrintSize::for_distance(25, 2.5);
+        assert!((size.module_size - 10.0).abs() < 0.001);
+        assert!((size.width() - 330.0).abs() < 0.01);
+        assert_eq!(size.min_dpi(), 11);
+        // The other way round, a code printed 33 mm wide scans from a quarter meter.
+        let size = PrintSize::for_width(25, 33.0);
+        assert!((size.distance - 0.25).abs() < 0.001);
+        assert_eq!(size.min_dpi(), 102);
+        assert!(size.to_string().starts_with("QR-Code:       version 2, 25 x 25 modules"));
+    }
+    #[test]
+    fn test_comparison_table() {
+        let table = comparison_table(&[(ECCLevel::Low, Some(PrintSize::for_width(25, 33.0))), (ECCLevel::High, None)]);
+        assert_eq!(table, "ECC-Level  Version  Modules  Module size  Printed size  Scan distance\n\
+            Low              2       25      1.00 mm       33.0 mm         0.25 m\n\
+            High       doesn't fit into a QR-Code\n");
+    }
+}
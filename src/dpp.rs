@@ -0,0 +1,153 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Wi-Fi Easy Connect (DPP) URIs, for `wifiqr dpp`: devices and access points supporting Easy Connect are
+//! onboarded by scanning the `DPP:` URI of their public bootstrapping key, which the configurator, such as the Wi-Fi
+//! settings of Android 10 and later, authenticates them with, instead of a shared password. The key is usually
+//! printed by the device or shown by `wpa_cli dpp_bootstrap_get_uri`, or written by `openssl ec -pubout`.
+
+use std::fmt;
+use std::path::Path;
+
+use base64::Engine;
+
+/// The object identifier of elliptic curve public keys, 1.2.840.10045.2.1, in DER.
+const EC_PUBLIC_KEY: [u8; 9] = [0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DppError{
+    /// The key isn't base64, PEM or DER.
+    Encoding,
+    /// The key isn't the DER of a SubjectPublicKeyInfo.
+    Structure,
+    /// The key is another kind of key than an elliptic curve one.
+    Algorithm,
+    Io(String)
+}
+impl fmt::Display for DppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DppError::Encoding => write!(f, "the key has to be base64, or a PEM or DER file"),
+            DppError::Structure => write!(f, "the key isn't a public key in DER, the SubjectPublicKeyInfo after K: of a DPP URI"),
+            DppError::Algorithm => write!(f, "the key isn't an elliptic curve key, which Easy Connect uses"),
+            DppError::Io(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+/// The length of the DER element at the start of the bytes, with its tag and length, if they hold all of it.
+fn element_length(der: &[u8]) -> Option<usize> {
+    let (length, header):(usize, usize) = match *der.get(1)? {
+        short if short < 0x80 => (short as usize, 2),
+        long => {
+            let bytes:usize = (long & 0x7f) as usize;
+            if bytes == 0 || bytes > 2 {
+                return None;
+            }
+            (der.get(2..2 + bytes)?.iter().fold(0, |length, x| length << 8 | *x as usize), 2 + bytes)
+        }
+    };
+    (header + length <= der.len()).then_some(header + length)
+}
+
+/// The key in base64 as the URI holds it, from the base64 or the DER of an elliptic curve public key.
+fn checked(der: &[u8]) -> Result<String, DppError> {
+    if der.first() != Some(&0x30) || element_length(der) != Some(der.len()) {
+        return Err(DppError::Structure);
+    }
+    if !der.windows(EC_PUBLIC_KEY.len()).any(|x| x == EC_PUBLIC_KEY) {
+        return Err(DppError::Algorithm);
+    }
+    Ok(base64::engine::general_purpose::STANDARD.encode(der))
+}
+
+/// The key of `--key`, the base64 of its DER.
+pub fn key(base64: &str) -> Result<String, DppError> {
+    let der:Vec<u8> = base64::engine::general_purpose::STANDARD.decode(base64.trim()).map_err(|_| DppError::Encoding)?;
+    checked(&der)
+}
+
+/// The key of `--key-file`, a PEM public key, its DER, or its base64.
+pub fn key_file(path: &Path) -> Result<String, DppError> {
+    let contents:Vec<u8> = std::fs::read(path).map_err(|err| DppError::Io(format!("{}: {}", path.display(), err)))?;
+    if contents.first() == Some(&0x30) {
+        return checked(&contents);
+    }
+    let text:String = String::from_utf8(contents).map_err(|_| DppError::Encoding)?;
+    // The base64 between the armor lines of PEM, or all of it.
+    let base64:String = text.lines().filter(|x| !x.starts_with("-----")).map(str::trim).collect();
+    key(&base64)
+}
+
+/// Reads a channel of `--channel`, `<CLASS>/<CHANNEL>` with the global operating class, e.g. `81/1`.
+pub fn parse_channel(value: &str) -> Result<(u8, u8), String> {
+    let error = || format!("expected <CLASS>/<CHANNEL> with the global operating class, e.g. 81/1 for channel 1 of 2.4 GHz, not '{}'", value);
+    let (class, channel) = value.split_once('/').ok_or_else(error)?;
+    match (class.trim().parse::<u8>(), channel.trim().parse::<u8>()) {
+        (Ok(class), Ok(channel)) if class > 0 && channel > 0 => Ok((class, channel)),
+        _ => Err(error())
+    }
+}
+
+/// Reads a MAC address of `--mac`, with colons, dashes or neither between the bytes, e.g. `52:54:00:58:28:e5`.
+pub fn parse_mac(value: &str) -> Result<[u8; 6], String> {
+    let digits:String = value.chars().filter(|x| ![':', '-', '.'].contains(x)).collect();
+    let mut mac:[u8; 6] = [0; 6];
+    if digits.len() != 12 || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+        return Err(format!("expected a MAC address of 6 bytes, e.g. 52:54:00:58:28:e5, not '{}'", value));
+    }
+    for (i, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).expect("the digits are hex");
+    }
+    Ok(mac)
+}
+
+/// Reads the information of `--info`, printable ASCII without `;`, which ends the field.
+pub fn parse_info(value: &str) -> Result<String, String> {
+    match value.chars().all(|x| (' '..='~').contains(&x) && x != ';') {
+        true => Ok(String::from(value)),
+        false => Err(String::from("the information can only hold printable ASCII characters other than ';'"))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    /// The P-256 key of the example URI of wpa_supplicant.
+    const KEY: &str = "MDkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDIgADM2206avxHJaHXgLMkq/24e0rsrfMP9K1Tm8gx+ovP0I=";
+    #[test]
+    fn test_key() {
+        assert_eq!(key(KEY), Ok(String::from(KEY)));
+        assert_eq!(key("not base64!"), Err(DppError::Encoding));
+        // Cut short, the length of the sequence is wrong.
+        assert_eq!(key(&KEY[..40]), Err(DppError::Structure));
+        // An RSA key.
+        assert_eq!(checked(&[0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, 0x05, 0x00]), Err(DppError::Algorithm));
+        let path:std::path::PathBuf = std::env::temp_dir().join(format!("wifiqr-dpp-{}.pem", std::process::id()));
+        std::fs::write(&path, format!("-----BEGIN PUBLIC KEY-----\n{}\n{}\n-----END PUBLIC KEY-----\n", &KEY[..64], &KEY[64..])).unwrap();
+        assert_eq!(key_file(&path), Ok(String::from(KEY)));
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_channel("115/36"), Ok((115, 36)));
+        assert!(parse_channel("36").is_err());
+        assert_eq!(parse_mac("52-54-00-58-28-E5"), Ok([0x52, 0x54, 0x00, 0x58, 0x28, 0xe5]));
+        assert!(parse_mac("52:54:00:58:28").is_err());
+        assert!(parse_info("SN=4774LH2b4044").is_ok());
+        assert!(parse_info("a;b").is_err());
+    }
+}
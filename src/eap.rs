@@ -0,0 +1,153 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! 802.1X networks (WPA2/WPA3-Enterprise). Their payloads have the type `WPA2-EAP` and the fields Android and ZXing
+//! read for them: `E:` for the EAP method, `A:` for the anonymous identity, `I:` for the identity and `PH2:` for
+//! the phase-2 authentication.
+
+use std::fmt;
+
+use crate::escape_payload_value;
+
+/// The options of enterprise networks. Given `--eap`, the network is one.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Enterprise{
+    /// EAP method of a WPA2/WPA3-Enterprise (802.1X) network. The password is the one of the identity.
+    #[clap(arg_enum, long, value_parser)]
+    pub eap: Option<EapMethod>,
+
+    /// Identity (user name) to authenticate with, for `--eap`.
+    #[clap(long, value_parser)]
+    pub identity: Option<String>,
+
+    /// Identity sent unencrypted before the tunnel is set up, for `--eap peap` and `ttls`, e.g. `anonymous@example.com`.
+    #[clap(long, value_parser)]
+    pub anonymous_identity: Option<String>,
+
+    /// Authentication within the tunnel of `--eap peap` and `ttls`.
+    #[clap(arg_enum, long, value_parser)]
+    pub phase2: Option<Phase2>
+}
+impl Enterprise {
+    /// What is missing or contradicting in the options, if anything.
+    pub fn validate(&self, psw: Option<&str>) -> Result<(), String> {
+        let Some(eap) = self.eap else {
+            let given:bool = self.identity.is_some() || self.anonymous_identity.is_some() || self.phase2.is_some();
+            return if given {Err(String::from("--identity, --anonymous-identity and --phase2 are only used with --eap"))}else{Ok(())};
+        };
+        if self.identity.as_deref().is_none_or(str::is_empty) {
+            return Err(format!("{} needs the identity to authenticate with, provide it with --identity", eap));
+        }
+        if eap.tunneled() && psw.is_none_or(str::is_empty) {
+            return Err(format!("{} needs the password of the identity, provide it with --psw", eap));
+        }
+        if !eap.tunneled() && (self.phase2.is_some() || self.anonymous_identity.is_some()) {
+            return Err(format!("{} has no tunnel, so --phase2 and --anonymous-identity can't be used with it", eap));
+        }
+        Ok(())
+    }
+
+    /// The fields of the payload between the SSID and the password, e.g. `E:PEAP;A:anonymous;I:alice;`.
+    pub fn fields(&self) -> String {
+        let mut fields:String = String::new();
+        if let Some(eap) = self.eap {
+            fields += &format!("E:{};", eap.payload_token());
+        }
+        if let Some(anonymous_identity) = &self.anonymous_identity {
+            fields += &format!("A:{};", escape_payload_value(anonymous_identity));
+        }
+        if let Some(identity) = &self.identity {
+            fields += &format!("I:{};", escape_payload_value(identity));
+        }
+        fields
+    }
+    /// The field of the payload after the password, e.g. `PH2:MSCHAPV2;`.
+    pub fn phase2_field(&self) -> String {
+        self.phase2.map_or(String::new(), |x| format!("PH2:{};", x.payload_token()))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum EapMethod{
+    Peap,
+    Ttls,
+    /// Certificates only, which the code can't carry, so the device has to have one installed.
+    Tls,
+    Pwd
+}
+impl EapMethod {
+    pub fn payload_token(self) -> &'static str {
+        match self {
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+            EapMethod::Tls => "TLS",
+            EapMethod::Pwd => "PWD"
+        }
+    }
+    /// Whether the method sets up a TLS tunnel, authenticating with a password within it.
+    pub fn tunneled(self) -> bool {
+        matches!(self, EapMethod::Peap | EapMethod::Ttls)
+    }
+}
+impl fmt::Display for EapMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EAP-{}", self.payload_token())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum Phase2{
+    Mschapv2,
+    Gtc,
+    Pap,
+    Mschap,
+    Chap
+}
+impl Phase2 {
+    pub fn payload_token(self) -> &'static str {
+        match self {
+            Phase2::Mschapv2 => "MSCHAPV2",
+            Phase2::Gtc => "GTC",
+            Phase2::Pap => "PAP",
+            Phase2::Mschap => "MSCHAP",
+            Phase2::Chap => "CHAP"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_fields() {
+        let enterprise = Enterprise{ eap: Some(EapMethod::Peap), identity: Some(String::from("corp\\alice")), anonymous_identity: Some(String::from("anonymous")), phase2: Some(Phase2::Mschapv2) };
+        assert_eq!(enterprise.fields(), "E:PEAP;A:anonymous;I:corp\\\\alice;");
+        assert_eq!(enterprise.phase2_field(), "PH2:MSCHAPV2;");
+        assert!(enterprise.validate(Some("secret")).is_ok());
+        assert!(enterprise.validate(None).unwrap_err().contains("--psw"));
+    }
+    #[test]
+    fn test_validate() {
+        let mut enterprise = Enterprise{ eap: Some(EapMethod::Tls), ..Enterprise::default() };
+        assert!(enterprise.validate(None).unwrap_err().contains("--identity"));
+        enterprise.identity = Some(String::from("alice"));
+        assert!(enterprise.validate(None).is_ok());
+        enterprise.phase2 = Some(Phase2::Gtc);
+        assert!(enterprise.validate(None).is_err());
+        enterprise.eap = None;
+        assert!(enterprise.validate(None).unwrap_err().contains("only used with --eap"));
+    }
+}
@@ -0,0 +1,81 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Encapsulated PostScript of the QR-Code, for print shops that take nothing else. The code is drawn as filled
+//! rectangles at a physical size, which the bounding box tells the programs placing it.
+
+use std::fmt::Write;
+
+use crate::color::Color;
+use crate::pdf::MM;
+use crate::with_border;
+
+/// Width and height of EPS files in millimeters unless set, including the border.
+pub const DEFAULT_SIZE_MM: f32 = 40.0;
+
+/// The color operator of PostScript for the color.
+fn set_color(color: Color) -> String {
+    format!("{:.3} {:.3} {:.3} setrgbcolor", color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0)
+}
+
+/// An EPS file of the symbol with a border of `border` modules, `size_mm` millimeters wide and high, in the colors
+/// of the dark and light modules. The dark modules are drawn as one rectangle per horizontal run.
+pub fn document(matrix: &[Vec<bool>], border: usize, size_mm: f32, dark: Color, light: Color) -> String {
+    let matrix:Vec<Vec<bool>> = with_border(matrix, border);
+    let modules:usize = matrix.len();
+    let points:f32 = size_mm * MM;
+    let mut eps:String = String::from("%!PS-Adobe-3.0 EPSF-3.0\n");
+    // The bounding box is in whole points, so it is rounded up to hold the code, past the error of the conversion.
+    let _ = writeln!(eps, "%%BoundingBox: 0 0 {size} {size}", size = ((points * 1000.0).round() / 1000.0).ceil() as u32);
+    let _ = writeln!(eps, "%%HiResBoundingBox: 0 0 {size:.3} {size:.3}", size = points);
+    let _ = writeln!(eps, "%%Creator: wifiqr {}", env!("CARGO_PKG_VERSION"));
+    eps += "%%Title: Wi-Fi QR-Code\n%%Pages: 1\n%%EndComments\n";
+    let _ = writeln!(eps, "save\n/R {{1 rectfill}} bind def\n{:.5} dup scale", points / modules as f32);
+    let _ = writeln!(eps, "{}\n0 0 {modules} {modules} rectfill\n{}", set_color(light), set_color(dark));
+    for (y, row) in matrix.iter().enumerate() {
+        let mut x:usize = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let start:usize = x;
+            while x < row.len() && row[x] {
+                x += 1;
+            }
+            // PostScript counts rows from the bottom.
+            let _ = writeln!(eps, "{} {} {} R", start, modules - 1 - y, x - start);
+        }
+    }
+    eps += "restore\nshowpage\n%%EOF\n";
+    eps
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_document() {
+        let eps:String = document(&[vec![true, true], vec![false, true]], 1, 25.4, Color::BLACK, Color::WHITE);
+        assert!(eps.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 72 72\n%%HiResBoundingBox: 0 0 72.000 72.000\n"));
+        // 72 points for 4 modules with the border.
+        assert!(eps.contains("18.00000 dup scale\n1.000 1.000 1.000 setrgbcolor\n0 0 4 4 rectfill\n0.000 0.000 0.000 setrgbcolor\n"));
+        // The first row is one run of two modules, the second a single module.
+        assert!(eps.contains("\n1 2 2 R\n2 1 1 R\nrestore\n"));
+        assert!(eps.ends_with("%%EOF\n"));
+        assert!(document(&[vec![true]], 0, 40.0, Color::BLACK, Color::WHITE).contains("%%BoundingBox: 0 0 114 114\n"));
+    }
+}
@@ -0,0 +1,85 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The errors the commands fail with. Each kind of failure exits with its own code, so scripts can tell them apart.
+
+use std::fmt;
+
+/// What went wrong, deciding the exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind{
+    /// The options or networks given are invalid, e.g. a security standard without a password.
+    Usage,
+    /// A file couldn't be read, or isn't in the format it should be in.
+    Input,
+    /// An output couldn't be written.
+    Output,
+    /// The network doesn't fit into a QR-Code, or can't be printed large enough to be scanned.
+    Encoding,
+    /// Another program or a device failed, e.g. a password manager, an NFC reader or the window of the GUI.
+    External
+}
+impl ErrorKind {
+    /// The exit code, 1 being left to `--check` finding invalid entries. 2 is also what invalid arguments exit with.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Usage => 2,
+            ErrorKind::Input => 3,
+            ErrorKind::Output => 4,
+            ErrorKind::Encoding => 5,
+            ErrorKind::External => 6
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error{
+    pub kind: ErrorKind,
+    pub message: String
+}
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error{ kind, message: message.into() }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+impl std::error::Error for Error {}
+
+/// Turns the errors of a step into an [`Error`] of the kind, with a message saying what failed.
+pub trait Context<T, E> {
+    fn or_fail(self, kind: ErrorKind, message: impl FnOnce(E) -> String) -> Result<T, Error>;
+}
+impl<T, E> Context<T, E> for Result<T, E> {
+    fn or_fail(self, kind: ErrorKind, message: impl FnOnce(E) -> String) -> Result<T, Error> {
+        self.map_err(|err| Error::new(kind, message(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_or_fail() {
+        let result:Result<(), Error> = Err::<(), &str>("denied").or_fail(ErrorKind::Output, |err| format!("Failed to write out.png: {}", err));
+        let err:Error = result.unwrap_err();
+        assert_eq!(err.to_string(), "Failed to write out.png: denied");
+        assert_eq!(err.kind.exit_code(), 4);
+    }
+}
@@ -0,0 +1,140 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Walking through how the payload and the QR-Code of a network came about, for `--explain`: the defaults
+//! resolved, the fields written, and the ECC-Level and version chosen. The password is masked, so the explanation
+//! can be pasted into a bug report.
+
+use crate::{assemble_qr_string, capacity, distance, hex_ssid, is_hex_like, quote_payload_value, raw_key, resolve_security, symbol, written_password, Args, ECCLevel, Security, SPECIAL_CHARACTERS};
+
+/// How a value of the payload is written, and what its special characters became.
+fn field(name: &str, value: &str) -> String {
+    let special:Vec<String> = SPECIAL_CHARACTERS.iter().filter(|x| value.contains(**x)).map(|x| format!("'{}'", x)).collect();
+    if is_hex_like(value) {
+        format!("The {} consists of hex digits only, so it is quoted for readers not to decode it as bytes.", name)
+    }else if special.is_empty() {
+        format!("The {} has no special characters, so nothing is escaped.", name)
+    }else{
+        format!("The {} contains {}, escaped with a backslash so readers don't take them for the end of the {}.", name, special.join(", "), name)
+    }
+}
+
+/// Explains the payload and the QR-Code of `args`, the network as encoded, given the ECC-Level that was asked for.
+pub fn explain(args: &Args, requested_ecc: ECCLevel) -> String {
+    let mut steps:Vec<String> = Vec::new();
+    steps.push(match hex_ssid::written(args) {
+        Some(hex) => format!("SSID: \"{}\" ({} bytes), written as S:{};, the hex digits of its bytes, which Android decodes but iOS may take for the name.", args.ssid, hex.len() / 2, hex),
+        None => format!("SSID: \"{}\" ({} bytes), written as S:{};. {}", args.ssid, args.ssid.len(), quote_payload_value(&args.ssid), field("SSID", &args.ssid))
+    });
+    let psw:&str = args.psw.as_deref().unwrap_or_default();
+    let masked:String = "*".repeat(psw.chars().count());
+    if psw.is_empty() {
+        steps.push(String::from("Password: none, written as an empty P:;."));
+    }else{
+        let written:String = match raw_key(args) {
+            true => format!("The password is {} hex digits, the raw key, so it is left unquoted for readers to decode it.", psw.len()),
+            false => field("password", psw)
+        };
+        steps.push(format!("Password: {} characters, written as P:{};. {}", psw.chars().count(), masked, written));
+    }
+    let security:String = resolve_security(args).map_or(String::new(), |x| x.to_string());
+    let token:&str = resolve_security(args).map_or("nopass", Security::payload_token);
+    steps.push(match (args.sec, psw.is_empty()) {
+        (Some(sec), _) => format!("Security: {} as given with --sec, written as T:{};.", sec, token),
+        (None, false) => format!("Security: not given, {} is assumed as a password is set, written as T:{};.", security, token),
+        (None, true) => String::from("Security: none, as the network is open, written as T:nopass;.")
+    });
+    steps.push(match (args.hidden, args.compat) {
+        (true, _) => String::from("Hidden: yes, written as H:true;. Phones probe for hidden networks by name, instead of waiting for them to announce themselves."),
+        (false, true) => String::from("Hidden: no, written as H:false; for --compat."),
+        (false, false) => String::from("Hidden: no, so H: is left out, which readers take for a visible network.")
+    });
+    let payload:String = assemble_qr_string(args);
+    let shown:String = if psw.is_empty() {payload.clone()}else{payload.replacen(&format!("P:{};", written_password(args)), &format!("P:{};", masked), 1)};
+    steps.push(format!("Payload: {} ({} bytes, in byte mode as ';' isn't alphanumeric).", shown, payload.len()));
+    let for_distance:ECCLevel = args.scan_distance.map_or(requested_ecc, |meters| requested_ecc.max(distance::min_ecc(meters)));
+    let raised:ECCLevel = if args.output.logo.is_some() {ECCLevel::High}else{for_distance};
+    steps.push(if args.ecc < raised {
+        format!("ECC-Level: {:?}, lowered from {:?} by --downgrade-ecc, as the payload exceeds the {} bytes {:?} holds.", args.ecc, raised, capacity::byte_capacity(raised), raised)
+    }else if raised > for_distance {
+        format!("ECC-Level: {:?}, raised from {:?} to restore the modules covered by the --logo.", args.ecc, requested_ecc)
+    }else if raised > requested_ecc {
+        format!("ECC-Level: {:?}, raised from {:?} to survive glare and blur at a --scan-distance of {} m.", args.ecc, requested_ecc, args.scan_distance.unwrap_or_default())
+    }else{
+        format!("ECC-Level: {:?}, as requested with --ecc. It restores up to {}% of damaged modules.", args.ecc, match args.ecc {
+            ECCLevel::Low => 7,
+            ECCLevel::Medium => 15,
+            ECCLevel::Quartile => 25,
+            ECCLevel::High => 30
+        })
+    });
+    steps.push(match symbol::encode(&payload, args.ecc, &args.symbol) {
+        Ok(matrix) if args.symbol.qr_version.is_some() => format!("Version: {} ({}x{} modules), as pinned with --qr-version.", (matrix.len() - 17) / 4, matrix.len(), matrix.len()),
+        Ok(matrix) if args.symbol.min_version == Some(((matrix.len() - 17) / 4) as u8) => format!("Version: {} ({}x{} modules), as the --min-version, though a smaller one would hold {} bytes.",
+            (matrix.len() - 17) / 4, matrix.len(), matrix.len(), payload.len()),
+        Ok(matrix) => format!("Version: {} ({}x{} modules), the smallest holding {} bytes at ECC-Level {:?}.", (matrix.len() - 17) / 4, matrix.len(), matrix.len(), payload.len(), args.ecc),
+        Err(_) => format!("Version: none, {} bytes don't fit into a QR-Code at ECC-Level {:?}.", payload.len(), args.ecc)
+    });
+    let mut explanation:String = format!("How the QR-Code for {} was built:\n", args.ssid);
+    for (index, step) in steps.iter().enumerate() {
+        explanation += &format!("  {}. {}\n", index + 1, step);
+    }
+    explanation
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    fn network(ssid: &str, psw: Option<&str>, ecc: ECCLevel) -> Args {
+        Args{ ecc, ..crate::tests::network(ssid, psw, None) }
+    }
+    #[test]
+    fn test_explain() {
+        let explanation = explain(&network("Café;Bar", Some("hunter22"), ECCLevel::Low), ECCLevel::Low);
+        let lines:Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines[0], "How the QR-Code for Café;Bar was built:");
+        assert_eq!(lines[1], "  1. SSID: \"Café;Bar\" (9 bytes), written as S:Café\\;Bar;. The SSID contains ';', escaped with a backslash so readers don't take them for the end of the SSID.");
+        assert_eq!(lines[2], "  2. Password: 8 characters, written as P:********;. The password has no special characters, so nothing is escaped.");
+        assert_eq!(lines[3], "  3. Security: not given, Wpa2 is assumed as a password is set, written as T:WPA;.");
+        assert_eq!(lines[5], "  5. Payload: WIFI:T:WPA;S:Café\\;Bar;P:********;; (36 bytes, in byte mode as ';' isn't alphanumeric).");
+        assert_eq!(lines[7], "  7. Version: 3 (29x29 modules), the smallest holding 36 bytes at ECC-Level Low.");
+        assert!(!explanation.contains("hunter22"));
+        let mut args = network("Home", Some("hunter22"), ECCLevel::Low);
+        args.ssid = String::from("C0FFEE");
+        assert!(explain(&args, ECCLevel::Low).contains("written as S:\"C0FFEE\";. The SSID consists of hex digits only, so it is quoted for readers not to decode it as bytes."));
+        args.hex_ssid.write_ssid_hex = true;
+        assert!(explain(&args, ECCLevel::Low).contains("SSID: \"C0FFEE\" (6 bytes), written as S:433046464545;, the hex digits"));
+        args.hex_ssid.write_ssid_hex = false;
+        args.psw = Some("0123456789abcdef".repeat(4));
+        let explanation:String = explain(&args, ECCLevel::Low);
+        assert!(explanation.contains("The password is 64 hex digits, the raw key, so it is left unquoted"));
+        assert!(explanation.contains(&format!("P:{};;", "*".repeat(64))));
+        args.psw = Some(String::from("hunter22"));
+        args.symbol.qr_version = Some(6);
+        assert!(explain(&args, ECCLevel::Low).contains("Version: 6 (41x41 modules), as pinned with --qr-version."));
+    }
+    #[test]
+    fn test_explain_ecc() {
+        let mut args = network("Lobby", None, ECCLevel::Low);
+        args.scan_distance = Some(6.0);
+        args.ecc = ECCLevel::Quartile;
+        assert!(explain(&args, ECCLevel::Low).contains("ECC-Level: Quartile, raised from Low to survive glare and blur at a --scan-distance of 6 m."));
+        let mut args = network("Lobby", Some("x"), ECCLevel::Medium);
+        args.sec = Some(Security::Wpa3);
+        assert!(explain(&args, ECCLevel::High).contains("ECC-Level: Medium, lowered from High by --downgrade-ecc, as the payload exceeds the 1273 bytes High holds."));
+        assert!(explain(&args, ECCLevel::High).contains("Security: Wpa3 as given with --sec, written as T:SAE;."));
+    }
+}
@@ -0,0 +1,142 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The C ABI of the library, behind the `ffi` feature, for scripts calling the shared library, e.g. through
+//! Python's ctypes. [`wifi_qr_generate`] returns the payload and the modules of a network, which are released with
+//! [`wifi_qr_free`]. `include/wifi_qr.h` is generated from this module with
+//! `cbindgen --config cbindgen.toml --output include/wifi_qr.h`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr::null_mut;
+
+use crate::render::{with_border, QUIET_ZONE};
+use crate::{ecc_level, QrCodeEcc, Security, WifiQrBuilder};
+
+/// The code of a network, or why it couldn't be encoded.
+#[repr(C)]
+pub struct WifiQrResult{
+    /// The `WIFI:` payload, or null if the network couldn't be encoded.
+    pub payload: *mut c_char,
+    /// The modules with the quiet zone, `size` times `size` bytes row after row, 1 being dark, or null.
+    pub modules: *mut u8,
+    /// The width and height of the code in modules.
+    pub size: usize,
+    /// What went wrong, or null.
+    pub error: *mut c_char
+}
+
+/// The string behind a pointer, `None` if it is null or empty.
+///
+/// # Safety
+/// The pointer has to be null or point to a null-terminated string.
+unsafe fn optional_string(pointer: *const c_char) -> Result<Option<String>, String> {
+    if pointer.is_null() {
+        return Ok(None);
+    }
+    let value:&str = CStr::from_ptr(pointer).to_str().map_err(|_| String::from("the strings have to be UTF-8"))?;
+    Ok((!value.is_empty()).then(|| String::from(value)))
+}
+
+/// A string handed out to C, with the null characters, which the payload can't hold, left out.
+fn c_string(value: String) -> *mut c_char {
+    CString::new(value.replace('\0', "")).expect("the null characters were removed").into_raw()
+}
+
+/// Encodes the network, from the arguments given as the C strings they are.
+///
+/// # Safety
+/// The pointers have to be null or point to null-terminated strings.
+unsafe fn generate(ssid: *const c_char, psw: *const c_char, security: *const c_char, hidden: bool, ecc: *const c_char) -> Result<(String, Vec<Vec<bool>>), String> {
+    let ssid:String = optional_string(ssid)?.unwrap_or_default();
+    let ecc:QrCodeEcc = match optional_string(ecc)? {
+        Some(name) => ecc_level(&name)?,
+        None => QrCodeEcc::Low
+    };
+    let mut builder:WifiQrBuilder = WifiQrBuilder::new(ssid).hidden(hidden).ecc(ecc);
+    if let Some(psw) = optional_string(psw)? {
+        builder = builder.password(psw);
+    }
+    if let Some(name) = optional_string(security)? {
+        builder = builder.security(name.parse::<Security>()?);
+    }
+    let code = builder.build().map_err(|err| err.to_string())?;
+    Ok((String::from(code.payload()), with_border(code.matrix(), QUIET_ZONE)))
+}
+
+/// Encodes the network as `wifiqr` does. `psw`, `security` (`wep`, `wpa`, `wpa2`, `wpa3` or `wpa2-wpa3`) and
+/// `ecc` (`low` unless given, `medium`, `quartile` or `high`) may be null. The result is never null and has to be
+/// released with [`wifi_qr_free`].
+///
+/// # Safety
+/// The pointers have to be null or point to null-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn wifi_qr_generate(ssid: *const c_char, psw: *const c_char, security: *const c_char, hidden: bool, ecc: *const c_char) -> *mut WifiQrResult {
+    let result:WifiQrResult = match generate(ssid, psw, security, hidden, ecc) {
+        Ok((payload, matrix)) => {
+            let modules:Box<[u8]> = matrix.iter().flatten().map(|x| *x as u8).collect();
+            WifiQrResult{ payload: c_string(payload), modules: Box::into_raw(modules).cast(), size: matrix.len(), error: null_mut() }
+        },
+        Err(err) => WifiQrResult{ payload: null_mut(), modules: null_mut(), size: 0, error: c_string(err) }
+    };
+    Box::into_raw(Box::new(result))
+}
+
+/// Releases a result of [`wifi_qr_generate`]. Null is ignored.
+///
+/// # Safety
+/// The result has to be one returned by [`wifi_qr_generate`] and not be released already.
+#[no_mangle]
+pub unsafe extern "C" fn wifi_qr_free(result: *mut WifiQrResult) {
+    if result.is_null() {
+        return;
+    }
+    let result:Box<WifiQrResult> = Box::from_raw(result);
+    for string in [result.payload, result.error] {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    }
+    if !result.modules.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(result.modules, result.size * result.size)));
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use std::ptr::null;
+    #[test]
+    fn test_generate() {
+        let ssid:CString = CString::new("My;Home").unwrap();
+        let psw:CString = CString::new("hunter22").unwrap();
+        // SAFETY: The strings outlive the calls, and the results are released once.
+        unsafe {
+            let result:*mut WifiQrResult = wifi_qr_generate(ssid.as_ptr(), psw.as_ptr(), null(), false, null());
+            assert!((*result).error.is_null());
+            assert_eq!(CStr::from_ptr((*result).payload).to_str(), Ok("WIFI:T:WPA;S:My\\;Home;P:hunter22;;"));
+            assert_eq!((*result).size, 29 + 2 * QUIET_ZONE);
+            // The quiet zone is light, the corner of the finder pattern dark.
+            assert_eq!((*(*result).modules, *(*result).modules.add(QUIET_ZONE * (*result).size + QUIET_ZONE)), (0, 1));
+            wifi_qr_free(result);
+            let security:CString = CString::new("wpa4").unwrap();
+            let result:*mut WifiQrResult = wifi_qr_generate(ssid.as_ptr(), psw.as_ptr(), security.as_ptr(), false, null());
+            assert!((*result).payload.is_null() && (*result).modules.is_null());
+            assert!(CStr::from_ptr((*result).error).to_str().unwrap().starts_with("unknown security standard"));
+            wifi_qr_free(result);
+            wifi_qr_free(null_mut());
+        }
+    }
+}
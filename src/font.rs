@@ -0,0 +1,376 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! TrueType and OpenType fonts for captions, shaped with rustybuzz so scripts such as Arabic, Devanagari or Thai
+//! come out right, and reordered with the Unicode bidirectional algorithm so right-to-left and mixed text reads
+//! correctly. PDFs embed them as subsets of the glyphs actually used, images get them rasterized.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ab_glyph_rasterizer::{point, Point, Rasterizer};
+use image::{GrayImage, Luma};
+use subsetter::GlyphRemapper;
+use ttf_parser::GlyphId;
+
+/// Source of the ids that keep the resource names of fonts in a document apart.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug)]
+pub enum FontError{
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf),
+    NotFound(String)
+}
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(path, err) => write!(f, "failed to read the font {}: {}", path.display(), err),
+            FontError::Parse(path) => write!(f, "{} is not a TrueType or OpenType font", path.display()),
+            FontError::NotFound(family) => write!(f, "neither a font file nor an installed font family named '{}' was found", family)
+        }
+    }
+}
+
+/// A font loaded from a file. Fonts live until the program exits, so [`crate::pdf::Font`] can stay `Copy`.
+pub struct EmbeddedFont{
+    pub id: usize,
+    pub postscript_name: String,
+    pub face: rustybuzz::Face<'static>,
+    pub data: &'static [u8],
+    index: u32,
+    /// Glyph ids of the subset, assigned as glyphs are first drawn, so content streams can use them directly.
+    pub remapper: RefCell<GlyphRemapper>,
+    /// Text each glyph of the subset stands for, for copying text out of the PDF.
+    pub unicode: RefCell<BTreeMap<u16, String>>
+}
+impl fmt::Debug for EmbeddedFont {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EmbeddedFont({})", self.postscript_name)
+    }
+}
+impl PartialEq for EmbeddedFont {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+impl Eq for EmbeddedFont {}
+
+/// Whether the text is a right-to-left paragraph, judged by its first strong character as in the bidirectional
+/// algorithm.
+pub fn is_rtl(text: &str) -> bool {
+    unicode_bidi::get_base_direction(text) == unicode_bidi::Direction::Rtl
+}
+
+/// A glyph placed by the shaper, in font units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapedGlyph{
+    pub glyph: u16,
+    /// The text the glyph was shaped from, empty for all but the first glyph of a cluster.
+    pub text: String,
+    pub x_advance: i32,
+    /// Advance of vertical text, negative as the pen moves down.
+    pub y_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32
+}
+
+/// A glyph of the subset as drawn into a PDF, in 1/1000 em.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfGlyph{
+    pub glyph: u16,
+    /// Advance the PDF viewer applies by itself, from the widths of the font dictionary.
+    pub width: f32,
+    pub x_advance: f32,
+    pub y_advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32
+}
+
+/// Feeds glyph outlines into the rasterizer, flipping them so y points down like in images.
+struct OutlineRasterizer<'a>{
+    rasterizer: &'a mut Rasterizer,
+    scale: f32,
+    origin: (f32, f32),
+    start: Point,
+    last: Point
+}
+impl OutlineRasterizer<'_> {
+    fn point(&self, x: f32, y: f32) -> Point {
+        point(self.origin.0 + x * self.scale, self.origin.1 - y * self.scale)
+    }
+}
+impl ttf_parser::OutlineBuilder for OutlineRasterizer<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to:Point = self.point(x, y);
+        self.rasterizer.draw_line(self.last, to);
+        self.last = to;
+    }
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let to:Point = self.point(x, y);
+        self.rasterizer.draw_quad(self.last, self.point(x1, y1), to);
+        self.last = to;
+    }
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let to:Point = self.point(x, y);
+        self.rasterizer.draw_cubic(self.last, self.point(x1, y1), self.point(x2, y2), to);
+        self.last = to;
+    }
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
+
+impl EmbeddedFont {
+    /// Loads the first face of a `.ttf`, `.otf` or collection file.
+    pub fn load(path: &Path) -> Result<&'static Self, FontError> {
+        let data:Vec<u8> = std::fs::read(path).map_err(|err| FontError::Io(path.to_path_buf(), err))?;
+        EmbeddedFont::from_data(data, 0, path)
+    }
+    /// Looks up an installed font family, such as "Noto Sans Arabic".
+    pub fn find(family: &str) -> Result<&'static Self, FontError> {
+        EmbeddedFont::query(&[fontdb::Family::Name(family)]).ok_or_else(|| FontError::NotFound(family.to_string()))
+    }
+    /// The default sans-serif font of the system, falling back to common families if it isn't installed.
+    pub fn find_sans_serif() -> Option<&'static Self> {
+        EmbeddedFont::query(&[fontdb::Family::SansSerif, fontdb::Family::Name("DejaVu Sans"), fontdb::Family::Name("Noto Sans"), fontdb::Family::Name("Liberation Sans")])
+    }
+    fn query(families: &[fontdb::Family]) -> Option<&'static Self> {
+        let mut database = fontdb::Database::new();
+        database.load_system_fonts();
+        let id = database.query(&fontdb::Query{ families, ..fontdb::Query::default() })?;
+        let path:PathBuf = match &database.face(id)?.source {
+            fontdb::Source::File(path) | fontdb::Source::SharedFile(path, _) => path.clone(),
+            fontdb::Source::Binary(_) => PathBuf::new()
+        };
+        let (data, index) = database.with_face_data(id, |data, index| (data.to_vec(), index))?;
+        EmbeddedFont::from_data(data, index, &path).ok()
+    }
+    fn from_data(data: Vec<u8>, index: u32, path: &Path) -> Result<&'static Self, FontError> {
+        let data:&'static [u8] = Box::leak(data.into_boxed_slice());
+        let face = rustybuzz::Face::from_slice(data, index).ok_or_else(|| FontError::Parse(path.to_path_buf()))?;
+        // PDF names can't contain spaces or delimiters, which some fonts have in their PostScript name anyway.
+        let postscript_name:String = face.names().into_iter()
+            .find(|x| x.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .and_then(|x| x.to_string())
+            .unwrap_or_else(|| path.file_stem().map(|x| x.to_string_lossy().into_owned()).unwrap_or_default())
+            .chars().filter(|x| x.is_ascii_alphanumeric() || *x == '-').collect();
+        Ok(Box::leak(Box::new(EmbeddedFont{
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            postscript_name,
+            face,
+            data,
+            index,
+            remapper: RefCell::new(GlyphRemapper::new()),
+            unicode: RefCell::new(BTreeMap::new())
+        })))
+    }
+    /// Whether the outlines are CFF rather than TrueType, which is embedded differently.
+    pub fn is_cff(&self) -> bool {
+        self.face.tables().cff.is_some()
+    }
+    /// Converts font units into 1/1000 em, the unit of PDF glyph space.
+    pub fn scale(&self, value: f32) -> f32 {
+        value * 1000.0 / self.face.units_per_em() as f32
+    }
+    fn default_advance(&self, glyph: u16) -> f32 {
+        self.scale(self.face.glyph_hor_advance(GlyphId(glyph)).unwrap_or(0) as f32)
+    }
+    /// Shapes a single line of text. The line is split into runs of one direction, which are shaped on their own
+    /// and put into visual order, so the glyphs can be drawn left to right.
+    pub fn shape(&self, text: &str) -> Vec<ShapedGlyph> {
+        let bidi = unicode_bidi::ParagraphBidiInfo::new(text, None);
+        let (levels, runs) = bidi.visual_runs(0..text.len());
+        runs.into_iter().flat_map(|run| {
+            let direction = if levels[run.start].is_rtl() {rustybuzz::Direction::RightToLeft}else{rustybuzz::Direction::LeftToRight};
+            self.shape_run(&text[run.clone()], direction)
+        }).collect()
+    }
+    /// Shapes a column of text set top to bottom, as in Japanese or Chinese signage. Fonts with vertical forms
+    /// substitute them, e.g. for brackets and the long vowel mark; all other glyphs stay upright. Glyph offsets are
+    /// relative to the pen on the center line of the column.
+    pub fn shape_vertical(&self, text: &str) -> Vec<ShapedGlyph> {
+        self.shape_run(text, rustybuzz::Direction::TopToBottom)
+    }
+    fn shape_run(&self, text: &str, direction: rustybuzz::Direction) -> Vec<ShapedGlyph> {
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        buffer.set_direction(direction);
+        let output = rustybuzz::shape(&self.face, &[], buffer);
+        let mut clusters:Vec<usize> = output.glyph_infos().iter().map(|x| x.cluster as usize).collect();
+        clusters.sort_unstable();
+        clusters.dedup();
+        let mut seen:Vec<usize> = Vec::new();
+        output.glyph_infos().iter().zip(output.glyph_positions()).map(|(info, position)| {
+            let start:usize = info.cluster as usize;
+            let cluster_text:String = if seen.contains(&start) {String::new()}else{
+                seen.push(start);
+                let end:usize = clusters.iter().find(|x| **x > start).copied().unwrap_or(text.len());
+                text[start..end].to_string()
+            };
+            ShapedGlyph{ glyph: info.glyph_id as u16, text: cluster_text, x_advance: position.x_advance, y_advance: position.y_advance, x_offset: position.x_offset, y_offset: position.y_offset }
+        }).collect()
+    }
+    /// Advance width of the shaped text in 1/1000 em.
+    pub fn text_width(&self, text: &str) -> f32 {
+        self.shape(text).iter().map(|x| self.scale(x.x_advance as f32)).sum()
+    }
+    /// Advance height of the text set vertically in 1/1000 em.
+    pub fn text_height(&self, text: &str) -> f32 {
+        self.shape_vertical(text).iter().map(|x| -self.scale(x.y_advance as f32)).sum()
+    }
+    /// Shapes the text and maps it to glyph ids of the subset, adding glyphs that weren't used before.
+    pub fn encode(&self, text: &str) -> Vec<PdfGlyph> {
+        self.encode_glyphs(self.shape(text))
+    }
+    /// Like [`EmbeddedFont::encode`], but for text set top to bottom.
+    pub fn encode_vertical(&self, text: &str) -> Vec<PdfGlyph> {
+        self.encode_glyphs(self.shape_vertical(text))
+    }
+    fn encode_glyphs(&self, glyphs: Vec<ShapedGlyph>) -> Vec<PdfGlyph> {
+        let mut remapper = self.remapper.borrow_mut();
+        let mut unicode = self.unicode.borrow_mut();
+        glyphs.into_iter().map(|x| {
+            let glyph:u16 = remapper.remap(x.glyph);
+            if !x.text.is_empty() {
+                unicode.entry(glyph).or_insert(x.text);
+            }
+            PdfGlyph{
+                glyph,
+                width: self.default_advance(x.glyph),
+                x_advance: self.scale(x.x_advance as f32),
+                y_advance: self.scale(x.y_advance as f32),
+                x_offset: self.scale(x.x_offset as f32),
+                y_offset: self.scale(x.y_offset as f32)
+            }
+        }).collect()
+    }
+    /// Renders a line of text into a coverage mask `size` pixels per em, as tall as the font's ascender and
+    /// descender.
+    pub fn render(&self, text: &str, size: f32) -> GrayImage {
+        let scale:f32 = size / self.face.units_per_em() as f32;
+        let glyphs:Vec<ShapedGlyph> = self.shape(text);
+        let ascent:f32 = self.face.ascender() as f32 * scale;
+        let width:usize = (glyphs.iter().map(|x| x.x_advance as f32 * scale).sum::<f32>().ceil() as usize).max(1);
+        let height:usize = ((self.face.ascender() - self.face.descender()) as f32 * scale).ceil() as usize;
+        let mut rasterizer = Rasterizer::new(width, height);
+        let mut pen:f32 = 0.0;
+        for glyph in &glyphs {
+            let origin:(f32, f32) = (pen + glyph.x_offset as f32 * scale, ascent - glyph.y_offset as f32 * scale);
+            let mut builder = OutlineRasterizer{ rasterizer: &mut rasterizer, scale, origin, start: point(0.0, 0.0), last: point(0.0, 0.0) };
+            self.face.outline_glyph(GlyphId(glyph.glyph), &mut builder);
+            pen += glyph.x_advance as f32 * scale;
+        }
+        let mut mask = GrayImage::new(width as u32, height as u32);
+        rasterizer.for_each_pixel_2d(|x, y, coverage| mask.put_pixel(x, y, Luma([(coverage.min(1.0) * 255.0) as u8])));
+        mask
+    }
+    /// The font program reduced to the glyphs used so far. Falls back to the whole font if it can't be subset.
+    pub fn subset(&self) -> Vec<u8> {
+        subsetter::subset(self.data, self.index, &self.remapper.borrow()).unwrap_or_else(|_| self.data.to_vec())
+    }
+    /// Width of every glyph of the subset in 1/1000 em, ordered by the new glyph id.
+    pub fn subset_widths(&self) -> Vec<f32> {
+        self.remapper.borrow().remapped_gids().map(|x| self.scale(self.face.glyph_hor_advance(GlyphId(x)).unwrap_or(0) as f32)).collect()
+    }
+    /// Six uppercase letters prefixed to the font name, telling readers that the font is a subset.
+    pub fn subset_tag(&self) -> String {
+        let mut hash:u32 = 2166136261;
+        for glyph in self.remapper.borrow().remapped_gids() {
+            hash = (hash ^ glyph as u32).wrapping_mul(16777619);
+        }
+        (0..6).map(|i| (b'A' + ((hash >> (i * 5)) % 26) as u8) as char).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    const DEJAVU_SANS: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+    #[test]
+    fn test_subset_glyph_ids() {
+        let Ok(font) = EmbeddedFont::load(Path::new(DEJAVU_SANS)) else { return; };
+        assert_eq!(font.postscript_name, "DejaVuSans");
+        // The subset ids start after .notdef and are handed out in order of first use.
+        assert_eq!(font.encode("aba").iter().map(|x| x.glyph).collect::<Vec<u16>>(), vec![1, 2, 1]);
+        assert_eq!(font.unicode.borrow()[&2], "b");
+        assert_eq!(font.subset_widths().len(), 3);
+        assert!(font.subset().len() < font.data.len() / 10);
+        assert!(font.text_width("ab") > 1000.0);
+    }
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(EmbeddedFont::load(Path::new("/nonexistent.ttf")), Err(FontError::Io(..))));
+        assert!(matches!(EmbeddedFont::load(Path::new("Cargo.toml")), Err(FontError::Parse(..))));
+        assert!(matches!(EmbeddedFont::find("No Such Family 1234"), Err(FontError::NotFound(..))));
+    }
+    #[test]
+    fn test_shaping() {
+        let Ok(font) = EmbeddedFont::load(Path::new(DEJAVU_SANS)) else { return; };
+        // Arabic letters take their joining forms and come out right to left, so the last letter is drawn first.
+        let glyphs = font.shape("بيت");
+        assert_eq!(glyphs.len(), 3);
+        assert_eq!(glyphs[0].text, "ت");
+        assert_ne!(glyphs[2].glyph, font.face.glyph_index('ب').unwrap().0);
+        // Kerning moves "A" and "V" closer than their advance widths.
+        let unshaped:f32 = ['A', 'V'].iter().map(|x| font.default_advance(font.face.glyph_index(*x).unwrap().0)).sum();
+        assert!(font.text_width("AV") < unshaped);
+    }
+    #[test]
+    fn test_bidirectional_text() {
+        assert!(is_rtl("مقهى Cafe") && !is_rtl("Cafe مقهى") && !is_rtl("123"));
+        let Ok(font) = EmbeddedFont::load(Path::new(DEJAVU_SANS)) else { return; };
+        // In a right-to-left paragraph the Latin word ends up on the left, still reading left to right.
+        let glyphs = font.shape("مقهى Cafe");
+        let texts:String = glyphs.iter().map(|x| x.text.clone()).collect();
+        assert_eq!(texts, "Cafe ىهقم");
+        // A number in parentheses after Arabic text joins its right-to-left run, with the parentheses mirrored so
+        // they still enclose it.
+        let glyphs = font.shape("Cafe مقهى (1)");
+        let texts:String = glyphs.iter().map(|x| x.text.clone()).collect();
+        assert_eq!(texts, "Cafe )1( ىهقم");
+        assert_eq!(glyphs[5].glyph, font.face.glyph_index('(').unwrap().0);
+    }
+    #[test]
+    fn test_vertical_text() {
+        let Ok(font) = EmbeddedFont::load(Path::new(DEJAVU_SANS)) else { return; };
+        // Glyphs are stacked one below the other, centered on the column rather than starting at it.
+        let glyphs = font.shape_vertical("ab");
+        assert_eq!(glyphs.len(), 2);
+        assert!(glyphs.iter().all(|x| x.x_advance == 0 && x.y_advance < 0 && x.x_offset < 0));
+        assert!((font.text_height("ab") - 2.0 * font.text_height("a")).abs() < 0.001);
+    }
+    #[test]
+    fn test_render() {
+        let Ok(font) = EmbeddedFont::load(Path::new(DEJAVU_SANS)) else { return; };
+        let mask = font.render("l", 100.0);
+        assert_eq!(mask.height(), 117);
+        // The stem of the "l" is solid, the space beside it empty.
+        assert_eq!(mask.get_pixel(mask.width() / 2, 50)[0], 255);
+        assert_eq!(mask.get_pixel(0, 50)[0], 0);
+    }
+}
@@ -0,0 +1,146 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Drawing the QR-Code as an image in terminals with a graphics protocol: Sixel, the Kitty graphics protocol or
+//! iTerm2 inline images. Real pixels scan better than character cells, whose gaps and aspect ratio vary by font.
+
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+use base64::Engine;
+use image::RgbaImage;
+
+use crate::console::Render;
+
+/// Pixels per module of the images, unless given with `--module-size`.
+pub const MODULE_PIXELS: u32 = 8;
+/// Kitty takes the image in chunks of at most this many bytes of base64.
+const KITTY_CHUNK: usize = 4096;
+
+/// The graphics protocol of the terminal, by the variables terminals set, if the output goes to one. Sixel can't be
+/// told from the environment for most terminals, only for those that always support it.
+pub fn detect() -> Option<Render> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    if std::env::var_os("KITTY_WINDOW_ID").is_some() || var("TERM") == "xterm-kitty" || var("TERM_PROGRAM") == "ghostty" {
+        Some(Render::Kitty)
+    }else if ["iTerm.app", "WezTerm"].contains(&var("TERM_PROGRAM").as_str()) {
+        Some(Render::Iterm)
+    }else if ["foot", "mlterm"].iter().any(|x| var("TERM").starts_with(x)) || var("TERM").contains("sixel") {
+        Some(Render::Sixel)
+    }else{
+        None
+    }
+}
+
+fn png(image: &RgbaImage) -> Vec<u8> {
+    let mut png:Vec<u8> = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+    png
+}
+
+/// The escape sequences showing the image with the protocol, followed by a newline. `render` has to be one of the
+/// graphics protocols.
+pub fn draw(render: Render, image: &RgbaImage) -> String {
+    match render {
+        Render::Kitty => kitty(&png(image)),
+        Render::Iterm => iterm(&png(image)),
+        Render::Sixel => sixel(image),
+        _ => unreachable!("{:?} isn't a graphics protocol", render)
+    }
+}
+
+/// Transmits and shows the PNG at the cursor in one go, in chunks, each continued with `m=1` but the last.
+fn kitty(png: &[u8]) -> String {
+    let encoded:String = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks:Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut output:String = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let keys:&str = if index == 0 {"a=T,f=100,"}else{""};
+        let more:u8 = u8::from(index + 1 < chunks.len());
+        let _ = write!(output, "\x1b_G{}m={};{}\x1b\\", keys, more, std::str::from_utf8(chunk).unwrap());
+    }
+    output + "\n"
+}
+
+fn iterm(png: &[u8]) -> String {
+    format!("\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07\n", png.len(), base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// Encodes the image as Sixel with a register per color, each band of six rows drawn once per color present in
+/// it, with runs of the same sixel compressed.
+fn sixel(image: &RgbaImage) -> String {
+    let mut palette:Vec<[u8; 3]> = Vec::new();
+    let indices:Vec<usize> = image.pixels().map(|pixel| {
+        let rgb:[u8; 3] = [pixel[0], pixel[1], pixel[2]];
+        palette.iter().position(|x| *x == rgb).unwrap_or_else(|| {
+            palette.push(rgb);
+            palette.len() - 1
+        })
+    }).collect();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    // Raster attributes with a 1:1 aspect ratio, then the palette in percent.
+    let mut output:String = format!("\x1bP0;1q\"1;1;{};{}", width, height);
+    for (index, rgb) in palette.iter().enumerate() {
+        let percent = |x: u8| (x as u32 * 100 + 127) / 255;
+        let _ = write!(output, "#{};2;{};{};{}", index, percent(rgb[0]), percent(rgb[1]), percent(rgb[2]));
+    }
+    for band in (0..height).step_by(6) {
+        for color in 0..palette.len() {
+            let sixels:Vec<u8> = (0..width).map(|x| {
+                (0..6).filter(|row| band + row < height && indices[(band + row) * width + x] == color).fold(0, |bits, row| bits | 1 << row)
+            }).collect();
+            if sixels.iter().all(|x| *x == 0) {
+                continue;
+            }
+            let _ = write!(output, "#{}", color);
+            let mut x:usize = 0;
+            while x < width {
+                let run:usize = sixels[x..].iter().take_while(|y| **y == sixels[x]).count();
+                let character:char = (63 + sixels[x]) as char;
+                if run > 3 {
+                    let _ = write!(output, "!{}{}", run, character);
+                }else{
+                    output.extend(std::iter::repeat_n(character, run));
+                }
+                x += run;
+            }
+            output.push('$');
+        }
+        output.push('-');
+    }
+    output + "\x1b\\\n"
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_kitty() {
+        let output = kitty(&[0; 4000]);
+        // 4000 bytes are 5336 bytes of base64, sent in two chunks.
+        assert!(output.starts_with("\x1b_Ga=T,f=100,m=1;AAAA"));
+        assert!(output.contains("\x1b\\\x1b_Gm=0;"));
+        assert!(output.ends_with("\x1b\\\n"));
+    }
+    #[test]
+    fn test_sixel() {
+        let image = RgbaImage::from_fn(8, 7, |x, _| if x < 4 {image::Rgba([0, 0, 0, 255])}else{image::Rgba([255, 255, 255, 255])});
+        assert_eq!(sixel(&image), "\x1bP0;1q\"1;1;8;7#0;2;0;0;0#1;2;100;100;100#0!4~!4?$#1!4?!4~$-#0!4@!4?$#1!4?!4@$-\x1b\\\n");
+    }
+}
@@ -0,0 +1,238 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A window with fields for the network, a live preview of the QR-Code and buttons to save or print it, for users
+//! who never open a terminal.
+
+use std::fmt;
+
+use crate::branding::Branding;
+use crate::Args;
+#[cfg(feature = "gui")]
+use crate::private_dir::PrivateDir;
+#[cfg(feature = "gui")]
+use crate::{alt_text, assemble_qr_string, image_caption, layout, pdf, wallpaper, ECCLevel, Security};
+
+/// Side length of the preview in points.
+#[cfg(feature = "gui")]
+const PREVIEW_SIZE: f32 = 320.0;
+
+#[derive(Debug)]
+pub enum GuiError{
+    #[cfg(not(feature = "gui"))]
+    Unsupported,
+    #[cfg(feature = "gui")]
+    Eframe(eframe::Error)
+}
+impl fmt::Display for GuiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "gui"))]
+            GuiError::Unsupported => write!(f, "wifiqr was built without the GUI, rebuild it with `--features gui`"),
+            #[cfg(feature = "gui")]
+            GuiError::Eframe(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+struct App<'a>{
+    ssid: String,
+    psw: String,
+    /// `None` for open networks, in which case the password is ignored.
+    sec: Option<Security>,
+    hidden: bool,
+    ecc: ECCLevel,
+    /// Directory the files are saved to.
+    directory: String,
+    shred: bool,
+    branding: &'a Branding,
+    /// The payload the preview shows, so it's only rendered again after the network changed.
+    preview: Option<(String, eframe::egui::TextureHandle)>,
+    status: String
+}
+#[cfg(feature = "gui")]
+impl App<'_> {
+    /// The network as arguments for the renderers, or why it can't be encoded yet.
+    fn args(&self) -> Result<Args, &'static str> {
+        if self.ssid.is_empty() {
+            return Err("Enter the SSID of the network.");
+        }
+        if self.sec.is_some() && self.psw.is_empty() {
+            return Err("Enter the password, or choose an open network.");
+        }
+        Ok(Args{
+            ssid: self.ssid.clone(),
+            hex_ssid: crate::hex_ssid::Options::default(),
+            psw: self.sec.map(|_| self.psw.clone()),
+            interactive: false,
+            psw_source: crate::credentials::PasswordSource::default(),
+            generator: crate::passphrase::Generator::default(),
+            saved: crate::saved::Source::default(),
+            sec: self.sec,
+            hidden: self.hidden,
+            compat: false,
+            transition_disable: false,
+            ecc: self.ecc,
+            symbol: crate::symbol::Options::default(),
+            branding: None,
+            font: None,
+            align: None,
+            frame: None,
+            palette: None,
+            scan_distance: None,
+            shred: self.shred,
+            downgrade_ecc: false,
+            force: false,
+            wep: crate::wep::Options::default(),
+            info_url: None,
+            explain: false,
+            verify: false,
+            output: crate::output::Options::default(),
+            config: None,
+            enterprise: crate::eap::Enterprise::default(),
+            networks: Vec::new(),
+            command: None
+        })
+    }
+    fn update_preview(&mut self, ctx: &eframe::egui::Context, args: &Args) {
+        let payload:String = format!("{}{:?}", assemble_qr_string(args), args.ecc);
+        if self.preview.as_ref().is_some_and(|(x, _)| *x == payload) {
+            return;
+        }
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(assemble_qr_string(args), args.ecc.into()).unwrap();
+        let image = wallpaper::qr_image(&matrix, 2 * PREVIEW_SIZE as u32);
+        let size:[usize; 2] = [image.width() as usize, image.height() as usize];
+        let texture = ctx.load_texture("qr", eframe::egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()), eframe::egui::TextureOptions::NEAREST);
+        self.preview = Some((payload, texture));
+    }
+    fn save_png(&self, args: &Args) -> Result<std::path::PathBuf, String> {
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(assemble_qr_string(args), args.ecc.into()).unwrap();
+        let image = wallpaper::qr_panel(&matrix, 1024, image_caption(args, self.branding, false));
+        let path = std::path::Path::new(&self.directory).join(format!("{}.png", args.ssid));
+        alt_text::save_image(&image, &path, Some(&alt_text::describe(args)), false).map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+        Ok(path)
+    }
+    fn table_tent(&self, args: &Args) -> Vec<u8> {
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(assemble_qr_string(args), args.ecc.into()).unwrap();
+        pdf::write_document(&layout::table_tent(args, &matrix, self.branding, layout::PaperSize::A4))
+    }
+    fn save_pdf(&self, args: &Args) -> Result<std::path::PathBuf, String> {
+        let path = std::path::Path::new(&self.directory).join(format!("{}-table-tent.pdf", args.ssid));
+        std::fs::write(&path, self.table_tent(args)).map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+        Ok(path)
+    }
+    /// Prints the table tent through `lp`, which CUPS provides on Linux and macOS.
+    fn print(&self, args: &Args) -> Result<(), String> {
+        let mut directory = PrivateDir::create(self.shred).map_err(|err| format!("Failed to create a temporary directory: {}", err))?;
+        let path = directory.write("table-tent.pdf", &self.table_tent(args)).map_err(|err| format!("Failed to write the temporary file: {}", err))?;
+        // lp hands the file to the spooler before it returns, so it can be deleted right after.
+        match std::process::Command::new("lp").arg(&path).output() {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(format!("Printing failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+            Err(err) => Err(format!("Failed to run lp: {}", err))
+        }
+    }
+}
+#[cfg(feature = "gui")]
+impl eframe::App for App<'_> {
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, _frame: &mut eframe::Frame) {
+        use eframe::egui;
+        egui::CentralPanel::default().show(ui, |ui| {
+            egui::Grid::new("network").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                ui.label("SSID");
+                ui.text_edit_singleline(&mut self.ssid);
+                ui.end_row();
+                ui.label("Security");
+                egui::ComboBox::from_id_salt("security").selected_text(self.sec.map_or(String::from("Open"), |x| x.to_string().to_uppercase())).show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.sec, None, "Open");
+                    for sec in [Security::Wep, Security::Wpa, Security::Wpa2, Security::Wpa3, Security::Wpa2Wpa3] {
+                        ui.selectable_value(&mut self.sec, Some(sec), sec.to_string().to_uppercase());
+                    }
+                });
+                ui.end_row();
+                ui.label("Password");
+                ui.add_enabled(self.sec.is_some(), egui::TextEdit::singleline(&mut self.psw).password(true));
+                ui.end_row();
+                ui.label("");
+                ui.checkbox(&mut self.hidden, "Hidden network");
+                ui.end_row();
+                ui.label("Error correction");
+                egui::ComboBox::from_id_salt("ecc").selected_text(format!("{:?}", self.ecc)).show_ui(ui, |ui| {
+                    for ecc in [ECCLevel::Low, ECCLevel::Medium, ECCLevel::Quartile, ECCLevel::High] {
+                        ui.selectable_value(&mut self.ecc, ecc, format!("{:?}", ecc));
+                    }
+                });
+                ui.end_row();
+                ui.label("Save to");
+                ui.text_edit_singleline(&mut self.directory);
+                ui.end_row();
+            });
+            ui.separator();
+            let args = match self.args() {
+                Ok(args) => args,
+                Err(message) => {
+                    ui.label(message);
+                    return;
+                }
+            };
+            self.update_preview(ui.ctx(), &args);
+            if let Some((_, texture)) = &self.preview {
+                ui.add(egui::Image::new(texture).fit_to_exact_size(egui::vec2(PREVIEW_SIZE, PREVIEW_SIZE)));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save PNG").clicked() {
+                    self.status = self.save_png(&args).map_or_else(|err| err, |path| format!("Saved {}.", path.display()));
+                }
+                if ui.button("Save PDF").clicked() {
+                    self.status = self.save_pdf(&args).map_or_else(|err| err, |path| format!("Saved {}.", path.display()));
+                }
+                if ui.button("Print").clicked() {
+                    self.status = self.print(&args).map_or_else(|err| err, |_| String::from("Sent the table tent to the printer."));
+                }
+            });
+            ui.label(&self.status);
+        });
+    }
+}
+
+/// Opens the window, filled in with the network from the command line, and blocks until it is closed.
+#[cfg(feature = "gui")]
+pub fn run(args: &Args, branding: &Branding) -> Result<(), GuiError> {
+    let app = App{
+        ssid: args.ssid.clone(),
+        psw: args.psw.clone().unwrap_or_default(),
+        // Like `resolve_security`, without insisting on a password yet.
+        sec: args.sec.or_else(|| args.psw.as_ref().filter(|x| !x.is_empty()).map(|_| Security::Wpa2)),
+        hidden: args.hidden,
+        ecc: args.ecc,
+        directory: String::from("."),
+        shred: args.shred,
+        branding,
+        preview: None,
+        status: String::new()
+    };
+    let options = eframe::NativeOptions{
+        viewport: eframe::egui::ViewportBuilder::default().with_inner_size([420.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native("WIFI-QR", options, Box::new(|_| Ok(Box::new(app)))).map_err(GuiError::Eframe)
+}
+
+#[cfg(not(feature = "gui"))]
+pub fn run(_args: &Args, _branding: &Branding) -> Result<(), GuiError> {
+    Err(GuiError::Unsupported)
+}
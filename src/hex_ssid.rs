@@ -0,0 +1,100 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! SSIDs written as the hex digits of their bytes. SSIDs are up to 32 bytes the access point announces, which are
+//! UTF-8 for emoji, umlauts or CJK characters, and encoded as such in byte mode. Some readers garble them, and a few
+//! access points announce bytes that aren't UTF-8 at all, so `--ssid-hex` and `--write-ssid-hex` write `S:` as
+//! hex digits, which Android decodes to the bytes. iOS may take them for the name itself, though.
+
+use crate::{hex_payload_value, Args};
+
+/// The options of SSIDs written as hex digits.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options{
+    /// WIFI SSID as the hex digits of its bytes, e.g. `436166C3A9` for `Café`, for SSIDs with bytes that aren't
+    /// UTF-8. Written as hex digits in the payload.
+    #[clap(long, value_parser = parse, conflicts_with = "ssid")]
+    pub ssid_hex: Option<String>,
+
+    /// Write the SSID as the hex digits of its UTF-8 bytes, for readers that garble emoji, umlauts or CJK
+    /// characters. Android decodes them, iOS may not, so keep this for codes scanned by Android devices.
+    #[clap(long, action)]
+    pub write_ssid_hex: bool
+}
+
+/// Checks the hex digits of `--ssid-hex`, which may be given in groups like MAC addresses, e.g. `43:61:66:65`.
+fn parse(value: &str) -> Result<String, String> {
+    let digits:String = value.chars().filter(|x| ![':', '-', ' '].contains(x)).collect::<String>().to_uppercase();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+        return Err(String::from("expected an even number of hex digits, two per byte of the SSID"));
+    }
+    Ok(digits)
+}
+
+/// The bytes of the hex digits checked by [`parse`].
+fn decode(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("--ssid-hex is checked when parsing")).collect()
+}
+
+/// The bytes of the SSID the access point announces: those of `--ssid-hex`, or the UTF-8 of `--ssid`.
+pub fn bytes(args: &Args) -> Vec<u8> {
+    match &args.hex_ssid.ssid_hex {
+        Some(hex) => decode(hex),
+        None => args.ssid.as_bytes().to_vec()
+    }
+}
+
+/// The bytes of the SSID, if it is written as hex digits.
+pub fn written_bytes(args: &Args) -> Option<Vec<u8>> {
+    (args.hex_ssid.ssid_hex.is_some() || args.hex_ssid.write_ssid_hex).then(|| bytes(args))
+}
+
+/// The SSID as hex digits, if it is written in that form.
+pub fn written(args: &Args) -> Option<String> {
+    written_bytes(args).map(|x| hex_payload_value(&x))
+}
+
+/// Fills in the SSID of `--ssid-hex` for captions, file names and messages, with the bytes that aren't UTF-8
+/// replaced with `�`.
+pub fn prepare(args: &mut Args) {
+    if let Some(hex) = &args.hex_ssid.ssid_hex {
+        args.ssid = String::from_utf8_lossy(&decode(hex)).into_owned();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("43:61:66:c3:a9"), Ok(String::from("436166C3A9")));
+        assert!(parse("4361F").is_err() && parse("43zz").is_err() && parse("").is_err());
+        assert_eq!(decode("436166C3A9"), "Café".as_bytes());
+    }
+    #[test]
+    fn test_prepare() {
+        let mut args:Args = Args::parse_from(["wifiqr", "--ssid-hex", "4361FE"]);
+        prepare(&mut args);
+        assert_eq!(args.ssid, "Ca\u{fffd}");
+        assert_eq!(bytes(&args), [0x43, 0x61, 0xfe]);
+        assert_eq!(written(&args).as_deref(), Some("4361FE"));
+        let mut args:Args = Args::parse_from(["wifiqr", "--ssid", "Café", "--write-ssid-hex"]);
+        prepare(&mut args);
+        assert_eq!((args.ssid.as_str(), written(&args).as_deref()), ("Café", Some("436166C3A9")));
+        assert!(Args::try_parse_from(["wifiqr", "--ssid", "Café", "--ssid-hex", "43"]).is_err());
+    }
+}
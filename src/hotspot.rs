@@ -0,0 +1,163 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Sharing the connection of the machine as a hotspot, for `hotspot`: NetworkManager brings up an access point
+//! connection like the one of `nmcli device wifi hotspot`, Windows the hosted network of `netsh wlan`, which not every driver supports.
+//! The hotspot is taken down again once Ctrl+C is pressed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::saved::SavedError;
+use crate::Args;
+
+/// The band of the hotspot, which NetworkManager picks itself unless given.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Band{
+    /// 2.4 GHz, which every device joins
+    #[clap(name = "2.4")]
+    Ghz2,
+    /// 5 GHz
+    #[clap(name = "5")]
+    Ghz5
+}
+
+/// Name of the NetworkManager connection of the hotspot, replaced by every `hotspot`.
+#[cfg(not(windows))]
+const CONNECTION: &str = "wifiqr-hotspot";
+
+/// A running hotspot, which [`Hotspot::stop`] takes down.
+pub struct Hotspot;
+
+/// The settings of the NetworkManager connection of the hotspot, as `nmcli connection add` takes them: the access
+/// point `nmcli device wifi hotspot` would make, without its password, which is handed over as it's brought up.
+#[cfg(not(windows))]
+fn nm_settings(args: &Args, interface: Option<&str>, band: Option<Band>) -> Vec<String> {
+    let mut settings:Vec<String> = ["type", "wifi", "con-name", CONNECTION, "autoconnect", "no", "ssid", &args.ssid, "802-11-wireless.mode", "ap", "ipv4.method", "shared"].map(String::from).to_vec();
+    if let Some(interface) = interface {
+        settings.extend([String::from("ifname"), String::from(interface)]);
+    }
+    if let Some(band) = band {
+        settings.extend([String::from("802-11-wireless.band"), String::from(if band == Band::Ghz5 {"a"}else{"bg"})]);
+    }
+    settings.extend(["wifi-sec.key-mgmt", "wpa-psk", "wifi-sec.proto", "rsn", "wifi-sec.pairwise", "ccmp", "wifi-sec.group", "ccmp"].map(String::from));
+    settings
+}
+
+/// Brings up the hotspot of the network, a WPA2 network with the password of `args`, on the Wi-Fi adapter
+/// `interface` or the first one.
+#[cfg(not(windows))]
+pub fn start(args: &Args, interface: Option<&str>, band: Option<Band>) -> Result<Hotspot, SavedError> {
+    let names:String = crate::saved::nmcli(&["--get-values", "NAME", "connection", "show"])?;
+    if names.lines().any(|x| x == CONNECTION) {
+        crate::saved::nmcli(&["connection", "delete", "id", CONNECTION])?;
+    }
+    let settings:Vec<String> = nm_settings(args, interface, band);
+    crate::saved::nmcli(&[&["connection", "add"], settings.iter().map(String::as_str).collect::<Vec<&str>>().as_slice()].concat())?;
+    let secrets:String = format!("802-11-wireless-security.psk:{}\n", args.psw.as_deref().unwrap_or_default());
+    crate::saved::nmcli_up(CONNECTION, Some(&secrets), args.shred)?;
+    Ok(Hotspot)
+}
+/// Brings up the hosted network of the network, a WPA2 network with the password of `args`. Windows doesn't let it
+/// pick the adapter or the band.
+#[cfg(windows)]
+pub fn start(args: &Args, _interface: Option<&str>, _band: Option<Band>) -> Result<Hotspot, SavedError> {
+    let quoted = |value: &str| format!("\"{}\"", value);
+    netsh(&["wlan", "set", "hostednetwork", "mode=allow", &format!("ssid={}", quoted(&args.ssid)), &format!("key={}", quoted(args.psw.as_deref().unwrap_or_default())), "keyUsage=temporary"])?;
+    netsh(&["wlan", "start", "hostednetwork"])?;
+    Ok(Hotspot)
+}
+
+/// Runs `netsh`, which reports its errors on stdout. The arguments are passed as they are, for the quotes around
+/// values to reach it.
+#[cfg(windows)]
+fn netsh(arguments: &[&str]) -> Result<String, SavedError> {
+    use std::os::windows::process::CommandExt;
+    let mut command = std::process::Command::new("netsh");
+    for argument in arguments {
+        command.raw_arg(argument);
+    }
+    let output = command.output().map_err(|err| SavedError::Io(String::from("netsh"), err))?;
+    let stdout:String = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        return Err(SavedError::CommandFailed(String::from("netsh"), stdout));
+    }
+    Ok(stdout)
+}
+
+impl Hotspot {
+    /// Takes the hotspot down, removing its connection.
+    #[cfg(not(windows))]
+    pub fn stop(self) -> Result<(), SavedError> {
+        crate::saved::nmcli(&["connection", "down", "id", CONNECTION])?;
+        crate::saved::nmcli(&["connection", "delete", "id", CONNECTION]).map(drop)
+    }
+    /// Takes the hosted network down.
+    #[cfg(windows)]
+    pub fn stop(self) -> Result<(), SavedError> {
+        netsh(&["wlan", "stop", "hostednetwork"]).map(drop)
+    }
+}
+
+/// Set once Ctrl+C is pressed, or the process is asked to terminate.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn interrupted(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+#[cfg(windows)]
+unsafe extern "system" fn interrupted(_: u32) -> windows_sys::Win32::Foundation::BOOL {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    1
+}
+
+/// Keeps Ctrl+C from ending the process, so [`wait_for_interrupt`] returns instead and the hotspot is taken down.
+pub fn catch_interrupt() {
+    // SAFETY: The handler only stores into an atomic, which is safe from a signal handler.
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+    // SAFETY: The handler only stores into an atomic, from the thread Windows calls it on.
+    #[cfg(windows)]
+    unsafe {
+        windows_sys::Win32::System::Console::SetConsoleCtrlHandler(Some(interrupted), 1);
+    }
+}
+
+/// Waits until Ctrl+C is pressed, once [`catch_interrupt`] was called.
+pub fn wait_for_interrupt() {
+    while !INTERRUPTED.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    #[test]
+    fn test_nm_settings() {
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Demo Room", "--psw", "hunter2222"]);
+        let security:&str = "wifi-sec.key-mgmt wpa-psk wifi-sec.proto rsn wifi-sec.pairwise ccmp wifi-sec.group ccmp";
+        assert_eq!(nm_settings(&args, None, None).join(" "), format!("type wifi con-name wifiqr-hotspot autoconnect no ssid Demo Room 802-11-wireless.mode ap ipv4.method shared {}", security));
+        assert_eq!(nm_settings(&args, Some("wlan1"), Some(Band::Ghz5)).join(" "),
+            format!("type wifi con-name wifiqr-hotspot autoconnect no ssid Demo Room 802-11-wireless.mode ap ipv4.method shared ifname wlan1 802-11-wireless.band a {}", security));
+        assert!(!nm_settings(&args, None, None).contains(&String::from("hunter2222")));
+    }
+}
@@ -0,0 +1,224 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A self-contained HTML page showing the QR-Code full-screen, for front desks, or printed on a page of its own.
+//! The SVG of the code, the logo and the fonts of the branding are inlined, so the page is a single file that works
+//! offline and can be sent by email.
+
+use base64::Engine;
+
+use crate::branding::Branding;
+use crate::font::EmbeddedFont;
+use crate::i18n::{Language, Texts};
+use crate::layout::{PaperSize, Sheet};
+use crate::pdf::{Font, MM};
+use crate::{alt_text, svg, Args};
+
+/// The font as a CSS font family, with an `@font-face` rule embedding it if it's a font file.
+fn css_font(font: Font, name: &str) -> (String, Option<String>) {
+    let fallback:&str = "Helvetica, Arial, sans-serif";
+    match font {
+        Font::Helvetica | Font::HelveticaBold => (String::from(fallback), None),
+        // Browsers can't pick a font out of a collection.
+        Font::Embedded(font) if font.data.starts_with(b"ttcf") => (format!("\"{}\", {}", font.postscript_name, fallback), None),
+        Font::Embedded(font) => (format!("\"{}\", {}", name, fallback), Some(font_face(font, name)))
+    }
+}
+fn font_face(font: &EmbeddedFont, name: &str) -> String {
+    let mime:&str = if font.is_cff() {"font/otf"}else{"font/ttf"};
+    format!("@font-face {{ font-family: \"{}\"; src: url(data:{};base64,{}); }}\n", name, mime, base64::engine::general_purpose::STANDARD.encode(font.data))
+}
+
+/// The page for the network. Clicking it switches the browser to full-screen.
+pub fn page(args: &Args, matrix: &[Vec<bool>], branding: &Branding) -> String {
+    let (heading, heading_face) = css_font(branding.fonts.heading, "wifiqr-heading");
+    let (body, body_face) = css_font(branding.fonts.body, "wifiqr-body");
+    let weight:&str = if branding.fonts.heading == Font::HelveticaBold {"bold"}else{"normal"};
+    let logo:String = branding.logo_image.as_ref().map_or(String::new(), |logo| {
+        let mut png:Vec<u8> = Vec::new();
+        logo.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+        format!("<img class=\"logo\" alt=\"\" src=\"data:image/png;base64,{}\">\n", base64::engine::general_purpose::STANDARD.encode(&png))
+    });
+    let texts:&Texts = args.output.lang.texts();
+    let password:String = match &args.psw {
+        Some(psw) if !psw.is_empty() => psw.clone(),
+        _ => String::from(texts.no_password)
+    };
+    let filled = |text: &str| svg::escape(&text.replace("{ssid}", &args.ssid));
+    let (title, banner) = match &args.output.page_title {
+        Some(title) => (filled(title), filled(title)),
+        None => (format!("{}: {}", texts.wifi, svg::escape(&args.ssid)), String::from(texts.wifi))
+    };
+    let hint:String = match (&args.output.instructions, &args.info_url) {
+        (Some(instructions), _) => filled(instructions),
+        (None, Some(link)) => format!("{}. {} {}", texts.scan_to_join, texts.cant_scan, svg::escape(link)),
+        (None, None) => String::from(texts.scan_to_join)
+    };
+    let footer:String = branding.footer.as_ref().map_or(String::new(), |x| format!("<footer dir=\"auto\">{}</footer>\n", svg::escape(x)));
+    format!("<!DOCTYPE html>
+<html lang=\"{lang}\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>
+{}{}html, body {{ height: 100%; margin: 0; }}
+body {{ display: flex; flex-direction: column; align-items: center; justify-content: center; gap: 2vmin; background: {background}; color: {text}; font-family: {body}; font-size: 4vmin; text-align: center; cursor: default; }}
+h1 {{ margin: 0; color: {accent}; font-family: {heading}; font-weight: {weight}; font-size: 8vmin; }}
+.logo {{ max-height: 12vh; max-width: 40vw; }}
+.code {{ width: min(60vh, 90vw); height: min(60vh, 90vw); }}
+.code svg {{ display: block; width: 100%; height: 100%; }}
+dl {{ display: grid; grid-template-columns: auto auto; gap: 0 1em; margin: 0; }}
+dt {{ color: {accent}; font-family: {heading}; font-weight: {weight}; text-align: end; }}
+dd {{ margin: 0; text-align: start; overflow-wrap: anywhere; }}
+p, footer {{ margin: 0; font-size: 3vmin; }}
+@media print {{
+html, body {{ height: auto; }}
+body {{ padding-top: 2cm; gap: 0.8cm; font-size: 14pt; print-color-adjust: exact; -webkit-print-color-adjust: exact; }}
+h1 {{ font-size: 32pt; }}
+.logo {{ max-height: 3cm; max-width: 8cm; }}
+.code {{ width: 10cm; height: 10cm; break-inside: avoid; }}
+p, footer {{ font-size: 11pt; }}
+}}
+</style>
+</head>
+<body onclick=\"document.fullscreenElement || document.documentElement.requestFullscreen()\">
+{logo}<h1>{banner}</h1>
+<div class=\"code\" role=\"img\" aria-label=\"{description}\">{}</div>
+<dl><dt>{network}</dt><dd dir=\"auto\">{ssid}</dd><dt>{password_label}</dt><dd dir=\"auto\">{password}</dd></dl>
+<p>{hint}</p>
+{footer}</body>
+</html>
+", heading_face.unwrap_or_default(), body_face.unwrap_or_default(), svg::qr_code(matrix, &alt_text::describe(args)),
+        title = title, banner = banner, lang = args.output.lang.code(), network = texts.network, password_label = texts.password, ssid = svg::escape(&args.ssid), password = svg::escape(&password), description = svg::escape(&alt_text::describe(args)),
+        background = branding.palette.background, text = branding.palette.text, accent = branding.palette.accent)
+}
+
+/// The start of a page of cards tiled on sheets of paper, printed and cut apart along their dashed outlines, for the
+/// networks of a batch. [`sheet_card`] adds the cards, and [`SHEET_END`] closes the page.
+pub fn sheet_start(branding: &Branding, sheet: Sheet, lang: Language) -> String {
+    let (heading, heading_face) = css_font(branding.fonts.heading, "wifiqr-heading");
+    let (body, body_face) = css_font(branding.fonts.body, "wifiqr-body");
+    let weight:&str = if branding.fonts.heading == Font::HelveticaBold {"bold"}else{"normal"};
+    let (width, height) = sheet.card_size();
+    let (width, height) = (width / MM, height / MM);
+    // The cards are laid out as the PDF lays them out, with the code on the left of cards wider than they are high.
+    let (padding, size, qr, areas) = if width > height * 1.2 {
+        let inner:(f32, f32) = (width - height * 0.16, height * 0.84);
+        let qr:f32 = inner.1.min(inner.0 * 0.45);
+        (height * 0.08, (inner.1 / 10.0).min((inner.0 - qr * 13.0 / 12.0) / 12.0), qr, format!("grid-template-columns: {:.2}mm 1fr; grid-template-areas: \"code title\" \"code details\" \"code footer\"; column-gap: {:.2}mm; text-align: start;", qr, qr / 12.0))
+    }else{
+        let size:f32 = width * 0.84 / 16.0;
+        (width * 0.08, size, (width * 0.84).min(height * 0.84 - size * 7.5), String::from("grid-template-areas: \"title\" \"code\" \"details\" \"footer\"; justify-items: center; text-align: center;"))
+    };
+    let paper:&str = match sheet.paper {
+        PaperSize::A4 => "A4",
+        PaperSize::Letter => "letter"
+    };
+    format!("<!DOCTYPE html>
+<html lang=\"{lang}\">
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+{}{}@page {{ size: {paper}; margin: 12mm; }}
+html, body {{ margin: 0; }}
+body {{ color: {text}; font-family: {body}; print-color-adjust: exact; -webkit-print-color-adjust: exact; }}
+.sheet {{ display: grid; grid-template-columns: repeat({columns}, {width:.2}mm); grid-auto-rows: {height:.2}mm; break-after: page; }}
+.card {{ box-sizing: border-box; display: grid; {areas} align-content: center; row-gap: {gap:.2}mm; padding: {padding:.2}mm; overflow: hidden; background: {background}; outline: 0.25pt dashed #999; outline-offset: -0.125pt; font-size: {size:.2}mm; }}
+.card h2 {{ grid-area: title; margin: 0; color: {accent}; font-family: {heading}; font-weight: {weight}; font-size: 2em; }}
+.card .code {{ grid-area: code; width: {qr:.2}mm; height: {qr:.2}mm; }}
+.card svg {{ display: block; width: 100%; height: 100%; }}
+.card dl {{ grid-area: details; display: grid; grid-template-columns: auto auto; gap: 0 0.5em; margin: 0; }}
+.card dt {{ color: {accent}; font-family: {heading}; font-weight: {weight}; }}
+.card dd {{ margin: 0; overflow-wrap: anywhere; }}
+.card footer {{ grid-area: footer; font-size: 0.75em; }}
+</style>
+</head>
+<body>
+", heading_face.unwrap_or_default(), body_face.unwrap_or_default(),
+        lang = lang.code(), title = lang.texts().wifi, paper = paper, columns = sheet.columns, width = width, height = height, areas = areas, gap = size * 0.5, padding = padding, size = size, qr = qr,
+        heading = heading, body = body, weight = weight, background = branding.palette.background, text = branding.palette.text, accent = branding.palette.accent)
+}
+
+/// The card of the network, the `index`-th of the page, starting a new sheet every `cards_per_page` cards.
+pub fn sheet_card(args: &Args, matrix: &[Vec<bool>], branding: &Branding, index: usize, cards_per_page: usize) -> String {
+    let mut card:String = String::new();
+    if index.is_multiple_of(cards_per_page) {
+        if index > 0 {
+            card.push_str("</section>\n");
+        }
+        card.push_str("<section class=\"sheet\">\n");
+    }
+    let texts:&Texts = args.output.lang.texts();
+    let password:String = match &args.psw {
+        Some(psw) if !psw.is_empty() => psw.clone(),
+        _ => String::from(texts.no_password)
+    };
+    let footer:String = branding.footer.as_ref().map_or(String::new(), |x| format!("<footer dir=\"auto\">{}</footer>", svg::escape(x)));
+    card.push_str(&format!("<div class=\"card\"><h2>{}</h2><div class=\"code\" role=\"img\" aria-label=\"{description}\">{}</div><dl><dt>{}</dt><dd dir=\"auto\">{}</dd><dt>{}</dt><dd dir=\"auto\">{}</dd></dl>{}</div>\n",
+        texts.wifi, svg::qr_code(matrix, &alt_text::describe(args)), texts.network, svg::escape(&args.ssid), texts.password, svg::escape(&password), footer, description = svg::escape(&alt_text::describe(args))));
+    card
+}
+
+/// Closes the page of [`sheet_start`] after the last card.
+pub const SHEET_END: &str = "</section>\n</body>\n</html>\n";
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_page() {
+        let args = Args{ info_url: Some(String::from("https://example.com/wifi")), ..crate::tests::network("Café <Lobby>", None, None) };
+        let mut branding = Branding::default();
+        branding.footer = Some(String::from("Reception 24/7"));
+        let page = page(&args, &[vec![true]], &branding);
+        assert!(page.contains("<title>Wi-Fi: Café &lt;Lobby&gt;</title>"));
+        assert!(page.contains("<dd dir=\"auto\">none (open network)</dd>"));
+        assert!(page.contains("Can't scan? Visit https://example.com/wifi"));
+        assert!(page.contains("<footer dir=\"auto\">Reception 24/7</footer>"));
+        assert!(page.contains("<path d=\""));
+        // Nothing is loaded from elsewhere.
+        assert!(!page.contains("src=\"http") && !page.contains("url(http"));
+    }
+    #[test]
+    fn test_page_title() {
+        let mut args:Args = <Args as clap::Parser>::parse_from(["wifiqr", "--ssid", "Lobby", "--psw", "hunter22", "--info-url", "https://example.com/wifi"]);
+        args.output.page_title = Some(String::from("Welcome to {ssid} & friends"));
+        args.output.instructions = Some(String::from("Point your camera at the code to join {ssid}."));
+        let page = page(&args, &[vec![true]], &Branding::default());
+        assert!(page.contains("<title>Welcome to Lobby &amp; friends</title>"));
+        assert!(page.contains("<h1>Welcome to Lobby &amp; friends</h1>"));
+        assert!(page.contains("<p>Point your camera at the code to join Lobby.</p>"));
+    }
+    #[test]
+    fn test_lang() {
+        let args:Args = <Args as clap::Parser>::parse_from(["wifiqr", "--ssid", "Lobby", "--lang", "de", "--info-url", "https://example.com/wifi"]);
+        let page = page(&args, &[vec![true]], &Branding::default());
+        assert!(page.contains("<html lang=\"de\">") && page.contains("<title>WLAN: Lobby</title>"));
+        assert!(page.contains("<dt>Netzwerk</dt>") && page.contains("<dt>Passwort</dt><dd dir=\"auto\">keins (offenes Netzwerk)</dd>"));
+        assert!(page.contains("<p>Zum Verbinden scannen. Scannen klappt nicht? Besuchen Sie https://example.com/wifi</p>"));
+    }    #[test]
+    fn test_sheet() {
+        let sheet = Sheet{ paper: PaperSize::Letter, columns: 2, rows: 4 };
+        let start:String = sheet_start(&Branding::default(), sheet, Language::En);
+        assert!(start.contains("@page { size: letter; margin: 12mm; }") && start.contains("grid-template-columns: repeat(2, "));
+        let args:Args = <Args as clap::Parser>::parse_from(["wifiqr", "--ssid", "Lobby & Bar"]);
+        let cards:Vec<String> = (0..9).map(|index| sheet_card(&args, &[vec![true]], &Branding::default(), index, sheet.cards_per_page())).collect();
+        assert!(cards[0].starts_with("<section class=\"sheet\">\n<div class=\"card\">") && cards[0].contains("<dd dir=\"auto\">Lobby &amp; Bar</dd>"));
+        assert!(cards[1].starts_with("<div") && cards[8].starts_with("</section>\n<section class=\"sheet\">"));
+    }
+}
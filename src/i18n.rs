@@ -0,0 +1,160 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The texts of printable outputs, the cards, layouts and HTML pages, in the languages of `--lang`, so guests read
+//! them in theirs. The tables are embedded, and only hold languages the standard PDF fonts can write.
+
+/// A language of `--lang`, named by its ISO 639-1 code.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Language{
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+    Nl
+}
+
+/// The texts of one language.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Texts{
+    /// Titles the code.
+    pub wifi: &'static str,
+    pub network: &'static str,
+    pub password: &'static str,
+    /// In place of the password of open networks.
+    pub no_password: &'static str,
+    pub scan_to_join: &'static str,
+    /// Beneath the code of a URL.
+    pub scan_to_open: &'static str,
+    /// Followed by the `--info-url`.
+    pub cant_scan: &'static str,
+    /// Heads the steps for joining.
+    pub join: &'static str,
+    pub steps: [&'static str; 3]
+}
+
+const EN: Texts = Texts{
+    wifi: "Wi-Fi",
+    network: "Network",
+    password: "Password",
+    no_password: "none (open network)",
+    scan_to_join: "Scan to join",
+    scan_to_open: "Scan to open",
+    cant_scan: "Can't scan? Visit",
+    join: "Join the Wi-Fi",
+    steps: ["1. Open the camera app of your phone.", "2. Point it at the QR-Code.", "3. Tap the notification to join the network."]
+};
+const DE: Texts = Texts{
+    wifi: "WLAN",
+    network: "Netzwerk",
+    password: "Passwort",
+    no_password: "keins (offenes Netzwerk)",
+    scan_to_join: "Zum Verbinden scannen",
+    scan_to_open: "Zum Öffnen scannen",
+    cant_scan: "Scannen klappt nicht? Besuchen Sie",
+    join: "Mit dem WLAN verbinden",
+    steps: ["1. Öffnen Sie die Kamera-App Ihres Smartphones.", "2. Richten Sie sie auf den QR-Code.", "3. Tippen Sie auf die Mitteilung, um sich zu verbinden."]
+};
+const FR: Texts = Texts{
+    wifi: "Wi-Fi",
+    network: "Réseau",
+    password: "Mot de passe",
+    no_password: "aucun (réseau ouvert)",
+    scan_to_join: "Scannez pour vous connecter",
+    scan_to_open: "Scannez pour ouvrir",
+    cant_scan: "Impossible de scanner ? Rendez-vous sur",
+    join: "Connectez-vous au Wi-Fi",
+    steps: ["1. Ouvrez l'appareil photo de votre téléphone.", "2. Visez le QR code.", "3. Touchez la notification pour rejoindre le réseau."]
+};
+const ES: Texts = Texts{
+    wifi: "Wi-Fi",
+    network: "Red",
+    password: "Contraseña",
+    no_password: "ninguna (red abierta)",
+    scan_to_join: "Escanea para conectarte",
+    scan_to_open: "Escanea para abrir",
+    cant_scan: "¿No puedes escanear? Visita",
+    join: "Conéctate al Wi-Fi",
+    steps: ["1. Abre la cámara de tu teléfono.", "2. Apunta al código QR.", "3. Toca la notificación para unirte a la red."]
+};
+const IT: Texts = Texts{
+    wifi: "Wi-Fi",
+    network: "Rete",
+    password: "Password",
+    no_password: "nessuna (rete aperta)",
+    scan_to_join: "Scansiona per connetterti",
+    scan_to_open: "Scansiona per aprire",
+    cant_scan: "Non riesci a scansionare? Visita",
+    join: "Connettiti al Wi-Fi",
+    steps: ["1. Apri la fotocamera del telefono.", "2. Inquadra il codice QR.", "3. Tocca la notifica per connetterti alla rete."]
+};
+const NL: Texts = Texts{
+    wifi: "Wifi",
+    network: "Netwerk",
+    password: "Wachtwoord",
+    no_password: "geen (open netwerk)",
+    scan_to_join: "Scan om te verbinden",
+    scan_to_open: "Scan om te openen",
+    cant_scan: "Lukt scannen niet? Ga naar",
+    join: "Verbind met de wifi",
+    steps: ["1. Open de camera-app van je telefoon.", "2. Richt hem op de QR-code.", "3. Tik op de melding om met het netwerk te verbinden."]
+};
+
+impl Language {
+    /// The texts of the language.
+    pub fn texts(self) -> &'static Texts {
+        match self {
+            Language::En => &EN,
+            Language::De => &DE,
+            Language::Fr => &FR,
+            Language::Es => &ES,
+            Language::It => &IT,
+            Language::Nl => &NL
+        }
+    }
+    /// The ISO 639-1 code, as the `lang` of HTML pages.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::De => "de",
+            Language::Fr => "fr",
+            Language::Es => "es",
+            Language::It => "it",
+            Language::Nl => "nl"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::ArgEnum;
+    #[test]
+    fn test_texts() {
+        assert_eq!(Language::from_str("de", true), Ok(Language::De));
+        assert_eq!(Language::Fr.texts().password, "Mot de passe");
+        for language in Language::value_variants() {
+            let texts:&Texts = language.texts();
+            // The standard fonts write WinAnsiEncoding, which holds Latin-1.
+            let text:String = format!("{}{}", [texts.wifi, texts.network, texts.password, texts.no_password, texts.scan_to_join, texts.scan_to_open, texts.cant_scan, texts.join].concat(), texts.steps.concat());
+            assert!(text.chars().all(|x| (x as u32) < 256), "{:?}", language);
+            assert_eq!(language.code(), format!("{:?}", language).to_lowercase());
+        }
+    }
+}
@@ -0,0 +1,400 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Decoding QR-Codes from images and checking `WIFI:` payloads against the format readers implement, which is
+//! defined by ZXing and extended by the WPA3 specification, to audit codes made by other tools.
+
+use std::fmt;
+
+use image::GrayImage;
+
+use crate::batch::Network;
+use crate::{ECCLevel, SPECIAL_CHARACTERS};
+
+/// Keys a `WIFI:` payload may contain. `E`, `A`, `I` and `PH2` describe WPA2-Enterprise networks, `R` disables
+/// WPA3 transition modes.
+const WIFI_KEYS: [&str; 9] = ["T", "S", "P", "H", "E", "A", "I", "PH2", "R"];
+
+#[derive(Debug)]
+pub enum InspectError{
+    Image(image::ImageError),
+    NotFound,
+    Decode(rqrr::DeQRError)
+}
+impl fmt::Display for InspectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InspectError::Image(err) => write!(f, "{}", err),
+            InspectError::NotFound => write!(f, "no QR-Code was found in the image"),
+            InspectError::Decode(err) => write!(f, "the QR-Code could not be decoded: {}", err)
+        }
+    }
+}
+impl From<image::ImageError> for InspectError {
+    fn from(err: image::ImageError) -> Self {
+        InspectError::Image(err)
+    }
+}
+
+/// A decoded QR-Code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol{
+    pub version: usize,
+    pub ecc: ECCLevel,
+    pub mask: u16,
+    pub payload: String
+}
+
+/// Decodes every QR-Code in the image. Codes drawn light on dark, as in screenshots of terminals, are found too.
+pub fn decode(image: &GrayImage) -> Result<Vec<Symbol>, InspectError> {
+    for inverted in [false, true] {
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(image.width() as usize, image.height() as usize, |x, y| {
+            let luma:u8 = image.get_pixel(x as u32, y as u32)[0];
+            if inverted {255 - luma}else{luma}
+        });
+        let grids = prepared.detect_grids();
+        if grids.is_empty() {
+            continue;
+        }
+        return grids.iter().map(|grid| {
+            let (meta, payload) = grid.decode().map_err(InspectError::Decode)?;
+            // The format information stores the levels as 01, 00, 11 and 10.
+            let ecc:ECCLevel = match meta.ecc_level {
+                0 => ECCLevel::Medium,
+                1 => ECCLevel::Low,
+                2 => ECCLevel::High,
+                _ => ECCLevel::Quartile
+            };
+            Ok(Symbol{ version: meta.version.0, ecc, mask: meta.mask, payload })
+        }).collect();
+    }
+    Err(InspectError::NotFound)
+}
+
+/// What scanning the payload does, going by the prefixes readers recognize.
+pub fn payload_type(payload: &str) -> &'static str {
+    let lowercase:String = payload.to_lowercase();
+    let prefixes:[(&[&str], &'static str); 9] = [
+        (&["wifi:"], "Wi-Fi network"),
+        (&["dpp:"], "Wi-Fi Easy Connect"),
+        (&["http://", "https://"], "URL"),
+        (&["mailto:", "matmsg:"], "e-mail"),
+        (&["tel:"], "phone number"),
+        (&["smsto:", "sms:"], "SMS"),
+        (&["begin:vcard", "mecard:"], "contact"),
+        (&["begin:vevent"], "calendar event"),
+        (&["geo:"], "location")
+    ];
+    prefixes.iter().find(|(x, _)| x.iter().any(|x| lowercase.starts_with(x))).map_or("text", |(_, name)| name)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity{
+    /// Readers reject the payload or join the wrong network.
+    Error,
+    /// Readers disagree on how to handle the payload.
+    Warning
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding{
+    pub severity: Severity,
+    pub message: String
+}
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", if self.severity == Severity::Error {"error"}else{"warning"}, self.message)
+    }
+}
+
+/// Splits at unescaped `separator`s, keeping whether each character was escaped.
+fn split_unescaped(chars: &[(char, bool)], separator: char) -> Vec<Vec<(char, bool)>> {
+    let mut parts:Vec<Vec<(char, bool)>> = vec![Vec::new()];
+    for &(character, escaped) in chars {
+        if character == separator && !escaped {
+            parts.push(Vec::new());
+        }else{
+            parts.last_mut().unwrap().push((character, escaped));
+        }
+    }
+    parts
+}
+
+/// Checks a `WIFI:` payload, returning no findings if readers will agree on the network it describes.
+pub fn validate_wifi(payload: &str) -> Vec<Finding> {
+    let mut findings:Vec<Finding> = Vec::new();
+    let mut report = |severity: Severity, message: String| findings.push(Finding{ severity, message });
+    let Some(values) = fields(payload, &mut report) else {
+        return findings;
+    };
+    let value = |key: &str| values.iter().find(|(x, _)| x == key).map(|(_, x)| x.as_str());
+    if value("S").is_none_or(str::is_empty) {
+        report(Severity::Error, String::from("the SSID (S) is missing"));
+    }
+    let security:&str = value("T").unwrap_or_default();
+    let standard:Option<&str> = ["WEP", "WPA", "SAE", "WPA2-EAP", "nopass"].into_iter().find(|x| x.eq_ignore_ascii_case(security));
+    match standard {
+        _ if security.is_empty() => {},
+        Some(standard) if standard != security => report(Severity::Warning, format!("T:{} should be written T:{}", security, standard)),
+        Some(_) => {},
+        None if ["WPA2", "WPA3"].iter().any(|x| x.eq_ignore_ascii_case(security)) =>
+            report(Severity::Warning, format!("T:{} isn't understood by all readers, WPA covers WPA2 and WPA3", security)),
+        None => report(Severity::Error, format!("T:{} is not a security type, expected WEP, WPA, SAE, WPA2-EAP or nopass", security))
+    }
+    let password:&str = value("P").unwrap_or_default();
+    let open:bool = security.is_empty() || security.eq_ignore_ascii_case("nopass");
+    if open && !password.is_empty() {
+        report(Severity::Warning, String::from("the network is open, so readers ignore the password"));
+    }else if !open && password.is_empty() {
+        report(Severity::Error, format!("the password (P) is missing for T:{}", security));
+    }
+    let hexadecimal:bool = password.chars().all(|x| x.is_ascii_hexdigit());
+    let length:usize = password.chars().count();
+    let personal:bool = security.to_uppercase().starts_with("WPA") && !security.eq_ignore_ascii_case("WPA2-EAP");
+    // 64 hex digits are the pre-shared key itself.
+    let valid_passphrase:bool = (8..=63).contains(&length) || (length == 64 && hexadecimal);
+    if personal && !password.is_empty() && !valid_passphrase {
+        report(Severity::Error, format!("WPA passphrases have 8 to 63 characters, this one has {}", length));
+    }
+    let valid_key:bool = [5, 13].contains(&length) || ([10, 26].contains(&length) && hexadecimal);
+    if security.eq_ignore_ascii_case("WEP") && !password.is_empty() && !valid_key {
+        report(Severity::Warning, format!("WEP keys have 5 or 13 characters or 10 or 26 hex digits, this one has {}", length));
+    }
+    if let Some(hidden) = value("H") {
+        if hidden != "true" && hidden != "false" {
+            report(Severity::Error, format!("H:{} should be H:true or H:false", hidden));
+        }
+    }
+    // The transition disable of the WPA3 specification, a bit mask of which only WPA3-Personal is defined for codes.
+    if let Some(transition_disable) = value("R") {
+        if !security.eq_ignore_ascii_case("SAE") {
+            report(Severity::Warning, format!("R:{} only disables the transition of T:SAE networks", transition_disable));
+        }else if transition_disable != "1" {
+            report(Severity::Warning, format!("R:{} isn't understood by all readers, R:1 disables the transition to WPA2", transition_disable));
+        }
+    }
+    findings}
+
+/// Splits a `WIFI:` payload into its keys and unescaped values, reporting what readers may trip over.
+pub fn fields(payload: &str, report: &mut impl FnMut(Severity, String)) -> Option<Vec<(String, String)>> {
+    let Some(body) = payload.strip_prefix("WIFI:") else {
+        report(Severity::Error, String::from("the payload has to start with WIFI: in upper case"));
+        return None;
+    };
+    let mut chars:Vec<(char, bool)> = Vec::new();
+    let mut iter = body.chars();
+    while let Some(character) = iter.next() {
+        if character != '\\' {
+            chars.push((character, false));
+            continue;
+        }
+        match iter.next() {
+            Some(escaped) if !SPECIAL_CHARACTERS.contains(&escaped) => {
+                report(Severity::Warning, format!("\\{} is escaped needlessly, which some readers keep the backslash of", escaped));
+                chars.push((escaped, true));
+            },
+            Some(escaped) => chars.push((escaped, true)),
+            None => report(Severity::Error, String::from("the payload ends in an incomplete escape sequence"))
+        }
+    }
+    let mut fields:Vec<Vec<(char, bool)>> = split_unescaped(&chars, ';');
+    if !fields.pop().unwrap_or_default().is_empty() {
+        report(Severity::Error, String::from("the last field isn't terminated with ;"));
+    }
+    if fields.last().is_some_and(|x| x.is_empty()) {
+        fields.pop();
+    }else{
+        report(Severity::Warning, String::from("the payload should end with ;;"));
+    }
+
+    let mut values:Vec<(String, String)> = Vec::new();
+    for field in fields {
+        if field.is_empty() {
+            report(Severity::Warning, String::from("the payload contains an empty field"));
+            continue;
+        }
+        let mut parts = split_unescaped(&field, ':');
+        let key:String = parts.remove(0).iter().map(|x| x.0).collect();
+        if parts.is_empty() {
+            report(Severity::Error, format!("the field {} has no value", key));
+            continue;
+        }
+        if parts.len() > 1 {
+            report(Severity::Warning, format!("the value of {} contains an unescaped :", key));
+        }
+        let mut value:Vec<(char, bool)> = parts.join(&(':', false));
+        // Values in double quotes are taken as text, e.g. `S:"CAFE"` for an SSID that would be read as hex otherwise.
+        if value.len() >= 2 && value[0] == ('"', false) && value[value.len() - 1] == ('"', false) {
+            value = value[1..value.len() - 1].to_vec();
+        }else if key == "S" && !value.is_empty() && value.len().is_multiple_of(2) && value.iter().all(|x| x.0.is_ascii_hexdigit()) {
+            let ssid:String = value.iter().map(|x| x.0).collect();
+            report(Severity::Warning, format!("S:{} may be read as hex digits, quote it as S:\"{}\" if it is the name itself", ssid, ssid));
+        }
+        for special in [',', '"'] {
+            if value.contains(&(special, false)) {
+                report(Severity::Warning, format!("the value of {} contains an unescaped {}", key, special));
+            }
+        }
+        if !WIFI_KEYS.contains(&key.as_str()) {
+            report(Severity::Warning, format!("the field {} is unknown to readers", key));
+        }
+        if values.iter().any(|(x, _)| *x == key) {
+            report(Severity::Error, format!("the field {} is given more than once", key));
+        }
+        values.push((key, value.iter().map(|x| x.0).collect()));
+    }
+
+    Some(values)
+}
+
+/// Reads the network of a `WIFI:` payload, failing if readers would reject it. `T:WPA`, which covers the whole WPA
+/// family, is read as WPA2.
+pub fn parse_wifi(payload: &str) -> Result<Network, String> {
+    if let Some(error) = validate_wifi(payload).into_iter().find(|x| x.severity == Severity::Error) {
+        return Err(error.message);
+    }
+    let values:Vec<(String, String)> = fields(payload, &mut |_, _| {}).unwrap_or_default();
+    let value = |key: &str| values.iter().find(|(x, _)| x == key).map(|(_, x)| x.clone());
+    let security:Option<&str> = match value("T").unwrap_or_default().to_uppercase().as_str() {
+        "" | "NOPASS" => None,
+        "WEP" => Some("wep"),
+        "WPA" | "WPA2" => Some("wpa2"),
+        "SAE" | "WPA3" => Some("wpa3"),
+        other => return Err(format!("T:{} networks can't be encoded yet", other))
+    };
+    Ok(Network{
+        ssid: value("S").unwrap_or_default(),
+        password: value("P").filter(|_| security.is_some()),
+        security: security.map(String::from),
+        hidden: value("H").as_deref() == Some("true")
+    })
+}
+
+/// Names of the fields of a `WIFI:` payload as shown to people, in the order they are shown.
+const WIFI_FIELD_NAMES: [(&str, &str); 9] = [
+    ("S", "SSID"), ("T", "Security"), ("P", "Password"), ("H", "Hidden"), ("R", "Transition disable"),
+    ("E", "EAP method"), ("PH2", "Phase 2"), ("I", "Identity"), ("A", "Anonymous identity")
+];
+
+/// The fields of a `WIFI:` payload by their names, with the security type spelled out, failing if readers would
+/// reject it. Unlike [`parse_wifi`], Enterprise networks are read too, and passwords of open networks are left out.
+pub fn describe_wifi(payload: &str) -> Result<Vec<(&'static str, String)>, String> {
+    if let Some(error) = validate_wifi(payload).into_iter().find(|x| x.severity == Severity::Error) {
+        return Err(error.message);
+    }
+    let values:Vec<(String, String)> = fields(payload, &mut |_, _| {}).unwrap_or_default();
+    let security:String = values.iter().find(|(x, _)| x == "T").map(|(_, x)| x.to_uppercase()).unwrap_or_default();
+    let open:bool = security.is_empty() || security == "NOPASS";
+    Ok(WIFI_FIELD_NAMES.iter().filter_map(|(key, name)| {
+        let value:&str = values.iter().find(|(x, _)| x == key).map(|(_, x)| x.as_str())?;
+        let value:String = match *key {
+            "P" if open => return None,
+            "T" => String::from(match security.as_str() {
+                "WEP" => "WEP",
+                "WPA" => "WPA/WPA2/WPA3",
+                "WPA2" => "WPA2",
+                "SAE" | "WPA3" => "WPA3",
+                "WPA2-EAP" => "WPA2-Enterprise",
+                _ => "none"
+            }),
+            "H" => String::from(if value == "true" {"yes"}else{"no"}),
+            _ => String::from(value)
+        };
+        Some((*name, value))
+    }).collect())
+}
+
+/// A report of the symbol and its payload, validating `WIFI:` payloads.
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size:usize = 17 + 4 * self.version;
+        writeln!(f, "Version:   {} ({}x{} modules)", self.version, size, size)?;
+        writeln!(f, "ECC-Level: {:?}", self.ecc)?;
+        writeln!(f, "Mask:      {}", self.mask)?;
+        writeln!(f, "Type:      {}", payload_type(&self.payload))?;
+        writeln!(f, "Payload:   {}", self.payload)?;
+        if payload_type(&self.payload) != "Wi-Fi network" {
+            return Ok(());
+        }
+        let findings:Vec<Finding> = validate_wifi(&self.payload);
+        if findings.is_empty() {
+            return writeln!(f, "The payload follows the WIFI: format.");
+        }
+        for finding in findings {
+            writeln!(f, "  {}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    fn messages(payload: &str) -> Vec<String> {
+        validate_wifi(payload).iter().map(|x| x.to_string()).collect::<Vec<String>>()
+    }
+    #[test]
+    fn test_decode() {
+        let image = qrcode_generator::to_image_buffer("WIFI:T:WPA;S:Home;P:password;;", qrcode_generator::QrCodeEcc::Quartile, 256).unwrap();
+        let symbols = decode(&image).unwrap();
+        assert_eq!((symbols[0].version, symbols[0].ecc, symbols[0].payload.as_str()), (3, ECCLevel::Quartile, "WIFI:T:WPA;S:Home;P:password;;"));
+        // Inverted, as in a screenshot of the terminal output.
+        let mut inverted = image.clone();
+        image::imageops::invert(&mut inverted);
+        assert_eq!(decode(&inverted).unwrap(), symbols);
+        assert!(matches!(decode(&GrayImage::new(64, 64)), Err(InspectError::NotFound)));
+    }
+    #[test]
+    fn test_payload_type() {
+        assert_eq!(payload_type("WIFI:S:Home;;"), "Wi-Fi network");
+        assert_eq!(payload_type("HTTPS://example.com"), "URL");
+        assert_eq!(payload_type("DPP:K:MDkw;;"), "Wi-Fi Easy Connect");
+        assert_eq!(payload_type("hello"), "text");
+    }
+    #[test]
+    fn test_parse_wifi() {
+        assert_eq!(parse_wifi("WIFI:T:WPA;S:Caf\\;\\:e;P:pass\\\\word;H:true;;"), Ok(Network{
+            ssid: String::from("Caf;:e"), password: Some(String::from("pass\\word")), security: Some(String::from("wpa2")), hidden: true
+        }));
+        assert_eq!(parse_wifi("WIFI:T:nopass;S:Guest;P:ignored;;").unwrap().password, None);
+        assert_eq!(parse_wifi("WIFI:T:WPA2-EAP;S:Office;P:x;;"), Err(String::from("T:WPA2-EAP networks can't be encoded yet")));
+        assert_eq!(parse_wifi("WIFI:T:WPA;P:password;;"), Err(String::from("the SSID (S) is missing")));
+    }
+    #[test]
+    fn test_describe_wifi() {
+        assert_eq!(describe_wifi("WIFI:T:WPA;S:Caf\\;e;P:password;H:true;;").unwrap(), vec![
+            ("SSID", String::from("Caf;e")), ("Security", String::from("WPA/WPA2/WPA3")), ("Password", String::from("password")), ("Hidden", String::from("yes"))
+        ]);
+        assert_eq!(describe_wifi("WIFI:T:WPA2-EAP;S:Office;E:PEAP;I:alice;P:password;PH2:MSCHAPV2;;").unwrap()[1..], [
+            ("Security", String::from("WPA2-Enterprise")), ("Password", String::from("password")), ("EAP method", String::from("PEAP")),
+            ("Phase 2", String::from("MSCHAPV2")), ("Identity", String::from("alice"))
+        ]);
+        assert_eq!(describe_wifi("WIFI:T:nopass;S:Guest;P:ignored;;").unwrap(), vec![("SSID", String::from("Guest")), ("Security", String::from("none"))]);
+        assert_eq!(describe_wifi("WIFI:T:WPA;S:Home;;"), Err(String::from("the password (P) is missing for T:WPA")));
+    }
+    #[test]
+    fn test_validate_wifi() {
+        assert!(validate_wifi("WIFI:T:WPA;S:Caf\\;\\:e;P:password;H:true;;").is_empty());
+        assert!(validate_wifi("WIFI:S:Guest;;").is_empty());
+        assert_eq!(messages("WIFI:T:Wpa2;S:Home;P:password;H:false;;"), vec!["warning: T:Wpa2 isn't understood by all readers, WPA covers WPA2 and WPA3"]);
+        assert_eq!(messages("WIFI:T:wep;S:Home;P:abc;"), vec!["warning: the payload should end with ;;", "warning: T:wep should be written T:WEP", "warning: WEP keys have 5 or 13 characters or 10 or 26 hex digits, this one has 3"]);
+        assert_eq!(messages("WIFI:T:WPA;S:a,b;S:c;P:short;;"), vec!["warning: the value of S contains an unescaped ,", "error: the field S is given more than once", "error: WPA passphrases have 8 to 63 characters, this one has 5"]);
+        assert_eq!(messages("WIFI:T:WPA;S:Home;H:maybe;;"), vec!["error: the password (P) is missing for T:WPA", "error: H:maybe should be H:true or H:false"]);
+        assert!(validate_wifi("WIFI:T:SAE;S:Home;P:password;R:1;H:false;;").is_empty());
+        assert_eq!(messages("WIFI:T:WPA;S:Home;P:password;R:1;;"), vec!["warning: R:1 only disables the transition of T:SAE networks"]);
+        assert_eq!(messages("wifi:S:Home;;"), vec!["error: the payload has to start with WIFI: in upper case"]);
+    }
+}
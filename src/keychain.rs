@@ -0,0 +1,53 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Looking up the passwords macOS saves for the Wi-Fi networks it joined in the keychain, through the Security
+//! framework. They are generic passwords of the `AirPort` service with the SSID as their account, kept in the System
+//! keychain, so macOS asks for an administrator's password before handing one out.
+
+use std::ffi::c_void;
+use std::ptr::{null, null_mut};
+
+/// Service of the keychain items of Wi-Fi networks.
+const SERVICE: &str = "AirPort";
+/// The status of lookups without a matching item.
+pub const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn SecKeychainFindGenericPassword(keychain_or_array: *const c_void, service_name_length: u32, service_name: *const u8,
+        account_name_length: u32, account_name: *const u8, password_length: *mut u32, password_data: *mut *mut c_void,
+        item_ref: *mut *mut c_void) -> i32;
+    fn SecKeychainItemFreeContent(attr_list: *mut c_void, data: *mut c_void) -> i32;
+}
+
+/// The password of the network, or the status the Security framework failed with.
+pub fn password(ssid: &str) -> Result<String, i32> {
+    let mut length:u32 = 0;
+    let mut data:*mut c_void = null_mut();
+    // SAFETY: The names are passed with their lengths, and the password data is copied before it is freed with the
+    // function the framework documents for it. Passing no keychain searches the default list.
+    unsafe {
+        let status:i32 = SecKeychainFindGenericPassword(null(), SERVICE.len() as u32, SERVICE.as_ptr(), ssid.len() as u32, ssid.as_ptr(),
+            &mut length, &mut data, null_mut());
+        if status != 0 {
+            return Err(status);
+        }
+        let password:String = String::from_utf8_lossy(std::slice::from_raw_parts(data as *const u8, length as usize)).into_owned();
+        SecKeychainItemFreeContent(null_mut(), data);
+        Ok(password)
+    }
+}
@@ -0,0 +1,952 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Print layouts, which place the QR-Code and captions onto PDF pages.
+
+use image::RgbaImage;
+
+use crate::branding::{Align, Branding, Frame, FrameStyle};
+use crate::color::Color;
+use crate::i18n::Texts;
+use crate::pdf::{Font, Page, Rect, MM};
+use crate::{with_quiet_zone, Args};
+
+/// Length of the crop marks, which start where the bleed ends.
+const CROP_MARK_LENGTH: f32 = 5.0 * MM;
+/// Distance between the trim box and any content.
+const SAFE_MARGIN: f32 = 4.0 * MM;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum CardSize{
+    /// 85 x 55 mm
+    Eu,
+    /// 3.5 x 2 in
+    Us,
+    /// 91 x 55 mm
+    Jp,
+    /// ISO/IEC 7810 ID-1, 85.6 x 54 mm
+    CreditCard
+}
+impl CardSize {
+    /// Width and height in points.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            CardSize::Eu => (85.0 * MM, 55.0 * MM),
+            CardSize::Us => (3.5 * 72.0, 2.0 * 72.0),
+            CardSize::Jp => (91.0 * MM, 55.0 * MM),
+            CardSize::CreditCard => (85.6 * MM, 54.0 * MM)
+        }
+    }
+}
+
+/// Thickness of frame borders, relative to the size of the code.
+const FRAME_BORDER: f32 = 0.05;
+/// Height of the band holding the call to action, relative to the size of the code.
+const FRAME_BAND: f32 = 0.22;
+/// Height of the top of a phone frame, above its screen.
+const PHONE_TOP: f32 = 0.1;
+
+/// Draws the frame of the branding into `rect`, returning the square left for the code.
+fn draw_frame(page: &mut Page, rect: Rect, frame: &Frame, branding: &Branding) -> Rect {
+    let top:f32 = if frame.style == FrameStyle::Phone {PHONE_TOP}else{0.0};
+    let qr_size:f32 = (rect.width / (1.0 + 2.0 * FRAME_BORDER)).min(rect.height / (1.0 + 2.0 * FRAME_BORDER + FRAME_BAND + top));
+    let (border, band) = (qr_size * FRAME_BORDER, qr_size * FRAME_BAND);
+    let (width, height) = (qr_size + 2.0 * border, qr_size * (1.0 + top) + 2.0 * border + band);
+    let outer = Rect::new(rect.x + (rect.width - width) / 2.0, rect.y + (rect.height - height) / 2.0, width, height);
+    let color:Color = branding.frame_color(frame);
+    let text_size:f32 = band * 0.45;
+    let center_x:f32 = outer.x + outer.width / 2.0;
+    page.set_fill_color(color);
+    match frame.style {
+        FrameStyle::ScanMe | FrameStyle::Phone => {
+            page.fill_rect(outer);
+            if frame.style == FrameStyle::Phone {
+                // The speaker slot of the phone.
+                page.set_fill_color(Color::WHITE);
+                page.fill_rect(Rect::new(center_x - qr_size * 0.125, outer.y + outer.height - qr_size * top * 0.6, qr_size * 0.25, qr_size * 0.025));
+            }
+            page.set_fill_color(frame.text_color);
+            aligned_text(page, (outer.x + border, qr_size), outer.y + (band - text_size * 0.7) / 2.0 + border / 2.0, branding.fonts.heading, text_size, frame.text(), Align::Center);
+            Rect::new(outer.x + border, outer.y + border + band, qr_size, qr_size)
+        },
+        FrameStyle::Arrow => {
+            let (side, line) = (qr_size + 2.0 * border, border / 2.0);
+            let top:f32 = outer.y + side;
+            for edge in [Rect::new(outer.x, outer.y, side, line), Rect::new(outer.x, top - line, side, line), Rect::new(outer.x, outer.y, line, side), Rect::new(outer.x + side - line, outer.y, line, side)] {
+                page.fill_rect(edge);
+            }
+            aligned_text(page, (outer.x, outer.width), outer.y + outer.height - text_size * 0.8, branding.fonts.heading, text_size, frame.text(), Align::Center);
+            // The arrow points down from the call to action at the code.
+            let arrow:f32 = band * 0.35;
+            page.fill_polygon(&[(center_x - arrow / 2.0, top + arrow), (center_x + arrow / 2.0, top + arrow), (center_x, top + line)]);
+            Rect::new(outer.x + border, outer.y + border, qr_size, qr_size)
+        }
+    }
+}
+
+/// Draws the symbol, including its quiet zone, on a white square, wrapped in the frame of the branding if it has one.
+fn draw_qr_code(page: &mut Page, matrix: &[Vec<bool>], rect: Rect, branding: &Branding) {
+    let rect:Rect = match &branding.frame {
+        Some(frame) => draw_frame(page, rect, frame, branding),
+        None => rect
+    };
+    page.set_fill_color(Color::WHITE);
+    page.fill_rect(rect);
+    page.set_fill_color(Color::BLACK);
+    page.qr_code(&with_quiet_zone(matrix), rect);
+}
+
+/// Largest font size not above `size` at which the text fits into `max_width`.
+fn fit_font_size(font: Font, text: &str, size: f32, max_width: f32) -> f32 {
+    let width:f32 = font.text_width(text, size);
+    if width > max_width {size * max_width / width}else{size}
+}
+
+/// Breaks text into lines no wider than `max_width`, splitting at spaces.
+fn wrap_text(font: Font, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines:Vec<String> = Vec::new();
+    let mut line:String = String::new();
+    for word in text.split(' ') {
+        let candidate:String = if line.is_empty() {word.to_string()}else{format!("{} {}", line, word)};
+        if font.text_width(&candidate, size) > max_width && !line.is_empty() {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        }else{
+            line = candidate;
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Creates a page for a piece of the given trim size, with room for the bleed and crop marks around it.
+/// Returns the page and the trim box.
+pub fn trimmed_page(width: f32, height: f32, bleed: f32) -> (Page, Rect) {
+    let slug:f32 = bleed + CROP_MARK_LENGTH + 1.0 * MM;
+    let mut page = Page::new(width + 2.0 * slug, height + 2.0 * slug);
+    let trim_box = Rect::new(slug, slug, width, height);
+    page.trim_box = Some(trim_box);
+    page.bleed_box = Some(trim_box.inset(-bleed));
+    (page, trim_box)
+}
+
+/// Draws crop marks at the corners of the trim box, outside of the bleed.
+pub fn draw_crop_marks(page: &mut Page, trim_box: Rect, bleed: f32) {
+    page.set_stroke_color(Color::BLACK);
+    let (left, bottom, right, top) = (trim_box.x, trim_box.y, trim_box.x + trim_box.width, trim_box.y + trim_box.height);
+    for x in [left, right] {
+        for (y, direction) in [(bottom, -1.0), (top, 1.0)] {
+            page.line((x, y + direction * bleed), (x, y + direction * (bleed + CROP_MARK_LENGTH)), 0.25);
+        }
+    }
+    for y in [bottom, top] {
+        for (x, direction) in [(left, -1.0), (right, 1.0)] {
+            page.line((x + direction * bleed, y), (x + direction * (bleed + CROP_MARK_LENGTH), y), 0.25);
+        }
+    }
+}
+
+/// Draws the image as large as it fits into `rect` without distorting it, either centered or left aligned, and
+/// vertically centered.
+fn draw_image(page: &mut Page, image: &RgbaImage, rect: Rect, centered: bool) {
+    let scale:f32 = (rect.width / image.width() as f32).min(rect.height / image.height() as f32);
+    let (width, height) = (image.width() as f32 * scale, image.height() as f32 * scale);
+    let x:f32 = if centered {rect.x + (rect.width - width) / 2.0}else{rect.x};
+    page.image(image, Rect::new(x, rect.y + (rect.height - height) / 2.0, width, height));
+}
+
+/// Fills `rect` with the background color of the branding, leaving it blank if that is white anyway.
+fn fill_background(page: &mut Page, rect: Rect, branding: &Branding) {
+    if branding.palette.background != Color::WHITE {
+        page.set_fill_color(branding.palette.background);
+        page.fill_rect(rect);
+    }
+}
+
+/// Draws the footer of the branding centered at the bottom of `rect`. Returns the part of `rect` above it.
+fn draw_footer(page: &mut Page, rect: Rect, branding: &Branding, size: f32) -> Rect {
+    match &branding.footer {
+        Some(footer) => {
+            page.set_fill_color(branding.palette.text);
+            aligned_text(page, (rect.x, rect.width), rect.y, branding.fonts.body, size, footer, branding.align.resolve(Align::Center, footer));
+            Rect::new(rect.x, rect.y + size * 2.0, rect.width, rect.height - size * 2.0)
+        },
+        None => rect
+    }
+}
+
+/// Draws `text` aligned within the span of `max_width` starting at `x`, shrinking it if it is wider than that.
+fn aligned_text(page: &mut Page, (x, max_width): (f32, f32), y: f32, font: Font, size: f32, text: &str, align: Align) {
+    let size:f32 = fit_font_size(font, text, size, max_width);
+    page.text(align.position(x, max_width, font.text_width(text, size)), y, font, size, text);
+}
+
+/// The lines of text around a QR-Code in a panel: a title above it, a caption and a hint below it, and the
+/// `--info-url` for devices that can't scan.
+struct Captions<'a>{
+    title: &'a str,
+    caption: &'a str,
+    hint: &'a str,
+    link: Option<&'a str>,
+    /// The texts around the link.
+    texts: &'static Texts
+}
+impl<'a> Captions<'a> {
+    fn wifi(args: &'a Args) -> Self {
+        let texts:&Texts = args.output.lang.texts();
+        Captions{ title: texts.wifi, caption: &args.ssid, hint: texts.scan_to_join, link: args.info_url.as_deref(), texts }
+    }
+}
+
+/// Draws a panel with the logo, the title, the QR-Code and its captions, all centered in `rect`.
+fn draw_qr_panel(page: &mut Page, rect: Rect, captions: &Captions, matrix: &[Vec<bool>], branding: &Branding) {
+    let center_x:f32 = rect.x + rect.width / 2.0;
+    let title_size:f32 = rect.width.min(rect.height) / 10.0;
+    let text_size:f32 = title_size / 2.0;
+    let logo_height:f32 = if branding.logo_image.is_some() {title_size * 1.5}else{0.0};
+    let captions_height:f32 = if captions.link.is_some() {text_size * 4.7}else{text_size * 3.5};
+    let qr_size:f32 = (rect.width * 0.8).min(rect.height - logo_height - title_size * 1.5 - captions_height);
+    // The logo, title, QR-Code and captions are stacked top to bottom, with the whole group centered vertically.
+    let mut top:f32 = rect.y + (rect.height + logo_height + title_size * 1.5 + qr_size + captions_height) / 2.0;
+    if let Some(logo) = &branding.logo_image {
+        draw_image(page, logo, Rect::new(rect.x, top - logo_height * 0.85, rect.width, logo_height * 0.85), true);
+        top -= logo_height;
+    }
+    let qr_rect = Rect::new(center_x - qr_size / 2.0, top - title_size * 1.5 - qr_size, qr_size, qr_size);
+    draw_qr_code(page, matrix, qr_rect, branding);
+    page.set_fill_color(branding.palette.accent);
+    let line = |text: &str| branding.align.resolve(Align::Center, text);
+    aligned_text(page, (rect.x, rect.width), top - title_size, branding.fonts.heading, title_size, captions.title, line(captions.title));
+    page.set_fill_color(branding.palette.text);
+    aligned_text(page, (rect.x, rect.width), qr_rect.y - text_size * 1.5, branding.fonts.body, text_size, captions.caption, line(captions.caption));
+    aligned_text(page, (rect.x, rect.width), qr_rect.y - text_size * 3.0, branding.fonts.body, text_size * 0.8, captions.hint, line(captions.hint));
+    if let Some(link) = captions.link {
+        let text:String = format!("{} {}", captions.texts.cant_scan, link);
+        let size:f32 = fit_font_size(branding.fonts.body, &text, text_size * 0.8, rect.width);
+        aligned_text(page, (rect.x, rect.width), qr_rect.y - text_size * 4.2, branding.fonts.body, size, &text, line(&text));
+    }
+}
+
+fn password_text(args: &Args) -> String {
+    match &args.psw {
+        Some(psw) if !psw.is_empty() => psw.clone(),
+        _ => String::from(args.output.lang.texts().no_password)
+    }
+}
+
+/// Draws the network name and password as labeled lines within the span of `max_width` starting at `x`, the first
+/// with its baseline at `y`. `natural` is the alignment of left-to-right lines. Returns the baseline below the last line.
+fn draw_details(page: &mut Page, (x, max_width): (f32, f32), mut y: f32, args: &Args, branding: &Branding, size: f32, natural: Align) -> f32 {
+    let (heading, body) = (branding.fonts.heading, branding.fonts.body);
+    let texts:&Texts = args.output.lang.texts();
+    for (label, value) in [(format!("{}: ", texts.network), args.ssid.clone()), (format!("{}: ", texts.password), password_text(args))] {
+        let label:&str = &label;
+        let label_width:f32 = heading.text_width(label, size);
+        let value_size:f32 = fit_font_size(body, &value, size, max_width - label_width);
+        let align:Align = branding.align.resolve(natural, &format!("{}{}", label, value));
+        let start:f32 = align.position(x, max_width, label_width + body.text_width(&value, value_size));
+        page.set_fill_color(branding.palette.accent);
+        page.text(start, y, heading, size, label);
+        page.set_fill_color(branding.palette.text);
+        page.text(start + label_width, y, body, value_size, &value);
+        y -= size * 1.375;
+    }
+    y
+}
+
+/// Draws the steps for joining the network, wrapped to `max_width`. Returns the baseline below the last line.
+fn draw_steps(page: &mut Page, x: f32, mut y: f32, max_width: f32, branding: &Branding, texts: &Texts, size: f32) -> f32 {
+    page.set_fill_color(branding.palette.text);
+    for step in texts.steps {
+        let align:Align = branding.align.resolve(Align::Left, step);
+        for line in wrap_text(branding.fonts.body, step, size, max_width) {
+            aligned_text(page, (x, max_width), y, branding.fonts.body, size, &line, align);
+            y -= size * 1.3;
+        }
+    }
+    y
+}
+
+/// Draws a panel with a heading, the network details and the steps for joining, top aligned in `rect`.
+fn draw_instructions_panel(page: &mut Page, rect: Rect, args: &Args, branding: &Branding, size: f32) {
+    let mut y:f32 = rect.y + rect.height - size * 1.5;
+    let heading:Font = branding.fonts.heading;
+    page.set_fill_color(branding.palette.accent);
+    let texts:&Texts = args.output.lang.texts();
+    aligned_text(page, (rect.x, rect.width), y, heading, size * 1.5, texts.join, branding.align.resolve(Align::Left, texts.join));
+    y -= size * 2.0;
+    y = draw_details(page, (rect.x, rect.width), y, args, branding, size, Align::Left);
+    draw_steps(page, rect.x, y - size * 0.5, rect.width, branding, texts, size * 0.875);
+}
+
+/// Draws the QR-Code on the left of `rect` and the logo, title and SSID next to it, scaled to the height of `rect`.
+fn draw_qr_with_caption(page: &mut Page, rect: Rect, args: &Args, matrix: &[Vec<bool>], branding: &Branding) {
+    let qr_rect = Rect::new(rect.x, rect.y, rect.height, rect.height);
+    draw_qr_code(page, matrix, qr_rect, branding);
+    let text_x:f32 = qr_rect.x + qr_rect.width + rect.height / 15.0;
+    let text_width:f32 = rect.x + rect.width - text_x;
+    let (heading, body) = (branding.fonts.heading, branding.fonts.body);
+    if let Some(logo) = &branding.logo_image {
+        draw_image(page, logo, Rect::new(text_x, rect.y + rect.height * 0.75, text_width, rect.height * 0.25), false);
+    }
+    let texts:&Texts = args.output.lang.texts();
+    let title_size:f32 = fit_font_size(heading, texts.wifi, rect.height / 9.5, text_width);
+    let ssid_size:f32 = rect.height / 13.0;
+    let title_y:f32 = rect.y + rect.height * 0.6;
+    page.set_fill_color(branding.palette.accent);
+    let line = |text: &str| branding.align.resolve(Align::Left, text);
+    aligned_text(page, (text_x, text_width), title_y, heading, title_size, texts.wifi, line(texts.wifi));
+    page.set_fill_color(branding.palette.text);
+    aligned_text(page, (text_x, text_width), title_y - ssid_size * 1.4, body, ssid_size, &args.ssid, line(&args.ssid));
+    let hint_size:f32 = rect.height / 19.0;
+    // The link goes below the hint, in the space the hint would take.
+    let hint_y:f32 = match &args.info_url {
+        Some(link) => {
+            let size:f32 = fit_font_size(body, link, hint_size, text_width);
+            aligned_text(page, (text_x, text_width), rect.y, body, size, link, line(link));
+            rect.y + hint_size * 1.3
+        },
+        None => rect.y
+    };
+    aligned_text(page, (text_x, text_width), hint_y, body, hint_size, texts.scan_to_join, line(texts.scan_to_join));
+}
+
+/// Draws the QR-Code on the left of `rect` and the SSID top to bottom in a column next to it, as Japanese and
+/// Chinese signage does on narrow labels.
+fn draw_qr_with_vertical_caption(page: &mut Page, rect: Rect, args: &Args, matrix: &[Vec<bool>], branding: &Branding) {
+    let qr_rect = Rect::new(rect.x, rect.y, rect.height, rect.height);
+    draw_qr_code(page, matrix, qr_rect, branding);
+    let text_x:f32 = qr_rect.x + qr_rect.width + rect.height / 15.0;
+    let text_width:f32 = rect.x + rect.width - text_x;
+    let body:Font = branding.fonts.body;
+    let size:f32 = (rect.height / 5.0).min(text_width).min(rect.height / body.text_height(&args.ssid, 1.0).max(0.001));
+    page.set_fill_color(branding.palette.text);
+    let top:f32 = rect.y + (rect.height + body.text_height(&args.ssid, size)) / 2.0;
+    page.vertical_text(text_x + text_width / 2.0, top, body, size, &args.ssid);
+}
+
+/// Lays out a two-sided business card: the QR-Code and SSID on the front, joining instructions on the back.
+/// The footer of the branding goes at the bottom of the back.
+pub fn business_card(args: &Args, matrix: &[Vec<bool>], branding: &Branding, size: CardSize, bleed: f32) -> Vec<Page> {
+    let (width, height) = size.dimensions();
+
+    let (mut front, trim_box) = trimmed_page(width, height, bleed);
+    page_background(&mut front, trim_box, bleed, branding);
+    draw_qr_with_caption(&mut front, trim_box.inset(SAFE_MARGIN), args, matrix, branding);
+    draw_crop_marks(&mut front, trim_box, bleed);
+
+    let (mut back, trim_box) = trimmed_page(width, height, bleed);
+    page_background(&mut back, trim_box, bleed, branding);
+    let panel:Rect = draw_footer(&mut back, trim_box.inset(SAFE_MARGIN), branding, 6.0);
+    draw_instructions_panel(&mut back, panel, args, branding, 8.0);
+    draw_crop_marks(&mut back, trim_box, bleed);
+
+    vec![front, back]
+}
+
+/// Fills the trim box and bleed with the background color, so RIPs treat the whole piece as artwork.
+fn page_background(page: &mut Page, trim_box: Rect, bleed: f32, branding: &Branding) {
+    page.set_fill_color(branding.palette.background);
+    page.fill_rect(trim_box.inset(-bleed));
+}
+
+/// Portrait sizes of the card of `--format pdf`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CardFormat{
+    /// 55 x 85 mm
+    BusinessCard,
+    /// ISO/IEC 7810 ID-1, 54 x 85.6 mm, for wallets and badge holders
+    CreditCard,
+    /// 105 x 148 mm, for the fridge or a picture frame
+    A6,
+    /// 148 x 210 mm
+    A5,
+    A4,
+    Letter
+}
+impl CardFormat {
+    /// Width and height in points.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            CardFormat::BusinessCard => (55.0 * MM, 85.0 * MM),
+            CardFormat::CreditCard => (54.0 * MM, 85.6 * MM),
+            CardFormat::A6 => (105.0 * MM, 148.0 * MM),
+            CardFormat::A5 => (148.0 * MM, 210.0 * MM),
+            CardFormat::A4 => PaperSize::A4.dimensions(),
+            CardFormat::Letter => PaperSize::Letter.dimensions()
+        }
+    }
+}
+
+/// Lays out a single-sided card with the QR-Code and, beneath it, the network and password for typing them in,
+/// scaled to the size of the card.
+pub fn card(args: &Args, matrix: &[Vec<bool>], branding: &Branding, format: CardFormat) -> Vec<Page> {
+    portrait_card(args, matrix, branding, format.dimensions())
+}
+fn portrait_card(args: &Args, matrix: &[Vec<bool>], branding: &Branding, (width, height): (f32, f32)) -> Vec<Page> {
+    let mut page = Page::new(width, height);
+    draw_portrait_card(&mut page, Rect::new(0.0, 0.0, width, height), args, matrix, branding);
+    vec![page]
+}
+/// Draws the title, the QR-Code and the details stacked top to bottom, filling the card `card` of the page.
+fn draw_portrait_card(page: &mut Page, card: Rect, args: &Args, matrix: &[Vec<bool>], branding: &Branding) {
+    fill_background(page, card, branding);
+    let rect:Rect = card.inset(card.width * 0.08);
+    let rect:Rect = draw_footer(page, rect, branding, card.width / 30.0);
+    let size:f32 = rect.width / 16.0;
+    let title_size:f32 = size * 2.0;
+    let details_height:f32 = size * 4.5;
+    let qr_size:f32 = rect.width.min(rect.height - title_size * 1.5 - details_height);
+    // The title, QR-Code and details are stacked top to bottom, with the whole group centered vertically.
+    let top:f32 = rect.y + (rect.height + title_size * 1.5 + qr_size + details_height) / 2.0;
+    page.set_fill_color(branding.palette.accent);
+    let title:&str = args.output.lang.texts().wifi;
+    aligned_text(page, (rect.x, rect.width), top - title_size, branding.fonts.heading, title_size, title, branding.align.resolve(Align::Center, title));
+    let qr_rect = Rect::new(rect.x + (rect.width - qr_size) / 2.0, top - title_size * 1.5 - qr_size, qr_size, qr_size);
+    draw_qr_code(page, matrix, qr_rect, branding);
+    draw_details(page, (rect.x, rect.width), qr_rect.y - size * 2.0, args, branding, size, Align::Center);
+}
+/// Draws the QR-Code on the left of the card `card` and the title and details next to it, for cards wider than
+/// they are high.
+fn draw_landscape_card(page: &mut Page, card: Rect, args: &Args, matrix: &[Vec<bool>], branding: &Branding) {
+    fill_background(page, card, branding);
+    let rect:Rect = card.inset(card.height * 0.08);
+    let rect:Rect = draw_footer(page, rect, branding, card.height / 20.0);
+    // The code leaves more than half of the width to the text.
+    let qr_size:f32 = rect.height.min(rect.width * 0.45);
+    let qr_rect = Rect::new(rect.x, rect.y + (rect.height - qr_size) / 2.0, qr_size, qr_size);
+    draw_qr_code(page, matrix, qr_rect, branding);
+    let text_x:f32 = qr_rect.x + qr_rect.width + qr_size / 12.0;
+    let text_width:f32 = rect.x + rect.width - text_x;
+    let size:f32 = (rect.height / 10.0).min(text_width / 12.0);
+    let title_size:f32 = size * 2.0;
+    // The title and both lines of details, centered vertically next to the code.
+    let top:f32 = rect.y + (rect.height + title_size * 1.5 + size * 2.375) / 2.0;
+    page.set_fill_color(branding.palette.accent);
+    let title:&str = args.output.lang.texts().wifi;
+    aligned_text(page, (text_x, text_width), top - title_size, branding.fonts.heading, title_size, title, branding.align.resolve(Align::Left, title));
+    draw_details(page, (text_x, text_width), top - title_size * 1.5 - size, args, branding, size, Align::Left);
+}
+
+/// Labels of label printers, for `--label`, in the orientation they are read in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum LabelPreset{
+    /// Brother DK-22205, 62 mm continuous tape, cut at 80 mm
+    #[clap(name = "brother-62")]
+    Brother62,
+    /// Brother DK-11202, 62 x 100 mm
+    #[clap(name = "brother-62x100")]
+    Brother62x100,
+    /// Brother DK-11201, 90 x 29 mm
+    #[clap(name = "brother-29x90")]
+    Brother29x90,
+    /// 54 x 70 mm, e.g. Zebra and Brother TD die-cut labels
+    #[clap(name = "54x70")]
+    DieCut54x70,
+    /// DYMO 99014 shipping labels, 54 x 101 mm
+    #[clap(name = "dymo-99014")]
+    Dymo99014,
+    /// DYMO 99012 address labels, 89 x 36 mm
+    #[clap(name = "dymo-99012")]
+    Dymo99012,
+    /// DYMO 11354 multi-purpose labels, 57 x 32 mm
+    #[clap(name = "dymo-11354")]
+    Dymo11354
+}
+impl LabelPreset {
+    /// Width and height in millimeters.
+    pub fn size_mm(&self) -> (f32, f32) {
+        match self {
+            LabelPreset::Brother62 => (62.0, 80.0),
+            LabelPreset::Brother62x100 => (62.0, 100.0),
+            LabelPreset::Brother29x90 => (90.0, 29.0),
+            LabelPreset::DieCut54x70 => (54.0, 70.0),
+            LabelPreset::Dymo99014 => (54.0, 101.0),
+            LabelPreset::Dymo99012 => (89.0, 36.0),
+            LabelPreset::Dymo11354 => (57.0, 32.0)
+        }
+    }
+    /// Width and height in points.
+    pub fn dimensions(&self) -> (f32, f32) {
+        let (width, height) = self.size_mm();
+        (width * MM, height * MM)
+    }
+    /// Width and height in pixels at the resolution of label printers.
+    pub fn pixels(&self) -> (u32, u32) {
+        let (width, height) = self.size_mm();
+        ((width / 25.4 * LABEL_DPI as f32).round() as u32, (height / 25.4 * LABEL_DPI as f32).round() as u32)
+    }
+    /// Whether the label is wider than high, so the SSID goes beside the QR-Code instead of beneath it.
+    fn landscape(&self) -> bool {
+        let (width, height) = self.size_mm();
+        width > height
+    }
+}
+/// Resolution of the print heads of Brother QL and DYMO LabelWriter printers.
+pub const LABEL_DPI: u32 = 300;
+
+/// Lays out a page the size of the label: the card scaled down on portrait labels, and the QR-Code with the SSID
+/// beside it on landscape ones.
+pub fn label(args: &Args, matrix: &[Vec<bool>], branding: &Branding, preset: LabelPreset) -> Vec<Page> {
+    let (width, height) = preset.dimensions();
+    if !preset.landscape() {
+        return portrait_card(args, matrix, branding, (width, height));
+    }
+    let mut page = Page::new(width, height);
+    fill_background(&mut page, Rect::new(0.0, 0.0, width, height), branding);
+    draw_qr_with_caption(&mut page, Rect::new(0.0, 0.0, width, height).inset(LABEL_PADDING), args, matrix, branding);
+    vec![page]
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum PaperSize{
+    A4,
+    Letter
+}
+impl PaperSize {
+    /// Width and height of the portrait sheet in points.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0 * MM, 297.0 * MM),
+            PaperSize::Letter => (8.5 * 72.0, 11.0 * 72.0)
+        }
+    }
+}
+
+/// Margin kept clear on sheets printed by office printers, which can't print up to the edge.
+const SHEET_MARGIN: f32 = 12.0 * MM;
+
+/// Draws a dashed light gray line where the sheet has to be folded.
+fn draw_fold_line(page: &mut Page, from: (f32, f32), to: (f32, f32)) {
+    page.set_stroke_color(Color::GRAY);
+    page.set_line_dash(&[3.0, 3.0]);
+    page.line(from, to, 0.5);
+    page.set_line_dash(&[]);
+}
+
+/// Lays out a table tent: a portrait sheet folded in half across its width, with the same face on both sides.
+/// The upper face is rotated by 180°, so both faces stand upright once the tent is folded.
+pub fn table_tent(args: &Args, matrix: &[Vec<bool>], branding: &Branding, paper: PaperSize) -> Vec<Page> {
+    let (width, height) = paper.dimensions();
+    let mut page = Page::new(width, height);
+    fill_background(&mut page, Rect::new(0.0, 0.0, width, height), branding);
+    let face:Rect = Rect::new(0.0, 0.0, width, height / 2.0).inset(SHEET_MARGIN);
+    // Rotating around the center of the sheet maps the lower face onto the upper one.
+    for transform in [[1.0, 0.0, 0.0, 1.0, 0.0, 0.0], [-1.0, 0.0, 0.0, -1.0, width, height]] {
+        page.push_transform(transform);
+        let panel:Rect = draw_footer(&mut page, face, branding, 10.0);
+        draw_qr_panel(&mut page, panel, &Captions::wifi(args), matrix, branding);
+        page.pop_transform();
+    }
+    draw_fold_line(&mut page, (0.0, height / 2.0), (width, height / 2.0));
+    vec![page]
+}
+
+/// Lays out a landscape sheet with the Wi-Fi QR-Code on the left and a QR-Code for `url`, e.g. a menu or feedback
+/// form, on the right, each with its own captions. The footer of the branding spans both.
+pub fn wifi_and_url(args: &Args, matrix: &[Vec<bool>], branding: &Branding, paper: PaperSize, url: &str, url_matrix: &[Vec<bool>], url_title: &str) -> Vec<Page> {
+    let (height, width) = paper.dimensions();
+    let mut page = Page::new(width, height);
+    fill_background(&mut page, Rect::new(0.0, 0.0, width, height), branding);
+    let area:Rect = draw_footer(&mut page, Rect::new(0.0, 0.0, width, height).inset(SHEET_MARGIN), branding, width / 60.0);
+    let half:f32 = area.width / 2.0;
+    draw_qr_panel(&mut page, Rect::new(area.x, area.y, half, area.height).inset(SHEET_MARGIN / 2.0), &Captions::wifi(args), matrix, branding);
+    let texts:&'static Texts = args.output.lang.texts();
+    let url_captions = Captions{ title: url_title, caption: url, hint: texts.scan_to_open, link: None, texts };
+    draw_qr_panel(&mut page, Rect::new(area.x + half, area.y, half, area.height).inset(SHEET_MARGIN / 2.0), &url_captions, url_matrix, branding);
+    vec![page]
+}
+
+/// A network of a multi-band sheet, such as the 5 GHz variant of a site's SSID.
+pub struct Band<'a>{
+    /// Name of the band, e.g. `5 GHz`, which titles the code.
+    pub label: &'a str,
+    pub ssid: &'a str,
+    pub matrix: Vec<Vec<bool>>
+}
+
+/// Parses a `--band`, which has the form `<LABEL>=<SSID>`.
+pub fn parse_band(band: &str) -> Result<(String, String), String> {
+    match band.split_once('=') {
+        Some((label, ssid)) if !label.is_empty() && !ssid.is_empty() => Ok((String::from(label), String::from(ssid))),
+        _ => Err(format!("{} isn't of the form <LABEL>=<SSID>", band))
+    }
+}
+
+/// Lays out the codes of the per-band SSIDs of a network side by side on a landscape sheet, each titled with its
+/// band.
+pub fn multi_band(bands: &[Band], branding: &Branding, paper: PaperSize, link: Option<&str>, texts: &'static Texts) -> Vec<Page> {
+    let (height, width) = paper.dimensions();
+    let mut page = Page::new(width, height);
+    fill_background(&mut page, Rect::new(0.0, 0.0, width, height), branding);
+    let area:Rect = draw_footer(&mut page, Rect::new(0.0, 0.0, width, height).inset(SHEET_MARGIN), branding, width / 60.0);
+    let column:f32 = area.width / bands.len() as f32;
+    for (index, band) in bands.iter().enumerate() {
+        let captions = Captions{ title: band.label, caption: band.ssid, hint: texts.scan_to_join, link, texts };
+        draw_qr_panel(&mut page, Rect::new(area.x + index as f32 * column, area.y, column, area.height).inset(SHEET_MARGIN / 2.0), &captions, &band.matrix, branding);
+    }
+    vec![page]
+}
+
+/// How much narrower the panel folding inside of a tri-fold is, so it doesn't buckle against the fold.
+const TRI_FOLD_FLAP_INSET: f32 = 2.0 * MM;
+
+/// Widths of the panels of a tri-fold (letter fold) on a sheet of the given width: the two outer panels and the
+/// narrower flap that folds in first.
+fn tri_fold_panels(width: f32) -> (f32, f32) {
+    let panel:f32 = (width + TRI_FOLD_FLAP_INSET) / 3.0;
+    (panel, panel - TRI_FOLD_FLAP_INSET)
+}
+
+/// Lays out a tri-fold on a landscape sheet, printed duplex (flip on the short edge).
+/// The outside holds the instructions on the flap, the details on the back and the QR-Code on the cover.
+/// The inside repeats the details, the QR-Code and the instructions, so the opened tri-fold can stand on a table.
+/// The footer of the branding goes below the QR-Code on the cover and inside.
+pub fn tri_fold(args: &Args, matrix: &[Vec<bool>], branding: &Branding, paper: PaperSize) -> Vec<Page> {
+    let (height, width) = paper.dimensions();
+    let (panel, flap) = tri_fold_panels(width);
+    let margin:f32 = SHEET_MARGIN / 2.0;
+    let text_size:f32 = panel / 22.0;
+    let panel_rect = |x: f32, width: f32| Rect::new(x, 0.0, width, height).inset(margin);
+
+    let mut outside = Page::new(width, height);
+    fill_background(&mut outside, Rect::new(0.0, 0.0, width, height), branding);
+    draw_instructions_panel(&mut outside, panel_rect(0.0, flap), args, branding, text_size);
+    let back:Rect = panel_rect(flap, panel);
+    draw_details(&mut outside, (back.x, back.width), back.y + back.height / 2.0, args, branding, text_size * 1.25, Align::Left);
+    let cover:Rect = draw_footer(&mut outside, panel_rect(flap + panel, panel), branding, text_size);
+    draw_qr_panel(&mut outside, cover, &Captions::wifi(args), matrix, branding);
+    draw_fold_line(&mut outside, (flap, 0.0), (flap, height));
+    draw_fold_line(&mut outside, (flap + panel, 0.0), (flap + panel, height));
+
+    // On the back of the sheet the flap ends up on the right.
+    let mut inside = Page::new(width, height);
+    fill_background(&mut inside, Rect::new(0.0, 0.0, width, height), branding);
+    let left:Rect = panel_rect(0.0, panel);
+    draw_details(&mut inside, (left.x, left.width), left.y + left.height / 2.0, args, branding, text_size * 1.25, Align::Left);
+    let center:Rect = draw_footer(&mut inside, panel_rect(panel, panel), branding, text_size);
+    draw_qr_panel(&mut inside, center, &Captions::wifi(args), matrix, branding);
+    draw_instructions_panel(&mut inside, panel_rect(2.0 * panel, flap), args, branding, text_size);
+    draw_fold_line(&mut inside, (panel, 0.0), (panel, height));
+    draw_fold_line(&mut inside, (2.0 * panel, 0.0), (2.0 * panel, height));
+
+    vec![outside, inside]
+}
+
+/// Geometry of a sheet of labels, all lengths in millimeters.
+struct SheetGeometry{
+    paper: PaperSize,
+    columns: usize,
+    rows: usize,
+    label_width: f32,
+    label_height: f32,
+    top_margin: f32,
+    left_margin: f32,
+    /// Distance between the left edges of neighbouring labels.
+    horizontal_pitch: f32,
+    /// Distance between the top edges of neighbouring labels.
+    vertical_pitch: f32
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum LabelSheet{
+    /// A4, 21 labels of 63.5 x 38.1 mm
+    AveryL7160,
+    /// A4, 24 labels of 63.5 x 33.9 mm
+    AveryL7159,
+    /// A4, 14 labels of 99.1 x 38.1 mm
+    AveryL7163,
+    /// A4, 65 labels of 38.1 x 21.2 mm
+    AveryL7651,
+    /// Letter, 30 labels of 2.625 x 1 in
+    Avery5160,
+    /// Letter, 10 labels of 4 x 2 in
+    Avery5163,
+    /// A4, 24 labels of 70 x 36 mm
+    Herma4360
+}
+impl LabelSheet {
+    fn geometry(&self) -> SheetGeometry {
+        const INCH: f32 = 25.4;
+        match self {
+            LabelSheet::AveryL7160 => SheetGeometry{ paper: PaperSize::A4, columns: 3, rows: 7, label_width: 63.5, label_height: 38.1, top_margin: 15.15, left_margin: 7.25, horizontal_pitch: 66.04, vertical_pitch: 38.1 },
+            LabelSheet::AveryL7159 => SheetGeometry{ paper: PaperSize::A4, columns: 3, rows: 8, label_width: 63.5, label_height: 33.9, top_margin: 12.9, left_margin: 7.21, horizontal_pitch: 66.04, vertical_pitch: 33.9 },
+            LabelSheet::AveryL7163 => SheetGeometry{ paper: PaperSize::A4, columns: 2, rows: 7, label_width: 99.1, label_height: 38.1, top_margin: 15.15, left_margin: 4.65, horizontal_pitch: 101.6, vertical_pitch: 38.1 },
+            LabelSheet::AveryL7651 => SheetGeometry{ paper: PaperSize::A4, columns: 5, rows: 13, label_width: 38.1, label_height: 21.2, top_margin: 10.7, left_margin: 4.75, horizontal_pitch: 40.6, vertical_pitch: 21.2 },
+            LabelSheet::Avery5160 => SheetGeometry{ paper: PaperSize::Letter, columns: 3, rows: 10, label_width: 2.625 * INCH, label_height: 1.0 * INCH, top_margin: 0.5 * INCH, left_margin: 0.1875 * INCH, horizontal_pitch: 2.75 * INCH, vertical_pitch: 1.0 * INCH },
+            LabelSheet::Avery5163 => SheetGeometry{ paper: PaperSize::Letter, columns: 2, rows: 5, label_width: 4.0 * INCH, label_height: 2.0 * INCH, top_margin: 0.5 * INCH, left_margin: 0.15625 * INCH, horizontal_pitch: 4.1875 * INCH, vertical_pitch: 2.0 * INCH },
+            LabelSheet::Herma4360 => SheetGeometry{ paper: PaperSize::A4, columns: 3, rows: 8, label_width: 70.0, label_height: 36.0, top_margin: 4.5, left_margin: 0.0, horizontal_pitch: 70.0, vertical_pitch: 36.0 }
+        }
+    }
+    pub fn labels_per_sheet(&self) -> usize {
+        let geometry = self.geometry();
+        geometry.columns * geometry.rows
+    }
+}
+
+/// Rectangle of the label at `index`, counting left to right and top to bottom.
+fn label_rect(geometry: &SheetGeometry, index: usize) -> Rect {
+    let (_, page_height) = geometry.paper.dimensions();
+    let (column, row) = (index % geometry.columns, index / geometry.columns);
+    let x:f32 = (geometry.left_margin + column as f32 * geometry.horizontal_pitch) * MM;
+    let top:f32 = page_height - (geometry.top_margin + row as f32 * geometry.vertical_pitch) * MM;
+    Rect::new(x, top - geometry.label_height * MM, geometry.label_width * MM, geometry.label_height * MM)
+}
+
+/// Padding inside each label, leaving room for the feed tolerance of printers.
+const LABEL_PADDING: f32 = 2.0 * MM;
+
+/// Fills the `labels` of the sheet with the QR-Code and SSID. Starting after the first labels lets partially used
+/// sheets be reused, labels past the end of the sheet continue on further sheets. `outline` draws the label borders
+/// for test prints on plain paper, `vertical` sets the SSID top to bottom.
+pub fn label_sheet(args: &Args, matrix: &[Vec<bool>], branding: &Branding, sheet: LabelSheet, labels: std::ops::Range<usize>, outline: bool, vertical: bool) -> Vec<Page> {
+    let geometry = sheet.geometry();
+    let (width, height) = geometry.paper.dimensions();
+    let per_sheet:usize = sheet.labels_per_sheet();
+    let mut pages:Vec<Page> = Vec::new();
+    for index in labels {
+        if index % per_sheet == 0 || pages.is_empty() {
+            pages.push(Page::new(width, height));
+        }
+        let page:&mut Page = pages.last_mut().unwrap();
+        let rect:Rect = label_rect(&geometry, index % per_sheet);
+        fill_background(page, rect, branding);
+        if outline {
+            page.set_stroke_color(Color::GRAY);
+            page.line((rect.x, rect.y), (rect.x + rect.width, rect.y), 0.25);
+            page.line((rect.x + rect.width, rect.y), (rect.x + rect.width, rect.y + rect.height), 0.25);
+            page.line((rect.x + rect.width, rect.y + rect.height), (rect.x, rect.y + rect.height), 0.25);
+            page.line((rect.x, rect.y + rect.height), (rect.x, rect.y), 0.25);
+        }
+        if vertical {
+            draw_qr_with_vertical_caption(page, rect.inset(LABEL_PADDING), args, matrix, branding);
+        }else{
+            draw_qr_with_caption(page, rect.inset(LABEL_PADDING), args, matrix, branding);
+        }
+    }
+    pages
+}
+
+/// A grid of cards tiled on sheets of paper, for `batch --sheet`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sheet{
+    pub paper: PaperSize,
+    pub columns: usize,
+    pub rows: usize
+}
+impl Sheet {
+    pub fn cards_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+    /// The area the cards are tiled in, within the margin of the sheet.
+    fn area(&self) -> Rect {
+        let (width, height) = self.paper.dimensions();
+        Rect::new(0.0, 0.0, width, height).inset(SHEET_MARGIN)
+    }
+    /// Width and height of a card in points.
+    pub fn card_size(&self) -> (f32, f32) {
+        let area:Rect = self.area();
+        (area.width / self.columns as f32, area.height / self.rows as f32)
+    }
+    /// The card at `index` of a page, counted row by row from the top left.
+    fn card(&self, index: usize) -> Rect {
+        let area:Rect = self.area();
+        let (width, height) = self.card_size();
+        let (column, row) = (index % self.columns, index / self.columns);
+        Rect::new(area.x + column as f32 * width, area.y + area.height - (row + 1) as f32 * height, width, height)
+    }
+}
+
+/// Parses the grid of a `--sheet`, which has the form `<COLUMNS>x<ROWS>`, e.g. `2x4`.
+pub fn parse_grid(grid: &str) -> Result<(usize, usize), String> {
+    let count = |x: &str| x.trim().parse::<usize>().ok().filter(|x| (1..=12).contains(x));
+    match grid.split_once(['x', 'X']).map(|(columns, rows)| (count(columns), count(rows))) {
+        Some((Some(columns), Some(rows))) => Ok((columns, rows)),
+        _ => Err(format!("{} isn't of the form <COLUMNS>x<ROWS>, with 1 to 12 of each", grid))
+    }
+}
+
+/// Cards being tiled on the pages of a sheet, one after another. The cards abut, so they are cut apart along the
+/// crop marks in the margin of the sheet.
+pub struct CardSheet{
+    sheet: Sheet,
+    page: Option<Page>,
+    cards: usize
+}
+impl CardSheet {
+    pub fn new(sheet: Sheet) -> Self {
+        CardSheet{ sheet, page: None, cards: 0 }
+    }
+    /// Adds the card of the network. Returns the page once it is full.
+    pub fn add(&mut self, args: &Args, matrix: &[Vec<bool>], branding: &Branding) -> Option<Page> {
+        let sheet:Sheet = self.sheet;
+        let page:&mut Page = self.page.get_or_insert_with(|| sheet_page(sheet));
+        let card:Rect = sheet.card(self.cards % sheet.cards_per_page());
+        if card.width > card.height * 1.2 {
+            draw_landscape_card(page, card, args, matrix, branding);
+        }else{
+            draw_portrait_card(page, card, args, matrix, branding);
+        }
+        self.cards += 1;
+        if self.cards.is_multiple_of(sheet.cards_per_page()) {self.page.take()}else{None}
+    }
+    /// The last page, if it isn't full.
+    pub fn finish(self) -> Option<Page> {
+        self.page
+    }
+}
+
+/// A blank page of the sheet, with crop marks in the margin at the edges of every column and row of cards.
+fn sheet_page(sheet: Sheet) -> Page {
+    let (width, height) = sheet.paper.dimensions();
+    let mut page = Page::new(width, height);
+    let area:Rect = sheet.area();
+    let (card_width, card_height) = sheet.card_size();
+    let (left, bottom, right, top) = (area.x, area.y, area.x + area.width, area.y + area.height);
+    let gap:f32 = 2.0 * MM;
+    page.set_stroke_color(Color::BLACK);
+    for column in 0..=sheet.columns {
+        let x:f32 = left + column as f32 * card_width;
+        page.line((x, top + gap), (x, top + gap + CROP_MARK_LENGTH), 0.25);
+        page.line((x, bottom - gap), (x, bottom - gap - CROP_MARK_LENGTH), 0.25);
+    }
+    for row in 0..=sheet.rows {
+        let y:f32 = bottom + row as f32 * card_height;
+        page.line((left - gap, y), (left - gap - CROP_MARK_LENGTH, y), 0.25);
+        page.line((right + gap, y), (right + gap + CROP_MARK_LENGTH, y), 0.25);
+    }
+    page
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::i18n::Language;
+    #[test]
+    fn test_wrap_text() {
+        let lines = wrap_text(Font::Helvetica, "aaa bbb ccc", 10.0, Font::Helvetica.text_width("aaa bbb", 10.0));
+        assert_eq!(lines, vec!["aaa bbb", "ccc"]);
+    }
+    #[test]
+    fn test_business_card_pages() {
+        let args = crate::tests::network("Martin Router King", Some("password"), None);
+        let pages = business_card(&args, &[vec![true]], &Branding::default(), CardSize::Us, 3.0 * MM);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].trim_box, pages[1].trim_box);
+    }
+    #[test]
+    fn test_card() {
+        let args = crate::tests::network("Martin Router King", Some("password"), None);
+        for format in [CardFormat::BusinessCard, CardFormat::A6, CardFormat::Letter] {
+            let pages = card(&args, &[vec![true]], &Branding::default(), format);
+            assert_eq!((pages[0].width, pages[0].height), format.dimensions());
+            // The code spans the width within the margins, leaving room for the details beneath it.
+            let module_size:f32 = pages[0].module_sizes[0] * (1 + 2 * crate::QUIET_ZONE) as f32;
+            assert!(module_size > pages[0].width * 0.6 && module_size < pages[0].width * 0.85);
+        }
+        for preset in [LabelPreset::Brother62, LabelPreset::Brother29x90, LabelPreset::Dymo11354] {
+            let pages = label(&args, &[vec![true]], &Branding::default(), preset);
+            assert_eq!(pages.len(), 1);
+            assert_eq!((pages[0].width, pages[0].height), preset.dimensions());
+        }
+        assert_eq!(LabelPreset::Brother62.pixels(), (732, 945));
+    }
+    #[test]
+    fn test_wifi_and_url() {
+        let args = crate::tests::network("Martin Router King", None, None);
+        let pages = wifi_and_url(&args, &[vec![true]], &Branding::default(), PaperSize::A4, "https://example.com/menu", &[vec![true, false], vec![false, true]], "Menu");
+        assert_eq!(pages.len(), 1);
+        // Landscape, with both codes drawn the same size, so the smaller matrix gets larger modules.
+        assert!(pages[0].width > pages[0].height);
+        let module_sizes = &pages[0].module_sizes;
+        assert_eq!(module_sizes.len(), 2);
+        assert!(module_sizes[0] > module_sizes[1]);
+    }
+    #[test]
+    fn test_multi_band() {
+        let bands:Vec<Band> = ["2.4 GHz", "5 GHz", "6 GHz"].into_iter().enumerate()
+            .map(|(index, label)| Band{ label, ssid: "Cafe", matrix: vec![vec![true; index + 1]; index + 1] }).collect();
+        let pages = multi_band(&bands, &Branding::default(), PaperSize::A4, Some("example.com/wifi"), Language::En.texts());
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].width > pages[0].height);
+        assert_eq!(pages[0].module_sizes.len(), 3);
+        assert_eq!(parse_band("5 GHz=Cafe=5G"), Ok((String::from("5 GHz"), String::from("Cafe=5G"))));
+        assert!(parse_band("Cafe-5G").is_err());
+    }
+    #[test]
+    fn test_frame_fits_rect() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        for style in [FrameStyle::ScanMe, FrameStyle::Phone, FrameStyle::Arrow] {
+            let mut branding = Branding::default();
+            branding.frame = Some(Frame::new(style));
+            let qr_rect = draw_frame(&mut Page::new(100.0, 100.0), rect, branding.frame.as_ref().unwrap(), &branding);
+            assert!(qr_rect.width == qr_rect.height && qr_rect.width < 80.0);
+            assert!(qr_rect.x > 0.0 && qr_rect.y > 0.0 && qr_rect.x + qr_rect.width < 100.0 && qr_rect.y + qr_rect.height < 100.0);
+        }
+    }
+    #[test]
+    fn test_tri_fold_panels() {
+        let (width, _) = PaperSize::Letter.dimensions();
+        let (panel, flap) = tri_fold_panels(width);
+        assert!((2.0 * panel + flap - width).abs() < 0.001);
+        assert!((panel - flap - TRI_FOLD_FLAP_INSET).abs() < 0.001);
+    }
+    #[test]
+    fn test_label_sheets_fit_paper() {
+        for sheet in [LabelSheet::AveryL7160, LabelSheet::AveryL7159, LabelSheet::AveryL7163, LabelSheet::AveryL7651, LabelSheet::Avery5160, LabelSheet::Avery5163, LabelSheet::Herma4360] {
+            let geometry = sheet.geometry();
+            let (width, _) = geometry.paper.dimensions();
+            let last = label_rect(&geometry, sheet.labels_per_sheet() - 1);
+            assert!(last.x + last.width <= width + 0.01, "{:?} overflows horizontally", sheet);
+            assert!(last.y >= -0.01, "{:?} overflows vertically", sheet);
+            // The margins are symmetrical on all supported sheets.
+            assert!((last.x + last.width - (width - label_rect(&geometry, 0).x)).abs() < 0.5 * MM, "{:?} isn't centered", sheet);
+        }
+    }
+    #[test]
+    fn test_label_sheet_skip_and_pages() {
+        let args = crate::tests::network("Martin Router King", None, None);
+        assert_eq!(label_sheet(&args, &[vec![true]], &Branding::default(), LabelSheet::AveryL7160, 0..21, false, false).len(), 1);
+        assert_eq!(label_sheet(&args, &[vec![true]], &Branding::default(), LabelSheet::AveryL7160, 1..22, false, false).len(), 2);
+        assert_eq!(label_sheet(&args, &[vec![true]], &Branding::default(), LabelSheet::AveryL7160, 20..21, false, true).len(), 1);
+    }
+    #[test]
+    fn test_card_sheet() {
+        assert_eq!(parse_grid("2x4"), Ok((2, 4)));
+        assert!(parse_grid("2").is_err() && parse_grid("0x4").is_err() && parse_grid("2x13").is_err());
+        let sheet = Sheet{ paper: PaperSize::A4, columns: 2, rows: 4 };
+        // The cards tile the sheet within its margin, from the top left.
+        assert!((sheet.card(0).y + sheet.card(0).height - 285.0 * MM).abs() < 0.01);
+        assert!((sheet.card(7).x + sheet.card(7).width - 198.0 * MM).abs() < 0.01 && (sheet.card(7).y - SHEET_MARGIN).abs() < 0.01);
+        let args:Args = <Args as clap::Parser>::parse_from(["wifiqr", "--ssid", "Lobby"]);
+        let mut cards = CardSheet::new(sheet);
+        let full:usize = (0..11).filter_map(|_| cards.add(&args, &[vec![true]], &Branding::default())).count();
+        assert_eq!(full, 1);
+        assert!(cards.finish().is_some());
+        assert!(CardSheet::new(sheet).finish().is_none());
+    }
+    #[test]
+    fn test_trimmed_page_geometry() {
+        let (width, height) = CardSize::Eu.dimensions();
+        let (page, trim_box) = trimmed_page(width, height, 3.0 * MM);
+        assert!((trim_box.width - 85.0 * MM).abs() < 0.001);
+        // The trim box is centered, with the bleed box 3 mm outside of it.
+        assert!((page.width - trim_box.width - 2.0 * trim_box.x).abs() < 0.001);
+        let bleed_box = page.bleed_box.unwrap();
+        assert!((trim_box.x - bleed_box.x - 3.0 * MM).abs() < 0.001);
+        assert!(bleed_box.x > 0.0 && bleed_box.y > 0.0);
+    }
+}
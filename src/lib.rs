@@ -0,0 +1,33 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The payload and QR-Code of a WIFI network, as the `wifiqr` command line tool builds them, for tools of their
+//! own. [`WifiQrBuilder`] checks the network and encodes it, [`WifiCredentials`] assembles the `WIFI:` payload
+//! alone, and [`render`] draws the matrix of modules as text. All of it lives in `wifi-qr-core`, which the
+//! WebAssembly bindings share.
+//!
+//! ```
+//! use wifi_qr::{Security, WifiQrBuilder};
+//!
+//! let code = WifiQrBuilder::new("Home").password("hunter22").security(Security::Wpa2).build().unwrap();
+//! assert_eq!(code.payload(), "WIFI:T:WPA;S:Home;P:hunter22;;");
+//! print!("{}", code.to_blocks());
+//! ```
+
+pub use wifi_qr_core::*;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
@@ -0,0 +1,210 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A logo in the center of the QR-Code for `--logo`. The modules beneath it are left light, and readers recover
+//! them through error correction, so the square it covers is kept to half of what the ECC-Level restores, leaving
+//! the rest for smudges and glare. SVG logos are inlined into SVG outputs as they are, staying sharp at any size.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+use crate::ECCLevel;
+
+/// Width of the logo in percent of the width of the code, without the quiet zone.
+pub const DEFAULT_SIZE: u8 = 20;
+/// Longest side logos are scaled down to before they are embedded into SVGs.
+pub const MAX_EMBEDDED_SIZE: u32 = 512;
+
+/// The square of modules the logo covers, including a light margin of one module around it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Area{
+    /// Row and column of the top-left module.
+    pub start: usize,
+    /// Width and height in modules.
+    pub side: usize
+}
+impl Area {
+    /// The centered square of a code `modules` wide for a logo `percent` as wide. Its side is odd, so it lines up
+    /// with the center module.
+    pub fn centered(modules: usize, percent: u8) -> Self {
+        let logo:usize = (modules * percent as usize / 100).max(1);
+        let side:usize = (logo | 1) + 2;
+        Area{ start: (modules - side) / 2, side }
+    }
+    /// Share of the modules of a code `modules` wide that are covered.
+    pub fn share(&self, modules: usize) -> f32 {
+        (self.side * self.side) as f32 / (modules * modules) as f32
+    }
+}
+
+/// Share of the modules that may be covered at the ECC-Level: half of those it restores.
+pub fn budget(ecc: ECCLevel) -> f32 {
+    match ecc {
+        ECCLevel::Low => 0.035,
+        ECCLevel::Medium => 0.075,
+        ECCLevel::Quartile => 0.125,
+        ECCLevel::High => 0.15
+    }
+}
+
+/// A logo as read from its file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Logo{
+    Raster(RgbaImage),
+    Vector(VectorLogo)
+}
+
+/// The root `<svg>` element of an SVG logo, without the size and position it had in its own file, which
+/// [`VectorLogo::element`] gives it on the code instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VectorLogo{
+    /// The namespace declarations and attributes of the root element, each with a leading space.
+    attributes: String,
+    content: String
+}
+impl VectorLogo {
+    /// Parses an SVG, which needs a `viewBox`, or a unitless or pixel width and height to scale it by.
+    pub fn parse(svg: &str) -> Result<Self, String> {
+        let document = roxmltree::Document::parse(svg).map_err(|err| err.to_string())?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(format!("the root element is <{}>, not <svg>", root.tag_name().name()));
+        }
+        let mut attributes:String = String::new();
+        for namespace in root.namespaces() {
+            match namespace.name() {
+                Some(prefix) => attributes.push_str(&format!(" xmlns:{}=\"{}\"", prefix, crate::svg::escape(namespace.uri()))),
+                None => attributes.push_str(&format!(" xmlns=\"{}\"", crate::svg::escape(namespace.uri())))
+            }
+        }
+        for attribute in root.attributes().filter(|x| x.namespace().is_some() || !["x", "y", "width", "height"].contains(&x.name())) {
+            let prefix:Option<&str> = attribute.namespace().and_then(|x| root.lookup_prefix(x));
+            attributes.push_str(&format!(" {}{}=\"{}\"", prefix.map_or(String::new(), |x| format!("{}:", x)), attribute.name(), crate::svg::escape(attribute.value())));
+        }
+        if root.attribute("viewBox").is_none() {
+            let pixels = |name: &str| root.attribute(name).and_then(|x| x.trim().trim_end_matches("px").parse::<f32>().ok()).filter(|x| *x > 0.0);
+            match (pixels("width"), pixels("height")) {
+                (Some(width), Some(height)) => attributes.push_str(&format!(" viewBox=\"0 0 {} {}\"", width, height)),
+                _ => return Err(String::from("it has neither a viewBox nor a width and height in pixels to scale it by"))
+            }
+        }
+        let content:String = match (root.first_child(), root.last_child()) {
+            (Some(first), Some(last)) => String::from(&svg[first.range().start..last.range().end]),
+            _ => String::new()
+        };
+        Ok(VectorLogo{ attributes, content })
+    }
+    /// The logo as an `<svg>` element filling the square `side` units wide at `start` on both axes, keeping its
+    /// aspect ratio.
+    pub fn element(&self, start: usize, side: usize) -> String {
+        format!("<svg x=\"{start}\" y=\"{start}\" width=\"{side}\" height=\"{side}\"{}>{}</svg>", self.attributes, self.content)
+    }
+}
+
+#[derive(Debug)]
+pub enum LogoError{
+    Image(PathBuf, image::ImageError),
+    Svg(PathBuf, String),
+    /// An SVG logo for an output that isn't an SVG, which it can't be drawn into.
+    VectorOnly(PathBuf),
+    /// The logo would cover this share of the modules, more than the budget of the ECC-Level.
+    TooLarge(f32, ECCLevel)
+}
+impl fmt::Display for LogoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogoError::Image(path, err) => write!(f, "failed to load the logo {}: {}", path.display(), err),
+            LogoError::Svg(path, err) => write!(f, "failed to load the logo {}: {}", path.display(), err),
+            LogoError::VectorOnly(path) => write!(f, "the SVG logo {} can only be placed on SVGs, pass a PNG logo for images", path.display()),
+            LogoError::TooLarge(share, ecc) => write!(f, "the logo would cover {:.1}% of the modules, but at ECC-Level {:?} at most {:.1}% can be covered for the code to still scan, pass a smaller --logo-size",
+                share * 100.0, ecc, budget(*ecc) * 100.0)
+        }
+    }
+}
+
+/// Reads the logo, an SVG if its extension is `.svg`. `vector` is whether the output can take an SVG logo.
+pub fn load(path: &Path, vector: bool) -> Result<Logo, LogoError> {
+    if !path.extension().is_some_and(|x| x.eq_ignore_ascii_case("svg")) {
+        return Ok(Logo::Raster(image::open(path).map_err(|err| LogoError::Image(path.to_path_buf(), err))?.to_rgba8()));
+    }
+    if !vector {
+        return Err(LogoError::VectorOnly(path.to_path_buf()));
+    }
+    let svg:String = std::fs::read_to_string(path).map_err(|err| LogoError::Svg(path.to_path_buf(), err.to_string()))?;
+    Ok(Logo::Vector(VectorLogo::parse(&svg).map_err(|err| LogoError::Svg(path.to_path_buf(), err))?))
+}
+
+/// The area of the logo on the code of `matrix`, failing if it would cover more modules than the ECC-Level restores.
+pub fn area(matrix: &[Vec<bool>], percent: u8, ecc: ECCLevel) -> Result<Area, LogoError> {
+    let area:Area = Area::centered(matrix.len(), percent);
+    let share:f32 = area.share(matrix.len());
+    if share > budget(ecc) {
+        return Err(LogoError::TooLarge(share, ecc));
+    }
+    Ok(area)
+}
+
+/// The matrix with the modules of the area left light, so the logo is placed on a light square.
+pub fn cleared(matrix: &[Vec<bool>], area: Area) -> Vec<Vec<bool>> {
+    let covered = |x: usize| (area.start..area.start + area.side).contains(&x);
+    matrix.iter().enumerate().map(|(y, row)| row.iter().enumerate().map(|(x, &dark)| dark && !(covered(x) && covered(y))).collect()).collect()
+}
+
+/// The logo scaled to fit into a square `size` pixels wide, keeping its aspect ratio.
+pub fn fitted(logo: &RgbaImage, size: u32) -> RgbaImage {
+    let scale:f32 = size as f32 / logo.width().max(logo.height()) as f32;
+    let (width, height) = (((logo.width() as f32 * scale) as u32).max(1), ((logo.height() as f32 * scale) as u32).max(1));
+    image::imageops::resize(logo, width, height, image::imageops::FilterType::Lanczos3)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_area() {
+        // 20% of version 3 is 5 modules, with the margin 7.
+        assert_eq!(Area::centered(29, 20), Area{ start: 11, side: 7 });
+        // Even widths are rounded up to odd ones.
+        assert_eq!(Area::centered(41, 20), Area{ start: 15, side: 11 });
+        let matrix:Vec<Vec<bool>> = vec![vec![true; 29]; 29];
+        assert!(area(&matrix, 20, ECCLevel::High).is_ok());
+        assert!(matches!(area(&matrix, 20, ECCLevel::Low), Err(LogoError::TooLarge(_, ECCLevel::Low))));
+        assert!(matches!(area(&matrix, 40, ECCLevel::High), Err(LogoError::TooLarge(..))));
+    }
+    #[test]
+    fn test_cleared() {
+        let matrix:Vec<Vec<bool>> = cleared(&vec![vec![true; 5]; 5], Area{ start: 1, side: 3 });
+        assert_eq!(matrix[0], vec![true; 5]);
+        assert_eq!(matrix[2], vec![true, false, false, false, true]);
+    }
+    #[test]
+    fn test_vector_logo() {
+        let logo = VectorLogo::parse("<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"40px\" height=\"20\" x=\"3\"><circle r=\"5\"/><use xlink:href=\"#a\"/></svg>").unwrap();
+        assert_eq!(logo.element(2, 3), "<svg x=\"2\" y=\"2\" width=\"3\" height=\"3\" xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" viewBox=\"0 0 40 20\"><circle r=\"5\"/><use xlink:href=\"#a\"/></svg>");
+        let logo = VectorLogo::parse("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 1 1\" width=\"10mm\"/>").unwrap();
+        assert_eq!(logo.element(0, 1), "<svg x=\"0\" y=\"0\" width=\"1\" height=\"1\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 1 1\"></svg>");
+        assert!(VectorLogo::parse("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10mm\" height=\"10mm\"/>").is_err());
+        assert!(VectorLogo::parse("<html/>").is_err());
+        assert!(matches!(load(Path::new("logo.svg"), false), Err(LogoError::VectorOnly(_))));
+    }
+    #[test]
+    fn test_fitted() {
+        let logo:RgbaImage = RgbaImage::new(200, 100);
+        assert_eq!(fitted(&logo, 50).dimensions(), (50, 25));
+    }
+}
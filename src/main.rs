@@ -14,95 +14,1658 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-use clap::Parser;
+mod alt_text;
+mod android;
+mod archive;
+mod batch;
+mod bench;
+mod branding;
+mod capacity;
+mod check;
+mod clipboard;
+mod color;
+mod config;
+mod connect;
+mod console;
+mod credentials;
+mod diagnostics;
+mod distance;
+mod dpp;
+mod eap;
+mod eps;
+mod error;
+mod explain;
+mod font;
+mod graphics;
+mod gui;
+mod hex_ssid;
+mod hotspot;
+mod html;
+mod i18n;
+mod inspect;
+#[cfg(target_os = "macos")]
+mod keychain;
+mod layout;
+mod logo;
+mod manpage;
+mod micro;
+mod mobileconfig;
+mod ndef;
+mod nfc;
+mod output;
+mod parse;
+mod passphrase;
+mod password_manager;
+mod pbm;
+mod pdf;
+mod plist;
+mod plugin;
+mod preview;
+mod print;
+mod private_dir;
+mod profile;
+mod prompt;
+mod raster;
+mod report;
+mod saved;
+mod scan;
+mod script;
+mod serve;
+mod svg;
+mod symbol;
+mod template;
+mod tui;
+mod wallet;
+mod verify;
+mod wallpaper;
+mod wep;
+mod wps;
+
+use clap::{CommandFactory, FromArgMatches, Parser};
+
+use error::{Context, Error, ErrorKind};
+use wifi_qr::render::{self, braille, half_blocks, scaled, with_border, with_quiet_zone, QUIET_ZONE};
+use wifi_qr::{escape as escape_payload_value, hex as hex_payload_value, is_hex_like, is_raw_key, quote as quote_payload_value, quote_password, BuildError, PayloadStyle, Security, WifiCredentials, WifiQrBuilder, SPECIAL_CHARACTERS};
 #[derive(Parser, Debug)]
 #[clap(name="WIFI-QR", author="Frostie314159", version="0.0.1", about="Creates QR-codes for logging into a WIFI-network.", long_about = None)]
 struct Args{
-    /// WIFI SSID
-    #[clap(short, long, value_parser)]
+    /// WIFI SSID. Not needed by `batch`, which reads the networks from a file, and `gui`.
+    #[clap(short, long, value_parser, default_value="", hide_default_value=true)]
     ssid: String,
+
+    #[clap(flatten)]
+    hex_ssid: hex_ssid::Options,
     
     /// WIFI password
     #[clap(short, long, value_parser)]
     psw: Option<String>,
 
+    /// Ask for the SSID and password on the terminal, without showing the password. Also asked for whenever `--sec`
+    /// is given without `--psw`. Keeps the password out of the shell history and the process list.
+    #[clap(short, long, action)]
+    interactive: bool,
+
+    #[clap(flatten)]
+    psw_source: credentials::PasswordSource,
+
+    #[clap(flatten)]
+    generator: passphrase::Generator,
+
+    #[clap(flatten)]
+    saved: saved::Source,
+
     /// WIFI security: If the Wifi is open omit this argument.
     #[clap(arg_enum, long, value_parser)]
-    sec: Option<SecurityTypes>,
+    sec: Option<Security>,
 
     /// Mark the WIFI as hidden.
     #[clap(short, long, action)]
     hidden: bool,
 
+    /// Write `H:false` for visible networks, as earlier versions did, for readers that expect the field. It is
+    /// otherwise left out, as some readers reject it.
+    #[clap(long, action, conflicts_with = "micro")]
+    compat: bool,
+
+    /// Tell devices to no longer fall back to WPA2 once they joined the network with WPA3, written as `R:1`. Only
+    /// for `--sec wpa3`.
+    #[clap(long, action)]
+    transition_disable: bool,
+
     /// Set the QR-Code ECC-Level. Low is the default.
-    #[clap(arg_enum, short, long, value_parser, default_value_t=ECCLevel::Low)]
-    ecc: ECCLevel
+    #[clap(arg_enum, short, long, value_parser, default_value_t=ECCLevel::Low, env = "WIFIQR_ECC")]
+    ecc: ECCLevel,
+
+    #[clap(flatten)]
+    symbol: symbol::Options,
+
+    /// Branding (logo, palette, fonts and footer) to apply to the outputs. Defaults to `branding.toml` in the
+    /// working directory, if there is one.
+    #[clap(long, value_parser)]
+    branding: Option<std::path::PathBuf>,
+
+    /// Font for all captions, overriding the fonts of the branding: a TrueType/OpenType file or the family name of
+    /// an installed font. It is embedded into PDFs as a subset.
+    #[clap(long, value_parser)]
+    font: Option<String>,
+
+    /// Alignment of captions, overriding the branding. `auto` keeps the alignment of the layout, but aligns
+    /// right-to-left lines to the right where they'd be on the left.
+    #[clap(arg_enum, long, value_parser)]
+    align: Option<branding::Align>,
+
+    /// Frame with a call to action wrapping the QR-Code in PDFs, overriding the style of the branding's frame.
+    #[clap(arg_enum, long, value_parser)]
+    frame: Option<branding::FrameStyle>,
+
+    /// Colors for text and accents, replacing the palette of the branding with one that stays readable with
+    /// color-vision deficiencies.
+    #[clap(arg_enum, long, value_parser)]
+    palette: Option<branding::PalettePreset>,
+
+    /// Distance in meters the code is scanned from, e.g. for lobby banners. Raises the ECC-Level, drops the logo
+    /// and colors of the branding, and rejects layouts whose modules are too small to be scanned from there.
+    #[clap(long, value_parser)]
+    scan_distance: Option<f32>,
+
+    /// Another network to generate the outputs for, e.g. `--network ssid=Staff,psw=secret,sec=wpa3`. The keys are
+    /// `ssid`, `psw`, `sec` and `hidden`; commas and backslashes in values are escaped with a backslash. Can be
+    /// repeated, and used in place of `--ssid`.
+    #[clap(long = "network", value_parser)]
+    networks: Vec<batch::Network>,
+
+    /// Overwrite temporary files, which contain the password, with zeros before deleting them.
+    #[clap(long, action)]
+    shred: bool,
+
+    /// Step down to a lower ECC-Level, with a warning, if the payload doesn't fit at the requested one, instead of
+    /// failing. For unattended runs.
+    #[clap(long, action)]
+    downgrade_ecc: bool,
+
+    /// Encode the network even if devices won't accept its SSID or password, e.g. a WPA passphrase of 7 characters.
+    #[clap(long, action)]
+    force: bool,
+
+    #[clap(flatten)]
+    wep: wep::Options,
+
+    /// Short URL printed beneath the QR-Code in layouts, for devices that can't scan it, such as a landing page
+    /// rendered with `template`.
+    #[clap(long, value_parser)]
+    info_url: Option<String>,
+
+    /// Walk through how the payload and the QR-Code were built, on stderr: the defaults resolved, the fields written,
+    /// and the ECC-Level and version chosen. The password is masked.
+    #[clap(long, action)]
+    explain: bool,
+
+    /// Check every code by decoding it as a reader would and comparing the network read back with the one given,
+    /// failing if they differ, e.g. for the codes of a `batch` in CI.
+    #[clap(long, action, conflicts_with = "micro")]
+    verify: bool,
+
+    #[clap(flatten)]
+    enterprise: eap::Enterprise,
+
+    #[clap(flatten)]
+    output: output::Options,
+
+    /// Config file with defaults for the ECC-Level and the output options. Defaults to `wifi-qr/config.toml` in the
+    /// user's config directory, if there is one.
+    #[clap(long, value_parser, env = "WIFIQR_CONFIG")]
+    config: Option<std::path::PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command{
+    /// Draw the network of the options into the terminal or write it to `--output`, as without a subcommand. The
+    /// options may follow it, e.g. `wifiqr encode --ssid Home --psw hunter22`.
+    Encode,
+    /// Write the network as a Wi-Fi Simple Config NDEF record to an NFC tag (NTAG21x) via a PC/SC reader.
+    WriteNfc{
+        /// Use the first PC/SC reader whose name contains this string, instead of the first reader.
+        #[clap(long, value_parser)]
+        reader: Option<String>
+    },
+    /// Encode a WPS PIN, for devices and apps joining through Wi-Fi Protected Setup, instead of the network. The
+    /// code holds the digits of the PIN alone, as the `WIFI:` payload has no field for it.
+    Wps{
+        /// The PIN of the access point or the device, 8 digits whose last is a checksum, or 4 digits. May be written
+        /// in groups, e.g. `1234-5670`.
+        #[clap(long, value_parser, required_unless_present = "generate", conflicts_with = "generate")]
+        pin: Option<String>,
+
+        /// Generate a random 8-digit PIN, to configure the access point with.
+        #[clap(long, action)]
+        generate: bool,
+
+        /// Write the QR-Code to a PNG or SVG instead of printing it to the terminal.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Encode the Wi-Fi Easy Connect (DPP) URI of a device or access point, which configurators such as Android 10 and
+    /// later scan to onboard it by its public key, instead of the network.
+    Dpp{
+        /// The public bootstrapping key, the base64 of its DER, e.g. as shown after `K:` by `wpa_cli
+        /// dpp_bootstrap_get_uri`.
+        #[clap(long, value_parser, required_unless_present = "key-file", conflicts_with = "key-file")]
+        key: Option<String>,
+
+        /// A file with the public key, in PEM or DER, e.g. written by `openssl ec -in key.pem -pubout`.
+        #[clap(long, value_parser)]
+        key_file: Option<std::path::PathBuf>,
+
+        /// A channel it listens on, `<CLASS>/<CHANNEL>` with the global operating class, e.g. `81/1` for channel 1 of
+        /// 2.4 GHz or `115/36` for channel 36 of 5 GHz. Can be repeated or separated by commas.
+        #[clap(long = "channel", value_parser = dpp::parse_channel, value_delimiter = ',')]
+        channels: Vec<(u8, u8)>,
+
+        /// The MAC address it listens on, e.g. `52:54:00:58:28:e5`.
+        #[clap(long, value_parser = dpp::parse_mac)]
+        mac: Option<[u8; 6]>,
+
+        /// Information such as its serial number, e.g. `SN=4774LH2b4044`.
+        #[clap(long, value_parser = dpp::parse_info)]
+        info: Option<String>,
+
+        /// Write the QR-Code to a PNG or SVG instead of printing it to the terminal.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Share the connection of the machine as a hotspot of the network, through NetworkManager or the hosted network
+    /// of Windows, and print its QR-Code until Ctrl+C takes it down, e.g. `wifiqr --ssid Demo --generate-psw hotspot`.
+    Hotspot{
+        /// The Wi-Fi adapter, e.g. `wlan0`, the first one by default. Only with NetworkManager.
+        #[clap(long, value_parser)]
+        interface: Option<String>,
+
+        /// The band, picked by NetworkManager by default. Only with NetworkManager.
+        #[clap(arg_enum, long, value_parser)]
+        band: Option<hotspot::Band>
+    },
+    /// Export the network to a password manager (pass, 1Password, Bitwarden), an encrypted archive, an Android
+    /// policy, a Windows profile, an Apple Wallet pass or an Apple configuration profile.
+    Export{
+        #[clap(subcommand)]
+        target: ExportTarget
+    },
+    /// Render a print-ready PDF layout.
+    Layout{
+        #[clap(subcommand)]
+        layout: LayoutKind
+    },
+    /// Print the card of `--card` with the QR-Code and the network, through CUPS on Linux and macOS, or the spooler
+    /// on Windows, which sends it as a PDF the printer has to take.
+    Print{
+        /// Name of the printer, as `lpstat -p` or the printer settings of Windows list it. Defaults to the default
+        /// printer.
+        #[clap(long, value_parser)]
+        printer: Option<String>,
+
+        /// Number of copies to print.
+        #[clap(long, value_parser = clap::value_parser!(u16).range(1..=999), default_value_t = 1)]
+        copies: u16
+    },
+    /// Serve a form in the browser, which makes the QR-Code of the network entered, and `/qr?ssid=...` returning
+    /// the code as an SVG or PNG. The other options apply to the codes as on the command line.
+    Serve{
+        /// Address and port to listen on. Passwords are sent in the URL, so only listen on other addresses than
+        /// `127.0.0.1` in a trusted network.
+        #[clap(long, value_parser, default_value = "127.0.0.1:8080")]
+        bind: std::net::SocketAddr
+    },
+    /// Composite the QR-Code into a desktop or phone wallpaper.
+    Wallpaper{
+        /// Background color (`#RRGGBB`) or path of a background image. Defaults to the background of the branding.
+        #[clap(long, value_parser)]
+        background: Option<wallpaper::Background>,
+
+        /// Width of the wallpaper in pixels. Defaults to the width of the background image, or 1920.
+        #[clap(long, value_parser)]
+        width: Option<u32>,
+
+        /// Height of the wallpaper in pixels. Defaults to the height of the background image, or 1080.
+        #[clap(long, value_parser)]
+        height: Option<u32>,
+
+        /// Where to place the QR-Code.
+        #[clap(arg_enum, long, value_parser, default_value_t=wallpaper::Corner::BottomRight)]
+        corner: wallpaper::Corner,
+
+        /// Size of the QR-Code in pixels. Defaults to a quarter of the shorter side.
+        #[clap(long, value_parser)]
+        qr_size: Option<u32>,
+
+        /// Distance between the QR-Code and the edges in pixels. Defaults to 1/20 of the shorter side.
+        #[clap(long, value_parser)]
+        margin: Option<u32>,
+
+        /// Leave out the SSID below the QR-Code.
+        #[clap(long, action)]
+        no_caption: bool,
+
+        /// Write a description of the network for screen readers to `<OUTPUT>.alt.txt`, also embedding it into PNGs.
+        #[clap(long, action)]
+        alt_text: bool,
+
+        /// Store TIFFs in CMYK, with the dark modules on the black ink only.
+        #[clap(long, action)]
+        cmyk: bool,
+
+        /// Path of the image, its extension picking the format. Defaults to `<SSID>-wallpaper.png`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Render a lock-screen image at the device's resolution, keeping the QR-Code clear of the clock.
+    LockScreen{
+        #[clap(arg_enum, value_parser)]
+        device: wallpaper::LockScreen,
+
+        /// Background color (`#RRGGBB`) or path of a background image. Defaults to the background of the branding.
+        #[clap(long, value_parser)]
+        background: Option<wallpaper::Background>,
+
+        /// Leave out the SSID below the QR-Code.
+        #[clap(long, action)]
+        no_caption: bool,
+
+        /// Write a description of the network for screen readers to `<OUTPUT>.alt.txt`, also embedding it into PNGs.
+        #[clap(long, action)]
+        alt_text: bool,
+
+        /// Store TIFFs in CMYK, with the dark modules on the black ink only.
+        #[clap(long, action)]
+        cmyk: bool,
+
+        /// Path of the image, its extension picking the format. Defaults to `<SSID>-lock-screen.png`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Render a user-supplied HTML, SVG or text template, filling in the network, the QR-Code and the branding.
+    Template{
+        /// Path of the Tera template.
+        #[clap(value_parser)]
+        template: std::path::PathBuf,
+
+        /// Path of the rendered file. Defaults to `<SSID>-<TEMPLATE NAME>`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>,
+
+        /// Text for the template as `key=value`, inserted with `{{ vars.key }}`. Can be given several times.
+        #[clap(long = "var", value_parser = plugin::parse_option)]
+        vars: Vec<(String, String)>
+    },
+    /// Write a self-contained HTML page showing the QR-Code full-screen, with the fonts and logo of the branding
+    /// inlined, so it works offline.
+    Html{
+        /// Path of the page. Defaults to `<SSID>.html`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Render the network with a WebAssembly plugin, for output formats wifiqr doesn't support itself.
+    Plugin{
+        /// Path of the `.wasm` module.
+        #[clap(value_parser)]
+        plugin: std::path::PathBuf,
+
+        /// Path of the rendered file.
+        #[clap(short, long, value_parser)]
+        output: std::path::PathBuf,
+
+        /// Option passed to the plugin, as `key=value`. Can be given several times.
+        #[clap(long = "option", value_parser = plugin::parse_option)]
+        options: Vec<(String, String)>
+    },
+    /// Open a window to enter the network, preview the QR-Code and save or print it. Needs the `gui` feature.
+    Gui,
+    /// Enter the network in a form in the terminal, with the QR-Code redrawn as you type and saved as a PNG or SVG
+    /// with Ctrl+S or Ctrl+E. The network of the command line is filled in.
+    Tui,
+    /// Serve the table tent, or a template, on localhost, reloading the page whenever the template or the branding
+    /// file is saved.
+    Preview{
+        /// Path of a Tera template to preview instead of the table tent.
+        #[clap(value_parser)]
+        template: Option<std::path::PathBuf>,
+
+        #[clap(long, value_parser, default_value_t=8000)]
+        port: u16
+    },
+    /// Generate the outputs for every network of a CSV file (columns `ssid`, `password`, `security`, `hidden`), a
+    /// JSON array of objects with those keys, or the `WifiConfigStore.xml` of an Android phone. Newline-delimited
+    /// JSON records (`.jsonl`, or `-` for stdin) are rendered as they arrive, with a JSON result printed for each.
+    Batch{
+        /// Path of the CSV file, a `.json` array, the `WifiConfigStore.xml` of an Android phone, or JSON records
+        /// (`.jsonl`, or `-` to read them from stdin).
+        #[clap(value_parser)]
+        networks: std::path::PathBuf,
+
+        /// Directory the outputs are written to.
+        #[clap(long, value_parser, default_value=".")]
+        out_dir: std::path::PathBuf,
+
+        /// Path of an output below `--out-dir`, its extension picking the format (png, tiff, gif, svg, txt, ndef, html
+        /// or pdf). `{ssid}`, `{security}` and `{index}` are filled in for each network; a pdf without them collects
+        /// the table tents of every network. Can be repeated.
+        #[clap(short, long = "output", value_parser, default_values=&batch::DEFAULT_OUTPUTS)]
+        outputs: Vec<String>,
+
+        /// Tile the cards of the networks on sheets in a grid of <COLUMNS>x<ROWS>, e.g. 2x4, with crop marks, in the
+        /// pdf and html outputs without placeholders.
+        #[clap(long, value_parser = layout::parse_grid)]
+        sheet: Option<(usize, usize)>,
+
+        /// Paper of the `--sheet`.
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::PaperSize::A4, requires = "sheet")]
+        paper: layout::PaperSize,
+
+        /// Rhai script defining `payload`, `caption` or `filename` hooks, which are called for each network. Needs
+        /// the `scripting` feature.
+        #[clap(long, value_parser)]
+        script: Option<std::path::PathBuf>,
+
+        /// Number of threads rendering images besides the one laying out PDFs and pages, one per CPU by default.
+        #[clap(long, value_parser)]
+        jobs: Option<usize>,
+
+        /// Only validate the networks, reporting what is wrong with each, and exit with 1 if any is invalid.
+        #[clap(long, value_parser)]
+        check: bool
+    },
+    /// Decode the QR-Codes of an image and report their version, ECC-Level, mask and payload, checking `WIFI:`
+    /// payloads against the format readers implement.
+    Inspect{
+        #[clap(value_parser)]
+        image: std::path::PathBuf
+    },
+    /// Decode the Wi-Fi QR-Codes of an image, e.g. a photo of a sticker, and print the SSID, security type and
+    /// password of their networks.
+    Decode{
+        #[clap(value_parser)]
+        image: std::path::PathBuf
+    },
+    /// Join the network of a Wi-Fi code on this machine, saving it as a NetworkManager connection or a Windows WLAN
+    /// profile named after the SSID, which replaces one of the same name.
+    Connect{
+        /// An image of the code, e.g. a photo, or its `WIFI:` payload.
+        #[clap(value_parser, value_name = "IMAGE|PAYLOAD")]
+        code: String
+    },
+    /// Print the fields of a `WIFI:` payload made elsewhere, e.g. by a scanner app, as JSON or YAML, with the
+    /// values unescaped and what readers may trip over. Exits with 3 if readers would reject the payload.
+    Parse{
+        /// The payload, or `-` to read it from stdin.
+        #[clap(value_parser)]
+        payload: String,
+
+        #[clap(arg_enum, long, value_parser, default_value_t = parse::Format::Json)]
+        format: parse::Format
+    },
+    /// Print the completion script of the shell, e.g. `wifiqr completions bash >
+    /// /usr/share/bash-completion/completions/wifiqr`.
+    Completions{
+        #[clap(arg_enum, value_parser)]
+        shell: clap_complete::Shell
+    },
+    /// Print the man page, e.g. `wifiqr manpage > /usr/share/man/man1/wifiqr.1`, or write it with the pages of the
+    /// subcommands it refers to into a directory.
+    Manpage{
+        /// Directory to write `wifiqr.1` and the pages of the subcommands, such as `wifiqr-export-wallet.1`, into.
+        #[clap(long, value_parser)]
+        out_dir: Option<std::path::PathBuf>
+    },
+    /// Convert network profiles between the formats of operating systems, e.g. a Windows profile exported with
+    /// `netsh wlan export profile key=clear` into a NetworkManager keyfile.
+    Convert{
+        /// The profile to read.
+        #[clap(value_parser)]
+        input: std::path::PathBuf,
+
+        /// Format of the input. Told from its extension and contents by default.
+        #[clap(arg_enum, long, value_parser)]
+        from: Option<profile::ProfileFormat>,
+
+        /// Format to write.
+        #[clap(arg_enum, long, value_parser, required_unless_present = "check")]
+        to: Option<profile::ProfileFormat>,
+
+        /// Path of the output. Defaults to a name that fits the format, such as `<SSID>.nmconnection`, in the
+        /// working directory.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>,
+
+        /// Only validate the networks of the profile, reporting what is wrong with each, and exit with 1 if any is
+        /// invalid.
+        #[clap(long, value_parser)]
+        check: bool
+    },
+    /// Measure the throughput and allocations of building payloads, generating QR-Codes and each output over
+    /// synthetic networks.
+    Bench{
+        /// Number of synthetic networks.
+        #[clap(long, value_parser, default_value_t=200)]
+        networks: usize
+    },
+    /// Report the printed size and resolution needed to scan the code from `--scan-distance`, or the distance a
+    /// code printed `--width` wide scans from.
+    SizeCalc{
+        /// Printed width of the code in millimeters, including the quiet zone.
+        #[clap(long, value_parser)]
+        width: Option<f32>,
+
+        /// Print a table of the version, module count and size at every ECC-Level instead.
+        #[clap(long, action)]
+        compare_ecc: bool
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum LayoutKind{
+    /// Two-sided business card: the QR-Code and SSID on the front, instructions on the back.
+    BusinessCard{
+        /// Trimmed size of the card.
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::CardSize::Eu)]
+        size: layout::CardSize,
+
+        /// Bleed around the trimmed card in millimeters.
+        #[clap(long, value_parser, default_value_t=3.0)]
+        bleed: f32,
+
+        /// Path of the PDF. Defaults to `<SSID>-card.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Sheet folded in half into a tent, showing the QR-Code on both sides.
+    TableTent{
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::PaperSize::A4)]
+        paper: layout::PaperSize,
+
+        /// Path of the PDF. Defaults to `<SSID>-table-tent.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Landscape sheet folded into thirds, printed on both sides.
+    TriFold{
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::PaperSize::A4)]
+        paper: layout::PaperSize,
+
+        /// Path of the PDF. Defaults to `<SSID>-tri-fold.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Landscape sheet with the Wi-Fi QR-Code next to a QR-Code for a website, such as a menu or feedback form.
+    WifiAndUrl{
+        /// Address the second QR-Code opens.
+        #[clap(long, value_parser)]
+        url: String,
+
+        /// Title above the second QR-Code.
+        #[clap(long, value_parser, default_value="Website")]
+        url_title: String,
+
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::PaperSize::A4)]
+        paper: layout::PaperSize,
+
+        /// Path of the PDF. Defaults to `<SSID>-wifi-and-url.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Landscape sheet with a QR-Code for each band of a network that uses distinct SSIDs per band, the one of
+    /// `--ssid` first.
+    MultiBand{
+        /// Label of the band of `--ssid`.
+        #[clap(long, value_parser, default_value="2.4 GHz")]
+        label: String,
+
+        /// Another band, as `<LABEL>=<SSID>`, e.g. `--band "5 GHz=Cafe-5G"`. The bands share the password. Can be
+        /// repeated.
+        #[clap(long = "band", value_parser = layout::parse_band, required = true)]
+        bands: Vec<(String, String)>,
+
+        #[clap(arg_enum, long, value_parser, default_value_t=layout::PaperSize::A4)]
+        paper: layout::PaperSize,
+
+        /// Path of the PDF. Defaults to `<SSID>-multi-band.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Sheet of sticker labels, each with the QR-Code and SSID.
+    Labels{
+        #[clap(arg_enum, long, value_parser)]
+        sheet: layout::LabelSheet,
+
+        /// Number of labels to print. Defaults to filling the (first) sheet.
+        #[clap(long, value_parser)]
+        count: Option<usize>,
+
+        /// Number of labels at the start of the sheet that were already used.
+        #[clap(long, value_parser, default_value_t=0)]
+        skip: usize,
+
+        /// Draw the outlines of the labels, for test prints on plain paper.
+        #[clap(long, action)]
+        outline: bool,
+
+        /// Set the SSID top to bottom beside the QR-Code, for Japanese or Chinese signage.
+        #[clap(long, action)]
+        vertical: bool,
+
+        /// Path of the PDF. Defaults to `<SSID>-labels.pdf`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ArgEnum)]
-enum SecurityTypes{
-    Wep,
-    Wpa,
-    Wpa2,
-    Wpa3
+#[derive(clap::Subcommand, Debug)]
+enum ExportTarget{
+    /// Create or update a pass entry, with the QR-Code stored as a sibling entry.
+    Pass{
+        /// Path of the entry. Defaults to `wifi/<SSID>`.
+        #[clap(long, value_parser)]
+        item: Option<String>
+    },
+    /// Create or update a 1Password item, with the QR-Code attached as an image.
+    #[clap(name = "1password")]
+    OnePassword{
+        /// Title of the item. Defaults to the SSID.
+        #[clap(long, value_parser)]
+        item: Option<String>,
+
+        /// Vault to store the item in.
+        #[clap(long, value_parser)]
+        vault: Option<String>
+    },
+    /// Create or update a Bitwarden item, with the QR-Code attached as an image.
+    Bitwarden{
+        /// Name of the item. Defaults to the SSID.
+        #[clap(long, value_parser)]
+        item: Option<String>
+    },
+    /// Write an age-encrypted tarball of all generated artifacts and a JSON manifest.
+    Archive{
+        /// age recipient (`age1...`) that can decrypt the archive. Can be repeated.
+        #[clap(long = "recipient", value_parser, required = true)]
+        recipients: Vec<age::x25519::Recipient>,
+
+        /// Path of the archive. Defaults to `<SSID>.tar.age`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>,
+
+        /// Include passwords and payloads in the manifest.
+        #[clap(long, action)]
+        include_secrets: bool
+    },
+    /// Write the `openNetworkConfiguration` of an Android Management API policy, for MDMs to push the network to
+    /// managed devices.
+    AndroidPolicy{
+        /// Path of the JSON file. Defaults to `<SSID>-android-policy.json`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Write a Windows WLAN profile, which `netsh wlan add profile filename=<PATH>` adds to a laptop.
+    WindowsProfile{
+        /// Path of the XML file. Defaults to `<SSID>.xml`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Build an Apple Wallet pass with the QR-Code and the name of the network, signed with the certificate of a
+    /// pass type ID by `openssl`.
+    Wallet{
+        /// The pass type ID the certificate was issued for, e.g. `pass.com.example.wifi`.
+        #[clap(long, value_parser)]
+        pass_type_id: String,
+
+        /// The team ID of the developer account.
+        #[clap(long, value_parser)]
+        team_id: String,
+
+        /// Name shown at the top of the pass. Defaults to the footer of the branding, or the SSID.
+        #[clap(long, value_parser)]
+        organization: Option<String>,
+
+        /// Certificate of the pass type ID, in PEM.
+        #[clap(long, value_parser)]
+        certificate: std::path::PathBuf,
+
+        /// Private key of the certificate, in PEM. openssl asks for its passphrase if it has one.
+        #[clap(long, value_parser)]
+        key: std::path::PathBuf,
+
+        /// Apple's WWDR intermediate certificate, in PEM.
+        #[clap(long, value_parser)]
+        wwdr: std::path::PathBuf,
+
+        /// Path of the pass. Defaults to `<SSID>.pkpass`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    },
+    /// Write an Apple configuration profile joining the network, for iPhones, iPads and Macs to install or MDMs to
+    /// push, signed by `openssl` if given a certificate.
+    Mobileconfig{
+        /// Identifier of the profile, which installing a profile of the same identifier replaces. Defaults to
+        /// `wifiqr.<SSID>`.
+        #[clap(long, value_parser)]
+        identifier: Option<String>,
+
+        /// Organization shown with the profile. Defaults to the footer of the branding.
+        #[clap(long, value_parser)]
+        organization: Option<String>,
+
+        /// Certificate to sign the profile with, in PEM.
+        #[clap(long, value_parser, requires = "key")]
+        certificate: Option<std::path::PathBuf>,
+
+        /// Private key of the certificate, in PEM. openssl asks for its passphrase if it has one.
+        #[clap(long, value_parser, requires = "certificate")]
+        key: Option<std::path::PathBuf>,
+
+        /// Intermediate certificates of the signing certificate, in PEM, to include in the signature.
+        #[clap(long, value_parser, requires = "certificate")]
+        chain: Option<std::path::PathBuf>,
+
+        /// Path of the profile. Defaults to `<SSID>.mobileconfig`.
+        #[clap(short, long, value_parser)]
+        output: Option<std::path::PathBuf>
+    }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ArgEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ArgEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
 enum ECCLevel{
     Low,
     Medium,
     Quartile,
     High
 }
-impl std::fmt::Display for SecurityTypes {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+impl From<ECCLevel> for qrcode_generator::QrCodeEcc {
+    fn from(level: ECCLevel) -> Self {
+        match level{
+            ECCLevel::Low => qrcode_generator::QrCodeEcc::Low,
+            ECCLevel::Medium => qrcode_generator::QrCodeEcc::Medium,
+            ECCLevel::Quartile => qrcode_generator::QrCodeEcc::Quartile,
+            ECCLevel::High => qrcode_generator::QrCodeEcc::High
+        }
     }
 }
-
 fn get_bool_matrix_as_string(mat: Vec<Vec<bool>>) -> String {
-    let mut output:String = String::new();
-    for x in mat {
-        for y in x{
-            output += if y {"██"}else{"  "};
-        }
-        output += "\n";
+    matrix_as_string(&mat, console::Charset::Blocks)
+}
+/// Draws two characters per module, so the modules come out roughly square in the terminal.
+fn matrix_as_string(mat: &[Vec<bool>], charset: console::Charset) -> String {
+    render::blocks(mat, charset.dark_module())
+}
+/// Resolves the security standard of the network like the payload does, defaulting to WPA2 if only a password was
+/// given. A standard given without a password is kept, for [`require_password`] to reject the network.
+fn resolve_security(args: &Args) -> Option<Security>{
+    WifiCredentials{ password: args.psw.clone(), security: args.sec, ..WifiCredentials::default() }.security()
+}
+/// Fails for a security standard without a password, which the payload can't hold, and for
+/// enterprise networks missing their identity or password.
+fn require_password(args: &Args) -> Result<(), Error> {
+    if args.enterprise.eap.is_some() && args.sec.is_some() {
+        return Err(Error::new(ErrorKind::Usage, "--sec is for networks with a shared password, leave it out with --eap!"));
+    }
+    network_builder(args).check().map_err(|err| match err {
+        BuildError::MissingPassword(sec) =>
+            Error::new(ErrorKind::Usage, format!("The security standard {} was provided, but no password! Provide it with --psw, or leave out --sec for an open network.", sec)),
+        BuildError::TransitionDisable => Error::new(ErrorKind::Usage, "--transition-disable is only for WPA3 networks, pass --sec wpa3!"),
+        err => Error::new(ErrorKind::Usage, format!("Invalid network: {}!", err))
+    })?;
+    args.enterprise.validate(args.psw.as_deref()).or_fail(ErrorKind::Usage, |err| format!("Invalid enterprise network: {}!", err))
+}
+/// Fails for SSIDs and passwords devices won't accept, unless forced to encode them anyway.
+fn validate_credentials(args: &Args) -> Result<(), Error> {
+    let findings:Vec<inspect::Finding> = check::credentials(args);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let list:String = findings.iter().map(|x| format!("\n  {}", x)).collect();
+    if args.force {
+        diagnostics::warn("forced", format!("encoding {} with --force, although devices won't accept it:{}", args.ssid, list));
+        return Ok(());
+    }
+    Err(Error::new(ErrorKind::Usage, format!("Devices won't accept {}, pass --force to encode it anyway:{}", args.ssid, list)))
+}
+/// Checks the credentials of the network like [`require_password`] and [`validate_credentials`] do, and normalizes
+/// its WEP key, as every network is before it's encoded.
+fn checked_credentials(network: &mut Args) -> Result<(), Error> {
+    require_password(network)?;
+    wep::prepare(network).or_fail(ErrorKind::Usage, |err| format!("Invalid WEP network: {}!", err))?;
+    validate_credentials(network)
+}
+/// The arguments of a network entered in the browser of `serve` or in the `tui`, with the other options of the
+/// command line, checked like the networks of the command line are.
+fn checked_network(args: &Args, network: &batch::Network) -> Result<Args, Error> {
+    let sec:Option<Security> = network.sec().or_fail(ErrorKind::Usage, |err| format!("Invalid network: {}!", err))?;
+    let mut network:Args = batch::with_network(args, network, sec);
+    checked_credentials(&mut network)?;
+    let payload:String = assemble_qr_string(&network);
+    capacity::fit(&mut network, &payload).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}", err))?;
+    symbol::encode(&payload, network.ecc, &network.symbol).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}!", err))?;
+    Ok(network)
+}
+/// Whether the password of the network is written as the raw key its hex digits spell, rather than as a passphrase.
+fn raw_key(args: &Args) -> bool {
+    args.enterprise.eap.is_none() && is_raw_key(args.psw.as_deref().unwrap_or_default(), resolve_security(args))
+}
+/// The value of `P:` of the network, as the payload writes it.
+fn written_password(args: &Args) -> String {
+    let psw:&str = args.psw.as_deref().unwrap_or_default();
+    match args.enterprise.eap {
+        Some(_) => quote_payload_value(psw),
+        None => quote_password(psw, resolve_security(args))
     }
-    output
 }
 fn assemble_qr_string(args: &Args) -> String{
-    let psw:String = match args.psw.clone() {
-        Some(x) => x,
-        None => String::new()
+    if args.enterprise.eap.is_some() {
+        let psw:String = args.psw.clone().unwrap_or_default();
+        let hidden:String = if args.hidden || args.compat {format!("H:{};", args.hidden)}else{String::new()};
+        return format!("WIFI:T:WPA2-EAP;S:{};{}P:{};{}{};", hex_ssid::written(args).unwrap_or_else(|| quote_payload_value(&args.ssid)), args.enterprise.fields(), quote_payload_value(&psw), args.enterprise.phase2_field(), hidden);
+    }
+    network_builder(args).payload()
+}
+/// The network with a shared password, or open, as the library builds its payload and checks it.
+fn network_builder(args: &Args) -> WifiQrBuilder {
+    let style:PayloadStyle = match (args.output.micro, args.compat) {
+        (true, _) => PayloadStyle::Compact,
+        (false, true) => PayloadStyle::Compat,
+        (false, false) => PayloadStyle::Standard
+    };
+    let mut builder:WifiQrBuilder = WifiQrBuilder::new(args.ssid.clone()).hidden(args.hidden).transition_disable(args.transition_disable).style(style);
+    if let Some(psw) = &args.psw {
+        builder = builder.password(psw.clone());
+    }
+    if let Some(sec) = args.sec {
+        builder = builder.security(sec);
+    }
+    if let Some(ssid_bytes) = hex_ssid::written_bytes(args) {
+        builder = builder.ssid_bytes(ssid_bytes);
+    }
+    builder
+}
+/// The modules of the code of `payload`, in the version and mask of the symbol options, or a Micro QR symbol with
+/// `--micro`. The networks of the command line are checked to fit before, others fail here if they don't.
+fn matrix(args: &Args, payload: &str) -> Result<Vec<Vec<bool>>, Error> {
+    match args.output.micro {
+        true => micro::encode(payload, args.ecc).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}!", err)),
+        false => symbol::encode(payload, args.ecc, &args.symbol).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}!", err))
+    }
+}
+/// The SSID as caption for images, set in the body font of the branding. Images can only use TrueType/OpenType
+/// fonts, so the standard fonts are replaced by the system's sans-serif font, if there is one.
+fn image_caption<'a>(args: &'a Args, branding: &branding::Branding, disabled: bool) -> Option<wallpaper::Caption<'a>> {
+    if disabled {
+        return None;
+    }
+    let font:&font::EmbeddedFont = match branding.fonts.body {
+        pdf::Font::Embedded(font) => font,
+        _ => font::EmbeddedFont::find_sans_serif()?
+    };
+    Some(wallpaper::Caption{ font, text: &args.ssid, color: branding.palette.text, align: branding.align })
+}
+/// Rejects enterprise networks for the exports that only hold a password, which would make them personal networks.
+fn personal_network(args: &Args, export: &str) -> Result<(), Error> {
+    match args.enterprise.eap {
+        Some(_) => Err(Error::new(ErrorKind::Usage, format!("{} is an enterprise network, which {} can't carry the credentials of!", args.ssid, export))),
+        None => Ok(())
+    }
+}
+
+/// Runs the command for one of the networks. With `several` networks, the codes printed to the terminal are
+/// headed by their SSID.
+fn run(command: Option<&Command>, args: &Args, branding: &branding::Branding, several: bool) -> Result<(), Error> {
+    match command {
+        Some(Command::WriteNfc{reader}) => {
+            personal_network(args, "NFC tags")?;
+            let tlv:Vec<u8> = ndef::type2_tlv(&ndef::wifi_message(args));
+            nfc::write_tlv(reader.as_deref(), &tlv).or_fail(ErrorKind::External, |err| format!("Failed to write the NFC tag: {}", err))?;
+            println!("Wrote {} bytes to the NFC tag.", tlv.len());
+        },
+        Some(Command::Hotspot{interface, band}) => {
+            if !matches!(resolve_security(args), Some(Security::Wpa | Security::Wpa2)) {
+                return Err(Error::new(ErrorKind::Usage, "Hotspots are WPA2 networks, give their password with --psw or --generate-psw and leave out --sec!"));
+            }
+            if args.hidden {
+                return Err(Error::new(ErrorKind::Usage, "Hotspots can't be hidden, leave out --hidden!"));
+            }
+            if cfg!(windows) && (interface.is_some() || band.is_some()) {
+                return Err(Error::new(ErrorKind::Usage, "--interface and --band are only used with NetworkManager!"));
+            }
+            if cfg!(windows) && (args.ssid.contains('"') || args.psw.as_deref().unwrap_or_default().contains('"')) {
+                return Err(Error::new(ErrorKind::Usage, "netsh can't be given SSIDs or passwords with quotes!"));
+            }
+            hotspot::catch_interrupt();
+            let hotspot:hotspot::Hotspot = hotspot::start(args, interface.as_deref(), *band).or_fail(ErrorKind::External, |err| format!("Failed to start the hotspot: {}", err))?;
+            println!("Sharing the connection as {} with the password {}, press Ctrl+C to stop.", args.ssid, args.psw.as_deref().unwrap_or_default());
+            print_code(args, &matrix(args, &assemble_qr_string(args))?);
+            hotspot::wait_for_interrupt();
+            hotspot.stop().or_fail(ErrorKind::External, |err| format!("Failed to stop the hotspot: {}", err))?;
+            println!("Stopped the hotspot.");
+        },
+        Some(Command::Wps{pin, output, ..}) => {
+            let pin:String = match pin {
+                Some(pin) => wps::validate(pin).or_fail(ErrorKind::Usage, |err| format!("Invalid WPS PIN: {}", err))?,
+                None => wps::generate().or_fail(ErrorKind::External, |err| format!("Failed to generate a WPS PIN: {}", err))?
+            };
+            let matrix:Vec<Vec<bool>> = symbol::encode(&pin, args.ecc, &args.symbol).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the WPS PIN: {}", err))?;
+            let Some(output) = output else {
+                println!("WPS PIN {}:", wps::display(&pin));
+                print_code(args, &matrix);
+                return Ok(());
+            };
+            write_symbol(args, &matrix, &format!("QR-Code of the WPS PIN {}", wps::display(&pin)), output)?;
+            println!("Wrote the QR-Code of the WPS PIN {} to {}.", wps::display(&pin), output.display());
+        },
+        Some(Command::Dpp{key, key_file, channels, mac, info, output}) => {
+            let key:String = match (key, key_file) {
+                (Some(key), _) => dpp::key(key),
+                (None, Some(path)) => dpp::key_file(path),
+                (None, None) => unreachable!("--key or --key-file is required")
+            }.or_fail(ErrorKind::Usage, |err| format!("Invalid DPP key: {}", err))?;
+            let uri:String = wifi_qr::DppUri{ key, channels: channels.clone(), mac: *mac, info: info.clone() }.uri();
+            let matrix:Vec<Vec<bool>> = symbol::encode(&uri, args.ecc, &args.symbol).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the DPP URI: {}", err))?;
+            let Some(output) = output else {
+                println!("{}", uri);
+                print_code(args, &matrix);
+                return Ok(());
+            };
+            write_symbol(args, &matrix, "QR-Code of a Wi-Fi Easy Connect URI", output)?;
+            println!("Wrote the QR-Code of the DPP URI to {}.", output.display());
+        },
+        Some(Command::Export{target: ExportTarget::Archive{recipients, output, include_secrets}}) => {
+            let artifacts:Vec<archive::Artifact> = archive::generated_artifacts(args, branding)?;
+            let manifest = archive::manifest(&[args], &artifacts, *include_secrets);
+            let tarball:Vec<u8> = archive::tarball(&artifacts, &manifest).unwrap();
+            let encrypted:Vec<u8> = archive::encrypt(&tarball, recipients).or_fail(ErrorKind::External, |err| format!("Failed to encrypt the archive: {}", err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}.tar.age", args.ssid).into());
+            std::fs::write(&output, encrypted).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the archive to {}.", output.display());
+        },
+        Some(Command::Export{target: ExportTarget::AndroidPolicy{output}}) => {
+            personal_network(args, "Android policies")?;
+            let output = output.clone().unwrap_or_else(|| format!("{}-android-policy.json", args.ssid).into());
+            let policy:String = serde_json::to_string_pretty(&android::policy(args)).unwrap();
+            std::fs::write(&output, policy + "\n").or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the Android policy to {}.", output.display());
+        },
+        Some(Command::Export{target: ExportTarget::WindowsProfile{output}}) => {
+            // The credentials of 802.1X networks are stored apart from the profile, with the EAP user data.
+            personal_network(args, "WLAN profiles")?;
+            let output = output.clone().unwrap_or_else(|| format!("{}.xml", args.ssid).into());
+            std::fs::write(&output, profile::write_netsh(args)).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the WLAN profile to {}, add it with netsh wlan add profile filename=\"{}\".", output.display(), output.display());
+        },
+        Some(Command::Export{target: ExportTarget::Wallet{pass_type_id, team_id, organization, certificate, key, wwdr, output}}) => {
+            personal_network(args, "Wallet passes")?;
+            let organization:&str = organization.as_deref().or(branding.footer.as_deref()).unwrap_or(&args.ssid);
+            let pass = wallet::PassInfo{ pass_type_id, team_id, organization };
+            let signing = wallet::Signing{ certificate, key, wwdr };
+            let pkpass:Vec<u8> = wallet::pkpass(args, branding, &pass, &signing).or_fail(ErrorKind::External, |err| format!("Failed to build the pass: {}", err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}.pkpass", args.ssid).into());
+            std::fs::write(&output, pkpass).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the Wallet pass to {}.", output.display());
+        },
+        Some(Command::Export{target: ExportTarget::Mobileconfig{identifier, organization, certificate, key, chain, output}}) => {
+            let identifier:String = identifier.clone().unwrap_or_else(|| mobileconfig::default_identifier(args));
+            let organization:Option<&str> = organization.as_deref().or(branding.footer.as_deref());
+            let profile:String = mobileconfig::profile(args, &identifier, organization).or_fail(ErrorKind::Usage, |err| format!("Failed to write the profile of {}: {}", args.ssid, err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}.mobileconfig", args.ssid).into());
+            let (contents, kind):(Vec<u8>, &str) = match (certificate, key) {
+                (Some(certificate), Some(key)) => {
+                    let signing = mobileconfig::Signing{ certificate, key, chain: chain.as_deref() };
+                    (mobileconfig::sign(&profile, &signing, args.shred).or_fail(ErrorKind::External, |err| format!("Failed to sign the profile: {}", err))?, "signed ")
+                },
+                _ => (profile.into_bytes(), "")
+            };
+            std::fs::write(&output, contents).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the {}configuration profile to {}.", kind, output.display());
+        },
+        Some(Command::Export{target}) => {
+            let (manager, item, vault) = match target {
+                ExportTarget::Pass{item} => (password_manager::PasswordManager::Pass, item, None),
+                ExportTarget::OnePassword{item, vault} => (password_manager::PasswordManager::OnePassword, item, vault.as_deref()),
+                ExportTarget::Bitwarden{item} => (password_manager::PasswordManager::Bitwarden, item, None),
+                ExportTarget::Archive{..} | ExportTarget::AndroidPolicy{..} | ExportTarget::WindowsProfile{..} | ExportTarget::Wallet{..} | ExportTarget::Mobileconfig{..} => unreachable!()
+            };
+            personal_network(args, "password manager items")?;
+            let item:String = item.clone().unwrap_or_else(|| password_manager::default_item_name(manager, args));
+            let png:Vec<u8> = symbol::png(&matrix(args, &assemble_qr_string(args))?, 512);
+            password_manager::export(manager, args, &item, vault, &png).or_fail(ErrorKind::External, |err| format!("Failed to export the network: {}", err))?;
+            println!("Stored the network as '{}'.", item);
+        },
+        Some(Command::Print{printer, copies}) => {
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let document:Vec<u8> = pdf::write_document(&output::document(args, &matrix, branding));
+            print::print(&document, &format!("Wi-Fi {}", args.ssid), printer.as_deref(), *copies).or_fail(ErrorKind::External, |err| format!("Failed to print the card of {}: {}", args.ssid, err))?;
+            println!("Sent {} of the card of {} to {}.", if *copies == 1 {String::from("a copy")}else{format!("{} copies", copies)}, args.ssid, printer.as_deref().unwrap_or("the default printer"));
+        },
+        Some(Command::Layout{layout: kind}) => {
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let (pages, output, name) = match kind {
+                LayoutKind::BusinessCard{size, bleed, output} => (layout::business_card(args, &matrix, branding, *size, bleed * pdf::MM), output, "card"),
+                LayoutKind::TableTent{paper, output} => (layout::table_tent(args, &matrix, branding, *paper), output, "table-tent"),
+                LayoutKind::TriFold{paper, output} => (layout::tri_fold(args, &matrix, branding, *paper), output, "tri-fold"),
+                LayoutKind::WifiAndUrl{url, url_title, paper, output} => {
+                    let url_matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(url, args.ecc.into()).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the URL: {}", err))?;
+                    (layout::wifi_and_url(args, &matrix, branding, *paper, url, &url_matrix, url_title), output, "wifi-and-url")
+                },
+                LayoutKind::MultiBand{label, bands, paper, output} => {
+                    let mut sheet:Vec<layout::Band> = vec![layout::Band{ label, ssid: &args.ssid, matrix: matrix.clone() }];
+                    for (label, ssid) in bands {
+                        let network = batch::Network{ ssid: ssid.clone(), password: args.psw.clone(), security: None, hidden: args.hidden };
+                        let mut band_args:Args = batch::with_network(args, &network, args.sec);
+                        checked_credentials(&mut band_args).map_err(|err| Error::new(err.kind, format!("Invalid band {}: {}", label, err.message)))?;
+                        let matrix:Vec<Vec<bool>> = symbol::encode(&assemble_qr_string(&band_args), band_args.ecc, &band_args.symbol)
+                            .or_fail(ErrorKind::Encoding, |err| format!("Failed to encode band {}: {}", label, err))?;
+                        sheet.push(layout::Band{ label, ssid, matrix });
+                    }
+                    (layout::multi_band(&sheet, branding, *paper, args.info_url.as_deref(), args.output.lang.texts()), output, "multi-band")
+                },
+                LayoutKind::Labels{sheet, count, skip, outline, vertical, output} => {
+                    let count:usize = count.unwrap_or_else(|| sheet.labels_per_sheet().saturating_sub(skip % sheet.labels_per_sheet()));
+                    (layout::label_sheet(args, &matrix, branding, *sheet, *skip..*skip + count, *outline, *vertical), output, "labels")
+                }
+            };
+            if let Some(meters) = args.scan_distance {
+                if let Some(module_size) = distance::too_small_module(&pages, meters) {
+                    return Err(Error::new(ErrorKind::Encoding, format!("The modules of the QR-Code are {:.1} mm wide, but scanning from {} m needs at least {:.1} mm. Use a larger layout or scan from closer.",
+                        module_size / pdf::MM, meters, distance::min_module_size(meters) / pdf::MM)));
+                }
+            }
+            let output = output.clone().unwrap_or_else(|| format!("{}-{}.pdf", args.ssid, name).into());
+            std::fs::write(&output, pdf::write_document(&pages)).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the layout to {}.", output.display());
+        },
+        Some(Command::Serve{bind}) => serve::serve(args, branding, *bind)?,
+        Some(Command::Wallpaper{background, width, height, corner, qr_size, margin, no_caption, alt_text, cmyk, output}) => {
+            let background = background.clone().unwrap_or(wallpaper::Background::Color(branding.palette.background));
+            let (default_width, default_height) = match &background {
+                wallpaper::Background::Image(path) => image::image_dimensions(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?,
+                wallpaper::Background::Color(_) => (1920, 1080)
+            };
+            let (width, height) = (width.unwrap_or(default_width), height.unwrap_or(default_height));
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let qr_size:u32 = qr_size.unwrap_or(width.min(height) / 4);
+            let margin:u32 = margin.unwrap_or(width.min(height) / 20);
+            let image = wallpaper::wallpaper(&matrix, image_caption(args, branding, *no_caption), &background, (width, height), *corner, qr_size, margin).or_fail(ErrorKind::Input, |err| format!("Failed to render the wallpaper: {}", err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}-wallpaper.png", args.ssid).into());
+            let description:Option<String> = alt_text.then(|| alt_text::describe(args));
+            alt_text::save_image(&image, &output, description.as_deref(), *cmyk).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the wallpaper to {}.", output.display());
+        },
+        Some(Command::LockScreen{device, background, no_caption, alt_text, cmyk, output}) => {
+            let background = background.clone().unwrap_or(wallpaper::Background::Color(branding.palette.background));
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let image = wallpaper::lock_screen(&matrix, image_caption(args, branding, *no_caption), &background, *device).or_fail(ErrorKind::Input, |err| format!("Failed to render the lock screen: {}", err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}-lock-screen.png", args.ssid).into());
+            let description:Option<String> = alt_text.then(|| alt_text::describe(args));
+            alt_text::save_image(&image, &output, description.as_deref(), *cmyk).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the lock screen to {}.", output.display());
+        },
+        Some(Command::Template{template: path, output, vars}) => {
+            let source:String = std::fs::read_to_string(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?;
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let mut context = template::context(args, &matrix, branding);
+            template::set_vars(&mut context, vars);
+            let rendered:String = template::render(&source, path, &context).or_fail(ErrorKind::Input, |err| format!("Failed to render {}: {}", path.display(), err))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}-{}", args.ssid, path.file_name().unwrap_or_default().to_string_lossy()).into());
+            std::fs::write(&output, rendered).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the template to {}.", output.display());
+        },
+        Some(Command::Html{output}) => {
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let output = output.clone().unwrap_or_else(|| format!("{}.html", args.ssid).into());
+            std::fs::write(&output, html::page(args, &matrix, branding)).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the page to {}.", output.display());
+        },
+        Some(Command::Plugin{plugin: path, output, options}) => {
+            let matrix:Vec<Vec<bool>> = matrix(args, &assemble_qr_string(args))?;
+            let rendered:Vec<u8> = plugin::render_file(path, &plugin::input(args, &matrix, branding, options)).or_fail(ErrorKind::External, |err| format!("Failed to render with {}: {}", path.display(), err))?;
+            std::fs::write(output, rendered).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))?;
+            println!("Wrote the plugin's output to {}.", output.display());
+        },
+        Some(Command::Gui) => {
+            gui::run(args, branding).or_fail(ErrorKind::External, |err| format!("Failed to open the window: {}", err))?;
+        },
+        Some(Command::Tui) => tui::run(args, branding)?,
+        Some(Command::Preview{template, port}) => {
+            let listener = std::net::TcpListener::bind(("127.0.0.1", *port)).or_fail(ErrorKind::External, |err| format!("Failed to listen on port {}: {}", port, err))?;
+            println!("Serving the preview on http://127.0.0.1:{}/, press Ctrl+C to stop.", port);
+            let preview = preview::Preview{ args, template: template.as_deref(), load_branding: || load_branding(args) };
+            preview.serve(listener).or_fail(ErrorKind::External, |err| format!("Failed to serve the preview: {}", err))?;
+        },
+        Some(Command::Batch{networks, check: true, ..}) => {
+            let path:&std::path::Path = networks;
+            let contents:String = if path == std::path::Path::new("-") {
+                std::io::read_to_string(std::io::stdin())
+            }else{
+                std::fs::read_to_string(path)
+            }.or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?;
+            let extension:String = path.extension().map_or(String::new(), |x| x.to_string_lossy().to_lowercase());
+            let entries:Vec<check::Entry> = match extension.as_str() {
+                _ if path == std::path::Path::new("-") => check::jsonl(args, &contents),
+                "jsonl" | "ndjson" => check::jsonl(args, &contents),
+                "json" => check::json(args, &contents),
+                "xml" => match android::read_networks(&contents) {
+                    Ok((networks, skipped)) => check::networks(args, &networks, &skipped),
+                    Err(err) => vec![check::Entry::unreadable(err)]
+                },
+                _ => check::csv(args, contents.as_bytes())
+            };
+            exit_with_report(&entries);
+        },
+        Some(Command::Batch{networks, out_dir, outputs, sheet, paper, script, jobs, check: false}) => {
+            let outputs = batch::Outputs{ templates: outputs, sheet: sheet.map(|(columns, rows)| layout::Sheet{ paper: *paper, columns, rows }) };
+            let hooks:Option<script::Hooks> = script.as_deref().map(script::Hooks::load).transpose().or_fail(ErrorKind::Input, |err| format!("Failed to load the script: {}", err))?;
+            let path:&std::path::Path = networks;
+            if path == std::path::Path::new("-") || path.extension().is_some_and(|x| x.eq_ignore_ascii_case("jsonl") || x.eq_ignore_ascii_case("ndjson")) {
+                let records:Box<dyn std::io::BufRead> = if path == std::path::Path::new("-") {
+                    Box::new(std::io::stdin().lock())
+                }else{
+                    Box::new(std::io::BufReader::new(std::fs::File::open(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?))
+                };
+                batch::stream(args, branding, records, std::io::stdout().lock(), out_dir, &outputs, hooks.as_ref())
+                    .map_err(|err| Error::new(err.kind(), format!("Failed to generate the batch: {}", err)))?;
+                return Ok(());
+            }
+            let file = std::fs::File::open(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?;
+            let networks:Vec<batch::Network> = if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("xml")) {
+                let xml:String = std::io::read_to_string(file).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?;
+                let (networks, skipped) = android::read_networks(&xml).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?;
+                for reason in skipped {
+                    diagnostics::warn("skipped", format!("skipping {}", reason));
+                }
+                networks
+            }else if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("json")) {
+                batch::read_json_networks(std::io::BufReader::new(file)).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?
+            }else{
+                batch::read_networks(file).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?
+            };
+            let written:Vec<std::path::PathBuf> = batch::run(args, branding, &networks, out_dir, &outputs, hooks.as_ref(), jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |x| x.get())))
+                .map_err(|err| Error::new(err.kind(), format!("Failed to generate the batch: {}", err)))?;
+            println!("Wrote {} files for {} networks to {}.", written.len(), networks.len(), out_dir.display());
+        },
+        Some(Command::Inspect{image: path}) => {
+            let image = image::open(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?.to_luma8();
+            let symbols:Vec<inspect::Symbol> = inspect::decode(&image).or_fail(ErrorKind::Input, |err| format!("Failed to inspect {}: {}", path.display(), err))?;
+            for (index, symbol) in symbols.iter().enumerate() {
+                if symbols.len() > 1 {
+                    println!("{}Code {} of {}:", if index > 0 {"\n"}else{""}, index + 1, symbols.len());
+                }
+                print!("{}", symbol);
+            }
+        },
+        Some(Command::Decode{image: path}) => {
+            let networks:Vec<inspect::Symbol> = wifi_codes(path)?;
+            for (index, symbol) in networks.iter().enumerate() {
+                if networks.len() > 1 {
+                    println!("{}Network {} of {}:", if index > 0 {"\n"}else{""}, index + 1, networks.len());
+                }
+                let fields:Vec<(&str, String)> = inspect::describe_wifi(&symbol.payload)
+                    .or_fail(ErrorKind::Input, |err| format!("Failed to read the network of {}: {}", path.display(), err))?;
+                for (name, value) in fields {
+                    println!("{:<20}{}", format!("{}:", name), value);
+                }
+            }
+        },
+        Some(Command::Connect{code}) => {
+            let payload:String = if code.starts_with("WIFI:") {code.clone()}else{
+                let path:&std::path::Path = std::path::Path::new(code);
+                let mut networks:Vec<inspect::Symbol> = wifi_codes(path)?;
+                if networks.len() > 1 {
+                    diagnostics::warn("several-codes", format!("{} holds {} Wi-Fi codes, joining the first", path.display(), networks.len()));
+                }
+                networks.swap_remove(0).payload
+            };
+            let network:batch::Network = inspect::parse_wifi(&payload).or_fail(ErrorKind::Input, |err| format!("Failed to read the network: {}", err))?;
+            let network_args:Args = batch::network_args(args, &network, 1).map_err(|err| Error::new(ErrorKind::Input, format!("Failed to read the network: {}", err)))?;
+            connect::connect(&network_args).or_fail(ErrorKind::External, |err| format!("Failed to join {}: {}", network.ssid, err))?;
+            println!("Joined {}.", network.ssid);
+        },
+        Some(Command::Completions{shell}) => clap_complete::generate(*shell, &mut Args::command(), env!("CARGO_BIN_NAME"), &mut std::io::stdout()),
+        Some(Command::Manpage{out_dir: None}) => {
+            clap_mangen::Man::new(Args::command().name(env!("CARGO_BIN_NAME"))).render(&mut std::io::stdout()).or_fail(ErrorKind::Output, |err| format!("Failed to write the man page: {}", err))?;
+        },
+        Some(Command::Manpage{out_dir: Some(out_dir)}) => {
+            let pages:Vec<std::path::PathBuf> = manpage::write_pages(Args::command().name(env!("CARGO_BIN_NAME")), out_dir).or_fail(ErrorKind::Output, |err| format!("Failed to write the man pages to {}: {}", out_dir.display(), err))?;
+            println!("Wrote {} man pages to {}.", pages.len(), out_dir.display());
+        },
+        Some(Command::Parse{payload, format}) => {
+            let payload:String = if payload == "-" {
+                let input:String = std::io::read_to_string(std::io::stdin()).or_fail(ErrorKind::Input, |err| format!("Failed to read the payload from stdin: {}", err))?;
+                String::from(input.trim_end_matches(['\n', '\r']))
+            }else{
+                payload.clone()
+            };
+            let (output, failed) = parse::parse(&payload);
+            print!("{}", parse::to_string(&output, *format));
+            if failed {
+                return Err(Error::new(ErrorKind::Input, String::from("Readers would reject the payload!")));
+            }
+        },
+        Some(Command::Convert{input, from, to, output, check}) => {
+            let contents:String = std::fs::read_to_string(input).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", input.display(), err))?;
+            let from:profile::ProfileFormat = from.or_else(|| profile::ProfileFormat::detect(input, &contents))
+                .ok_or_else(|| Error::new(ErrorKind::Usage, format!("Failed to tell the format of {}, pass it with --from!", input.display())))?;
+            if *check {
+                exit_with_report(&match profile::read(from, input, &contents) {
+                    Ok((networks, skipped)) => check::networks(args, &networks, &skipped),
+                    Err(err) => vec![check::Entry::unreadable(err)]
+                });
+            }
+            let to:&profile::ProfileFormat = to.as_ref().unwrap();
+            let (networks, skipped) = profile::read(from, input, &contents).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", input.display(), err))?;
+            for reason in skipped {
+                diagnostics::warn("skipped", format!("skipping {}", reason));
+            }
+            if output.is_some() && networks.len() > 1 && !to.holds_several() {
+                return Err(Error::new(ErrorKind::Usage, format!("Only one network can be written to --output, as {:?} profiles hold one network each!", to)));
+            }
+            let networks:Vec<Args> = networks.iter().enumerate().map(|(index, x)| batch::network_args(args, x, index + 1))
+                .collect::<Result<Vec<Args>, batch::BatchError>>().or_fail(ErrorKind::Input, |err| format!("Failed to convert {}: {}", input.display(), err))?;
+            let files:Vec<(String, String)> = profile::write(*to, &networks).or_fail(ErrorKind::Input, |err| format!("Failed to convert {}: {}", input.display(), err))?;
+            for (name, contents) in files {
+                let path:std::path::PathBuf = output.clone().unwrap_or_else(|| name.into());
+                std::fs::write(&path, contents).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", path.display(), err))?;
+                println!("Wrote {}.", path.display());
+            }
+        },
+        Some(Command::Bench{networks}) => {
+            let networks:Vec<Args> = bench::synthetic_networks(args, (*networks).max(1));
+            print!("{}", bench::report(&bench::run(&networks, branding)));
+        },
+        Some(Command::SizeCalc{width, compare_ecc}) => {
+            match (width, args.scan_distance) {
+                (Some(_), Some(_)) => return Err(Error::new(ErrorKind::Usage, "Only one of --scan-distance and --width can be provided!")),
+                (None, None) => return Err(Error::new(ErrorKind::Usage, "Either --scan-distance or --width has to be provided!")),
+                _ => {}
+            }
+            let size = |modules: usize| match width {
+                Some(width) => distance::PrintSize::for_width(modules, *width),
+                None => distance::PrintSize::for_distance(modules, args.scan_distance.unwrap())
+            };
+            let payload:String = assemble_qr_string(args);
+            if *compare_ecc {
+                let rows:Vec<(ECCLevel, Option<distance::PrintSize>)> = [ECCLevel::Low, ECCLevel::Medium, ECCLevel::Quartile, ECCLevel::High].into_iter()
+                    .map(|ecc| (ecc, symbol::encode(&payload, ecc, &args.symbol).ok().map(|x| size(x.len())))).collect();
+                print!("{}", distance::comparison_table(&rows));
+            }else{
+                print!("{}", size(matrix(args, &payload)?.len()));
+            }
+        },
+        None if args.output.output_format == report::Format::Json => {
+            let payload:String = assemble_qr_string(args);
+            let matrix:Vec<Vec<bool>> = matrix(args, &payload)?;
+            println!("{}", report::document(args, &payload, &matrix, args.output.with_matrix));
+        },
+        None if args.output.clipboard.is_some() => {
+            let contents:clipboard::Contents = match args.output.clipboard.unwrap() {
+                clipboard::Content::Payload => clipboard::Contents::Text(assemble_qr_string(args)),
+                clipboard::Content::Image => {
+                    let png:Vec<u8> = output::contents(args, branding, output::OutputFormat::Png, std::path::Path::new("the clipboard"))
+                        .map_err(|err| Error::new(err.kind(), format!("Failed to draw the QR-Code: {}", err)))?;
+                    clipboard::Contents::Image(image::load_from_memory(&png).expect("the PNG was just encoded").to_rgba8())
+                }
+            };
+            clipboard::copy(&contents).or_fail(ErrorKind::External, |err| format!("Failed to copy to the clipboard: {}", err))?;
+            println!("Copied the {} of {} to the clipboard.", if matches!(contents, clipboard::Contents::Text(_)) {"payload"}else{"QR-Code"}, args.ssid);
+        },
+        None if args.output.destination().is_some() => {
+            let path:&std::path::Path = args.output.destination().unwrap();
+            let ndef:bool = matches!(args.output.format.or_else(|| output::OutputFormat::detect(path)), Some(output::OutputFormat::Ndef | output::OutputFormat::NdefHex));
+            let what:&str = if ndef {"NDEF record"}else{"QR-Code"};
+            output::write(args, branding, path).map_err(|err| Error::new(err.kind(), format!("Failed to write the {}: {}", what, err)))?;
+            // Written to stdout, the file is all there is on it.
+            if path != std::path::Path::new("-") {
+                println!("Wrote the {} to {}.", what, path.display());
+            }
+        },
+        None | Some(Command::Encode) => {
+            let qr_code:String = assemble_qr_string(args);
+            let qr_code:Vec<Vec<bool>> = matrix(args, &qr_code)?;
+
+            if several {
+                println!("{}:", args.ssid);
+            }
+            print_code(args, &qr_code);
+        }
+    }
+    Ok(())
+}
+/// Writes a code that isn't of a network to a PNG or SVG, by the extension of `output`.
+fn write_symbol(args: &Args, matrix: &[Vec<bool>], description: &str, output: &std::path::Path) -> Result<(), Error> {
+    let (dark, light) = args.output.colors();
+    let contents:Vec<u8> = match output::OutputFormat::detect(output) {
+        Some(output::OutputFormat::Png) => {
+            let modules:u32 = (matrix.len() + 2 * args.output.border) as u32;
+            let image = output::image(matrix, args.output.border, args.output.module_size.unwrap_or(args.output.size as u32 / modules).max(1), dark, light);
+            let mut png:Vec<u8> = Vec::new();
+            image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+            png
+        },
+        Some(output::OutputFormat::Svg) => svg::document(matrix, description, args.output.border, args.output.module_size, dark, light, None).into_bytes(),
+        _ => return Err(Error::new(ErrorKind::Usage, format!("Can't tell what to write to {}, use .png or .svg!", output.display())))
+    };
+    std::fs::write(output, contents).or_fail(ErrorKind::Output, |err| format!("Failed to write {}: {}", output.display(), err))
+}
+/// Draws the code in the terminal, as an image in terminals showing them and otherwise in characters.
+fn print_code(args: &Args, qr_code: &[Vec<bool>]) {
+    let render:Option<console::Render> = args.output.render.or_else(graphics::detect);
+    if let Some(render @ (console::Render::Sixel | console::Render::Kitty | console::Render::Iterm)) = render {
+        let module_pixels:u32 = args.output.module_size.unwrap_or(graphics::MODULE_PIXELS);
+        let image = output::image(qr_code, args.output.border, module_pixels, args.output.fg.unwrap_or(color::Color::BLACK), args.output.bg.unwrap_or(color::Color::WHITE));
+        print!("{}", graphics::draw(render, &image));
+        return;
+    }
+    let mut terminal:console::Terminal = console::Terminal::detect();
+    if render == Some(console::Render::Ascii) {
+        terminal.charset = console::Charset::Ascii;
+    }
+    // Consoles that can't show blocks can't show colors either.
+    let colors:Option<(color::Color, color::Color)> = match (args.output.fg, args.output.bg) {
+        _ if terminal.charset != console::Charset::Blocks => None,
+        (None, None) => None,
+        (fg, bg) => Some((fg.unwrap_or(color::Color::BLACK), bg.unwrap_or(color::Color::WHITE)))
     };
-    let sec:String;
-    if args.sec.is_none() && !psw.is_empty(){
-        sec = SecurityTypes::Wpa2.to_string();
-    }else if args.sec.is_some() && psw.is_empty(){
-        panic!("No password was provided, but a security-standard was provided! Provided security-standard {}.", args.sec.unwrap());
-    }else if args.sec.is_none() && psw.is_empty(){
-        sec = String::new();
+    let inverted:bool = match args.output.invert {
+        console::Invert::Auto => colors.is_none() && console::dark_background() == Some(true),
+        console::Invert::Always => true,
+        console::Invert::Never => false
+    };
+    let qr_code:Vec<Vec<bool>> = scaled(&with_border(qr_code, args.output.border), args.output.module_size.unwrap_or(1) as usize);
+    // Inverted, the border is drawn too, as the terminal around the code is dark.
+    let qr_code:Vec<Vec<bool>> = if inverted {
+        qr_code.into_iter().map(|row| row.into_iter().map(|x| !x).collect()).collect()
     }else{
-        sec = args.sec.unwrap().to_string();
+        qr_code
+    };
+    let render:console::Render = match render.unwrap_or_else(|| terminal.fitting(qr_code.len())) {
+        // Half blocks and braille are blocks too.
+        console::Render::HalfBlocks | console::Render::Braille if terminal.charset != console::Charset::Blocks => console::Render::Blocks,
+        render => render
+    };
+    let drawn:String = match render {
+        console::Render::HalfBlocks => half_blocks(&qr_code),
+        console::Render::Braille => braille(&qr_code),
+        _ => matrix_as_string(&qr_code, terminal.charset)
+    };
+    match colors {
+        Some((fg, bg)) => print!("{}", console::paint(&drawn, fg, bg)),
+        None => print!("{}", drawn)
+    }
+    // Beneath the code, as the lines above it scroll out of view with its top.
+    if let Some(overflow) = terminal.overflow(render, qr_code.len()) {
+        diagnostics::warn("overflow", overflow);
+    }
+}
+/// Prints what `--check` found and exits, with 1 if any entry is invalid.
+fn exit_with_report(entries: &[check::Entry]) -> ! {
+    let (report, failed) = check::report(entries);
+    print!("{}", report);
+    std::process::exit(if failed {1}else{0})
+}
+/// The Wi-Fi codes of the image, failing if there are none.
+fn wifi_codes(path: &std::path::Path) -> Result<Vec<inspect::Symbol>, Error> {
+    let image = image::open(path).or_fail(ErrorKind::Input, |err| format!("Failed to read {}: {}", path.display(), err))?.to_luma8();
+    let symbols:Vec<inspect::Symbol> = inspect::decode(&image).or_fail(ErrorKind::Input, |err| format!("Failed to decode {}: {}", path.display(), err))?;
+    let types:Vec<&str> = symbols.iter().map(|x| inspect::payload_type(&x.payload)).collect();
+    if !types.contains(&"Wi-Fi network") {
+        return Err(Error::new(ErrorKind::Input, format!("No Wi-Fi network in {}, its QR-Codes hold: {}!", path.display(), types.join(", "))));
+    }
+    Ok(symbols.into_iter().filter(|x| inspect::payload_type(&x.payload) == "Wi-Fi network").collect())
+}
+/// Whether the command reads or makes up its networks, or lets them be entered, instead of encoding the one of the
+/// command line.
+fn ignores_networks(command: Option<&Command>) -> bool {
+    matches!(command, Some(Command::Batch{..} | Command::Bench{..} | Command::Convert{..} | Command::Gui | Command::Inspect{..} | Command::Decode{..} | Command::Parse{..} | Command::Connect{..} | Command::Completions{..} | Command::Manpage{..} | Command::Serve{..} | Command::Tui | Command::Wps{..} | Command::Dpp{..}))
+}
+
+/// Why the command can't be run for several networks, if it can't.
+fn single_network_only(command: &Command) -> Option<&'static str> {
+    let output:bool = match command {
+        Command::WriteNfc{..} => return Some("write-nfc writes a single tag"),
+        Command::Gui | Command::Preview{..} => return Some("the window shows a single network"),
+        Command::Tui => return Some("the form shows a single network"),
+        Command::Wps{..} => return Some("wps encodes a PIN instead of the networks"),
+        Command::Dpp{..} => return Some("dpp encodes a key instead of the networks"),
+        Command::Hotspot{..} => return Some("the machine shares its connection as a single hotspot"),
+        Command::Inspect{..} => return Some("inspect reads the code from its image"),
+        Command::Decode{..} => return Some("decode reads the networks from its image"),
+        Command::Parse{..} => return Some("parse reads the network from its payload"),
+        Command::Connect{..} => return Some("connect joins the network of its code"),
+        Command::Completions{..} | Command::Manpage{..} => return Some("the command line is described once"),
+        Command::Batch{..} => return Some("batch reads the networks from its file"),
+        Command::Bench{..} => return Some("bench generates its own networks"),
+        Command::Convert{..} => return Some("convert reads the networks from its input"),
+        Command::Serve{..} => return Some("serve encodes the networks entered in the browser"),
+        Command::Export{target: ExportTarget::Archive{output, ..} | ExportTarget::AndroidPolicy{output} | ExportTarget::WindowsProfile{output}
+            | ExportTarget::Wallet{output, ..} | ExportTarget::Mobileconfig{output, ..}} => output.is_some(),
+        Command::Encode | Command::Export{..} | Command::Print{..} | Command::SizeCalc{..} => false,
+        Command::Layout{layout} => match layout {
+            LayoutKind::BusinessCard{output, ..} | LayoutKind::TableTent{output, ..} | LayoutKind::TriFold{output, ..}
+                | LayoutKind::WifiAndUrl{output, ..} | LayoutKind::MultiBand{output, ..} | LayoutKind::Labels{output, ..} => output.is_some()
+        },
+        Command::Wallpaper{output, ..} | Command::LockScreen{output, ..} | Command::Template{output, ..} | Command::Html{output} => output.is_some(),
+        Command::Plugin{..} => true
+    };
+    output.then_some("--output names a single file")
+}
+/// Loads the branding and applies the styling options of the command line to it.
+fn load_branding(args: &Args) -> Result<branding::Branding, String> {
+    let mut branding = branding::Branding::load(args.branding.as_deref()).map_err(|err| format!("Failed to load the branding: {}", err))?;
+    if let Some(style) = args.frame {
+        let frame = branding.frame.get_or_insert_with(|| branding::Frame::new(style));
+        frame.style = style;
+    }
+    if let Some(preset) = args.palette {
+        branding.palette = preset.palette();
+    }
+    for (name, contrast) in branding.low_contrast() {
+        diagnostics::warn("low-contrast", format!("the {} color of the branding has a contrast of only {:.1}:1 to what it's drawn onto, which may be hard to read", name, contrast));
+    }
+    for (name, deficiency, contrast) in branding.low_contrast_with_deficiency() {
+        diagnostics::warn("low-contrast", format!("with {:?} the {} color of the branding has a contrast of only {:.1}:1 to what it's drawn onto. See --palette for presets that don't", deficiency, name, contrast));
+    }
+    if let Some(name) = &args.font {
+        let font = branding::resolve_font(name, std::path::Path::new("")).map_err(|err| format!("Failed to load the font: {}", err))?;
+        branding.fonts = branding::Fonts{ heading: font, body: font };
+    }
+    if let Some(align) = args.align {
+        branding.align = align;
+    }
+    if args.scan_distance.is_some() {
+        distance::simplify(&mut branding);
+    }
+    Ok(branding)
+}
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+/// The arguments with an `encode` given first moved behind the options, which belong to the top level, so clap
+/// parses them the same as without the subcommand.
+fn options_before_encode(mut arguments: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    if arguments.get(1).is_some_and(|x| x == "encode") {
+        let encode:std::ffi::OsString = arguments.remove(1);
+        arguments.push(encode);
     }
-    format!("WIFI:T:{};S:{};P:{};H:{};;", sec, args.ssid, psw, args.hidden)
+    arguments
 }
 fn main(){
-    let args:Args = Args::parse();
-    let qr_code:String = assemble_qr_string(&args);
-    let qr_code:Vec<Vec<bool>> = qrcode_generator::to_matrix(qr_code, match args.ecc{
-        ECCLevel::Low => qrcode_generator::QrCodeEcc::Low,
-        ECCLevel::Medium => qrcode_generator::QrCodeEcc::Medium,
-        ECCLevel::Quartile => qrcode_generator::QrCodeEcc::Quartile,
-        ECCLevel::High => qrcode_generator::QrCodeEcc::High
-    }).unwrap();
-    
-    print!("{}", get_bool_matrix_as_string(qr_code));
+    let matches:clap::ArgMatches = Args::command().get_matches_from(options_before_encode(std::env::args_os().collect()));
+    let mut args:Args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    diagnostics::init(&args.output.log);
+    let result:Result<(), Error> = config::Config::load(args.config.as_deref())
+        .or_fail(ErrorKind::Input, |err| format!("Failed to read the config: {}", err))
+        .and_then(|config| {
+            config.apply(&mut args, &matches);
+            try_main(args)
+        });
+    if let Err(err) = result {
+        diagnostics::error(&err);
+        std::process::exit(err.kind.exit_code());
+    }
+}
+fn try_main(mut args: Args) -> Result<(), Error> {
+    if matches!(args.command, Some(Command::Encode)) {
+        args.command = None;
+    }
+    let requested_ecc:ECCLevel = args.ecc;
+    hex_ssid::prepare(&mut args);
+    if let Some(psw) = args.psw_source.read().or_fail(ErrorKind::Input, |err| format!("Failed to read the password: {}", err))? {
+        args.psw = Some(psw);
+    }
+    if let Some(network) = args.saved.read(&args.ssid).or_fail(ErrorKind::External, |err| format!("Failed to read the saved network: {}", err))? {
+        args.sec = network.sec().or_fail(ErrorKind::Input, |err| format!("Failed to read the saved network: {}", err))?.or(args.sec);
+        args.ssid = network.ssid;
+        args.psw = network.password;
+        args.hidden |= network.hidden;
+    }
+    if args.saved.scan {
+        scan::pick(&mut args).or_fail(ErrorKind::Input, |err| format!("Failed to pick a network: {}", err))?;
+    }
+    let generated:Option<String> = match args.generator.generate_psw {
+        Some(length) => Some(passphrase::generate(length, args.generator.psw_alphabet).or_fail(ErrorKind::Usage, |err| format!("Failed to generate the password: {}", err))?),
+        None => None
+    };
+    if let Some(psw) = &generated {
+        if let Some(path) = &args.generator.psw_out {
+            passphrase::write(path, psw).or_fail(ErrorKind::Output, |err| format!("Failed to write the password to {}: {}", path.display(), err))?;
+        }
+        args.psw = Some(psw.clone());
+    }
+    if prompt::wanted(&args) {
+        prompt::fill(&mut args).or_fail(ErrorKind::Input, |err| format!("Failed to read the network from the terminal: {}", err))?;
+    }
+    if args.ssid.is_empty() && args.networks.is_empty() && !ignores_networks(args.command.as_ref()) {
+        <Args as clap::CommandFactory>::command().error(clap::ErrorKind::MissingRequiredArgument, "The SSID has to be provided with --ssid <SSID> or --network ssid=<SSID>").exit();
+    }
+    if let Some(meters) = args.scan_distance {
+        args.ecc = args.ecc.max(distance::min_ecc(meters));
+    }
+    if args.output.logo.is_some() {
+        args.ecc = ECCLevel::High;
+    }
+    if args.output.micro && args.output.border == QUIET_ZONE {
+        args.output.border = micro::QUIET_ZONE;
+    }
+    let branding:branding::Branding = load_branding(&args).or_fail(ErrorKind::Input, |err| err)?;
+    let command:Option<Command> = args.command.take();
+    if command.is_some() && args.output.path.is_some() {
+        return Err(Error::new(ErrorKind::Usage, "--output is only used without a command, the commands take their own!"));
+    }
+    if command.is_some() && args.output.micro {
+        return Err(Error::new(ErrorKind::Usage, "--micro is only used without a command, for the code in the terminal or of --output!"));
+    }
+    let extra:Vec<Args> = args.networks.iter().map(|x| batch::network_args(&args, x, 0).expect("networks are validated when parsing")).collect();
+    let mut networks:Vec<Args> = if args.ssid.is_empty() && !extra.is_empty() {extra}else{std::iter::once(args).chain(extra).collect()};
+    let quiet_zone:usize = if networks[0].output.micro {micro::QUIET_ZONE}else{QUIET_ZONE};
+    if networks[0].output.border < quiet_zone {
+        diagnostics::warn("quiet-zone", format!("the border is narrower than the quiet zone of {} modules readers expect, which may keep the code from scanning", quiet_zone));
+    }
+    if networks[0].output.micro {
+        diagnostics::warn("micro-qr", "the cameras of most phones can't read Micro QR symbols, only dedicated scanners can");
+    }
+    if let Some(warning) = networks[0].output.color_warning() {
+        diagnostics::warn("colors", warning);
+    }
+    if networks.len() > 1 {
+        if let Some(reason) = command.as_ref().and_then(single_network_only) {
+            return Err(Error::new(ErrorKind::Usage, format!("Only one network can be given, as {}!", reason)));
+        }
+        if command.is_none() && networks[0].output.path.is_some() {
+            return Err(Error::new(ErrorKind::Usage, "Only one network can be given, as --output holds one QR-Code!"));
+        }
+    }
+    // The other commands don't encode the networks given on the command line.
+    if !ignores_networks(command.as_ref()) {
+        for network in &mut networks {
+            checked_credentials(network)?;
+            let payload:String = assemble_qr_string(network);
+            if network.output.micro {
+                micro::encode(&payload, network.ecc).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}!", err))?;
+            }else{
+                capacity::fit(network, &payload).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}", err))?;
+                symbol::encode(&payload, network.ecc, &network.symbol).or_fail(ErrorKind::Encoding, |err| format!("Failed to encode the network: {}!", err))?;
+            }
+            if network.verify {
+                verify::verify(network, &payload, &matrix(network, &payload)?).or_fail(ErrorKind::Encoding, |err| format!("Failed to verify the QR-Code of {}: {}!", network.ssid, err))?;
+            }
+            diagnostics::network(network, &payload, || matrix(network, &payload));
+            if network.explain {
+                eprint!("{}", explain::explain(network, requested_ecc));
+            }
+        }
+    }
+    for network in &networks {
+        run(command.as_ref(), network, &branding, networks.len() > 1)?;
+    }
+    if let Some(psw) = generated {
+        // Kept off stdout next to JSON documents, which only parse on their own.
+        if networks[0].output.output_format == report::Format::Json {
+            eprintln!("Password: {}", psw);
+        }else{
+            println!("Password: {}", psw);
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests{
     use super::*;
+    /// A network with the given credentials and the defaults of every other option, shared by the tests of every module.
+    pub fn network(ssid: &str, psw: Option<&str>, sec: Option<Security>) -> Args {
+        Args{
+            ssid: String::from(ssid),
+            hex_ssid: hex_ssid::Options::default(),
+            psw: psw.map(String::from),
+            interactive: false,
+            psw_source: credentials::PasswordSource::default(),
+            generator: passphrase::Generator::default(),
+            saved: saved::Source::default(),
+            sec,
+            hidden: false,
+            compat: false,
+            transition_disable: false,
+            ecc: ECCLevel::Low,
+            symbol: symbol::Options::default(),
+            branding: None,
+            font: None,
+            align: None,
+            frame: None,
+            palette: None,
+            scan_distance: None,
+            shred: false,
+            downgrade_ecc: false,
+            force: false,
+            wep: wep::Options::default(),
+            info_url: None,
+            explain: false,
+            verify: false,
+            output: output::Options::default(),
+            config: None,
+            enterprise: eap::Enterprise::default(),
+            networks: Vec::new(),
+            command: None
+        }
+    }
+    #[test]
+    fn test_cli() {
+        <Args as clap::CommandFactory>::command().debug_assert();
+    }
+    #[test]
+    fn test_encode_subcommand() {
+        let parse = |x: &str| Args::try_parse_from(options_before_encode(x.split(' ').map(std::ffi::OsString::from).collect()));
+        let args:Args = parse("wifiqr encode --ssid Home --psw hunter22").unwrap();
+        assert!(matches!(args.command, Some(Command::Encode)));
+        assert_eq!((args.ssid.as_str(), args.psw.as_deref()), ("Home", Some("hunter22")));
+        assert!(matches!(parse("wifiqr --ssid Home encode").unwrap().command, Some(Command::Encode)));
+        assert!(matches!(parse("wifiqr --ssid Home inspect code.png").unwrap().command, Some(Command::Inspect{..})));
+    }
     #[test]
     fn test_bool_matrix_string_conversion() {
         /*
@@ -118,55 +1681,112 @@ mod tests{
         assert_eq!(get_bool_matrix_as_string(test_matrix), correct_result);
     }
     #[test]
+    fn test_ascii_matrix_string() {
+        assert_eq!(matrix_as_string(&[vec![true, false], vec![false, true]], console::Charset::Ascii), "##  \n  ##\n");
+    }
+    #[test]
     fn test_no_psw() {
-        let mut args = Args{
-            ssid: String::from("Martin Router King"),
-            psw: None,
-            sec: None,
-            hidden: false,
-            ecc: ECCLevel::Low
-        };
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:;S:Martin Router King;P:;H:false;;");
+        let mut args = network("Martin Router King", None, None);
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:nopass;S:Martin Router King;P:;;");
         args.hidden = true;
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:;S:Martin Router King;P:;H:true;;");
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:nopass;S:Martin Router King;P:;H:true;;");
+        args.hidden = false;
+        args.compat = true;
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:nopass;S:Martin Router King;P:;H:false;;");
+    }
+    #[test]
+    fn test_escaping() {
+        let mut args = network("My;Home:WiFi", Some("C:\\Users\\\"me\",x"), None);
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:My\\;Home\\:WiFi;P:C\\:\\\\Users\\\\\\\"me\\\"\\,x;;");
+        assert_eq!(inspect::parse_wifi(&assemble_qr_string(&args)).unwrap().password.as_deref(), Some("C:\\Users\\\"me\",x"));
+        args.psw = Some(String::from("pass\\word"));
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:My\\;Home\\:WiFi;P:pass\\\\word;;");
+        assert!(inspect::validate_wifi(&assemble_qr_string(&args)).iter().all(|x| !x.message.contains("escape")));
+        args.ssid = String::from("DEADBEEF");
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:\"DEADBEEF\";P:pass\\\\word;;");
+        assert_eq!(inspect::parse_wifi(&assemble_qr_string(&args)).unwrap().ssid, "DEADBEEF");
+        assert!(inspect::validate_wifi(&assemble_qr_string(&args)).iter().all(|x| !x.message.contains("hex")));
     }
     #[test]
     fn test_no_sec_with_psw() {
-        let mut args = Args{
-            ssid: String::from("Martin Router King"),
-            psw: Some(String::from("password")),
-            sec: None,
-            hidden: false,
-            ecc: ECCLevel::Low
-        };
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:Wpa2;S:Martin Router King;P:password;H:false;;");
+        let mut args = network("Martin Router King", Some("password"), None);
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:Martin Router King;P:password;;");
         args.hidden = true;
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:Wpa2;S:Martin Router King;P:password;H:true;;");
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:Martin Router King;P:password;H:true;;");
+        args.sec = Some(Security::Wpa2Wpa3);
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:Martin Router King;P:password;H:true;;");
+        args.transition_disable = true;
+        assert_eq!(require_password(&args).unwrap_err().message, "--transition-disable is only for WPA3 networks, pass --sec wpa3!");
+        args.sec = Some(Security::Wpa3);
+        assert!(require_password(&args).is_ok());
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:SAE;S:Martin Router King;P:password;R:1;H:true;;");
     }
     #[test]
     fn test_sec_with_psw() {
-        let mut args = Args{
-            ssid: String::from("Martin Router King"),
-            psw: Some(String::from("password")),
-            sec: Some(SecurityTypes::Wpa2),
-            hidden: false,
-            ecc: ECCLevel::Low
-        };
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:Wpa2;S:Martin Router King;P:password;H:false;;");
+        let mut args = network("Martin Router King", Some("password"), Some(Security::Wpa2));
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:Martin Router King;P:password;;");
         args.hidden = true;
-        assert_eq!(assemble_qr_string(&args), "WIFI:T:Wpa2;S:Martin Router King;P:password;H:true;;");
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA;S:Martin Router King;P:password;H:true;;");
     }
-    #[should_panic]
     #[test]
     fn test_protected_with_no_psw() {
-        
-        let args = Args{
-            ssid: String::from("Martin Router King"),
-            psw: None,
-            sec: Some(SecurityTypes::Wpa2),
-            hidden: false,
-            ecc: ECCLevel::Low
-        };
-        assemble_qr_string(&args);
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Martin Router King", "--sec", "wpa2"]);
+        assert_eq!(resolve_security(&args), Some(Security::Wpa2));
+        let err:Error = require_password(&args).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Usage);
+        assert!(err.message.starts_with("The security standard Wpa2 was provided, but no password!"), "{}", err.message);
+    }
+    #[test]
+    fn test_require_password() {
+        let mut args = network("Martin Router King", Some(""), Some(Security::Wpa2));
+        assert_eq!(require_password(&args).unwrap_err().kind.exit_code(), 2);
+        args.psw = Some(String::from("password"));
+        assert!(require_password(&args).is_ok());
+        args.sec = None;
+        args.psw = None;
+        assert!(require_password(&args).is_ok());
+        args.psw = Some(String::from("short"));
+        assert!(validate_credentials(&args).unwrap_err().message.contains("8 to 63 characters"));
+        args.force = true;
+        assert!(validate_credentials(&args).is_ok());
+    }
+    #[test]
+    fn test_enterprise() {
+        let mut args = Args{ enterprise: eap::Enterprise{ eap: Some(eap::EapMethod::Ttls), identity: Some(String::from("alice")), anonymous_identity: None, phase2: Some(eap::Phase2::Pap) }, ..network("Corp", Some("hunter2"), None) };
+        assert!(require_password(&args).is_ok());
+        assert_eq!(assemble_qr_string(&args), "WIFI:T:WPA2-EAP;S:Corp;E:TTLS;I:alice;P:hunter2;PH2:PAP;;");
+        args.sec = Some(Security::Wpa2);
+        assert_eq!(require_password(&args).unwrap_err().kind, ErrorKind::Usage);
+    }
+    #[test]
+    fn test_multi_band() {
+        let output:std::path::PathBuf = std::env::temp_dir().join(format!("wifiqr-multi-band-{}.pdf", std::process::id()));
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Cafe", "--psw", "hunter22", "--sec", "wpa2-wpa3", "layout", "multi-band", "--band", "5 GHz=Cafe-5G", "--output", output.to_str().unwrap()]);
+        run(args.command.as_ref(), &args, &branding::Branding::default(), false).unwrap();
+        assert!(std::fs::read(&output).unwrap().starts_with(b"%PDF"));
+        let band:String = format!("5 GHz={}", "x".repeat(40));
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Cafe", "--psw", "hunter22", "layout", "multi-band", "--band", &band, "--output", output.to_str().unwrap()]);
+        let err:Error = run(args.command.as_ref(), &args, &branding::Branding::default(), false).unwrap_err();
+        assert!(err.message.starts_with("Invalid band 5 GHz: Devices won't accept"), "{}", err.message);
+        let _ = std::fs::remove_file(&output);
+    }
+    #[test]
+    fn test_enterprise_exports() {
+        let exports:[&[&str]; 7] = [&["write-nfc"], &["export", "android-policy"], &["export", "windows-profile"], &["export", "pass"], &["export", "1password"], &["export", "bitwarden"],
+            &["export", "wallet", "--pass-type-id", "pass.com.example.wifi", "--team-id", "ABCDE12345", "--certificate", "pass.pem", "--key", "pass.key", "--wwdr", "wwdr.pem"]];
+        for export in exports {
+            let args:Args = Args::parse_from(["wifiqr", "--ssid", "Office", "--psw", "secret", "--eap", "peap", "--identity", "alice"].iter().chain(export));
+            let err:Error = run(args.command.as_ref(), &args, &branding::Branding::default(), false).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::Usage, "{:?}", export);
+            assert!(err.message.starts_with("Office is an enterprise network"), "{}", err.message);
+        }
+    }
+    #[test]
+    fn test_completions() {
+        let mut script:Vec<u8> = Vec::new();
+        clap_complete::generate(clap_complete::Shell::Fish, &mut Args::command(), "wifiqr", &mut script);
+        let script:String = String::from_utf8(script).unwrap();
+        assert!(script.contains("complete -c wifiqr -n \"__fish_use_subcommand\" -f -a \"write-nfc\""), "{}", script);
+        assert!(script.contains("-l ecc") && script.contains("-a \"wallet\""));
     }
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The man pages, in roff, for `manpage`. clap_mangen writes them from the definition of the command line: a page
+//! for the command and one for each subcommand, which the pages refer to by their names.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Command;
+use clap_mangen::Man;
+
+/// Writes the man page of the command and the pages of its subcommands into `dir`, in section 1, named like
+/// `wifiqr-export-wallet.1` as the pages refer to them. Returns the paths written.
+pub fn write_pages(mut command: Command, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    command.build();
+    let mut pages:Vec<PathBuf> = Vec::new();
+    write_page(&command, dir, &mut pages)?;
+    Ok(pages)
+}
+
+fn write_page(command: &Command, dir: &Path, pages: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut page:Vec<u8> = Vec::new();
+    Man::new(command.clone()).render(&mut page)?;
+    let path:PathBuf = dir.join(format!("{}.1", command.get_name()));
+    std::fs::write(&path, page)?;
+    pages.push(path);
+    for subcommand in command.get_subcommands().filter(|x| !x.is_hide_set()) {
+        let name:String = format!("{}-{}", command.get_name(), subcommand.get_name());
+        write_page(&subcommand.clone().name(name), dir, pages)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Arg;
+    #[test]
+    fn test_write_pages() {
+        let command:Command = Command::new("qr").version("1.0").about("Makes codes.")
+            .arg(Arg::new("ecc").long("ecc").takes_value(true).value_parser(["low", "high"]).value_name("LEVEL"))
+            .subcommand(Command::new("batch").about("Read a file").arg(Arg::new("file")).subcommand(Command::new("csv")));
+        let dir:PathBuf = std::env::temp_dir().join(format!("wifiqr-manpage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pages:Vec<PathBuf> = write_pages(command, &dir).unwrap();
+        let names:Vec<String> = pages.iter().map(|x| x.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(&names[..3], ["qr.1", "qr-batch.1", "qr-batch-csv.1"]);
+        let page:String = std::fs::read_to_string(&pages[0]).unwrap();
+        assert!(page.starts_with(".ie \\n(.g .ds Aq \\(aq\n") && page.contains(".TH qr 1  \"qr 1.0\""), "{}", page);
+        assert!(page.contains("\\fB\\-\\-ecc\\fR") && page.contains("qr\\-batch(1)"), "{}", page);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
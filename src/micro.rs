@@ -0,0 +1,266 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Micro QR symbols for `--micro`, encoded as in ISO/IEC 18004. `WIFI:` payloads need byte mode as `;` isn't
+//! alphanumeric, and even the shortest, `WIFI:S:x;;`, has 10 bytes, more than M3 holds, so only M4 symbols are
+//! written: 17 x 17 modules with a single finder pattern, which need a quiet zone of only 2 modules.
+
+use std::fmt;
+
+use crate::ECCLevel;
+
+/// Width of the quiet zone readers expect around Micro QR symbols.
+pub const QUIET_ZONE: usize = 2;
+/// Width of M4 symbols in modules.
+const SIZE: usize = 17;
+/// Mode indicator of byte mode in M4 symbols.
+const BYTE_MODE: u8 = 0b010;
+/// Bits of the character count in byte mode in M4 symbols.
+const COUNT_BITS: usize = 5;
+/// Zeros ending the data of M4 symbols, as far as there is room.
+const TERMINATOR_BITS: usize = 9;
+/// Mask XORed onto the format information, so it is never all light.
+const FORMAT_MASK: u16 = 0x4445;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MicroError{
+    /// Micro QR has no ECC-Level high.
+    Ecc(ECCLevel),
+    /// The payload has this many bytes, more than M4 holds at the ECC-Level.
+    TooLong(usize, ECCLevel)
+}
+impl fmt::Display for MicroError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MicroError::Ecc(ecc) => write!(f, "Micro QR symbols have no ECC-Level {:?}, use low, medium or quartile", ecc),
+            MicroError::TooLong(length, ecc) => write!(f, "the payload has {} bytes, but a Micro QR symbol with ECC-Level {:?} holds at most {}, leave out --micro",
+                length, ecc, capacity(*ecc).unwrap_or_default())
+        }
+    }
+}
+
+/// The symbol number of M4 at the ECC-Level, for the format information, and its data and error correction
+/// codewords.
+fn symbol(ecc: ECCLevel) -> Result<(u16, usize, usize), MicroError> {
+    match ecc {
+        ECCLevel::Low => Ok((5, 16, 8)),
+        ECCLevel::Medium => Ok((6, 14, 10)),
+        ECCLevel::Quartile => Ok((7, 10, 14)),
+        ECCLevel::High => Err(MicroError::Ecc(ecc))
+    }
+}
+
+/// Bytes an M4 symbol holds at the ECC-Level, `None` for high.
+pub fn capacity(ecc: ECCLevel) -> Option<usize> {
+    let (_, data, _) = symbol(ecc).ok()?;
+    Some((data * 8 - 3 - COUNT_BITS) / 8)
+}
+
+/// Multiplies in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1, the field of the Reed-Solomon codes of QR-Codes.
+fn multiply(x: u8, y: u8) -> u8 {
+    let mut product:u16 = 0;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x11d);
+        product ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    product as u8
+}
+
+/// The `degree` error correction codewords of `data`.
+fn reed_solomon(data: &[u8], degree: usize) -> Vec<u8> {
+    // The coefficients of the generator polynomial, highest first, without the leading 1.
+    let mut generator:Vec<u8> = vec![0; degree];
+    generator[degree - 1] = 1;
+    let mut root:u8 = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            generator[j] = multiply(generator[j], root);
+            if j + 1 < degree {
+                generator[j] ^= generator[j + 1];
+            }
+        }
+        root = multiply(root, 0x02);
+    }
+    let mut remainder:Vec<u8> = vec![0; degree];
+    for &byte in data {
+        let factor:u8 = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (x, &y) in remainder.iter_mut().zip(&generator) {
+            *x ^= multiply(y, factor);
+        }
+    }
+    remainder
+}
+
+/// The data codewords of `payload`: byte mode, the terminator and the pad codewords filling the rest.
+fn data_codewords(payload: &[u8], data: usize) -> Vec<u8> {
+    let mut bits:Vec<bool> = Vec::with_capacity(data * 8);
+    let mut push = |value: usize, length: usize| bits.extend((0..length).rev().map(|i| (value >> i) & 1 == 1));
+    push(BYTE_MODE as usize, 3);
+    push(payload.len(), COUNT_BITS);
+    for &byte in payload {
+        push(byte as usize, 8);
+    }
+    let terminator:usize = TERMINATOR_BITS.min(data * 8 - bits.len());
+    bits.resize(bits.len() + terminator, false);
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+    let mut codewords:Vec<u8> = bits.chunks(8).map(|x| x.iter().fold(0, |byte, &bit| (byte << 1) | bit as u8)).collect();
+    codewords.extend([0xec, 0x11].into_iter().cycle().take(data - codewords.len()));
+    codewords
+}
+
+/// The 15 bits of the format information: the symbol number and the mask, the BCH code protecting them, and the
+/// [`FORMAT_MASK`].
+fn format_bits(symbol: u16, mask: u8) -> u16 {
+    let data:u16 = (symbol << 2) | mask as u16;
+    let mut remainder:u16 = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    ((data << 10) | remainder) ^ FORMAT_MASK
+}
+
+/// Whether the mask pattern inverts the module in `row` and `column`.
+fn masked(mask: u8, row: usize, column: usize) -> bool {
+    match mask {
+        0 => row.is_multiple_of(2),
+        1 => (row / 2 + column / 3).is_multiple_of(2),
+        2 => ((row * column) % 2 + (row * column) % 3).is_multiple_of(2),
+        _ => ((row + column) % 2 + (row * column) % 3).is_multiple_of(2)
+    }
+}
+
+/// The pattern and timing modules, and which modules they and the format information take.
+fn function_patterns() -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
+    let mut matrix:Vec<Vec<bool>> = vec![vec![false; SIZE]; SIZE];
+    let mut reserved:Vec<Vec<bool>> = vec![vec![false; SIZE]; SIZE];
+    // The finder pattern with its separator, which only runs along the right and bottom.
+    for row in 0..8 {
+        for column in 0..8 {
+            let ring:usize = row.max(column);
+            let inner:usize = row.min(column);
+            matrix[row][column] = ring < 7 && (ring == 6 || inner == 0 || (2..=4).contains(&row) && (2..=4).contains(&column));
+            reserved[row][column] = true;
+        }
+    }
+    // The timing patterns along the top and left edges.
+    for i in 8..SIZE {
+        matrix[0][i] = i % 2 == 0;
+        matrix[i][0] = i % 2 == 0;
+        reserved[0][i] = true;
+        reserved[i][0] = true;
+    }
+    // The format information, right of and beneath the separator.
+    reserved[8][1..=8].fill(true);
+    for line in &mut reserved[1..=8] {
+        line[8] = true;
+    }
+    (matrix, reserved)
+}
+
+/// Places the codewords in two columns wide strips from the bottom right, up and down in turn, skipping the
+/// function patterns.
+fn place(matrix: &mut [Vec<bool>], reserved: &[Vec<bool>], codewords: &[u8]) {
+    let mut bits = codewords.iter().flat_map(|&x| (0..8).rev().map(move |i| (x >> i) & 1 == 1));
+    let mut upward:bool = true;
+    for right in (1..SIZE).rev().step_by(2) {
+        for vertical in 0..SIZE {
+            let row:usize = if upward {SIZE - 1 - vertical}else{vertical};
+            for column in [right, right - 1] {
+                if !reserved[row][column] {
+                    matrix[row][column] = bits.next().unwrap_or(false);
+                }
+            }
+        }
+        upward = !upward;
+    }
+}
+
+/// How well the mask leaves dark modules on the right and bottom edges, which readers need to find the symbol.
+/// Higher is better.
+fn score(matrix: &[Vec<bool>]) -> usize {
+    let right:usize = (1..SIZE).filter(|&row| matrix[row][SIZE - 1]).count();
+    let bottom:usize = (1..SIZE).filter(|&column| matrix[SIZE - 1][column]).count();
+    right.min(bottom) * 16 + right.max(bottom)
+}
+
+/// The modules of the M4 symbol of `payload`, without the quiet zone.
+pub fn encode(payload: &str, ecc: ECCLevel) -> Result<Vec<Vec<bool>>, MicroError> {
+    let (symbol, data, ecc_codewords) = symbol(ecc)?;
+    if payload.len() > capacity(ecc).unwrap_or_default() {
+        return Err(MicroError::TooLong(payload.len(), ecc));
+    }
+    let mut codewords:Vec<u8> = data_codewords(payload.as_bytes(), data);
+    codewords.extend(reed_solomon(&codewords, ecc_codewords));
+    let (mut matrix, reserved) = function_patterns();
+    place(&mut matrix, &reserved, &codewords);
+    let masked_with = |mask: u8| {
+        let mut matrix:Vec<Vec<bool>> = matrix.clone();
+        for (row, line) in matrix.iter_mut().enumerate() {
+            for (column, module) in line.iter_mut().enumerate() {
+                *module ^= !reserved[row][column] && masked(mask, row, column);
+            }
+        }
+        let format:u16 = format_bits(symbol, mask);
+        for i in 0..15 {
+            let dark:bool = (format >> i) & 1 == 1;
+            if i < 8 {
+                matrix[i + 1][8] = dark;
+            }else{
+                matrix[8][15 - i] = dark;
+            }
+        }
+        matrix
+    };
+    // The first of the best masks wins ties.
+    Ok((0..4).map(masked_with).rev().max_by_key(|x| score(x)).unwrap())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_reed_solomon() {
+        // The M2-L symbol of "01234567" from the standard.
+        assert_eq!(reed_solomon(&[0x40, 0x18, 0xac, 0xc3, 0x00], 5), [0x86, 0x0d, 0x22, 0xae, 0x30]);
+    }
+    #[test]
+    fn test_format_bits() {
+        assert_eq!([0, 1, 2, 3].map(|mask| format_bits(5, mask)), [0x1735, 0x1202, 0x1d5b, 0x186c]);
+        assert_eq!(format_bits(7, 3), 0x3bba);
+    }
+    #[test]
+    fn test_data_codewords() {
+        let codewords:Vec<u8> = data_codewords(b"WIFI:S:Cafe;;", 16);
+        // 010, 13 bytes as 01101, then "W" (0x57).
+        assert_eq!(codewords[..2], [0b0100_1101, 0b0101_0111]);
+        assert_eq!(codewords.len(), 16);
+        // The terminator fills the last two bytes, leaving no room for pad codewords.
+        assert_eq!(codewords[14..], [0x00, 0x00]);
+        assert_eq!(data_codewords(b"WIFI:S:Caf;;", 16)[13..], [0x00, 0x00, 0xec]);
+    }
+    #[test]
+    fn test_encode() {
+        let matrix:Vec<Vec<bool>> = encode("WIFI:S:Cafe;;", ECCLevel::Low).unwrap();
+        assert_eq!(matrix.len(), SIZE);
+        assert_eq!(matrix[0][..9], [true, true, true, true, true, true, true, false, true]);
+        assert_eq!(matrix[3][..8], [true, false, true, true, true, false, true, false]);
+        assert_eq!(matrix[8..].iter().map(|x| x[0]).collect::<Vec<bool>>(), (8..SIZE).map(|x| x % 2 == 0).collect::<Vec<bool>>());
+        assert_eq!(capacity(ECCLevel::Low), Some(15));
+        assert_eq!(encode("WIFI:S:Coffee;;", ECCLevel::Medium), Err(MicroError::TooLong(15, ECCLevel::Medium)));
+        assert_eq!(encode("WIFI:S:Cafe;;", ECCLevel::High), Err(MicroError::Ecc(ECCLevel::High)));
+    }
+}
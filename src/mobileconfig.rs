@@ -0,0 +1,223 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Apple configuration profiles (`.mobileconfig`) with a `com.apple.wifi.managed` payload, which iPhones, iPads and
+//! Macs install from a download or an MDM to join the network without scanning its code. Profiles can be signed by
+//! `openssl` with a certificate the devices trust, which they show as verified instead of unsigned.
+
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::eap::{EapMethod, Phase2};
+use crate::plist::{self, entries, Value};
+use crate::private_dir::PrivateDir;
+use crate::{resolve_security, wallet, Args, Security};
+
+/// The signing certificate with its private key, in PEM, and the intermediate certificates of its chain.
+pub struct Signing<'a>{
+    pub certificate: &'a Path,
+    pub key: &'a Path,
+    pub chain: Option<&'a Path>
+}
+
+#[derive(Debug)]
+pub enum MobileconfigError{
+    /// The network can't be described by the profile.
+    Unsupported(String),
+    Io(std::io::Error),
+    /// openssl failed, with what it printed.
+    Signing(String)
+}
+impl fmt::Display for MobileconfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MobileconfigError::Unsupported(reason) => write!(f, "{}", reason),
+            MobileconfigError::Io(err) => write!(f, "failed to run openssl: {}", err),
+            MobileconfigError::Signing(stderr) => write!(f, "openssl failed to sign the profile: {}", stderr.trim())
+        }
+    }
+}
+impl From<std::io::Error> for MobileconfigError {
+    fn from(err: std::io::Error) -> Self {
+        MobileconfigError::Io(err)
+    }
+}
+
+/// The identifier of the profile of a network unless one is given, e.g. `wifiqr.my-home` for `My Home`. Devices
+/// replace an installed profile with one of the same identifier.
+pub fn default_identifier(args: &Args) -> String {
+    let name:String = args.ssid.to_lowercase().chars().map(|x| if x.is_ascii_alphanumeric() {x}else{'-'}).collect();
+    format!("wifiqr.{}", name)
+}
+
+/// A UUID derived from the name, the same for every run, in the form of a name-based UUID (version 5).
+fn uuid(name: &str) -> String {
+    let mut bytes:[u8; 20] = wallet::sha1(name.as_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex:String = bytes[..16].iter().map(|x| format!("{:02X}", x)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// The `EAPClientConfiguration` of an enterprise network. Devices don't support EAP-pwd, and EAP-TLS needs an
+/// identity certificate in the profile.
+fn eap_client_configuration(args: &Args, eap: EapMethod) -> Result<Value, MobileconfigError> {
+    let eap_type:i64 = match eap {
+        EapMethod::Peap => 25,
+        EapMethod::Ttls => 21,
+        EapMethod::Tls | EapMethod::Pwd => return Err(MobileconfigError::Unsupported(format!("{} networks can't be written to configuration profiles", eap)))
+    };
+    let mut configuration:Vec<(String, Value)> = entries([("AcceptEAPTypes", Value::Array(vec![Value::from(eap_type)]))]);
+    if let Some(identity) = &args.enterprise.identity {
+        configuration.push((String::from("UserName"), Value::from(identity.as_str())));
+    }
+    if let Some(psw) = &args.psw {
+        configuration.push((String::from("UserPassword"), Value::from(psw.as_str())));
+    }
+    if let Some(anonymous_identity) = &args.enterprise.anonymous_identity {
+        configuration.push((String::from("OuterIdentity"), Value::from(anonymous_identity.as_str())));
+    }
+    // PEAP negotiates its inner method, while TTLS is told which one to use.
+    if let (EapMethod::Ttls, Some(phase2)) = (eap, args.enterprise.phase2) {
+        let inner:&str = match phase2 {
+            Phase2::Pap => "PAP",
+            Phase2::Chap => "CHAP",
+            Phase2::Mschap => "MSCHAP",
+            Phase2::Mschapv2 => "MSCHAPv2",
+            Phase2::Gtc => return Err(MobileconfigError::Unsupported(String::from("TTLS with GTC can't be written to configuration profiles")))
+        };
+        configuration.push((String::from("TTLSInnerAuthentication"), Value::from(inner)));
+    }
+    Ok(Value::Dictionary(configuration))
+}
+
+/// The `com.apple.wifi.managed` payload of the network.
+fn wifi_payload(args: &Args, identifier: &str) -> Result<Value, MobileconfigError> {
+    // WPA2 devices join WPA2/WPA3 networks with WPA2, and WPA3 devices with WPA3.
+    let encryption:&str = match resolve_security(args) {
+        _ if args.enterprise.eap.is_some() => "WPA2",
+        None => "None",
+        Some(Security::Wep) => "WEP",
+        Some(Security::Wpa) => "WPA",
+        Some(Security::Wpa2 | Security::Wpa2Wpa3) => "WPA2",
+        Some(Security::Wpa3) => "WPA3"
+    };
+    let identifier:String = format!("{}.wifi", identifier);
+    let mut payload:Vec<(String, Value)> = entries([
+        ("PayloadType", Value::from("com.apple.wifi.managed")),
+        ("PayloadVersion", Value::from(1)),
+        ("PayloadIdentifier", Value::from(identifier.as_str())),
+        ("PayloadUUID", Value::from(uuid(&identifier))),
+        ("PayloadDisplayName", Value::from(format!("Wi-Fi ({})", args.ssid))),
+        ("SSID_STR", Value::from(args.ssid.as_str())),
+        ("HIDDEN_NETWORK", Value::from(args.hidden)),
+        ("AutoJoin", Value::from(true)),
+        ("EncryptionType", Value::from(encryption))
+    ]);
+    if let Some(eap) = args.enterprise.eap {
+        payload.push((String::from("EAPClientConfiguration"), eap_client_configuration(args, eap)?));
+    }else if let Some(psw) = args.psw.as_ref().filter(|_| encryption != "None") {
+        payload.push((String::from("Password"), Value::from(psw.as_str())));
+    }
+    Ok(Value::Dictionary(payload))
+}
+
+/// The unsigned profile of the network, as an XML property list.
+pub fn profile(args: &Args, identifier: &str, organization: Option<&str>) -> Result<String, MobileconfigError> {
+    let mut profile:Vec<(String, Value)> = entries([
+        ("PayloadType", Value::from("Configuration")),
+        ("PayloadVersion", Value::from(1)),
+        ("PayloadIdentifier", Value::from(identifier)),
+        ("PayloadUUID", Value::from(uuid(identifier))),
+        ("PayloadDisplayName", Value::from(format!("Wi-Fi {}", args.ssid))),
+        ("PayloadDescription", Value::from(format!("Joins the Wi-Fi network {}.", args.ssid))),
+        ("PayloadRemovalDisallowed", Value::from(false)),
+        ("PayloadContent", Value::Array(vec![wifi_payload(args, identifier)?]))
+    ]);
+    if let Some(organization) = organization {
+        profile.push((String::from("PayloadOrganization"), Value::from(organization)));
+    }
+    Ok(plist::write(&Value::Dictionary(profile)))
+}
+
+/// The profile wrapped in a PKCS#7 signature, in DER. openssl asks for the passphrase of the key if it has one.
+pub fn sign(profile: &str, signing: &Signing, shred: bool) -> Result<Vec<u8>, MobileconfigError> {
+    let mut directory = PrivateDir::create(shred)?;
+    let profile:std::path::PathBuf = directory.write("profile.mobileconfig", profile.as_bytes())?;
+    let mut command = Command::new("openssl");
+    command.args(["smime", "-sign", "-nodetach", "-outform", "DER"])
+        .arg("-signer").arg(signing.certificate)
+        .arg("-inkey").arg(signing.key)
+        .arg("-in").arg(&profile);
+    if let Some(chain) = signing.chain {
+        command.arg("-certfile").arg(chain);
+    }
+    let output = command.stdin(Stdio::inherit()).stdout(Stdio::piped()).stderr(Stdio::piped()).output()?;
+    if !output.status.success() {
+        return Err(MobileconfigError::Signing(String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    fn args(arguments: &[&str]) -> Args {
+        Args::parse_from(["wifiqr"].iter().chain(arguments))
+    }
+    #[test]
+    fn test_uuid() {
+        assert_eq!(uuid("wifiqr.home"), uuid("wifiqr.home"));
+        assert_ne!(uuid("wifiqr.home"), uuid("wifiqr.home.wifi"));
+        let uuid:String = uuid("wifiqr.home");
+        assert_eq!(uuid.split('-').map(str::len).collect::<Vec<usize>>(), [8, 4, 4, 4, 12]);
+        assert!(uuid[14..].starts_with('5') && "89AB".contains(&uuid[19..20]));
+    }
+    #[test]
+    fn test_profile() {
+        let home:Args = args(&["--ssid", "My Home", "--psw", "hunter22", "--sec", "wpa3", "--hidden"]);
+        assert_eq!(default_identifier(&home), "wifiqr.my-home");
+        let profile:String = profile(&home, "wifiqr.my-home", Some("ACME")).unwrap();
+        for line in ["<string>com.apple.wifi.managed</string>", "<key>SSID_STR</key>\n\t\t\t<string>My Home</string>",
+            "<key>HIDDEN_NETWORK</key>\n\t\t\t<true/>", "<key>EncryptionType</key>\n\t\t\t<string>WPA3</string>",
+            "<key>Password</key>\n\t\t\t<string>hunter22</string>", "<key>PayloadIdentifier</key>\n\t\t\t<string>wifiqr.my-home.wifi</string>",
+            "<key>PayloadOrganization</key>\n\t<string>ACME</string>"] {
+            assert!(profile.contains(line), "{} is missing from {}", line, profile);
+        }
+        let open:String = super::profile(&args(&["--ssid", "Cafe"]), "wifiqr.cafe", None).unwrap();
+        assert!(open.contains("<string>None</string>") && !open.contains("Password") && !open.contains("PayloadOrganization"));
+    }
+    #[test]
+    fn test_enterprise() {
+        let office:Args = args(&["--ssid", "Office", "--psw", "secret", "--eap", "ttls", "--identity", "alice", "--anonymous-identity", "anonymous", "--phase2", "mschapv2"]);
+        let profile:String = profile(&office, "wifiqr.office", None).unwrap();
+        for line in ["<key>AcceptEAPTypes</key>\n\t\t\t\t<array>\n\t\t\t\t\t<integer>21</integer>", "<key>UserName</key>\n\t\t\t\t<string>alice</string>",
+            "<key>UserPassword</key>\n\t\t\t\t<string>secret</string>", "<key>OuterIdentity</key>\n\t\t\t\t<string>anonymous</string>",
+            "<key>TTLSInnerAuthentication</key>\n\t\t\t\t<string>MSCHAPv2</string>", "<key>EncryptionType</key>\n\t\t\t<string>WPA2</string>"] {
+            assert!(profile.contains(line), "{} is missing from {}", line, profile);
+        }
+        assert!(!profile.contains("<key>Password</key>"));
+        let tls:Args = args(&["--ssid", "Office", "--eap", "tls", "--identity", "alice"]);
+        assert!(matches!(super::profile(&tls, "wifiqr.office", None), Err(MobileconfigError::Unsupported(_))));
+    }
+    #[test]
+    fn test_signing_options() {
+        assert!(Args::try_parse_from(["wifiqr", "--ssid", "Home", "export", "mobileconfig", "--certificate", "cert.pem"]).is_err());
+        assert!(Args::try_parse_from(["wifiqr", "--ssid", "Home", "export", "mobileconfig", "--certificate", "cert.pem", "--key", "key.pem"]).is_ok());
+    }
+}
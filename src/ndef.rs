@@ -0,0 +1,178 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Wi-Fi Simple Configuration (WSC) NDEF records, as read by Android and iOS when tapping an NFC tag.
+
+use crate::{resolve_security, Args, Security};
+
+/// MIME type of a WSC configuration token.
+const WSC_MIME_TYPE: &[u8] = b"application/vnd.wfa.wsc";
+
+const ATTR_VERSION: u16 = 0x104A;
+const ATTR_CREDENTIAL: u16 = 0x100E;
+const ATTR_NETWORK_INDEX: u16 = 0x1026;
+const ATTR_SSID: u16 = 0x1045;
+const ATTR_AUTH_TYPE: u16 = 0x1003;
+const ATTR_ENCRYPTION_TYPE: u16 = 0x100F;
+const ATTR_NETWORK_KEY: u16 = 0x1027;
+const ATTR_MAC_ADDRESS: u16 = 0x1020;
+const ATTR_VENDOR_EXTENSION: u16 = 0x1049;
+
+const AUTH_OPEN: u16 = 0x0001;
+const AUTH_WPA_PERSONAL: u16 = 0x0002;
+const AUTH_SHARED: u16 = 0x0004;
+const AUTH_WPA2_PERSONAL: u16 = 0x0020;
+
+const ENCRYPTION_NONE: u16 = 0x0001;
+const ENCRYPTION_WEP: u16 = 0x0002;
+const ENCRYPTION_TKIP: u16 = 0x0004;
+const ENCRYPTION_AES: u16 = 0x0008;
+
+/// WFA vendor extension carrying "Version2: 2.0".
+const WFA_VENDOR_EXTENSION: &[u8] = &[0x00, 0x37, 0x2A, 0x00, 0x01, 0x20];
+
+fn push_attribute(buffer: &mut Vec<u8>, attribute: u16, value: &[u8]) {
+    buffer.extend_from_slice(&attribute.to_be_bytes());
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// WSC has no dedicated SAE authentication type, so WPA3 networks are advertised as WPA2-Personal,
+/// which is what phones fall back to for transition-mode networks anyway.
+fn wsc_auth_and_encryption(sec: Option<Security>) -> (u16, u16) {
+    match sec {
+        None => (AUTH_OPEN, ENCRYPTION_NONE),
+        Some(Security::Wep) => (AUTH_SHARED, ENCRYPTION_WEP),
+        Some(Security::Wpa) => (AUTH_WPA_PERSONAL, ENCRYPTION_TKIP),
+        Some(Security::Wpa2 | Security::Wpa3 | Security::Wpa2Wpa3) => (AUTH_WPA2_PERSONAL, ENCRYPTION_AES)
+    }
+}
+
+/// Builds the WSC configuration token (the NDEF record payload) for the network.
+pub fn wsc_payload(args: &Args) -> Vec<u8> {
+    let (auth, encryption) = wsc_auth_and_encryption(resolve_security(args));
+    let psw:String = args.psw.clone().unwrap_or_default();
+
+    let mut credential:Vec<u8> = Vec::new();
+    push_attribute(&mut credential, ATTR_NETWORK_INDEX, &[0x01]);
+    push_attribute(&mut credential, ATTR_SSID, &crate::hex_ssid::bytes(args));
+    push_attribute(&mut credential, ATTR_AUTH_TYPE, &auth.to_be_bytes());
+    push_attribute(&mut credential, ATTR_ENCRYPTION_TYPE, &encryption.to_be_bytes());
+    push_attribute(&mut credential, ATTR_NETWORK_KEY, psw.as_bytes());
+    // The broadcast address tells the enrollee to accept any BSSID broadcasting the SSID.
+    push_attribute(&mut credential, ATTR_MAC_ADDRESS, &[0xFF; 6]);
+
+    let mut payload:Vec<u8> = Vec::new();
+    push_attribute(&mut payload, ATTR_VERSION, &[0x10]);
+    push_attribute(&mut payload, ATTR_CREDENTIAL, &credential);
+    push_attribute(&mut payload, ATTR_VENDOR_EXTENSION, WFA_VENDOR_EXTENSION);
+    payload
+}
+
+/// Wraps the WSC payload into a single-record NDEF message with a MIME type name format.
+pub fn wifi_message(args: &Args) -> Vec<u8> {
+    let payload:Vec<u8> = wsc_payload(args);
+    let mut message:Vec<u8> = Vec::new();
+    // MB | ME | TNF=0x02 (MIME media type), plus SR when the payload length fits into a byte.
+    if payload.len() <= u8::MAX as usize {
+        message.push(0xD2);
+        message.push(WSC_MIME_TYPE.len() as u8);
+        message.push(payload.len() as u8);
+    }else{
+        message.push(0xC2);
+        message.push(WSC_MIME_TYPE.len() as u8);
+        message.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    }
+    message.extend_from_slice(WSC_MIME_TYPE);
+    message.extend_from_slice(&payload);
+    message
+}
+
+/// Wraps an NDEF message into the NDEF Message TLV (plus terminator TLV) stored on Type 2 tags.
+pub fn type2_tlv(message: &[u8]) -> Vec<u8> {
+    let mut tlv:Vec<u8> = vec![0x03];
+    if message.len() < 0xFF {
+        tlv.push(message.len() as u8);
+    }else{
+        tlv.push(0xFF);
+        tlv.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    }
+    tlv.extend_from_slice(message);
+    tlv.push(0xFE);
+    tlv
+}
+
+/// The message as hex digits, 16 bytes a line, e.g. `D2 17 4D 61 70 ...`.
+pub fn hex_dump(message: &[u8]) -> String {
+    message.chunks(16).map(|line| line.iter().map(|x| format!("{:02X}", x)).collect::<Vec<String>>().join(" ") + "\n").collect()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    fn test_args(psw: Option<&str>, sec: Option<Security>) -> Args {
+        crate::tests::network("Home", psw, sec)
+    }
+    #[test]
+    fn test_wsc_payload_wpa2() {
+        let payload = wsc_payload(&test_args(Some("password"), None));
+        let mut credential: Vec<u8> = vec![
+            0x10, 0x26, 0x00, 0x01, 0x01,
+            0x10, 0x45, 0x00, 0x04, b'H', b'o', b'm', b'e',
+            0x10, 0x03, 0x00, 0x02, 0x00, 0x20,
+            0x10, 0x0F, 0x00, 0x02, 0x00, 0x08,
+            0x10, 0x27, 0x00, 0x08
+        ];
+        credential.extend_from_slice(b"password");
+        credential.extend_from_slice(&[0x10, 0x20, 0x00, 0x06, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(&payload[..5], &[0x10, 0x4A, 0x00, 0x01, 0x10]);
+        assert_eq!(&payload[5..9], &[0x10, 0x0E, 0x00, credential.len() as u8]);
+        assert_eq!(&payload[9..9 + credential.len()], &credential[..]);
+        assert_eq!(&payload[9 + credential.len()..], &[0x10, 0x49, 0x00, 0x06, 0x00, 0x37, 0x2A, 0x00, 0x01, 0x20]);
+    }
+    #[test]
+    fn test_wsc_open_network() {
+        let payload = wsc_payload(&test_args(None, None));
+        // Auth type open and encryption type none.
+        assert!(payload.windows(6).any(|x| x == [0x10, 0x03, 0x00, 0x02, 0x00, 0x01]));
+        assert!(payload.windows(6).any(|x| x == [0x10, 0x0F, 0x00, 0x02, 0x00, 0x01]));
+        assert!(payload.windows(4).any(|x| x == [0x10, 0x27, 0x00, 0x00]));
+    }
+    #[test]
+    fn test_ndef_record_header() {
+        let args = test_args(Some("password"), Some(Security::Wpa2));
+        let message = wifi_message(&args);
+        let payload = wsc_payload(&args);
+        assert_eq!(message[0], 0xD2);
+        assert_eq!(message[1] as usize, WSC_MIME_TYPE.len());
+        assert_eq!(message[2] as usize, payload.len());
+        assert_eq!(&message[3..3 + WSC_MIME_TYPE.len()], WSC_MIME_TYPE);
+        assert_eq!(&message[3 + WSC_MIME_TYPE.len()..], &payload[..]);
+    }
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(hex_dump(&[0xD2, 0x17, 0x0A]), "D2 17 0A\n");
+        assert_eq!(hex_dump(&[0; 17]).lines().map(str::len).collect::<Vec<usize>>(), [47, 2]);
+    }
+    #[test]
+    fn test_type2_tlv() {
+        assert_eq!(type2_tlv(&[0xAA, 0xBB]), vec![0x03, 0x02, 0xAA, 0xBB, 0xFE]);
+        let long_message = vec![0u8; 300];
+        let tlv = type2_tlv(&long_message);
+        assert_eq!(&tlv[..4], &[0x03, 0xFF, 0x01, 0x2C]);
+        assert_eq!(tlv.len(), 300 + 5);
+    }
+}
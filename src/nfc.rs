@@ -0,0 +1,117 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Writing NDEF messages to NFC Forum Type 2 tags (NTAG21x) through a PC/SC reader.
+
+use std::fmt;
+
+/// First user memory page of NTAG21x tags, which is where the NDEF TLV starts.
+#[cfg(feature = "nfc")]
+const FIRST_USER_PAGE: u8 = 4;
+/// Page holding the capability container.
+#[cfg(feature = "nfc")]
+const CAPABILITY_CONTAINER_PAGE: u8 = 3;
+
+#[derive(Debug)]
+#[cfg_attr(not(feature = "nfc"), allow(dead_code))]
+pub enum NfcError{
+    #[cfg(not(feature = "nfc"))]
+    Unsupported,
+    NoReader,
+    ReaderNotFound(String),
+    #[cfg(feature = "nfc")]
+    Pcsc(pcsc::Error),
+    Apdu(u8, u8),
+    NotNdefFormatted,
+    TagTooSmall{ needed: usize, available: usize }
+}
+impl fmt::Display for NfcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "nfc"))]
+            NfcError::Unsupported => write!(f, "wifiqr was built without NFC support, rebuild it with `--features nfc`"),
+            NfcError::NoReader => write!(f, "no PC/SC reader is connected"),
+            NfcError::ReaderNotFound(name) => write!(f, "no PC/SC reader matching '{}' was found", name),
+            #[cfg(feature = "nfc")]
+            NfcError::Pcsc(err) => write!(f, "PC/SC error: {}", err),
+            NfcError::Apdu(sw1, sw2) => write!(f, "the reader rejected the command with status {:02X}{:02X}", sw1, sw2),
+            NfcError::NotNdefFormatted => write!(f, "the tag is not formatted for NDEF (capability container missing)"),
+            NfcError::TagTooSmall{ needed, available } => write!(f, "the NDEF message needs {} bytes, but the tag only has {}", needed, available)
+        }
+    }
+}
+#[cfg(feature = "nfc")]
+impl From<pcsc::Error> for NfcError {
+    fn from(err: pcsc::Error) -> Self {
+        NfcError::Pcsc(err)
+    }
+}
+
+/// Lists the names of all connected PC/SC readers.
+#[cfg(feature = "nfc")]
+fn reader_names(ctx: &pcsc::Context) -> Result<Vec<std::ffi::CString>, NfcError> {
+    let mut buffer:Vec<u8> = vec![0; ctx.list_readers_len()?];
+    Ok(ctx.list_readers(&mut buffer)?.map(|x| x.to_owned()).collect())
+}
+
+/// Sends an APDU and checks for the `90 00` status word, returning the response data.
+#[cfg(feature = "nfc")]
+fn transmit(card: &pcsc::Card, apdu: &[u8]) -> Result<Vec<u8>, NfcError> {
+    let mut buffer = [0; pcsc::MAX_BUFFER_SIZE];
+    let response:&[u8] = card.transmit(apdu, &mut buffer)?;
+    match response {
+        [data @ .., 0x90, 0x00] => Ok(data.to_vec()),
+        [.., sw1, sw2] => Err(NfcError::Apdu(*sw1, *sw2)),
+        _ => Err(NfcError::Apdu(0, 0))
+    }
+}
+
+/// Uses the PC/SC storage card pseudo-APDUs (READ/UPDATE BINARY), which readers such as the
+/// ACR122U translate into the native Type 2 READ and WRITE commands.
+#[cfg(feature = "nfc")]
+pub fn write_tlv(reader: Option<&str>, tlv: &[u8]) -> Result<(), NfcError> {
+    let ctx = pcsc::Context::establish(pcsc::Scope::User)?;
+    let readers = reader_names(&ctx)?;
+    let reader = match reader {
+        Some(name) => readers.iter().find(|x| x.to_string_lossy().contains(name)).ok_or_else(|| NfcError::ReaderNotFound(name.to_string()))?,
+        None => readers.first().ok_or(NfcError::NoReader)?
+    };
+    let card = ctx.connect(reader, pcsc::ShareMode::Exclusive, pcsc::Protocols::ANY)?;
+
+    // READ BINARY returns 16 bytes starting at the capability container page.
+    let capability_container:Vec<u8> = transmit(&card, &[0xFF, 0xB0, 0x00, CAPABILITY_CONTAINER_PAGE, 0x04])?;
+    if capability_container.len() < 4 || capability_container[0] != 0xE1 {
+        return Err(NfcError::NotNdefFormatted);
+    }
+    let available:usize = capability_container[2] as usize * 8;
+    if tlv.len() > available {
+        return Err(NfcError::TagTooSmall{ needed: tlv.len(), available });
+    }
+
+    for (i, chunk) in tlv.chunks(4).enumerate() {
+        let mut page = [0u8; 4];
+        page[..chunk.len()].copy_from_slice(chunk);
+        let mut apdu:Vec<u8> = vec![0xFF, 0xD6, 0x00, FIRST_USER_PAGE + i as u8, 0x04];
+        apdu.extend_from_slice(&page);
+        transmit(&card, &apdu)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "nfc"))]
+pub fn write_tlv(_reader: Option<&str>, _tlv: &[u8]) -> Result<(), NfcError> {
+    Err(NfcError::Unsupported)
+}
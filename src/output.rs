@@ -0,0 +1,540 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Writing the QR-Code to a file with `--output`, in place of printing it to the terminal. The format is told by
+//! the extension of the file, unless given with `--format`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use qrcode_generator::QRCodeError;
+
+use crate::error::ErrorKind;
+use crate::i18n::Language;
+use crate::branding::Branding;
+use crate::color::{self, Color};
+use crate::console::{Invert, Render};
+use crate::layout::{self, CardFormat, LabelPreset};
+use crate::logo::{self, Area, Logo, LogoError};
+use crate::micro::{self, MicroError};
+use crate::symbol::{self, SymbolError};
+use crate::{alt_text, assemble_qr_string, clipboard, diagnostics, eps, html, image_caption, ndef, pbm, pdf, raster, report, svg, wallpaper, with_border, Args, QUIET_ZONE};
+
+/// How the QR-Code is drawn in the terminal, or written to a file in its place.
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct Options{
+    /// How the modules are drawn in the terminal. Defaults to an image in terminals known to show them, and
+    /// otherwise blocks, or half blocks or braille if those would be wider than the terminal.
+    #[clap(arg_enum, long, value_parser, env = "WIFIQR_RENDER")]
+    pub render: Option<Render>,
+
+    /// Draw the light modules instead of the dark ones in the terminal, for dark themes, e.g. `--invert=auto`.
+    /// `auto` inverts if the terminal says its background is dark, unless colors are given.
+    #[clap(arg_enum, long, value_parser, default_value_t = Invert::Auto, default_missing_value = "always", min_values = 0, require_equals = true, env = "WIFIQR_INVERT")]
+    pub invert: Invert,
+
+    /// Color of the dark modules in the terminal and in PNGs and SVGs, as #RRGGBB, e.g. the color of a brand. Black
+    /// if only `--bg` is given.
+    #[clap(long, visible_alias = "color", value_parser, env = "WIFIQR_FG")]
+    pub fg: Option<Color>,
+
+    /// Color of the light modules and the border in the terminal and in PNGs and SVGs, as #RRGGBB. White if only
+    /// `--fg` is given.
+    #[clap(long, visible_alias = "background", value_parser, env = "WIFIQR_BG")]
+    pub bg: Option<Color>,
+
+    /// Write the QR-Code to the file instead of printing it to the terminal, e.g. `wifi.png`, or with `-` to stdout
+    /// in the format of `--format`. Only used without a command.
+    #[clap(short = 'o', long = "output", value_parser)]
+    pub path: Option<PathBuf>,
+
+    /// Format of the file. Told by its extension if omitted. Given without `--output`, the file is written to stdout;
+    /// the defaults of the environment variable and the config only apply to `--output`.
+    #[clap(arg_enum, long, value_parser, env = "WIFIQR_FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Width and height of PNG images in pixels, including the border around the code. Ignored with
+    /// `--module-size` and `--dpi`.
+    #[clap(long, value_parser, default_value_t = 512, env = "WIFIQR_SIZE")]
+    pub size: usize,
+
+    /// Width and height of EPS files in millimeters, including the border around the code, which the bounding box
+    /// is set to, and of PNG images with `--dpi`.
+    #[clap(long, value_parser = parse_size_mm, default_value_t = eps::DEFAULT_SIZE_MM, env = "WIFIQR_SIZE_MM")]
+    pub size_mm: f32,
+
+    /// Resolution of PNG images in dots per inch, which they are tagged with, so they print `--size-mm` wide without
+    /// being scaled, e.g. `--dpi 300 --size-mm 40`.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..=10000), conflicts_with = "label", env = "WIFIQR_DPI")]
+    pub dpi: Option<u32>,
+
+    /// Width of the light border around the code in modules, in the terminal and in images. Readers expect a quiet
+    /// zone of at least 4.
+    #[clap(long, value_parser, default_value_t = QUIET_ZONE, env = "WIFIQR_BORDER")]
+    pub border: usize,
+
+    /// Size of a module: in pixels in PNGs, SVGs and images in the terminal, and the times each module is repeated
+    /// in characters in the terminal. Without it, SVGs scale to wherever they're placed.
+    #[clap(long, value_parser, env = "WIFIQR_MODULE_SIZE")]
+    pub module_size: Option<u32>,
+
+    /// Size of the card of PDFs, which shows the network and password beneath the QR-Code.
+    #[clap(arg_enum, long, value_parser, default_value_t = CardFormat::A6, env = "WIFIQR_CARD")]
+    pub card: CardFormat,
+
+    /// Label of a label printer to size PNGs and PDFs for, in place of `--size` and `--card`: PDFs get a page the
+    /// size of the label, PNGs its size at 300 dpi, for the driver to print without scaling. Also used by `print`.
+    #[clap(arg_enum, long, value_parser, conflicts_with_all = &["logo", "micro"])]
+    pub label: Option<LabelPreset>,
+
+    /// Language of the texts of cards, layouts and HTML pages, such as "Network" and "Scan to join".
+    #[clap(arg_enum, long, value_parser, default_value_t = Language::En, env = "WIFIQR_LANG")]
+    pub lang: Language,
+
+    /// Title of HTML pages, in place of "Wi-Fi", with `{ssid}` replaced by the SSID, e.g. `--page-title "Welcome to
+    /// {ssid}"`.
+    #[clap(long, value_parser)]
+    pub page_title: Option<String>,
+
+    /// Instructions beneath the network on HTML pages, in place of "Scan to join", with `{ssid}` replaced by the SSID.
+    #[clap(long, value_parser)]
+    pub instructions: Option<String>,
+
+    /// Logo placed in the center of PNGs and SVGs, on a light square. Raises the ECC-Level to high, whose error
+    /// correction makes up for the modules it covers. SVG logos are inlined into SVGs as they are, not rasterized.
+    #[clap(long, value_parser)]
+    pub logo: Option<PathBuf>,
+
+    /// Width of the logo in percent of the width of the code. Fails if it would cover more modules than the
+    /// ECC-Level can restore.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=50), default_value_t = logo::DEFAULT_SIZE, requires = "logo")]
+    pub logo_size: u8,
+
+    /// Print the QR-Code as a JSON document with the payload, version and ECC-Level instead of drawing it, for
+    /// scripts. Only used without `--output` and a command.
+    #[clap(arg_enum, long, value_parser, default_value_t = report::Format::Terminal, conflicts_with = "path")]
+    pub output_format: report::Format,
+
+    /// Add the modules to the JSON document of `--output-format json`, as rows of 1s and 0s.
+    #[clap(long, action)]
+    pub with_matrix: bool,
+
+    /// Encode the network as a Micro QR symbol, 17 modules wide with a quiet zone of 2, for tiny labels. It holds at
+    /// most 15 bytes at ECC-Level low, e.g. `WIFI:S:Cafe;;` for an open network, so what readers assume when
+    /// missing is left out of the payload. Many phone cameras can't read Micro QR.
+    #[clap(long, action, conflicts_with_all = &["logo", "qr-version", "min-version", "mask"])]
+    pub micro: bool,
+
+    /// Copy the QR-Code as an image to the clipboard instead of printing it, or the payload as text with
+    /// `--clipboard=payload`, to paste it into a chat or a document. The image is drawn like PNGs are.
+    #[clap(arg_enum, long, value_parser, default_missing_value = "image", min_values = 0, require_equals = true, conflicts_with_all = &["path", "output-format"])]
+    pub clipboard: Option<clipboard::Content>,
+
+    #[clap(flatten)]
+    pub log: diagnostics::Options
+}
+impl Options {
+    /// Where the QR-Code is written to instead of the terminal: the file of `--output`, or stdout if only `--format`
+    /// is given.
+    pub fn destination(&self) -> Option<&Path> {
+        self.path.as_deref().or(self.format.map(|_| Path::new("-")))
+    }
+    /// Width and height of PNG images in pixels, `--size-mm` at `--dpi` if that is given.
+    fn pixels(&self) -> usize {
+        match self.dpi {
+            Some(dpi) => (self.size_mm / 25.4 * dpi as f32).round() as usize,
+            None => self.size
+        }
+    }
+    /// The colors of the dark and light modules.
+    pub fn colors(&self) -> (Color, Color) {
+        (self.fg.unwrap_or(Color::BLACK), self.bg.unwrap_or(Color::WHITE))
+    }
+    /// Why readers may fail to scan a code in the colors given, if they may.
+    pub fn color_warning(&self) -> Option<String> {
+        if self.fg.is_none() && self.bg.is_none() {
+            return None;
+        }
+        let (dark, light) = self.colors();
+        if dark.luminance() >= light.luminance() {
+            Some(format!("the dark modules ({}) aren't darker than the light ones ({}), and many readers only scan dark codes on light backgrounds", dark, light))
+        }else if dark.contrast(light) < color::RECOMMENDED_CONTRAST {
+            Some(format!("the colors of the QR-Code have a contrast of only {:.1}:1, readers may fail to tell the modules apart below {}:1", dark.contrast(light), color::RECOMMENDED_CONTRAST))
+        }else{
+            None
+        }
+    }
+}
+/// Checks that the size of EPS files is positive, so the code can be drawn.
+fn parse_size_mm(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(size) if size > 0.0 && size.is_finite() => Ok(size),
+        _ => Err(String::from("expected a size in millimeters above 0, e.g. 40"))
+    }
+}
+impl Default for Options {
+    fn default() -> Self {
+        Options{ render: None, invert: Invert::Auto, fg: None, bg: None, path: None, format: None, size: 512, size_mm: eps::DEFAULT_SIZE_MM, dpi: None, border: QUIET_ZONE, module_size: None, card: CardFormat::A6, label: None, lang: Language::En, page_title: None, instructions: None, logo: None, logo_size: logo::DEFAULT_SIZE, output_format: report::Format::Terminal, with_matrix: false, micro: false, clipboard: None, log: diagnostics::Options::default() }
+    }
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat{
+    Png,
+    /// The image of the PNG as a TIFF, for print workflows and archives that want it
+    Tiff,
+    /// The image of the PNG as a GIF, for old or embedded software that only shows GIFs
+    Gif,
+    Svg,
+    /// A printable card
+    Pdf,
+    /// A self-contained page with the network and password, for printing from a browser or emailing
+    Html,
+    /// Encapsulated PostScript, for print shops
+    Eps,
+    /// A NetPBM bitmap, a pixel per module unless `--module-size` is given, for piping into image tools
+    Pbm,
+    /// The NDEF message of a Wi-Fi Simple Configuration record, as `write-nfc` writes it to tags
+    Ndef,
+    /// The NDEF message in hex, 16 bytes a line, for apps writing tags from hex
+    NdefHex
+}
+impl OutputFormat {
+    /// The format of the file, by its extension.
+    pub fn detect(path: &Path) -> Option<Self> {
+        match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "gif" => Some(OutputFormat::Gif),
+            "svg" => Some(OutputFormat::Svg),
+            "pdf" => Some(OutputFormat::Pdf),
+            "html" | "htm" => Some(OutputFormat::Html),
+            "eps" => Some(OutputFormat::Eps),
+            "pbm" => Some(OutputFormat::Pbm),
+            "ndef" => Some(OutputFormat::Ndef),
+            // The NDEF message is the only file written as hex, e.g. `entrance.ndef.hex`.
+            "hex" => Some(OutputFormat::NdefHex),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OutputError{
+    UnsupportedFormat(PathBuf),
+    /// Micro QR symbols are only written as images, SVGs, EPS and PBM files.
+    MicroFormat(OutputFormat),
+    /// The NDEF record of a network given `--eap`, which can only hold a password.
+    EnterpriseRecord,
+    Micro(MicroError),
+    Symbol(SymbolError),
+    Logo(LogoError),
+    QrCode(PathBuf, QRCodeError),
+    Io(PathBuf, std::io::Error)
+}
+impl OutputError {
+    /// What kind of failure this is, for the exit code.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            OutputError::UnsupportedFormat(_) | OutputError::MicroFormat(_) | OutputError::EnterpriseRecord | OutputError::QrCode(_, QRCodeError::ImageSizeTooSmall | QRCodeError::ImageSizeTooLarge) => ErrorKind::Usage,
+            OutputError::QrCode(_, QRCodeError::DataTooLong) | OutputError::Micro(_) | OutputError::Symbol(_) => ErrorKind::Encoding,
+            OutputError::Logo(LogoError::Image(..) | LogoError::Svg(..)) => ErrorKind::Input,
+            OutputError::Logo(LogoError::TooLarge(..) | LogoError::VectorOnly(_)) => ErrorKind::Usage,
+            OutputError::QrCode(..) | OutputError::Io(..) => ErrorKind::Output
+        }
+    }
+}
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputError::UnsupportedFormat(path) => write!(f, "can't tell what to write to {}, use .png, .tiff, .gif, .svg, .eps, .pbm, .pdf, .html, .ndef or .hex, or pass --format", path.display()),
+            OutputError::MicroFormat(format) => write!(f, "Micro QR symbols are only written as PNGs, TIFFs, GIFs, SVGs, EPS and PBM files, not as {}", format!("{:?}", format).to_uppercase()),
+            OutputError::EnterpriseRecord => write!(f, "NFC records can't carry the credentials of enterprise networks"),
+            OutputError::Micro(err) => write!(f, "{}", err),
+            OutputError::Symbol(err) => write!(f, "{}", err),
+            OutputError::Logo(err) => write!(f, "{}", err),
+            OutputError::QrCode(path, QRCodeError::ImageSizeTooSmall) => write!(f, "--size is too small to draw the modules of {}", path.display()),
+            OutputError::QrCode(path, err) => write!(f, "{}: {}", path.display(), err),
+            OutputError::Io(path, err) => write!(f, "{}: {}", path.display(), err)
+        }
+    }
+}
+impl From<MicroError> for OutputError {
+    fn from(err: MicroError) -> Self {
+        OutputError::Micro(err)
+    }
+}
+impl From<SymbolError> for OutputError {
+    fn from(err: SymbolError) -> Self {
+        OutputError::Symbol(err)
+    }
+}
+impl From<LogoError> for OutputError {
+    fn from(err: LogoError) -> Self {
+        OutputError::Logo(err)
+    }
+}
+
+/// The symbol with a light border of `border` modules, `module_pixels` per module.
+pub fn image(matrix: &[Vec<bool>], border: usize, module_pixels: u32, dark: Color, light: Color) -> RgbaImage {
+    let matrix:Vec<Vec<bool>> = with_border(matrix, border);
+    let size:u32 = matrix.len() as u32 * module_pixels;
+    RgbaImage::from_fn(size, size, |x, y| {
+        if matrix[(y / module_pixels) as usize][(x / module_pixels) as usize] {dark.to_rgba()}else{light.to_rgba()}
+    })
+}
+
+/// The image of a PNG: `--module-size` pixels per module, or otherwise the largest whole number of pixels per module
+/// that fits into `--size`, or `--size-mm` at `--dpi`, centered on a canvas that size. `None` if not even one pixel per module fits. A logo is
+/// drawn into its area, whose modules are expected to be cleared.
+fn png_image(matrix: &[Vec<bool>], options: &Options, logo: Option<(&RgbaImage, Area)>) -> Option<RgbaImage> {
+    let (dark, light) = options.colors();
+    let modules:usize = matrix.len() + 2 * options.border;
+    let module_pixels:u32 = options.module_size.unwrap_or((options.pixels() / modules) as u32);
+    if module_pixels == 0 {
+        return None;
+    }
+    let mut code:RgbaImage = image(matrix, options.border, module_pixels, dark, light);
+    if let Some((logo, area)) = logo {
+        // The margin of the area stays light.
+        let (start, side) = ((options.border + area.start + 1) as u32 * module_pixels, (area.side - 2) as u32 * module_pixels);
+        let logo:RgbaImage = logo::fitted(logo, side);
+        image::imageops::overlay(&mut code, &logo, (start + (side - logo.width()) / 2) as i64, (start + (side - logo.height()) / 2) as i64);
+    }
+    if options.module_size.is_some() {
+        return Some(code);
+    }
+    let mut canvas:RgbaImage = RgbaImage::from_pixel(options.pixels() as u32, options.pixels() as u32, light.to_rgba());
+    let offset:i64 = ((options.pixels() as u32 - code.width()) / 2) as i64;
+    image::imageops::overlay(&mut canvas, &code, offset, offset);
+    Some(canvas)
+}
+
+/// The image as a PNG, with a pHYs chunk telling its resolution in dots per inch, if given, which printers and layout
+/// programs size it by.
+fn encode_png(image: &RgbaImage, dpi: Option<u32>) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png:Vec<u8> = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    if let Some(dpi) = dpi {
+        // Pixels per meter on both axes, and 1 for the meter as the unit.
+        let pixels_per_meter:[u8; 4] = ((dpi as f64 / 0.0254).round() as u32).to_be_bytes();
+        let mut dimensions:Vec<u8> = [pixels_per_meter, pixels_per_meter].concat();
+        dimensions.push(1);
+        writer.write_chunk(png::chunk::pHYs, &dimensions)?;
+    }
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// The pages of the card, or of the label of `--label`.
+pub fn document(args: &Args, matrix: &[Vec<bool>], branding: &Branding) -> Vec<pdf::Page> {
+    match args.output.label {
+        Some(label) => layout::label(args, matrix, branding, label),
+        None => layout::card(args, matrix, branding, args.output.card)
+    }
+}
+
+/// Writes the QR-Code of the network to the file, or to stdout if it is `-`. Only cards and pages are laid out with
+/// the branding.
+pub fn write(args: &Args, branding: &Branding, path: &Path) -> Result<(), OutputError> {
+    let format:OutputFormat = args.output.format.or_else(|| OutputFormat::detect(path)).ok_or_else(|| OutputError::UnsupportedFormat(path.to_path_buf()))?;
+    let contents:Vec<u8> = contents(args, branding, format, path)?;
+    if path == Path::new("-") {
+        use std::io::Write;
+        let mut stdout = std::io::stdout().lock();
+        return stdout.write_all(&contents).and_then(|_| stdout.flush()).map_err(|err| OutputError::Io(PathBuf::from("stdout"), err));
+    }
+    std::fs::write(path, contents).map_err(|err| OutputError::Io(path.to_path_buf(), err))
+}
+
+/// The file of the format with the QR-Code of the network, named `path` in errors.
+pub fn contents(args: &Args, branding: &Branding, format: OutputFormat, path: &Path) -> Result<Vec<u8>, OutputError> {
+    // The record holds the credentials themselves, not the code of the payload.
+    match format {
+        OutputFormat::Ndef | OutputFormat::NdefHex if args.enterprise.eap.is_some() => return Err(OutputError::EnterpriseRecord),
+        OutputFormat::Ndef => return Ok(ndef::wifi_message(args)),
+        OutputFormat::NdefHex => return Ok(ndef::hex_dump(&ndef::wifi_message(args)).into_bytes()),
+        _ => ()
+    }
+    let payload:String = assemble_qr_string(args);
+    let matrix:Vec<Vec<bool>> = match args.output.micro {
+        true if !matches!(format, OutputFormat::Png | OutputFormat::Tiff | OutputFormat::Gif | OutputFormat::Svg | OutputFormat::Eps | OutputFormat::Pbm) => return Err(OutputError::MicroFormat(format)),
+        true => micro::encode(&payload, args.ecc)?,
+        false => symbol::encode(&payload, args.ecc, &args.symbol)?
+    };
+    rendered(args, branding, format, matrix, path)
+}
+
+/// The file of the format with the symbol, drawn with the border, size, colors and logo of the output options, named
+/// `path` in errors. The symbol may have been encoded from another payload than the network's own.
+pub fn rendered(args: &Args, branding: &Branding, format: OutputFormat, matrix: Vec<Vec<bool>>, path: &Path) -> Result<Vec<u8>, OutputError> {
+    let logo:Option<(Logo, Area)> = match &args.output.logo {
+        Some(logo) if matches!(format, OutputFormat::Png | OutputFormat::Tiff | OutputFormat::Gif | OutputFormat::Svg) =>
+            Some((logo::load(logo, format == OutputFormat::Svg)?, logo::area(&matrix, args.output.logo_size, args.ecc)?)),
+        _ => None
+    };
+    let matrix:Vec<Vec<bool>> = match logo {
+        Some((_, area)) => logo::cleared(&matrix, area),
+        None => matrix
+    };
+    let logo:Option<(&Logo, Area)> = logo.as_ref().map(|(logo, area)| (logo, *area));
+    Ok(match format {
+        OutputFormat::Png | OutputFormat::Tiff | OutputFormat::Gif => {
+            let image:Option<RgbaImage> = match args.output.label {
+                Some(label) => Some(wallpaper::label(&matrix, image_caption(args, branding, false), label.pixels())),
+                None => png_image(&matrix, &args.output, logo.map(|(logo, area)| match logo {
+                    Logo::Raster(image) => (image, area),
+                    Logo::Vector(_) => unreachable!("SVG logos are only loaded for SVGs")
+                }))
+            };
+            let image:RgbaImage = image.ok_or_else(|| OutputError::QrCode(path.to_path_buf(), QRCodeError::ImageSizeTooSmall))?;
+            let image_error = |err: image::ImageError| OutputError::QrCode(path.to_path_buf(), QRCodeError::ImageError(err));
+            match format {
+                OutputFormat::Tiff => raster::encode_tiff(&image, false).map_err(image_error)?,
+                OutputFormat::Gif => raster::encode_gif(&image).map_err(image_error)?,
+                _ => {
+                    // Labels are drawn at the resolution of label printers.
+                    let dpi:Option<u32> = if args.output.label.is_some() {Some(layout::LABEL_DPI)}else{args.output.dpi};
+                    let encoding_error = |err: png::EncodingError| image::ImageError::Encoding(image::error::EncodingError::new(image::ImageFormat::Png.into(), err));
+                    encode_png(&image, dpi).map_err(|err| image_error(encoding_error(err)))?
+                }
+            }
+        },
+        OutputFormat::Svg => {
+            let (dark, light) = args.output.colors();
+            svg::document(&matrix, &alt_text::describe(args), args.output.border, args.output.module_size, dark, light, logo).into_bytes()
+        },
+        OutputFormat::Eps => {
+            let (dark, light) = args.output.colors();
+            eps::document(&matrix, args.output.border, args.output.size_mm, dark, light).into_bytes()
+        },
+        OutputFormat::Pbm => pbm::document(&matrix, args.output.border, args.output.module_size.unwrap_or(1) as usize),
+        OutputFormat::Pdf => pdf::write_document(&document(args, &matrix, branding)),
+        OutputFormat::Html => html::page(args, &matrix, branding).into_bytes(),
+        OutputFormat::Ndef | OutputFormat::NdefHex => unreachable!("{:?} is written without the code", format)
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_write() {
+        let mut args = Args{ output: Options{ size: 300, ..Options::default() }, ..crate::tests::network("Home", Some("secret123"), None) };
+        let path:PathBuf = std::env::temp_dir().join(format!("wifiqr-output-{}.png", std::process::id()));
+        write(&args, &Branding::default(), &path).unwrap();
+        assert_eq!(image::image_dimensions(&path).unwrap(), (300, 300));
+        args.output.label = Some(LabelPreset::Dymo99014);
+        write(&args, &Branding::default(), &path).unwrap();
+        // 54x101mm at 300 dpi.
+        assert_eq!(image::image_dimensions(&path).unwrap(), (638, 1193));
+        args.output.label = None;
+        args.output.dpi = Some(300);
+        write(&args, &Branding::default(), &path).unwrap();
+        // 40mm at 300 dpi, tagged with 11811 pixels per meter.
+        let reader = png::Decoder::new(std::fs::File::open(&path).unwrap()).read_info().unwrap();
+        let dimensions:png::PixelDimensions = reader.info().pixel_dims.unwrap();
+        assert_eq!((reader.info().width, dimensions.xppu, dimensions.yppu, dimensions.unit), (472, 11811, 11811, png::Unit::Meter));
+        args.output.dpi = None;
+        std::fs::remove_file(&path).unwrap();
+        args.output.format = Some(OutputFormat::Svg);
+        args.output.module_size = Some(8);
+        write(&args, &Branding::default(), &path).unwrap();
+        let svg:String = std::fs::read_to_string(&path).unwrap();
+        // 25 modules of version 2 and the quiet zone.
+        assert!(svg.contains("width=\"264\" height=\"264\" viewBox=\"0 0 33 33\""));
+        args.output.format = Some(OutputFormat::Eps);
+        args.output.size_mm = 50.0;
+        write(&args, &Branding::default(), &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("%%BoundingBox: 0 0 142 142\n"));
+        args.output.format = Some(OutputFormat::Pbm);
+        write(&args, &Branding::default(), &path).unwrap();
+        // 33 modules of 8 pixels, a row of 264 pixels is 33 bytes.
+        assert_eq!(std::fs::read(&path).unwrap().len(), "P4\n264 264\n".len() + 264 * 33);
+        args.output.format = Some(OutputFormat::Tiff);
+        write(&args, &Branding::default(), &path).unwrap();
+        assert_eq!(image::load_from_memory(&std::fs::read(&path).unwrap()).unwrap().width(), 264);
+        args.output.format = Some(OutputFormat::Gif);
+        write(&args, &Branding::default(), &path).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"GIF89a"));
+        args.output.format = Some(OutputFormat::Pdf);
+        write(&args, &Branding::default(), &path).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"%PDF-"));
+        args.output.format = Some(OutputFormat::NdefHex);
+        write(&args, &Branding::default(), &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("D2 17 "));
+        args.enterprise = crate::eap::Enterprise{ eap: Some(crate::eap::EapMethod::Peap), identity: Some(String::from("alice")), ..crate::eap::Enterprise::default() };
+        assert!(matches!(write(&args, &Branding::default(), &path), Err(OutputError::EnterpriseRecord)));
+        args.enterprise = crate::eap::Enterprise::default();
+        std::fs::remove_file(&path).unwrap();
+        args.output.format = None;
+        assert_eq!(OutputFormat::detect(Path::new("entrance.NDEF")), Some(OutputFormat::Ndef));
+        assert_eq!(OutputFormat::detect(Path::new("wifi.tif")), Some(OutputFormat::Tiff));
+        assert_eq!(OutputFormat::detect(Path::new("entrance.ndef.hex")), Some(OutputFormat::NdefHex));
+        assert_eq!(OutputFormat::detect(Path::new("wifi.gif")), Some(OutputFormat::Gif));
+        assert_eq!(write(&args, &Branding::default(), Path::new("wifi.bmp")).unwrap_err().kind(), ErrorKind::Usage);
+        args.output.module_size = None;
+        args.output.size = 4;
+        assert_eq!(write(&args, &Branding::default(), &path).unwrap_err().kind(), ErrorKind::Usage);
+    }
+    #[test]
+    fn test_destination() {
+        assert_eq!(Options::default().destination(), None);
+        assert_eq!(Options{ format: Some(OutputFormat::NdefHex), ..Options::default() }.destination(), Some(Path::new("-")));
+        assert_eq!(Options{ path: Some(PathBuf::from("wifi.svg")), format: Some(OutputFormat::Svg), ..Options::default() }.destination(), Some(Path::new("wifi.svg")));
+    }
+    #[test]
+    fn test_image() {
+        let image = image(&[vec![true]], 2, 3, Color::BLACK, Color::WHITE);
+        assert_eq!(image.width(), 15);
+        assert_eq!(image.get_pixel(7, 7).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 0).0, [255, 255, 255, 255]);
+    }
+    #[test]
+    fn test_png_image() {
+        let matrix:Vec<Vec<bool>> = vec![vec![true; 21]; 21];
+        // 29 modules with the quiet zone fit 3 times into 100 pixels, with 6 pixels left around them.
+        let image = png_image(&matrix, &Options{ size: 100, ..Options::default() }, None).unwrap();
+        assert_eq!(image.dimensions(), (100, 100));
+        assert_eq!(image.get_pixel(6 + 12, 6 + 12).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(6 + 11, 6 + 12).0, [255, 255, 255, 255]);
+        let image = png_image(&matrix, &Options{ border: 0, module_size: Some(2), ..Options::default() }, None).unwrap();
+        assert_eq!(image.dimensions(), (42, 42));
+    }
+    #[test]
+    fn test_color_warning() {
+        assert_eq!(Options::default().color_warning(), None);
+        assert_eq!(Options{ fg: Some(Color::new(0x1e, 0x3c, 0x8c)), ..Options::default() }.color_warning(), None);
+        assert!(Options{ fg: Some(Color::new(0x99, 0x99, 0x99)), ..Options::default() }.color_warning().unwrap().contains("contrast of only 2.8:1"));
+        assert!(Options{ fg: Some(Color::WHITE), bg: Some(Color::BLACK), ..Options::default() }.color_warning().unwrap().contains("aren't darker"));
+    }
+    #[test]
+    fn test_vector_logo() {
+        let logo:PathBuf = std::env::temp_dir().join(format!("wifiqr-logo-{}.svg", std::process::id()));
+        std::fs::write(&logo, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\"><circle cx=\"5\" cy=\"5\" r=\"5\"/></svg>").unwrap();
+        let args = Args{ ecc: crate::ECCLevel::High, output: Options{ logo: Some(logo.clone()), ..Options::default() }, ..crate::tests::network("Home", Some("secret123"), None) };
+        let svg:String = String::from_utf8(contents(&args, &Branding::default(), OutputFormat::Svg, Path::new("wifi.svg")).unwrap()).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 10 10\"><circle cx=\"5\" cy=\"5\" r=\"5\"/></svg></svg>"));
+        assert!(matches!(contents(&args, &Branding::default(), OutputFormat::Png, Path::new("wifi.png")), Err(OutputError::Logo(LogoError::VectorOnly(_)))));
+        std::fs::remove_file(&logo).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The fields of `WIFI:` payloads made elsewhere, e.g. read by a scanner app, as JSON or YAML for `parse`. Values
+//! are unescaped and taken out of their quotes, and what readers may trip over is listed along with them.
+
+use serde_json::{json, Map, Value};
+
+use crate::inspect::{self, Finding, Severity};
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format{
+    Json,
+    Yaml
+}
+
+/// Names of the fields in the output by their keys in the payload. Unknown keys are kept as they are.
+const FIELD_NAMES: [(&str, &str); 9] = [
+    ("S", "ssid"), ("T", "security"), ("P", "password"), ("H", "hidden"), ("R", "transition_disable"),
+    ("E", "eap"), ("PH2", "phase2"), ("I", "identity"), ("A", "anonymous_identity")
+];
+
+/// The fields of the payload with its errors and warnings, and whether there are errors.
+pub fn parse(payload: &str) -> (Value, bool) {
+    let mut output:Map<String, Value> = Map::new();
+    for (key, value) in inspect::fields(payload, &mut |_, _| {}).unwrap_or_default() {
+        let name:String = FIELD_NAMES.iter().find(|(x, _)| *x == key).map_or(key.clone(), |(_, x)| String::from(*x));
+        let value:Value = match value.as_str() {
+            "true" | "false" if key == "H" => Value::Bool(value == "true"),
+            _ => Value::String(value)
+        };
+        output.insert(name, value);
+    }
+    let findings:Vec<Finding> = inspect::validate_wifi(payload);
+    for (severity, name) in [(Severity::Error, "errors"), (Severity::Warning, "warnings")] {
+        let messages:Vec<&str> = findings.iter().filter(|x| x.severity == severity).map(|x| x.message.as_str()).collect();
+        if !messages.is_empty() {
+            output.insert(String::from(name), json!(messages));
+        }
+    }
+    (Value::Object(output), findings.iter().any(|x| x.severity == Severity::Error))
+}
+
+/// Writes the output of [`parse`] in the format, ending with a line break.
+pub fn to_string(output: &Value, format: Format) -> String {
+    match format {
+        Format::Json => serde_json::to_string_pretty(output).unwrap() + "\n",
+        Format::Yaml => to_yaml(output)
+    }
+}
+
+/// Writes a flat object as YAML. Strings are quoted as in JSON, which YAML reads the same, so SSIDs such as `yes` or
+/// `0x1F` stay strings.
+fn to_yaml(output: &Value) -> String {
+    let mut yaml:String = String::new();
+    for (key, value) in output.as_object().into_iter().flatten() {
+        let key:String = if key.chars().all(|x| x.is_ascii_alphanumeric() || x == '_') {key.clone()}else{Value::String(key.clone()).to_string()};
+        match value {
+            Value::Array(items) => {
+                yaml += &format!("{}:\n", key);
+                for item in items {
+                    yaml += &format!("  - {}\n", item);
+                }
+            },
+            value => yaml += &format!("{}: {}\n", key, value)
+        }
+    }
+    yaml
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_parse() {
+        let (output, failed) = parse("WIFI:T:WPA;S:\"CAFE\";P:pass\\;word;H:true;;");
+        assert_eq!(output, json!({"ssid": "CAFE", "security": "WPA", "password": "pass;word", "hidden": true}));
+        assert!(!failed);
+        let (output, failed) = parse("WIFI:T:WPA;S:CAFE;X:1;;");
+        assert_eq!(output, json!({"ssid": "CAFE", "security": "WPA", "X": "1", "errors": ["the password (P) is missing for T:WPA"], "warnings": [
+            "S:CAFE may be read as hex digits, quote it as S:\"CAFE\" if it is the name itself", "the field X is unknown to readers"
+        ]}));
+        assert!(failed);
+    }
+    #[test]
+    fn test_yaml() {
+        let (output, _) = parse("WIFI:T:nopass;S:yes;P:x;H:false;;");
+        assert_eq!(to_string(&output, Format::Yaml), "hidden: false\npassword: \"x\"\nsecurity: \"nopass\"\nssid: \"yes\"\nwarnings:\n  - \"the network is open, so readers ignore the password\"\n");
+    }
+}
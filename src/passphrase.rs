@@ -0,0 +1,107 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Generating random passphrases with `--generate-psw`, e.g. for guest networks set up in bulk. The randomness comes
+//! from the operating system, and each character is drawn without bias towards any part of the alphabet.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Length of the passphrases when `--generate-psw` is given without one. 20 characters of the unambiguous alphabet
+/// are about 110 bits.
+const DEFAULT_LENGTH: &str = "20";
+
+/// The options of generated passphrases.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Generator{
+    /// Generate a random passphrase of the length, 20 if omitted, and print it after the QR-Code, e.g.
+    /// `--generate-psw=24`.
+    #[clap(long, value_parser, value_name = "LENGTH", min_values = 0, require_equals = true, default_missing_value = DEFAULT_LENGTH,
+        conflicts_with_all = &["psw", "interactive", "psw-stdin", "psw-file", "psw-env"])]
+    pub generate_psw: Option<usize>,
+
+    /// Characters of generated passphrases.
+    #[clap(arg_enum, long, value_parser, default_value_t = Alphabet::Unambiguous, requires = "generate-psw")]
+    pub psw_alphabet: Alphabet,
+
+    /// Also write the generated passphrase to the file, created readable only by the current user.
+    #[clap(long, value_parser, value_name = "PATH", requires = "generate-psw")]
+    pub psw_out: Option<PathBuf>
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alphabet{
+    /// Letters and digits without those easily mistaken for each other, such as 0 and O, for codes typed off a card
+    #[default]
+    Unambiguous,
+    Alphanumeric,
+    /// All printable ASCII characters but the space
+    Ascii,
+    /// Hex digits, 64 of which are a raw pre-shared key
+    Hex
+}
+impl Alphabet {
+    fn characters(self) -> Vec<char> {
+        match self {
+            Alphabet::Unambiguous => "ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789".chars().collect(),
+            Alphabet::Alphanumeric => ('A'..='Z').chain('a'..='z').chain('0'..='9').collect(),
+            Alphabet::Ascii => ('!'..='~').collect(),
+            Alphabet::Hex => ('0'..='9').chain('a'..='f').collect()
+        }
+    }
+}
+
+/// A random passphrase of the length. WPA takes 8 to 63 characters, or 64 hex digits; the length is checked
+/// against those before anything is drawn.
+pub fn generate(length: usize, alphabet: Alphabet) -> Result<String, String> {
+    if !((8..=63).contains(&length) || length == 64 && alphabet == Alphabet::Hex) {
+        return Err(format!("WPA passphrases have 8 to 63 characters, or 64 with --psw-alphabet hex, not {}", length));
+    }
+    let characters:Vec<char> = alphabet.characters();
+    // Bytes from this limit up would favour the first characters, so they are drawn again.
+    let limit:usize = 256 - 256 % characters.len();
+    let mut passphrase:String = String::with_capacity(length);
+    let mut bytes:[u8; 64] = [0; 64];
+    while passphrase.len() < length {
+        getrandom::getrandom(&mut bytes).map_err(|err| format!("the system has no randomness to offer: {}", err))?;
+        passphrase.extend(bytes.iter().map(|x| *x as usize).filter(|x| *x < limit).map(|x| characters[x % characters.len()]).take(length - passphrase.len()));
+    }
+    Ok(passphrase)
+}
+
+/// Writes the passphrase to the file, ending it with a line break. Files it creates are readable only by the current user.
+pub fn write(path: &Path, passphrase: &str) -> io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    writeln!(options.open(path)?, "{}", passphrase)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_generate() {
+        let passphrase:String = generate(63, Alphabet::Unambiguous).unwrap();
+        assert_eq!(passphrase.len(), 63);
+        assert!(passphrase.chars().all(|x| x.is_ascii_alphanumeric() && !"0O1lI".contains(x)));
+        assert_ne!(passphrase, generate(63, Alphabet::Unambiguous).unwrap());
+        assert!(generate(64, Alphabet::Hex).unwrap().chars().all(|x| x.is_ascii_hexdigit()));
+        assert!(generate(64, Alphabet::Ascii).is_err());
+        assert!(generate(7, Alphabet::Alphanumeric).is_err());
+    }
+}
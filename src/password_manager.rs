@@ -0,0 +1,238 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Storing the network in a password manager by driving its CLI (`pass`, `op` or `bw`).
+
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::private_dir::PrivateDir;
+use crate::{assemble_qr_string, resolve_security, Args};
+
+/// File name of the QR attachment in 1Password and Bitwarden.
+const ATTACHMENT_NAME: &str = "wifi-qr.png";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PasswordManager{
+    Pass,
+    OnePassword,
+    Bitwarden
+}
+
+#[derive(Debug)]
+pub enum ExportError{
+    Io(String, std::io::Error),
+    CommandFailed(String, String),
+    InvalidResponse(String)
+}
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(program, err) => write!(f, "failed to run {}: {}", program, err),
+            ExportError::CommandFailed(program, stderr) => write!(f, "{} failed: {}", program, stderr.trim()),
+            ExportError::InvalidResponse(program) => write!(f, "{} returned an unexpected response", program)
+        }
+    }
+}
+
+/// Runs a password manager CLI, optionally feeding it `stdin`, and returns its stdout.
+fn run(program: &str, args: &[String], stdin: Option<&[u8]>) -> Result<Vec<u8>, ExportError> {
+    let io_err = |err| ExportError::Io(program.to_string(), err);
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {Stdio::piped()}else{Stdio::null()})
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(io_err)?;
+    if let Some(input) = stdin {
+        // Dropping the handle closes the pipe, which signals EOF to the child.
+        child.stdin.take().unwrap().write_all(input).map_err(io_err)?;
+    }
+    let output = child.wait_with_output().map_err(io_err)?;
+    if !output.status.success() {
+        return Err(ExportError::CommandFailed(program.to_string(), String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(output.stdout)
+}
+
+fn security_name(args: &Args) -> String {
+    match resolve_security(args) {
+        Some(x) => x.to_string(),
+        None => String::from("Open")
+    }
+}
+
+/// Contents of the pass entry: the password on the first line, as is the convention, followed by metadata.
+fn pass_entry(args: &Args) -> String {
+    format!("{}\nSSID: {}\nSecurity: {}\nHidden: {}\nQR-Payload: {}\n",
+        args.psw.clone().unwrap_or_default(), args.ssid, security_name(args), args.hidden, assemble_qr_string(args))
+}
+
+/// pass re-encrypts over the existing entry with `--force`, so creating and updating are the same operation.
+/// The QR image is stored as a sibling entry, as pass has no notion of attachments.
+fn export_pass(args: &Args, item: &str, png: &[u8]) -> Result<(), ExportError> {
+    let insert = |path: &str, contents: &[u8]| run("pass", &["insert".into(), "--multiline".into(), "--force".into(), path.into()], Some(contents));
+    insert(item, pass_entry(args).as_bytes())?;
+    insert(&format!("{}.png", item), png)?;
+    Ok(())
+}
+
+/// The item template of 1Password's "Wireless Router" category with the network, piped to `op` so the password
+/// doesn't show up in the argument list of the process.
+fn op_template(args: &Args, item: &str) -> Value {
+    json!({
+        "title": item,
+        "category": "WIRELESS_ROUTER",
+        "fields": [
+            {"id": "network_name", "type": "STRING", "label": "network name", "value": args.ssid},
+            {"id": "wireless_password", "type": "CONCEALED", "label": "wireless network password", "value": args.psw.clone().unwrap_or_default()},
+            {"id": "wireless_security", "type": "STRING", "label": "wireless security", "value": security_name(args)}
+        ]
+    })
+}
+
+fn export_one_password(args: &Args, item: &str, vault: Option<&str>, png_path: &Path) -> Result<(), ExportError> {
+    let mut vault_args:Vec<String> = Vec::new();
+    if let Some(vault) = vault {
+        vault_args.extend(["--vault".to_string(), vault.to_string()]);
+    }
+    let mut get_args:Vec<String> = vec!["item".into(), "get".into(), item.into()];
+    get_args.extend(vault_args.clone());
+    let mut op_args:Vec<String> = if run("op", &get_args, None).is_ok() {
+        vec!["item".into(), "edit".into(), item.into()]
+    }else{
+        vec!["item".into(), "create".into()]
+    };
+    op_args.extend(vault_args);
+    // Files can't be attached by the template, but their path isn't a secret.
+    op_args.push(format!("QR code[file]={}", png_path.display()));
+    let template:Vec<u8> = serde_json::to_vec(&op_template(args, item)).unwrap();
+    run("op", &op_args, Some(&template))?;
+    Ok(())
+}
+
+/// Applies the network to a Bitwarden login item, keeping unrelated fields of an existing item intact.
+fn bitwarden_item(args: &Args, item: &str, existing: Option<Value>) -> Value {
+    let mut value:Value = existing.unwrap_or_else(|| json!({
+        "type": 1,
+        "name": item,
+        "favorite": false,
+        "reprompt": 0,
+        "login": {"uris": [], "totp": null}
+    }));
+    value["login"]["username"] = json!(args.ssid);
+    value["login"]["password"] = json!(args.psw.clone().unwrap_or_default());
+    value["notes"] = json!(assemble_qr_string(args));
+    value["fields"] = json!([
+        {"name": "SSID", "value": args.ssid, "type": 0},
+        {"name": "Security", "value": security_name(args), "type": 0},
+        {"name": "Hidden", "value": args.hidden.to_string(), "type": 0}
+    ]);
+    value
+}
+
+/// `bw` expects items as base64 encoded JSON, which is piped via stdin to keep the password out of the process list.
+fn export_bitwarden(args: &Args, item: &str, png_path: &Path) -> Result<(), ExportError> {
+    let invalid = || ExportError::InvalidResponse(String::from("bw"));
+    let items:Value = serde_json::from_slice(&run("bw", &["list".into(), "items".into(), "--search".into(), item.into()], None)?).map_err(|_| invalid())?;
+    let existing:Option<Value> = items.as_array().ok_or_else(invalid)?.iter().find(|x| x["name"] == item).cloned();
+
+    let encoded:String = base64::engine::general_purpose::STANDARD.encode(bitwarden_item(args, item, existing.clone()).to_string());
+    let item_id:String = match &existing {
+        Some(existing) => {
+            let id:String = existing["id"].as_str().ok_or_else(invalid)?.to_string();
+            for attachment in existing["attachments"].as_array().into_iter().flatten() {
+                if attachment["fileName"] == ATTACHMENT_NAME {
+                    let attachment_id:&str = attachment["id"].as_str().ok_or_else(invalid)?;
+                    run("bw", &["delete".into(), "attachment".into(), attachment_id.into(), "--itemid".into(), id.clone()], None)?;
+                }
+            }
+            run("bw", &["edit".into(), "item".into(), id.clone()], Some(encoded.as_bytes()))?;
+            id
+        },
+        None => {
+            let created:Value = serde_json::from_slice(&run("bw", &["create".into(), "item".into()], Some(encoded.as_bytes()))?).map_err(|_| invalid())?;
+            created["id"].as_str().ok_or_else(invalid)?.to_string()
+        }
+    };
+    run("bw", &["create".into(), "attachment".into(), "--file".into(), png_path.display().to_string(), "--itemid".into(), item_id], None)?;
+    Ok(())
+}
+
+/// Name of the item if none was given: a path below `wifi/` for pass, the SSID otherwise.
+pub fn default_item_name(manager: PasswordManager, args: &Args) -> String {
+    match manager {
+        PasswordManager::Pass => format!("wifi/{}", args.ssid),
+        _ => args.ssid.clone()
+    }
+}
+
+/// Creates or updates the item in the password manager, attaching the QR code as a PNG image.
+pub fn export(manager: PasswordManager, args: &Args, item: &str, vault: Option<&str>, png: &[u8]) -> Result<(), ExportError> {
+    if manager == PasswordManager::Pass {
+        return export_pass(args, item, png);
+    }
+    // 1Password and Bitwarden attach files from disk, so the image has to be written out first.
+    let mut directory:PrivateDir = PrivateDir::create(args.shred).map_err(|err| ExportError::Io(String::from("wifiqr"), err))?;
+    let png_path:PathBuf = directory.write(ATTACHMENT_NAME, png).map_err(|err| ExportError::Io(String::from("wifiqr"), err))?;
+    match manager {
+        PasswordManager::OnePassword => export_one_password(args, item, vault, &png_path),
+        _ => export_bitwarden(args, item, &png_path)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn test_args() -> Args {
+        crate::tests::network("Martin Router King", Some("password"), Some(Security::Wpa2))
+    }
+    #[test]
+    fn test_pass_entry() {
+        assert_eq!(pass_entry(&test_args()), "password\nSSID: Martin Router King\nSecurity: Wpa2\nHidden: false\nQR-Payload: WIFI:T:WPA;S:Martin Router King;P:password;;\n");
+    }
+    #[test]
+    fn test_op_template() {
+        let template = op_template(&test_args(), "Office");
+        assert_eq!(template["title"], "Office");
+        assert_eq!(template["category"], "WIRELESS_ROUTER");
+        assert_eq!(template["fields"][0]["value"], "Martin Router King");
+        assert_eq!(template["fields"][1], json!({"id": "wireless_password", "type": "CONCEALED", "label": "wireless network password", "value": "password"}));
+        assert_eq!(template["fields"][2]["value"], "Wpa2");
+    }
+    #[test]
+    fn test_bitwarden_item_update_keeps_other_fields() {
+        let existing = json!({"id": "abc", "name": "Office", "folderId": "folder", "login": {"username": "old", "password": "old", "uris": []}});
+        let item = bitwarden_item(&test_args(), "Office", Some(existing));
+        assert_eq!(item["id"], "abc");
+        assert_eq!(item["folderId"], "folder");
+        assert_eq!(item["login"]["username"], "Martin Router King");
+        assert_eq!(item["login"]["password"], "password");
+        assert_eq!(item["fields"][1]["value"], "Wpa2");
+    }
+    #[test]
+    fn test_default_item_name() {
+        assert_eq!(default_item_name(PasswordManager::Pass, &test_args()), "wifi/Martin Router King");
+        assert_eq!(default_item_name(PasswordManager::Bitwarden, &test_args()), "Martin Router King");
+    }
+}
@@ -0,0 +1,50 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! NetPBM bitmaps of the QR-Code, `P4` files with a bit per pixel, which ImageMagick, netpbm and most image tools
+//! read from a pipe, e.g. `wifiqr -o - --format pbm ... | convert - wifi.png`.
+
+use crate::with_border;
+
+/// A PBM file of the symbol with a border of `border` modules and `module_pixels` pixels per module. A set bit is a
+/// black pixel, and each row is padded to whole bytes.
+pub fn document(matrix: &[Vec<bool>], border: usize, module_pixels: usize) -> Vec<u8> {
+    let matrix:Vec<Vec<bool>> = with_border(matrix, border);
+    let size:usize = matrix.len() * module_pixels;
+    let mut pbm:Vec<u8> = format!("P4\n{} {}\n", size, size).into_bytes();
+    for row in &matrix {
+        let pixels:Vec<bool> = row.iter().flat_map(|x| std::iter::repeat_n(*x, module_pixels)).collect();
+        let packed:Vec<u8> = pixels.chunks(8).map(|byte| byte.iter().enumerate().fold(0, |bits, (i, x)| bits | ((*x as u8) << (7 - i)))).collect();
+        for _ in 0..module_pixels {
+            pbm.extend_from_slice(&packed);
+        }
+    }
+    pbm
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_document() {
+        assert_eq!(document(&[vec![true, false], vec![false, true]], 1, 1), b"P4\n4 4\n\x00\x40\x20\x00");
+        // Rows of 10 pixels take two bytes each.
+        let pbm:Vec<u8> = document(&[vec![true]], 2, 2);
+        assert!(pbm.starts_with(b"P4\n10 10\n"));
+        assert_eq!(pbm.len(), 9 + 10 * 2);
+        assert_eq!(&pbm[9 + 4 * 2..9 + 6 * 2], [0x0c, 0x00, 0x0c, 0x00]);
+    }
+}
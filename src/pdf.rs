@@ -0,0 +1,585 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A minimal PDF writer for the print layouts. Text is set in the standard 14 fonts, which need no embedding, or in
+//! TrueType/OpenType fonts, which are embedded as subsets.
+
+use std::fmt::Write;
+use std::io::Write as _;
+
+use image::RgbaImage;
+
+use crate::color::Color;
+use crate::font::{EmbeddedFont, PdfGlyph};
+
+/// Points per millimeter, PDF user space units being 1/72 inch.
+pub const MM: f32 = 72.0 / 25.4;
+
+/// Advance widths (1/1000 em) of the WinAnsiEncoding codes 32 to 255, taken from the Adobe AFM files.
+const HELVETICA_WIDTHS: [u16; 224] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 224] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    278, 333, 556, 556, 556, 556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 611, 556, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278,
+    611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Font{
+    Helvetica,
+    HelveticaBold,
+    Embedded(&'static EmbeddedFont)
+}
+impl Font {
+    /// The standard fonts, which are available on every page.
+    const STANDARD: [Font; 2] = [Font::Helvetica, Font::HelveticaBold];
+    /// Looks up a standard font by the name used in configuration files.
+    pub fn from_name(name: &str) -> Option<Font> {
+        match name {
+            "helvetica" => Some(Font::Helvetica),
+            "helvetica-bold" => Some(Font::HelveticaBold),
+            _ => None
+        }
+    }
+    fn resource_name(&self) -> String {
+        match self {
+            Font::Helvetica => String::from("F1"),
+            Font::HelveticaBold => String::from("F2"),
+            Font::Embedded(font) => format!("E{}", font.id)
+        }
+    }
+    fn base_font(&self) -> &str {
+        match self {
+            Font::Helvetica => "Helvetica",
+            Font::HelveticaBold => "Helvetica-Bold",
+            Font::Embedded(font) => &font.postscript_name
+        }
+    }
+    /// Width of the text in points when set at `size`.
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let widths:&[u16; 224] = match self {
+            Font::Helvetica => &HELVETICA_WIDTHS,
+            Font::HelveticaBold => &HELVETICA_BOLD_WIDTHS,
+            Font::Embedded(font) => return font.text_width(text) * size / 1000.0
+        };
+        let total:u32 = encode_win_ansi(text).iter().map(|x| widths[(*x - 32) as usize] as u32).sum();
+        total as f32 * size / 1000.0
+    }
+    /// Height of the text in points when set top to bottom at `size`. The standard fonts have no vertical metrics,
+    /// so their characters are stacked one em apart.
+    pub fn text_height(&self, text: &str, size: f32) -> f32 {
+        match self {
+            Font::Embedded(font) => font.text_height(text) * size / 1000.0,
+            _ => text.chars().count() as f32 * size
+        }
+    }
+}
+
+/// Encodes text as WinAnsiEncoding. Latin-1 maps onto it directly, everything else becomes `?`.
+fn encode_win_ansi(text: &str) -> Vec<u8> {
+    text.chars().map(|x| match x as u32 {
+        0x20..=0x7E | 0xA0..=0xFF => x as u8,
+        _ => b'?'
+    }).collect()
+}
+
+/// A rectangle in PDF user space, with its origin at the bottom left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect{
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32
+}
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rect{ x, y, width, height }
+    }
+    /// Shrinks the rectangle by `amount` on every side.
+    pub fn inset(&self, amount: f32) -> Self {
+        Rect::new(self.x + amount, self.y + amount, self.width - 2.0 * amount, self.height - 2.0 * amount)
+    }
+}
+
+/// A raster image, split into the color samples and an optional soft mask for its transparency.
+#[derive(PartialEq)]
+struct Image{
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+    alpha: Option<Vec<u8>>
+}
+impl Image {
+    fn new(image: &RgbaImage) -> Self {
+        let rgb:Vec<u8> = image.pixels().flat_map(|x| [x[0], x[1], x[2]]).collect();
+        let alpha:Vec<u8> = image.pixels().map(|x| x[3]).collect();
+        let opaque:bool = alpha.iter().all(|x| *x == 255);
+        Image{ width: image.width(), height: image.height(), rgb, alpha: if opaque {None}else{Some(alpha)} }
+    }
+}
+
+pub struct Page{
+    pub width: f32,
+    pub height: f32,
+    /// Final size of the printed piece after trimming, if it differs from the media box.
+    pub trim_box: Option<Rect>,
+    /// Extent of the artwork that bleeds past the trim box.
+    pub bleed_box: Option<Rect>,
+    content: String,
+    images: Vec<Image>,
+    embedded_fonts: Vec<&'static EmbeddedFont>,
+    /// Size of a module of each QR-Code drawn on the page, for checking they can be scanned from afar.
+    pub module_sizes: Vec<f32>
+}
+impl Page {
+    pub fn new(width: f32, height: f32) -> Self {
+        Page{ width, height, trim_box: None, bleed_box: None, content: String::new(), images: Vec::new(), embedded_fonts: Vec::new(), module_sizes: Vec::new() }
+    }
+    pub fn set_fill_color(&mut self, color: Color) {
+        let _ = writeln!(self.content, "{:.3} {:.3} {:.3} rg", color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0);
+    }
+    pub fn set_stroke_color(&mut self, color: Color) {
+        let _ = writeln!(self.content, "{:.3} {:.3} {:.3} RG", color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0);
+    }
+    pub fn fill_rect(&mut self, rect: Rect) {
+        let _ = writeln!(self.content, "{:.3} {:.3} {:.3} {:.3} re f", rect.x, rect.y, rect.width, rect.height);
+    }
+    /// Fills the polygon through the points.
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)]) {
+        let Some(((x, y), rest)) = points.split_first() else { return; };
+        let _ = write!(self.content, "{:.3} {:.3} m", x, y);
+        for (x, y) in rest {
+            let _ = write!(self.content, " {:.3} {:.3} l", x, y);
+        }
+        self.content += " h f\n";
+    }
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32) {
+        let _ = writeln!(self.content, "{:.3} w {:.3} {:.3} m {:.3} {:.3} l S", width, from.0, from.1, to.0, to.1);
+    }
+    /// Sets the dash pattern for stroked lines, an empty pattern meaning solid lines.
+    pub fn set_line_dash(&mut self, pattern: &[f32]) {
+        let pattern:Vec<String> = pattern.iter().map(|x| format!("{:.3}", x)).collect();
+        let _ = writeln!(self.content, "[{}] 0 d", pattern.join(" "));
+    }
+    /// Saves the graphics state and applies the transformation matrix `[a b c d e f]` to everything drawn until
+    /// the matching [`Page::pop_transform`].
+    pub fn push_transform(&mut self, matrix: [f32; 6]) {
+        let _ = writeln!(self.content, "q {:.3} {:.3} {:.3} {:.3} {:.3} {:.3} cm", matrix[0], matrix[1], matrix[2], matrix[3], matrix[4], matrix[5]);
+    }
+    pub fn pop_transform(&mut self) {
+        self.content += "Q\n";
+    }
+    /// Draws text with its baseline starting at `x`, `y`.
+    pub fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        if let Font::Embedded(embedded) = font {
+            if !self.embedded_fonts.contains(&embedded) {
+                self.embedded_fonts.push(embedded);
+            }
+            let _ = write!(self.content, "BT /{} {:.3} Tf {:.3} {:.3} Td", font.resource_name(), size, x, y);
+            shaped_text(&mut self.content, &embedded.encode(text), size);
+            self.content += " ET\n";
+            return;
+        }
+        let mut literal:String = String::new();
+        for byte in encode_win_ansi(text) {
+            match byte {
+                b'(' | b')' | b'\\' => { literal.push('\\'); literal.push(byte as char); },
+                0x20..=0x7E => literal.push(byte as char),
+                _ => { let _ = write!(literal, "\\{:03o}", byte); }
+            }
+        }
+        let _ = writeln!(self.content, "BT /{} {:.3} Tf {:.3} {:.3} Td ({}) Tj ET", font.resource_name(), size, x, y, literal);
+    }
+    /// Draws the text top to bottom in a column centered on `x`, starting at `top`.
+    pub fn vertical_text(&mut self, x: f32, top: f32, font: Font, size: f32, text: &str) {
+        let Font::Embedded(embedded) = font else {
+            // The baseline of Helvetica sits about a fifth of an em above the bottom of its line.
+            for (index, character) in text.chars().enumerate() {
+                let character:String = character.to_string();
+                self.text(x - font.text_width(&character, size) / 2.0, top - (index as f32 + 0.8) * size, font, size, &character);
+            }
+            return;
+        };
+        if !self.embedded_fonts.contains(&embedded) {
+            self.embedded_fonts.push(embedded);
+        }
+        let _ = write!(self.content, "BT /{} {:.3} Tf", font.resource_name(), size);
+        let mut pen:f32 = top;
+        for glyph in embedded.encode_vertical(text) {
+            let _ = write!(self.content, " 1 0 0 1 {:.3} {:.3} Tm <{:04X}> Tj", x + glyph.x_offset * size / 1000.0, pen + glyph.y_offset * size / 1000.0, glyph.glyph);
+            pen += glyph.y_advance * size / 1000.0;
+        }
+        self.content += " ET\n";
+    }
+    /// Draws the image stretched to `rect`. Drawing the same image several times embeds it only once.
+    pub fn image(&mut self, image: &RgbaImage, rect: Rect) {
+        let image = Image::new(image);
+        let index:usize = match self.images.iter().position(|x| *x == image) {
+            Some(index) => index,
+            None => {
+                self.images.push(image);
+                self.images.len() - 1
+            }
+        };
+        let _ = writeln!(self.content, "q {:.3} 0 0 {:.3} {:.3} {:.3} cm /Im{} Do Q", rect.width, rect.height, rect.x, rect.y, index + 1);
+    }
+    /// Draws the dark modules of the matrix into `rect`, merging horizontal runs into single rectangles.
+    pub fn qr_code(&mut self, matrix: &[Vec<bool>], rect: Rect) {
+        let module_width:f32 = rect.width / matrix.len() as f32;
+        let module_height:f32 = rect.height / matrix.len() as f32;
+        self.module_sizes.push(module_width.min(module_height));
+        for (row_index, row) in matrix.iter().enumerate() {
+            let y:f32 = rect.y + rect.height - (row_index + 1) as f32 * module_height;
+            let mut column:usize = 0;
+            while column < row.len() {
+                if !row[column] {
+                    column += 1;
+                    continue;
+                }
+                let start:usize = column;
+                while column < row.len() && row[column] {
+                    column += 1;
+                }
+                self.fill_rect(Rect::new(rect.x + start as f32 * module_width, y, (column - start) as f32 * module_width, module_height));
+            }
+        }
+    }
+}
+
+/// Writes shaped glyphs as `TJ` arrays of two byte glyph ids (Identity-H), adjusting every glyph whose shaped
+/// position differs from the advance the viewer applies by itself. Vertical offsets, as used for marks, switch
+/// the text rise.
+fn shaped_text(content: &mut String, glyphs: &[PdfGlyph], size: f32) {
+    let mut rise:f32 = 0.0;
+    let mut array:String = String::new();
+    for glyph in glyphs {
+        if glyph.y_offset != rise {
+            if !array.is_empty() {
+                let _ = write!(content, " [{}] TJ", std::mem::take(&mut array));
+            }
+            rise = glyph.y_offset;
+            let _ = write!(content, " {:.3} Ts", rise * size / 1000.0);
+        }
+        // Numbers in TJ arrays move the next glyph to the left, in 1/1000 em.
+        if glyph.x_offset != 0.0 {
+            let _ = write!(array, "{:.0}", -glyph.x_offset);
+        }
+        let _ = write!(array, "<{:04X}>", glyph.glyph);
+        let adjustment:f32 = glyph.width + glyph.x_offset - glyph.x_advance;
+        if adjustment.abs() >= 0.5 {
+            let _ = write!(array, "{:.0}", adjustment);
+        }
+    }
+    if !array.is_empty() {
+        let _ = write!(content, " [{}] TJ", array);
+    }
+    if rise != 0.0 {
+        content.push_str(" 0 Ts");
+    }
+}
+
+fn format_box(name: &str, rect: &Rect) -> String {
+    format!("/{} [{:.3} {:.3} {:.3} {:.3}]", name, rect.x, rect.y, rect.x + rect.width, rect.y + rect.height)
+}
+
+/// Wraps `data` into a stream object, compressing it if `compress` is set.
+fn stream_object(dictionary: &str, data: &[u8], compress: bool) -> Vec<u8> {
+    let (filter, data):(&str, Vec<u8>) = if compress {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        let _ = encoder.write_all(data);
+        (" /Filter /FlateDecode", encoder.finish().unwrap())
+    }else{
+        ("", data.to_vec())
+    };
+    let dictionary:String = format!("{}{} /Length {}", dictionary, filter, data.len());
+    let mut stream:Vec<u8> = format!("<< {} >>\nstream\n", dictionary.trim_start()).into_bytes();
+    stream.extend_from_slice(&data);
+    stream.extend_from_slice(b"\nendstream");
+    stream
+}
+
+/// Maps the glyph ids of the subset back to the text they stand for.
+fn to_unicode_cmap(font: &EmbeddedFont) -> String {
+    let mut cmap:String = String::from("/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+        /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n/CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+        1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n");
+    let unicode = font.unicode.borrow();
+    let entries:Vec<(&u16, &String)> = unicode.iter().collect();
+    // A bfchar block may hold at most 100 mappings.
+    for chunk in entries.chunks(100) {
+        let _ = writeln!(cmap, "{} beginbfchar", chunk.len());
+        for (glyph, text) in chunk {
+            let utf16:String = text.encode_utf16().map(|x| format!("{:04X}", x)).collect();
+            let _ = writeln!(cmap, "<{:04X}> <{}>", glyph, utf16);
+        }
+        cmap += "endbfchar\n";
+    }
+    cmap += "endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend";
+    cmap
+}
+
+/// Objects of an embedded font, numbered from `first`: the Type0 font, its descendant CID font, the font
+/// descriptor, the ToUnicode CMap and the font program.
+fn embedded_font_objects(font: &EmbeddedFont, first: usize) -> Vec<Vec<u8>> {
+    let name:String = format!("{}+{}", font.subset_tag(), font.postscript_name);
+    let widths:Vec<String> = font.subset_widths().iter().map(|x| format!("{:.0}", x)).collect();
+    let (subtype, font_file):(&str, Vec<u8>) = if font.is_cff() {
+        ("CIDFontType0", stream_object("/Subtype /OpenType", &font.subset(), true))
+    }else{
+        let program:Vec<u8> = font.subset();
+        ("CIDFontType2", stream_object(&format!("/Length1 {}", program.len()), &program, true))
+    };
+    let face = &font.face;
+    let bbox = face.global_bounding_box();
+    let flags:u32 = 4 | if face.is_italic() {64}else{0} | if face.is_monospaced() {1}else{0};
+    vec![
+        format!("<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>", name, first + 1, first + 3).into_bytes(),
+        format!("<< /Type /Font /Subtype /{} /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {} 0 R /W [0 [{}]]{} >>",
+            subtype, name, first + 2, widths.join(" "), if font.is_cff() {""}else{" /CIDToGIDMap /Identity"}).into_bytes(),
+        format!("<< /Type /FontDescriptor /FontName /{} /Flags {} /FontBBox [{:.0} {:.0} {:.0} {:.0}] /ItalicAngle {:.0} /Ascent {:.0} /Descent {:.0} /CapHeight {:.0} /StemV 80 /{} {} 0 R >>",
+            name, flags, font.scale(bbox.x_min as f32), font.scale(bbox.y_min as f32), font.scale(bbox.x_max as f32), font.scale(bbox.y_max as f32), face.italic_angle(),
+            font.scale(face.ascender() as f32), font.scale(face.descender() as f32), font.scale(face.capital_height().unwrap_or(face.ascender()) as f32),
+            if font.is_cff() {"FontFile3"}else{"FontFile2"}, first + 4).into_bytes(),
+        stream_object("", to_unicode_cmap(font).as_bytes(), true),
+        font_file
+    ]
+}
+
+/// Number of objects a page takes up: the page, its content stream and its images, each with an optional soft mask.
+fn object_count(page: &Page) -> usize {
+    2 + page.images.iter().map(|x| if x.alpha.is_some() {2}else{1}).sum::<usize>()
+}
+
+/// Writes a PDF page by page, so documents of thousands of pages aren't held in memory: only the object numbers and
+/// offsets are kept. The embedded fonts, whose subsets are only known once every page is written, are numbered when a
+/// page first uses them and written at the end, with the page tree.
+pub struct DocumentWriter<W: std::io::Write>{
+    output: W,
+    position: usize,
+    /// Offsets of the objects, by their number minus one.
+    offsets: Vec<usize>,
+    pages: Vec<usize>,
+    /// The embedded fonts with the first of their five objects.
+    embedded_fonts: Vec<(&'static EmbeddedFont, usize)>
+}
+impl<W: std::io::Write> DocumentWriter<W> {
+    /// Writes the header, the catalog and the standard fonts. Object 2, the page tree, is written by
+    /// [`DocumentWriter::finish`].
+    pub fn new(output: W) -> std::io::Result<Self> {
+        let mut writer = DocumentWriter{ output, position: 0, offsets: Vec::new(), pages: Vec::new(), embedded_fonts: Vec::new() };
+        writer.write_bytes(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+        writer.reserve(2 + Font::STANDARD.len());
+        writer.write_object(1, b"<< /Type /Catalog /Pages 2 0 R >>")?;
+        for (i, font) in Font::STANDARD.iter().enumerate() {
+            writer.write_object(3 + i, format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font.base_font()).as_bytes())?;
+        }
+        Ok(writer)
+    }
+    fn write_bytes(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.output.write_all(bytes)?;
+        self.position += bytes.len();
+        Ok(())
+    }
+    /// Numbers `count` objects, returning the first number.
+    fn reserve(&mut self, count: usize) -> usize {
+        self.offsets.resize(self.offsets.len() + count, 0);
+        self.offsets.len() - count + 1
+    }
+    fn write_object(&mut self, number: usize, object: &[u8]) -> std::io::Result<()> {
+        self.offsets[number - 1] = self.position;
+        self.write_bytes(format!("{} 0 obj\n", number).as_bytes())?;
+        self.write_bytes(object)?;
+        self.write_bytes(b"\nendobj\n")
+    }
+    pub fn add_page(&mut self, page: &Page) -> std::io::Result<()> {
+        for font in &page.embedded_fonts {
+            if !self.embedded_fonts.iter().any(|(x, _)| x == font) {
+                let first:usize = self.reserve(5);
+                self.embedded_fonts.push((font, first));
+            }
+        }
+        let number:usize = self.reserve(object_count(page));
+        self.pages.push(number);
+        let mut fonts:Vec<String> = Font::STANDARD.iter().enumerate().map(|(i, x)| format!("/{} {} 0 R", x.resource_name(), 3 + i)).collect();
+        fonts.extend(self.embedded_fonts.iter().map(|(font, first)| format!("/{} {} 0 R", Font::Embedded(font).resource_name(), first)));
+        let mut dictionary:String = format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}]", page.width, page.height);
+        if let Some(bleed_box) = &page.bleed_box {
+            dictionary += &format!(" {}", format_box("BleedBox", bleed_box));
+        }
+        if let Some(trim_box) = &page.trim_box {
+            dictionary += &format!(" {}", format_box("TrimBox", trim_box));
+        }
+        let mut image_objects:Vec<Vec<u8>> = Vec::new();
+        let mut x_objects:Vec<String> = Vec::new();
+        for (j, image) in page.images.iter().enumerate() {
+            let object:usize = number + 2 + image_objects.len();
+            x_objects.push(format!("/Im{} {} 0 R", j + 1, object));
+            let header:String = format!("/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8", image.width, image.height);
+            let soft_mask:String = if image.alpha.is_some() {format!(" /SMask {} 0 R", object + 1)}else{String::new()};
+            image_objects.push(stream_object(&format!("{} /ColorSpace /DeviceRGB{}", header, soft_mask), &image.rgb, true));
+            if let Some(alpha) = &image.alpha {
+                image_objects.push(stream_object(&format!("{} /ColorSpace /DeviceGray", header), alpha, true));
+            }
+        }
+        dictionary += &format!(" /Resources << /Font << {} >>", fonts.join(" "));
+        if !x_objects.is_empty() {
+            dictionary += &format!(" /XObject << {} >>", x_objects.join(" "));
+        }
+        dictionary += &format!(" >> /Contents {} 0 R >>", number + 1);
+        self.write_object(number, dictionary.as_bytes())?;
+        self.write_object(number + 1, &stream_object("", page.content.as_bytes(), false))?;
+        for (j, object) in image_objects.iter().enumerate() {
+            self.write_object(number + 2 + j, object)?;
+        }
+        Ok(())
+    }
+    /// Writes the embedded fonts, the page tree and the cross reference table, and returns the output.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        for (font, first) in std::mem::take(&mut self.embedded_fonts) {
+            for (i, object) in embedded_font_objects(font, first).iter().enumerate() {
+                self.write_object(first + i, object)?;
+            }
+        }
+        let kids:Vec<String> = self.pages.iter().map(|x| format!("{} 0 R", x)).collect();
+        self.write_object(2, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()).as_bytes())?;
+        let xref_offset:usize = self.position;
+        let mut xref:String = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(xref, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", self.offsets.len() + 1, xref_offset);
+        self.write_bytes(xref.as_bytes())?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+/// Serializes the pages into a complete PDF file.
+pub fn write_document(pages: &[Page]) -> Vec<u8> {
+    let mut writer = DocumentWriter::new(Vec::new()).unwrap();
+    for page in pages {
+        writer.add_page(page).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_text_width() {
+        // "Wi" is 944 + 222 units in Helvetica.
+        assert!((Font::Helvetica.text_width("Wi", 10.0) - 11.66).abs() < 0.001);
+        assert!(Font::HelveticaBold.text_width("Wi", 10.0) > Font::Helvetica.text_width("Wi", 10.0));
+    }
+    #[test]
+    fn test_text_escaping() {
+        let mut page = Page::new(100.0, 100.0);
+        page.text(0.0, 0.0, Font::Helvetica, 10.0, "(a\\b) ä €");
+        assert!(page.content.contains("(\\(a\\\\b\\) \\344 ?) Tj"));
+    }
+    #[test]
+    fn test_qr_code_merges_runs() {
+        let mut page = Page::new(100.0, 100.0);
+        page.qr_code(&[vec![true, true], vec![false, true]], Rect::new(0.0, 0.0, 20.0, 20.0));
+        assert_eq!(page.content, "0.000 10.000 20.000 10.000 re f\n10.000 0.000 10.000 10.000 re f\n");
+    }
+    #[test]
+    fn test_xref_offsets() {
+        let document = write_document(&[Page::new(100.0, 100.0), Page::new(100.0, 100.0)]);
+        // The binary comment in the header isn't UTF-8, so everything after it is parsed on its own.
+        let text = std::str::from_utf8(&document[15..]).unwrap();
+        let start_xref:usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(document[start_xref..].starts_with(b"xref\n0 9\n"));
+        // Every entry of the cross reference table has to point at the start of its object.
+        for (i, line) in text[start_xref - 15..].lines().skip(3).take(8).enumerate() {
+            let offset:usize = line[..10].parse().unwrap();
+            assert!(document[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+        assert!(text.contains("/Count 2"));
+    }
+    #[test]
+    fn test_images_are_embedded_once() {
+        let mut page = Page::new(100.0, 100.0);
+        let opaque = RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        page.image(&opaque, Rect::new(0.0, 0.0, 10.0, 10.0));
+        page.image(&opaque, Rect::new(20.0, 0.0, 10.0, 10.0));
+        page.image(&RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 128])), Rect::new(40.0, 0.0, 10.0, 10.0));
+        assert_eq!(page.images.len(), 2);
+        assert!(page.images[0].alpha.is_none() && page.images[1].alpha.is_some());
+        assert!(page.content.contains("/Im1 Do Q\nq 10.000 0 0 10.000 20.000 0.000 cm /Im1 Do Q\nq 10.000 0 0 10.000 40.000 0.000 cm /Im2 Do Q"));
+        let document = write_document(&[page]);
+        // Catalog, pages, two fonts, page, content, the opaque image and the translucent one with its soft mask.
+        let text = String::from_utf8_lossy(&document);
+        assert!(text.contains("/XObject << /Im1 7 0 R /Im2 8 0 R >>"));
+        assert!(text.contains("/SMask 9 0 R"));
+        assert!(text.contains("trailer\n<< /Size 10 "));
+    }
+    #[test]
+    fn test_embedded_font() {
+        let Ok(font) = EmbeddedFont::load(std::path::Path::new("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")) else { return; };
+        let mut page = Page::new(100.0, 100.0);
+        page.text(0.0, 0.0, Font::Embedded(font), 10.0, "Wi-Fi");
+        // Shaping kerns "Wi" and "Fi" closer than the widths of the glyphs.
+        assert!(page.content.contains(&format!("BT /E{} 10.000 Tf 0.000 0.000 Td [<0001>22<0002><0003><0004>73<0002>] TJ ET", font.id)));
+        let document = write_document(&[page]);
+        let text = String::from_utf8_lossy(&document);
+        // The page comes after the standard fonts and the five objects of the embedded font.
+        assert!(text.contains(&format!("/E{} 5 0 R", font.id)));
+        assert!(text.contains("/Kids [10 0 R]"));
+        assert!(text.contains("/Subtype /CIDFontType2") && text.contains("+DejaVuSans /Encoding /Identity-H"));
+    }
+    #[test]
+    fn test_shaped_text_adjustments() {
+        let glyph = |glyph, x_advance, x_offset, y_offset| PdfGlyph{ glyph, width: 500.0, x_advance, y_advance: 0.0, x_offset, y_offset };
+        let mut content = String::new();
+        // A kerned glyph, followed by a mark placed above the one before it.
+        shaped_text(&mut content, &[glyph(1, 450.0, 0.0, 0.0), glyph(2, 0.0, -300.0, 200.0), glyph(3, 500.0, 0.0, 0.0)], 10.0);
+        assert_eq!(content, " [<0001>50] TJ 2.000 Ts [300<0002>200] TJ 0.000 Ts [<0003>] TJ");
+    }
+    #[test]
+    fn test_vertical_text() {
+        let mut page = Page::new(100.0, 100.0);
+        page.vertical_text(50.0, 100.0, Font::Helvetica, 10.0, "ab");
+        assert_eq!(Font::Helvetica.text_height("ab", 10.0), 20.0);
+        assert!(page.content.contains("(a) Tj") && page.content.contains(" 82.000 Td (b) Tj"));
+    }
+}
@@ -0,0 +1,123 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Writing XML property lists, the format of Apple's configuration profiles. Only the types the profiles need are
+//! supported, and dictionaries keep the order their keys were given in.
+
+use crate::svg;
+
+/// A value of a property list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value{
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Dictionary(Vec<(String, Value)>)
+}
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(String::from(value))
+    }
+}
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+/// The pairs of a dictionary, in their order, to add more to.
+pub fn entries<const N: usize>(pairs: [(&str, Value); N]) -> Vec<(String, Value)> {
+    pairs.into_iter().map(|(key, value)| (String::from(key), value)).collect()
+}
+
+/// Appends the value as XML indented by `depth` tabs, as Apple's tools write it.
+fn write_value(xml: &mut String, value: &Value, depth: usize) {
+    let indent:String = "\t".repeat(depth);
+    match value {
+        Value::String(text) => xml.push_str(&format!("{}<string>{}</string>\n", indent, svg::escape(text))),
+        Value::Integer(number) => xml.push_str(&format!("{}<integer>{}</integer>\n", indent, number)),
+        Value::Boolean(flag) => xml.push_str(&format!("{}<{}/>\n", indent, flag)),
+        Value::Array(values) if values.is_empty() => xml.push_str(&format!("{}<array/>\n", indent)),
+        Value::Array(values) => {
+            xml.push_str(&format!("{}<array>\n", indent));
+            for value in values {
+                write_value(xml, value, depth + 1);
+            }
+            xml.push_str(&format!("{}</array>\n", indent));
+        },
+        Value::Dictionary(pairs) if pairs.is_empty() => xml.push_str(&format!("{}<dict/>\n", indent)),
+        Value::Dictionary(pairs) => {
+            xml.push_str(&format!("{}<dict>\n", indent));
+            for (key, value) in pairs {
+                xml.push_str(&format!("{}\t<key>{}</key>\n", indent, svg::escape(key)));
+                write_value(xml, value, depth + 1);
+            }
+            xml.push_str(&format!("{}</dict>\n", indent));
+        }
+    }
+}
+
+/// The XML property list of the value.
+pub fn write(value: &Value) -> String {
+    let mut xml:String = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">\n");
+    write_value(&mut xml, value, 0);
+    xml.push_str("</plist>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_write() {
+        let value:Value = Value::Dictionary(entries([
+            ("SSID_STR", Value::from("Tom & Jerry's <Wi-Fi>")),
+            ("HIDDEN_NETWORK", Value::from(true)),
+            ("PayloadVersion", Value::from(1)),
+            ("AcceptEAPTypes", Value::Array(vec![Value::from(25)])),
+            ("PayloadContent", Value::Array(Vec::new()))
+        ]));
+        assert_eq!(write(&value).lines().skip(3).collect::<Vec<&str>>(), [
+            "<dict>",
+            "\t<key>SSID_STR</key>",
+            "\t<string>Tom &amp; Jerry&apos;s &lt;Wi-Fi&gt;</string>",
+            "\t<key>HIDDEN_NETWORK</key>",
+            "\t<true/>",
+            "\t<key>PayloadVersion</key>",
+            "\t<integer>1</integer>",
+            "\t<key>AcceptEAPTypes</key>",
+            "\t<array>",
+            "\t\t<integer>25</integer>",
+            "\t</array>",
+            "\t<key>PayloadContent</key>",
+            "\t<array/>",
+            "</dict>",
+            "</plist>"
+        ]);
+        assert!(write(&Value::from(false)).starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist"));
+    }
+}
@@ -0,0 +1,157 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Renderers shipped as WebAssembly modules, for output formats that don't belong in this crate, such as those of
+//! proprietary signage players. Plugins run in an interpreter without any imports, so they can't touch files or
+//! the network, and with limits on their memory and the instructions they execute.
+//!
+//! A plugin exports its `memory` and two functions:
+//! - `wifiqr_alloc(len: i32) -> i32` returns the address of `len` free bytes, which the input is written to.
+//! - `wifiqr_render(ptr: i32, len: i32) -> i64` renders the input and returns the address of the output in the
+//!   upper 32 bits and its length in the lower 32 bits.
+//!
+//! The input is a UTF-8 JSON object with the values templates get, `matrix`, the modules as rows of booleans
+//! (`true` is dark), `ecc`, and `options`, the `--option key=value` pairs of the command line.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use wasmi::{Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::branding::Branding;
+use crate::{template, Args};
+
+/// Instructions a plugin may execute per render, which stops plugins that hang.
+const FUEL: u64 = 1_000_000_000;
+/// Bytes of linear memory a plugin may use.
+const MEMORY_LIMIT: usize = 256 << 20;
+
+#[derive(Debug)]
+pub enum PluginError{
+    Io(PathBuf, std::io::Error),
+    Wasm(wasmi::Error),
+    /// The module doesn't implement the plugin interface.
+    Interface(String)
+}
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PluginError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            PluginError::Wasm(err) => write!(f, "{}", err),
+            PluginError::Interface(message) => write!(f, "{}", message)
+        }
+    }
+}
+impl From<wasmi::Error> for PluginError {
+    fn from(err: wasmi::Error) -> Self {
+        PluginError::Wasm(err)
+    }
+}
+
+/// The input of a plugin: the template context with the matrix, the ECC-Level and the options added.
+pub fn input(args: &Args, matrix: &[Vec<bool>], branding: &Branding, options: &[(String, String)]) -> Value {
+    let mut input:Value = template::context(args, matrix, branding);
+    input["matrix"] = serde_json::json!(matrix);
+    input["ecc"] = serde_json::json!(format!("{:?}", args.ecc));
+    input["options"] = Value::Object(options.iter().map(|(key, value)| (key.clone(), Value::from(value.as_str()))).collect());
+    input
+}
+
+/// Parses an `--option`, which has the form `key=value`.
+pub fn parse_option(option: &str) -> Result<(String, String), String> {
+    match option.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((String::from(key), String::from(value))),
+        _ => Err(format!("{} isn't of the form key=value", option))
+    }
+}
+
+/// Runs the plugin, given as a WebAssembly binary.
+pub fn render(wasm: &[u8], input: &Value) -> Result<Vec<u8>, PluginError> {
+    render_with_fuel(wasm, input, FUEL)
+}
+
+fn render_with_fuel(wasm: &[u8], input: &Value, fuel: u64) -> Result<Vec<u8>, PluginError> {
+    let mut config = wasmi::Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm)?;
+    if let Some(import) = module.imports().next() {
+        return Err(PluginError::Interface(format!("plugins can't import anything, but this one imports {}::{}", import.module(), import.name())));
+    }
+    let mut store:Store<StoreLimits> = Store::new(&engine, StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build());
+    store.limiter(|limits| limits);
+    store.set_fuel(fuel)?;
+    let instance = Linker::new(&engine).instantiate_and_start(&mut store, &module)?;
+
+    let missing = |name: &str| PluginError::Interface(format!("the plugin doesn't export {} as the interface requires", name));
+    let memory = instance.get_memory(&store, "memory").ok_or_else(|| missing("memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&store, "wifiqr_alloc").map_err(|_| missing("wifiqr_alloc(i32) -> i32"))?;
+    let render = instance.get_typed_func::<(i32, i32), i64>(&store, "wifiqr_render").map_err(|_| missing("wifiqr_render(i32, i32) -> i64"))?;
+
+    let input:Vec<u8> = serde_json::to_vec(input).unwrap();
+    let length:i32 = i32::try_from(input.len()).map_err(|_| PluginError::Interface(String::from("the input doesn't fit into the plugin's memory")))?;
+    let pointer:i32 = alloc.call(&mut store, length)?;
+    memory.write(&mut store, pointer as u32 as usize, &input)
+        .map_err(|_| PluginError::Interface(String::from("wifiqr_alloc returned memory out of bounds")))?;
+    let result:u64 = render.call(&mut store, (pointer, length))? as u64;
+    let (pointer, length) = ((result >> 32) as usize, (result & 0xFFFF_FFFF) as usize);
+    memory.data(&store).get(pointer..pointer + length).map(<[u8]>::to_vec)
+        .ok_or_else(|| PluginError::Interface(String::from("wifiqr_render returned memory out of bounds")))
+}
+
+/// Loads the plugin from `path` and renders with it.
+pub fn render_file(path: &Path, input: &Value) -> Result<Vec<u8>, PluginError> {
+    let wasm:Vec<u8> = std::fs::read(path).map_err(|err| PluginError::Io(path.to_path_buf(), err))?;
+    render(&wasm, input)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    /// Echoes its input, with memory allocated after the first page.
+    const ECHO: &str = r#"(module
+        (memory (export "memory") 2)
+        (func (export "wifiqr_alloc") (param i32) (result i32) i32.const 65536)
+        (func (export "wifiqr_render") (param i32 i32) (result i64)
+            (i64.or (i64.shl (i64.extend_i32_u (local.get 0)) (i64.const 32)) (i64.extend_i32_u (local.get 1)))))"#;
+    #[test]
+    fn test_render() {
+        let input:Value = serde_json::json!({"ssid": "Home", "matrix": [[true, false]]});
+        assert_eq!(render(ECHO.as_bytes(), &input).unwrap(), serde_json::to_vec(&input).unwrap());
+    }
+    #[test]
+    fn test_sandbox() {
+        let input:Value = serde_json::json!({});
+        let hanging:&str = r#"(module (memory (export "memory") 1)
+            (func (export "wifiqr_alloc") (param i32) (result i32) i32.const 0)
+            (func (export "wifiqr_render") (param i32 i32) (result i64) (loop (br 0)) i64.const 0))"#;
+        assert!(matches!(render_with_fuel(hanging.as_bytes(), &input, 1_000_000), Err(PluginError::Wasm(_))));
+        let importing:&str = r#"(module (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32))))"#;
+        assert!(matches!(render(importing.as_bytes(), &input), Err(PluginError::Interface(_))));
+        let out_of_bounds:&str = r#"(module (memory (export "memory") 1)
+            (func (export "wifiqr_alloc") (param i32) (result i32) i32.const 0)
+            (func (export "wifiqr_render") (param i32 i32) (result i64) i64.const -1))"#;
+        assert!(matches!(render(out_of_bounds.as_bytes(), &input), Err(PluginError::Interface(_))));
+        assert!(matches!(render(b"(module)", &input), Err(PluginError::Interface(_))));
+    }
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(parse_option("orientation=portrait=1"), Ok((String::from("orientation"), String::from("portrait=1"))));
+        assert!(parse_option("=x").is_err());
+        assert!(parse_option("portrait").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A local web server showing the table tent or a template as it is being designed. The page polls for changes
+//! of the template and branding files and reloads itself, so every save shows up right away.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+
+use crate::branding::{self, Branding};
+use crate::{assemble_qr_string, layout, pdf, svg, template, Args};
+
+/// How often the page asks whether the files changed, in milliseconds.
+const POLL_INTERVAL: u32 = 500;
+
+struct Response{
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>
+}
+impl Response {
+    fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response{ status: "200 OK", content_type, body: body.into() }
+    }
+}
+
+pub struct Preview<'a, F: Fn() -> Result<Branding, String>>{
+    pub args: &'a Args,
+    pub template: Option<&'a Path>,
+    /// Loads the branding with the options of the command line applied, which is repeated for every render.
+    pub load_branding: F
+}
+impl<'a, F: Fn() -> Result<Branding, String>> Preview<'a, F> {
+    fn watched_files(&self) -> Vec<PathBuf> {
+        let branding:PathBuf = self.args.branding.clone().unwrap_or_else(|| PathBuf::from(branding::DEFAULT_FILE_NAME));
+        self.template.map(Path::to_path_buf).into_iter().chain(std::iter::once(branding)).collect()
+    }
+    /// Changes whenever one of the watched files is saved, created or deleted.
+    fn version(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for path in self.watched_files() {
+            std::fs::metadata(path).and_then(|x| x.modified()).ok().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+    /// The page as HTML, with the script reloading it once the files changed.
+    fn page(&self) -> String {
+        let reload:String = format!("<script>setInterval(() => fetch(\"/version\").then(x => x.text()).then(x => {{ if (x !== \"{}\") location.reload(); }}).catch(() => {{}}), {});</script>",
+            self.version(), POLL_INTERVAL);
+        let body:String = match self.template {
+            None => String::from("<embed src=\"/poster.pdf\" type=\"application/pdf\" style=\"display:block;width:100%;height:100vh\">"),
+            Some(path) => match self.render_template(path) {
+                // HTML templates are the whole page, the script goes into them.
+                Ok(html) if is_html(path) => return inject(&html, &reload),
+                Ok(rendered) if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("svg")) => rendered,
+                Ok(rendered) => format!("<pre>{}</pre>", svg::escape(&rendered)),
+                Err(err) => format!("<pre style=\"color:#b00020\">{}</pre>", svg::escape(&err))
+            }
+        };
+        format!("<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>WIFI-QR preview</title></head><body style=\"margin:0\">{}{}</body></html>", body, reload)
+    }
+    fn render_template(&self, path: &Path) -> Result<String, String> {
+        let branding:Branding = (self.load_branding)()?;
+        let source:String = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let matrix:Vec<Vec<bool>> = crate::matrix(self.args, &assemble_qr_string(self.args)).map_err(|err| err.message)?;
+        template::render(&source, path, &template::context(self.args, &matrix, &branding)).map_err(|err| format!("Failed to render {}: {}", path.display(), err))
+    }
+    fn poster(&self) -> Result<Vec<u8>, String> {
+        let branding:Branding = (self.load_branding)()?;
+        let matrix:Vec<Vec<bool>> = crate::matrix(self.args, &assemble_qr_string(self.args)).map_err(|err| err.message)?;
+        Ok(pdf::write_document(&layout::table_tent(self.args, &matrix, &branding, layout::PaperSize::A4)))
+    }
+    fn respond(&self, path: &str) -> Response {
+        // Query strings are only used to get around caches.
+        match path.split('?').next().unwrap_or_default() {
+            "/" => Response::ok("text/html; charset=utf-8", self.page()),
+            "/version" => Response::ok("text/plain", self.version().to_string()),
+            "/poster.pdf" => match self.poster() {
+                Ok(pdf) => Response::ok("application/pdf", pdf),
+                Err(err) => Response{ status: "500 Internal Server Error", content_type: "text/plain; charset=utf-8", body: err.into_bytes() }
+            },
+            _ => Response{ status: "404 Not Found", content_type: "text/plain", body: b"Not found".to_vec() }
+        }
+    }
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut request_line:String = String::new();
+        reader.read_line(&mut request_line)?;
+        // The headers are of no interest, but have to be read before answering.
+        let mut header:String = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+        let path:&str = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let response:Response = self.respond(path);
+        let mut stream = stream;
+        write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            response.status, response.content_type, response.body.len())?;
+        stream.write_all(&response.body)
+    }
+    /// Answers requests until the process is stopped. The listener should be bound to a loopback address, as the
+    /// pages contain the password.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            if let Err(err) = stream.and_then(|x| self.handle(x)) {
+                crate::diagnostics::warn("request-failed", format!("failed to answer a preview request: {}", err));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_html(path: &Path) -> bool {
+    path.extension().is_some_and(|x| x.eq_ignore_ascii_case("html") || x.eq_ignore_ascii_case("htm"))
+}
+
+/// Inserts the script before the end of the body, or appends it to documents without one.
+fn inject(html: &str, script: &str) -> String {
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(index) => format!("{}{}{}", &html[..index], script, &html[index..]),
+        None => format!("{}{}", html, script)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn test_args() -> Args {
+        Args{ branding: Some(PathBuf::from("/nonexistent/branding.toml")), ..crate::tests::network("Martin Router King", Some("password"), Some(Security::Wpa2)) }
+    }
+    #[test]
+    fn test_inject() {
+        assert_eq!(inject("<html><BODY>x</BODY></html>", "<script></script>"), "<html><BODY>x<script></script></BODY></html>");
+        assert_eq!(inject("<p>x</p>", "<script></script>"), "<p>x</p><script></script>");
+    }
+    #[test]
+    fn test_respond() {
+        let args = test_args();
+        let template:PathBuf = std::env::temp_dir().join(format!("wifiqr-preview-{}.html", std::process::id()));
+        std::fs::write(&template, "<body><h1>{{ ssid }}</h1></body>").unwrap();
+        let preview = Preview{ args: &args, template: Some(&template), load_branding: || Ok(Branding::default()) };
+        let page = String::from_utf8(preview.respond("/").body).unwrap();
+        assert!(page.starts_with("<body><h1>Martin Router King</h1><script>"));
+        assert!(page.contains(&format!("x !== \"{}\"", preview.version())));
+        assert_eq!(String::from_utf8(preview.respond("/version?1").body).unwrap(), preview.version().to_string());
+        assert!(preview.respond("/poster.pdf").body.starts_with(b"%PDF"));
+        assert_eq!(preview.respond("/favicon.ico").status, "404 Not Found");
+
+        std::fs::write(&template, "{{ ssid").unwrap();
+        assert!(String::from_utf8(preview.respond("/").body).unwrap().contains("Failed to render"));
+        let _ = std::fs::remove_file(&template);
+    }
+}
@@ -0,0 +1,130 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Sending the card straight to a printer, for `print`: CUPS gets the PDF through `lp` on Linux and macOS, which
+//! converts it for the printer, and the Windows spooler as a raw job, which the printer has to take as PDF itself,
+//! as most network printers do.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum PrintError{
+    #[cfg(not(windows))]
+    Io(String, std::io::Error),
+    #[cfg(not(windows))]
+    CommandFailed(String, String),
+    /// A function of the spooler failed with the error.
+    #[cfg(windows)]
+    Spooler(&'static str, std::io::Error)
+}
+impl fmt::Display for PrintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(not(windows))]
+            PrintError::Io(program, err) => write!(f, "failed to run {}: {}", program, err),
+            #[cfg(not(windows))]
+            PrintError::CommandFailed(program, stderr) => write!(f, "{} failed: {}", program, stderr.trim()),
+            #[cfg(windows)]
+            PrintError::Spooler(function, err) => write!(f, "{} failed: {}", function, err)
+        }
+    }
+}
+
+/// The arguments of `lp` for a job named `title`, which reads the document from stdin.
+#[cfg(not(windows))]
+fn lp_args(title: &str, printer: Option<&str>, copies: u16) -> Vec<String> {
+    let mut args:Vec<String> = vec![String::from("-t"), String::from(title), String::from("-n"), copies.to_string()];
+    if let Some(printer) = printer {
+        args.extend([String::from("-d"), String::from(printer)]);
+    }
+    args
+}
+
+/// Prints `copies` of the PDF on the printer, or on the default one, as a job named `title`.
+#[cfg(not(windows))]
+pub fn print(pdf: &[u8], title: &str, printer: Option<&str>, copies: u16) -> Result<(), PrintError> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("lp").args(lp_args(title, printer, copies)).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn()
+        .map_err(|err| PrintError::Io(String::from("lp"), err))?;
+    child.stdin.take().expect("stdin is piped").write_all(pdf).map_err(|err| PrintError::Io(String::from("lp"), err))?;
+    let output = child.wait_with_output().map_err(|err| PrintError::Io(String::from("lp"), err))?;
+    if !output.status.success() {
+        return Err(PrintError::CommandFailed(String::from("lp"), String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(())
+}
+/// Prints `copies` of the PDF on the printer, or on the default one, as jobs named `title`.
+#[cfg(windows)]
+pub fn print(pdf: &[u8], title: &str, printer: Option<&str>, copies: u16) -> Result<(), PrintError> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Graphics::Printing::*;
+    let wide = |text: &str| text.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let spooler = |function: &'static str| PrintError::Spooler(function, std::io::Error::last_os_error());
+    let name:Vec<u16> = match printer {
+        Some(printer) => wide(printer),
+        None => {
+            let mut length:u32 = 0;
+            // SAFETY: The first call only asks for the length, the second writes at most that many characters.
+            unsafe {
+                GetDefaultPrinterW(null_mut(), &mut length);
+                let mut name:Vec<u16> = vec![0; length as usize];
+                if GetDefaultPrinterW(name.as_mut_ptr(), &mut length) == 0 {
+                    return Err(spooler("GetDefaultPrinterW"));
+                }
+                name
+            }
+        }
+    };
+    let mut title:Vec<u16> = wide(title);
+    let mut datatype:Vec<u16> = wide("RAW");
+    // SAFETY: The strings are null-terminated and outlive the calls, and the printer is closed on every path.
+    unsafe {
+        let mut handle = null_mut();
+        if OpenPrinterW(name.as_ptr(), &mut handle, null()) == 0 {
+            return Err(spooler("OpenPrinterW"));
+        }
+        let document = DOC_INFO_1W{ pDocName: title.as_mut_ptr(), pOutputFile: null_mut(), pDatatype: datatype.as_mut_ptr() };
+        let mut result:Result<(), PrintError> = Ok(());
+        for _ in 0..copies {
+            if StartDocPrinterW(handle, 1, &document) == 0 {
+                result = Err(spooler("StartDocPrinterW"));
+                break;
+            }
+            let mut written:u32 = 0;
+            let sent:bool = WritePrinter(handle, pdf.as_ptr().cast(), pdf.len() as u32, &mut written) != 0 && written as usize == pdf.len();
+            if !sent {
+                result = Err(spooler("WritePrinter"));
+            }
+            EndDocPrinter(handle);
+            if !sent {
+                break;
+            }
+        }
+        ClosePrinter(handle);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    #[cfg(not(windows))]
+    #[test]
+    fn test_lp_args() {
+        assert_eq!(super::lp_args("Wi-Fi Home", None, 1), ["-t", "Wi-Fi Home", "-n", "1"]);
+        assert_eq!(super::lp_args("Wi-Fi Home", Some("Front_Desk"), 3), ["-t", "Wi-Fi Home", "-n", "3", "-d", "Front_Desk"]);
+    }
+}
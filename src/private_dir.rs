@@ -0,0 +1,103 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Temporary directories for files handed to other programs, such as the QR-Code attached by a password manager.
+//! They contain the credentials of the network, so only the current user can read them.
+
+use std::fs::{DirBuilder, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A directory below the system's temporary directory, removed with its files when dropped.
+pub struct PrivateDir{
+    path: PathBuf,
+    files: Vec<PathBuf>,
+    shred: bool
+}
+impl PrivateDir {
+    /// Creates a directory with an unpredictable name, failing instead of reusing one that already exists. With
+    /// `shred`, the files are overwritten with zeros before they are deleted.
+    pub fn create(shred: bool) -> io::Result<Self> {
+        // RandomState is seeded randomly per process, which is enough to make the name unpredictable.
+        let random:u64 = std::collections::hash_map::RandomState::new().build_hasher().finish();
+        let path:PathBuf = std::env::temp_dir().join(format!("wifiqr-{}-{:016x}", std::process::id(), random));
+        let builder = DirBuilder::new();
+        #[cfg(unix)]
+        let builder = {
+            let mut builder = builder;
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder
+        };
+        builder.create(&path)?;
+        Ok(PrivateDir{ path, files: Vec::new(), shred })
+    }
+    /// Writes a new file readable only by the current user, and returns its path.
+    pub fn write(&mut self, name: &str, contents: &[u8]) -> io::Result<PathBuf> {
+        let path:PathBuf = self.path.join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file:File = options.open(&path)?;
+        self.files.push(path.clone());
+        file.write_all(contents)?;
+        Ok(path)
+    }
+}
+/// Overwrites the file in place. This can't reach copies that copy-on-write filesystems or SSDs keep elsewhere,
+/// but keeps the credentials out of the freed blocks of plain disks.
+fn shred(path: &Path) -> io::Result<()> {
+    let length:u64 = std::fs::metadata(path)?.len();
+    let mut file:File = OpenOptions::new().write(true).open(path)?;
+    io::copy(&mut io::repeat(0).take(length), &mut file)?;
+    file.sync_all()
+}
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        if self.shred {
+            for file in &self.files {
+                if let Err(err) = shred(file) {
+                    crate::diagnostics::warn("shred-failed", format!("failed to shred {}: {}", file.display(), err));
+                }
+            }
+        }
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_private_dir() {
+        let mut directory = PrivateDir::create(true).unwrap();
+        let path = directory.write("secret.txt", b"password").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"password");
+        assert!(directory.write("secret.txt", b"again").is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(path.parent().unwrap()).unwrap().permissions().mode() & 0o777, 0o700);
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        shred(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0; 8]);
+        let root = path.parent().unwrap().to_path_buf();
+        drop(directory);
+        assert!(!root.exists());
+    }
+}
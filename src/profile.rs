@@ -0,0 +1,652 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Reading and writing the network profiles of operating systems, for `convert`: Windows WLAN profiles as
+//! exported by `netsh`, NetworkManager keyfiles, `wpa_supplicant.conf`, iwd network files and the Open Network
+//! Configuration of ChromeOS and Android MDMs. Every profile is read into the fields of a `WIFI:` payload (SSID,
+//! password, security standard and whether the network is hidden), which is all the formats have in common, and
+//! written from them, so `WIFI:` payloads themselves are a format too. Enterprise networks can't be converted yet.
+
+use std::fmt;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+use serde_json::{json, Value};
+
+use crate::batch::Network;
+use crate::{android, assemble_qr_string, inspect, resolve_security, svg, wep, Args, Security};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum ProfileFormat{
+    /// Windows WLAN profile, as exported by `netsh wlan export profile key=clear`.
+    Netsh,
+    /// NetworkManager keyfile, as in `/etc/NetworkManager/system-connections/`.
+    Nmconnection,
+    /// The `network` blocks of a `wpa_supplicant.conf`.
+    WpaSupplicant,
+    /// iwd network file, as in `/var/lib/iwd/`, named after the SSID.
+    Iwd,
+    /// Open Network Configuration, including the `openNetworkConfiguration` of Android Management API policies.
+    Onc,
+    /// The `WifiConfigStore.xml` of Android, which can only be read.
+    Android,
+    /// `WIFI:` payloads, one per line.
+    Wifi
+}
+impl ProfileFormat {
+    /// Tells the format from the extension of the file, and its contents where extensions are ambiguous.
+    pub fn detect(path: &Path, contents: &str) -> Option<Self> {
+        let extension:String = path.extension().and_then(|x| x.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match extension.as_str() {
+            "nmconnection" => Some(ProfileFormat::Nmconnection),
+            "psk" | "open" | "8021x" => Some(ProfileFormat::Iwd),
+            "onc" | "json" => Some(ProfileFormat::Onc),
+            "xml" if contents.contains("<WifiConfigStoreData") => Some(ProfileFormat::Android),
+            "xml" => Some(ProfileFormat::Netsh),
+            _ if contents.trim_start().starts_with("WIFI:") => Some(ProfileFormat::Wifi),
+            _ if contents.contains("network={") => Some(ProfileFormat::WpaSupplicant),
+            _ => None
+        }
+    }
+    /// Whether a file of the format holds several networks, instead of one file being written per network.
+    pub fn holds_several(self) -> bool {
+        !matches!(self, ProfileFormat::Netsh | ProfileFormat::Nmconnection | ProfileFormat::Iwd)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfileError{
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    Android(android::AndroidError),
+    /// The profile doesn't describe a network that can be converted.
+    Invalid(String),
+    /// The format can only be read.
+    ReadOnly(ProfileFormat)
+}
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProfileError::Xml(err) => write!(f, "{}", err),
+            ProfileError::Json(err) => write!(f, "{}", err),
+            ProfileError::Android(err) => write!(f, "{}", err),
+            ProfileError::Invalid(message) => write!(f, "{}", message),
+            ProfileError::ReadOnly(format) => write!(f, "{:?} profiles can only be read", format)
+        }
+    }
+}
+impl From<roxmltree::Error> for ProfileError {
+    fn from(err: roxmltree::Error) -> Self {
+        ProfileError::Xml(err)
+    }
+}
+impl From<serde_json::Error> for ProfileError {
+    fn from(err: serde_json::Error) -> Self {
+        ProfileError::Json(err)
+    }
+}
+impl From<String> for ProfileError {
+    fn from(message: String) -> Self {
+        ProfileError::Invalid(message)
+    }
+}
+impl From<&str> for ProfileError {
+    fn from(message: &str) -> Self {
+        ProfileError::Invalid(String::from(message))
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len()).step_by(2).map(|i| hex.get(i..i + 2).and_then(|x| u8::from_str_radix(x, 16).ok())).collect()
+}
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02X}", x)).collect()
+}
+/// Decodes an SSID stored as hex digits, which profiles fall back to for those that aren't printable.
+fn hex_ssid(hex: &str) -> Result<String, String> {
+    decode_hex(hex).and_then(|x| String::from_utf8(x).ok()).ok_or_else(|| format!("the SSID {} isn't hex-encoded UTF-8", hex))
+}
+/// Pre-shared keys given as 64 hex digits instead of a passphrase.
+fn is_raw_key(password: &str) -> bool {
+    password.len() == 64 && password.chars().all(|x| x.is_ascii_hexdigit())
+}
+
+/// The security standard and password of a network with a WEP key or a WPA pre-shared key.
+fn secured(security: &str, password: Option<&str>) -> Result<(Option<String>, Option<String>), String> {
+    match password {
+        Some(password) if !password.is_empty() => Ok((Some(String::from(security)), Some(String::from(password)))),
+        _ => Err(String::from("the key is missing"))
+    }
+}
+
+/// Reads the networks of a profile, returning them and why the others were skipped. iwd profiles take the SSID
+/// from their file name.
+pub fn read(format: ProfileFormat, path: &Path, contents: &str) -> Result<(Vec<Network>, Vec<String>), ProfileError> {
+    let results:Vec<Result<Network, String>> = match format {
+        ProfileFormat::Netsh => vec![Ok(read_netsh(contents)?)],
+        ProfileFormat::Nmconnection => vec![Ok(read_nmconnection(contents)?)],
+        ProfileFormat::Iwd => vec![Ok(read_iwd(path, contents)?)],
+        ProfileFormat::WpaSupplicant => read_wpa_supplicant(contents),
+        ProfileFormat::Onc => read_onc(contents)?,
+        ProfileFormat::Android => return android::read_networks(contents).map_err(ProfileError::Android),
+        ProfileFormat::Wifi => contents.lines().map(str::trim).filter(|x| !x.is_empty())
+            .map(|x| inspect::parse_wifi(x).map_err(|err| format!("{}: {}", x, err))).collect()
+    };
+    let mut networks:Vec<Network> = Vec::new();
+    let mut skipped:Vec<String> = Vec::new();
+    for result in results {
+        match result {
+            Ok(network) => networks.push(network),
+            Err(err) => skipped.push(err)
+        }
+    }
+    Ok((networks, skipped))
+}
+
+/// Writes the networks in the format, returning the default names and the contents of the files. Formats that
+/// hold a single network get a file per network.
+pub fn write(format: ProfileFormat, networks: &[Args]) -> Result<Vec<(String, String)>, ProfileError> {
+    match format {
+        ProfileFormat::Netsh => networks.iter().map(|x| Ok((format!("{}.xml", x.ssid), write_netsh(x)))).collect(),
+        ProfileFormat::Nmconnection => networks.iter().map(|x| Ok((format!("{}.nmconnection", x.ssid), write_nmconnection(x)))).collect(),
+        ProfileFormat::Iwd => networks.iter().map(write_iwd).collect(),
+        ProfileFormat::WpaSupplicant => Ok(vec![(String::from("wpa_supplicant.conf"), networks.iter().map(write_wpa_supplicant).collect::<Vec<String>>().join("\n"))]),
+        ProfileFormat::Onc => {
+            let onc:Value = json!({
+                "Type": "UnencryptedConfiguration",
+                "NetworkConfigurations": networks.iter().map(onc_configuration).collect::<Vec<Value>>()
+            });
+            Ok(vec![(String::from("networks.onc"), serde_json::to_string_pretty(&onc).unwrap() + "\n")])
+        },
+        ProfileFormat::Android => Err(ProfileError::ReadOnly(format)),
+        ProfileFormat::Wifi => Ok(vec![(String::from("networks.txt"), networks.iter().map(|x| assemble_qr_string(x) + "\n").collect())])
+    }
+}
+
+/// The text of the element at `path` below `node`.
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    path.iter().try_fold(node, |node, name| node.children().find(|x| x.has_tag_name(*name)))?.text()
+}
+
+pub fn read_netsh(contents: &str) -> Result<Network, ProfileError> {
+    let document = Document::parse(contents)?;
+    let profile = document.root_element();
+    if !profile.has_tag_name("WLANProfile") {
+        return Err("the file isn't a WLAN profile".into());
+    }
+    let ssid:String = match (text(profile, &["SSIDConfig", "SSID", "hex"]), text(profile, &["SSIDConfig", "SSID", "name"])) {
+        (Some(hex), _) => hex_ssid(hex)?,
+        (None, Some(name)) => String::from(name),
+        (None, None) => return Err("the SSID is missing".into())
+    };
+    let name = |err: &str| ProfileError::Invalid(format!("{}: {}", ssid, err));
+    let hidden:bool = text(profile, &["SSIDConfig", "nonBroadcast"]) == Some("true");
+    let security:&[&str] = &["MSM", "security"];
+    let field = |path: &[&str]| text(profile, &[security, path].concat());
+    if field(&["sharedKey", "protected"]) == Some("true") {
+        return Err(name("the key is encrypted, export the profile with key=clear"));
+    }
+    let key:Option<&str> = field(&["sharedKey", "keyMaterial"]);
+    let authentication:&str = field(&["authEncryption", "authentication"]).unwrap_or("open");
+    let encryption:&str = field(&["authEncryption", "encryption"]).unwrap_or("none");
+    let (security, password) = match (authentication, encryption) {
+        ("open", "none") => (None, None),
+        ("open" | "shared", "WEP") => secured("wep", key).map_err(|err| name(&err))?,
+        ("WPAPSK", _) => secured("wpa", key).map_err(|err| name(&err))?,
+        ("WPA2PSK", _) => secured("wpa2", key).map_err(|err| name(&err))?,
+        ("WPA3SAE", _) => secured("wpa3", key).map_err(|err| name(&err))?,
+        ("WPA" | "WPA2" | "WPA3" | "WPA3ENT" | "WPA3ENT192", _) => return Err(name("enterprise networks can't be converted yet")),
+        ("OWE", _) => return Err(name("enhanced open networks can't be converted yet")),
+        (authentication, encryption) => return Err(name(&format!("unknown security {}/{}", authentication, encryption)))
+    };
+    Ok(Network{ ssid, password, security, hidden })
+}
+
+pub fn write_netsh(args: &Args) -> String {
+    let password:&str = args.psw.as_deref().unwrap_or_default();
+    let (authentication, encryption) = match resolve_security(args) {
+        None => ("open", "none"),
+        Some(Security::Wep) => ("open", "WEP"),
+        Some(Security::Wpa) => ("WPAPSK", "TKIP"),
+        // Windows joins transition-mode networks with WPA3 by itself where it can.
+        Some(Security::Wpa2 | Security::Wpa2Wpa3) => ("WPA2PSK", "AES"),
+        Some(Security::Wpa3) => ("WPA3SAE", "AES")
+    };
+    let shared_key:String = if authentication == "open" && encryption == "none" {String::new()}else{
+        let key_type:&str = if encryption == "WEP" || is_raw_key(password) {"networkKey"}else{"passPhrase"};
+        let key_index:String = if encryption == "WEP" {format!("\n\t\t\t<keyIndex>{}</keyIndex>", args.wep.key_index())}else{String::new()};
+        format!("\n\t\t\t<sharedKey>\n\t\t\t\t<keyType>{}</keyType>\n\t\t\t\t<protected>false</protected>\n\t\t\t\t<keyMaterial>{}</keyMaterial>\n\t\t\t</sharedKey>{}",
+            key_type, svg::escape(password), key_index)
+    };
+    format!("<?xml version=\"1.0\"?>
+<WLANProfile xmlns=\"http://www.microsoft.com/networking/WLAN/profile/v1\">
+\t<name>{name}</name>
+\t<SSIDConfig>
+\t\t<SSID>
+\t\t\t<hex>{}</hex>
+\t\t\t<name>{name}</name>
+\t\t</SSID>
+\t\t<nonBroadcast>{}</nonBroadcast>
+\t</SSIDConfig>
+\t<connectionType>ESS</connectionType>
+\t<connectionMode>auto</connectionMode>
+\t<MSM>
+\t\t<security>
+\t\t\t<authEncryption>
+\t\t\t\t<authentication>{}</authentication>
+\t\t\t\t<encryption>{}</encryption>
+\t\t\t\t<useOneX>false</useOneX>
+\t\t\t</authEncryption>{}
+\t\t</security>
+\t</MSM>
+</WLANProfile>
+", encode_hex(&crate::hex_ssid::bytes(args)), args.hidden, authentication, encryption, shared_key, name = svg::escape(&args.ssid))
+}
+
+/// The entries of a key file, as used by NetworkManager and iwd, with their section.
+fn key_file(contents: &str) -> Vec<(String, String, String)> {
+    let mut section:String = String::new();
+    let mut entries:Vec<(String, String, String)> = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            section = String::from(name);
+        }else if let Some((key, value)) = line.split_once('=') {
+            entries.push((section.clone(), String::from(key.trim()), unescape_key_file(value.trim_start())));
+        }
+    }
+    entries
+}
+fn entry<'a>(entries: &'a [(String, String, String)], section: &str, key: &str) -> Option<&'a str> {
+    entries.iter().rev().find(|(x, y, _)| x == section && y == key).map(|(_, _, x)| x.as_str())
+}
+fn unescape_key_file(value: &str) -> String {
+    let mut unescaped:String = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(character) = chars.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\')
+        }
+    }
+    unescaped
+}
+fn escape_key_file(value: &str) -> String {
+    let escaped:String = value.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t").replace('\r', "\\r");
+    match escaped.strip_prefix(' ') {
+        Some(rest) => format!("\\s{}", rest),
+        None => escaped
+    }
+}
+
+fn read_nmconnection(contents: &str) -> Result<Network, ProfileError> {
+    let entries = key_file(contents);
+    read_nm_settings(|section, key| entry(&entries, section, key))
+}
+/// Reads the network of a NetworkManager connection from its settings, looked up by setting and property as keyfiles
+/// and `nmcli` name them, e.g. `802-11-wireless` and `ssid`.
+pub fn read_nm_settings<'a>(value: impl Fn(&str, &str) -> Option<&'a str>) -> Result<Network, ProfileError> {
+    if value("connection", "type").is_some_and(|x| x != "wifi" && x != "802-11-wireless") {
+        return Err("the connection isn't a Wi-Fi network".into());
+    }
+    let ssid:&str = value("wifi", "ssid").or(value("802-11-wireless", "ssid")).ok_or("the SSID is missing")?;
+    // Older keyfiles list the bytes of the SSID, e.g. 72;111;109;101;.
+    let bytes:Option<Vec<u8>> = ssid.strip_suffix(';').and_then(|x| x.split(';').map(|x| x.parse().ok()).collect());
+    let ssid:String = match bytes {
+        Some(bytes) => String::from_utf8(bytes).map_err(|_| "the SSID isn't UTF-8")?,
+        None => String::from(ssid)
+    };
+    let name = |err: &str| ProfileError::Invalid(format!("{}: {}", ssid, err));
+    // nmcli shows booleans as yes and no.
+    let hidden:bool = matches!(value("wifi", "hidden").or(value("802-11-wireless", "hidden")), Some("true" | "yes"));
+    let wifi_security = |key: &str| value("wifi-security", key).or(value("802-11-wireless-security", key));
+    let (security, password) = match wifi_security("key-mgmt") {
+        None => (None, None),
+        Some("none") => {
+            let index:&str = wifi_security("wep-tx-keyidx").unwrap_or("0");
+            secured("wep", wifi_security(&format!("wep-key{}", index))).map_err(|err| name(&err))?
+        },
+        Some("wpa-psk") => secured(if wifi_security("proto") == Some("wpa") {"wpa"}else{"wpa2"}, wifi_security("psk")).map_err(|err| name(&err))?,
+        Some("sae") => secured("wpa3", wifi_security("psk")).map_err(|err| name(&err))?,
+        Some("wpa-eap" | "wpa-eap-suite-b-192" | "ieee8021x") => return Err(name("enterprise networks can't be converted yet")),
+        Some("owe") => return Err(name("enhanced open networks can't be converted yet")),
+        Some(other) => return Err(name(&format!("unknown key management {}", other)))
+    };
+    Ok(Network{ ssid, password, security, hidden })
+}
+
+fn write_nmconnection(args: &Args) -> String {
+    let mut keyfile:String = format!("[connection]\nid={}\ntype=wifi\n\n[wifi]\nmode=infrastructure\nssid={}\n",
+        escape_key_file(&args.ssid), escape_key_file(&args.ssid));
+    if args.hidden {
+        keyfile += "hidden=true\n";
+    }
+    let password:String = escape_key_file(args.psw.as_deref().unwrap_or_default());
+    match resolve_security(args) {
+        None => {},
+        Some(Security::Wep) => {
+            let index:u8 = args.wep.key_index();
+            keyfile += &format!("\n[wifi-security]\nkey-mgmt=none\nwep-key-type=1\nwep-tx-keyidx={}\nwep-key{}={}\n", index, index, password)
+        },
+        Some(Security::Wpa) => keyfile += &format!("\n[wifi-security]\nkey-mgmt=wpa-psk\nproto=wpa\npsk={}\n", password),
+        Some(Security::Wpa2) => keyfile += &format!("\n[wifi-security]\nkey-mgmt=wpa-psk\npsk={}\n", password),
+        Some(Security::Wpa3) => keyfile += &format!("\n[wifi-security]\nkey-mgmt=sae\npsk={}\n", password),
+        // NetworkManager picks SAE for wpa-psk connections by itself if the access point offers it.
+        Some(Security::Wpa2Wpa3) => keyfile += &format!("\n[wifi-security]\nkey-mgmt=wpa-psk\npsk={}\n", password)
+    }
+    keyfile + "\n[ipv4]\nmethod=auto\n\n[ipv6]\nmethod=auto\n"
+}
+
+/// iwd names files after SSIDs made of letters, digits, spaces, `-` and `_`, and after the hex digits of others.
+fn iwd_file_name(ssid: &str) -> String {
+    if !ssid.is_empty() && ssid.chars().all(|x| x.is_ascii_alphanumeric() || " -_".contains(x)) {
+        String::from(ssid)
+    }else{
+        format!("={}", encode_hex(ssid.as_bytes()).to_lowercase())
+    }
+}
+
+fn read_iwd(path: &Path, contents: &str) -> Result<Network, ProfileError> {
+    let stem:&str = path.file_stem().and_then(|x| x.to_str()).ok_or("the file isn't named after an SSID")?;
+    let ssid:String = match stem.strip_prefix('=') {
+        Some(hex) => hex_ssid(hex)?,
+        None => String::from(stem)
+    };
+    let name = |err: &str| ProfileError::Invalid(format!("{}: {}", ssid, err));
+    let entries = key_file(contents);
+    let hidden:bool = entry(&entries, "Settings", "Hidden") == Some("true");
+    let (security, password) = match path.extension().and_then(|x| x.to_str()) {
+        Some("open") => (None, None),
+        // iwd picks WPA3 by itself where the network offers it.
+        Some("psk") => secured("wpa2", entry(&entries, "Security", "Passphrase").or(entry(&entries, "Security", "PreSharedKey"))).map_err(|err| name(&err))?,
+        Some("8021x") => return Err(name("enterprise networks can't be converted yet")),
+        _ => return Err(name("iwd profiles end in .psk, .open or .8021x"))
+    };
+    Ok(Network{ ssid, password, security, hidden })
+}
+
+fn write_iwd(args: &Args) -> Result<(String, String), ProfileError> {
+    let password:&str = args.psw.as_deref().unwrap_or_default();
+    let (extension, mut contents) = match resolve_security(args) {
+        None => ("open", String::new()),
+        Some(Security::Wep) => return Err(ProfileError::Invalid(format!("{}: iwd doesn't support WEP", args.ssid))),
+        Some(_) if is_raw_key(password) => ("psk", format!("[Security]\nPreSharedKey={}\n", password)),
+        Some(_) => ("psk", format!("[Security]\nPassphrase={}\n", escape_key_file(password)))
+    };
+    if args.hidden {
+        contents += &format!("{}[Settings]\nHidden=true\n", if contents.is_empty() {""}else{"\n"});
+    }
+    Ok((format!("{}.{}", iwd_file_name(&args.ssid), extension), contents))
+}
+
+/// The `network={...}` blocks of a `wpa_supplicant.conf`.
+fn read_wpa_supplicant(contents: &str) -> Vec<Result<Network, String>> {
+    let mut networks:Vec<Result<Network, String>> = Vec::new();
+    let mut block:Option<Vec<(String, String)>> = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if line == "}" {
+            if let Some(fields) = block.take() {
+                networks.push(wpa_supplicant_network(&fields));
+            }
+        }else if let Some(fields) = &mut block {
+            if let Some((key, value)) = line.split_once('=') {
+                fields.push((String::from(key.trim()), String::from(value.trim())));
+            }
+        }else if line.split_whitespace().collect::<String>() == "network={" {
+            block = Some(Vec::new());
+        }
+    }
+    networks
+}
+
+fn wpa_supplicant_network(fields: &[(String, String)]) -> Result<Network, String> {
+    let value = |key: &str| fields.iter().rev().find(|(x, _)| x == key).map(|(_, x)| x.as_str());
+    // Strings are quoted, except for SSIDs given as hex digits, raw keys and hex WEP keys.
+    let quoted = |value: &str| value.strip_prefix('"').and_then(|x| x.strip_suffix('"')).map(String::from);
+    let ssid:&str = value("ssid").ok_or("a network has no ssid")?;
+    let ssid:String = match quoted(ssid) {
+        Some(ssid) => ssid,
+        None => hex_ssid(ssid)?
+    };
+    let name = |err: &str| format!("{}: {}", ssid, err);
+    let key = |key: &str| value(key).map(|x| quoted(x).unwrap_or_else(|| String::from(x)));
+    let hidden:bool = value("scan_ssid") == Some("1");
+    let key_management:Vec<&str> = value("key_mgmt").unwrap_or("WPA-PSK WPA-EAP").split_whitespace().collect();
+    let psk:Option<String> = key("psk").or(key("sae_password"));
+    let (security, password) = if psk.is_some() && key_management.iter().any(|x| ["WPA-PSK", "WPA-PSK-SHA256", "SAE"].contains(x)) {
+        let security:&str = if !key_management.iter().any(|x| x.starts_with("WPA-PSK")) {
+            "wpa3"
+        }else if key_management.contains(&"SAE") {
+            "wpa2-wpa3"
+        }else if value("proto").is_some_and(|x| x.split_whitespace().all(|x| x == "WPA")) {
+            "wpa"
+        }else{
+            "wpa2"
+        };
+        secured(security, psk.as_deref()).map_err(|err| name(&err))?
+    }else if key_management.iter().any(|x| x.starts_with("WPA-EAP") || *x == "IEEE8021X") {
+        return Err(name("enterprise networks can't be converted yet"));
+    }else if key_management.contains(&"OWE") {
+        return Err(name("enhanced open networks can't be converted yet"));
+    }else if key_management.contains(&"NONE") {
+        match key(&format!("wep_key{}", value("wep_tx_keyidx").unwrap_or("0"))) {
+            Some(key) => (Some(String::from("wep")), Some(key)),
+            None => (None, None)
+        }
+    }else{
+        return Err(name("the psk is missing"));
+    };
+    Ok(Network{ ssid, password, security, hidden })
+}
+
+fn write_wpa_supplicant(args: &Args) -> String {
+    let mut block:String = format!("network={{\n\tssid=\"{}\"\n", args.ssid);
+    if args.hidden {
+        block += "\tscan_ssid=1\n";
+    }
+    let password:&str = args.psw.as_deref().unwrap_or_default();
+    let psk:String = if is_raw_key(password) {String::from(password)}else{format!("\"{}\"", password)};
+    block += &match resolve_security(args) {
+        None => String::from("\tkey_mgmt=NONE\n"),
+        Some(Security::Wep) => {
+            let hex:bool = wep::is_hex(password);
+            let index:u8 = args.wep.key_index();
+            format!("\tkey_mgmt=NONE\n\twep_key{}={}\n\twep_tx_keyidx={}\n", index, if hex {String::from(password)}else{format!("\"{}\"", password)}, index)
+        },
+        Some(Security::Wpa) => format!("\tkey_mgmt=WPA-PSK\n\tproto=WPA\n\tpsk={}\n", psk),
+        Some(Security::Wpa2) => format!("\tkey_mgmt=WPA-PSK\n\tpsk={}\n", psk),
+        // SAE requires management frame protection.
+        Some(Security::Wpa3) => format!("\tkey_mgmt=SAE\n\tieee80211w=2\n\tpsk={}\n", psk),
+        // Management frame protection is optional in transition mode, WPA2 devices can't use it.
+        Some(Security::Wpa2Wpa3) => format!("\tkey_mgmt=WPA-PSK SAE\n\tieee80211w=1\n\tpsk={}\n", psk)
+    };
+    block + "}\n"
+}
+
+fn read_onc(contents: &str) -> Result<Vec<Result<Network, String>>, ProfileError> {
+    let onc:Value = serde_json::from_str(contents)?;
+    let onc:&Value = onc.get("openNetworkConfiguration").unwrap_or(&onc);
+    if onc["Type"] == "EncryptedConfiguration" {
+        return Err("the ONC file is encrypted, export it unencrypted".into());
+    }
+    let configurations:&Vec<Value> = onc["NetworkConfigurations"].as_array().ok_or("the file has no NetworkConfigurations")?;
+    Ok(configurations.iter().filter(|x| x["Type"] == "WiFi").map(onc_network).collect())
+}
+
+fn onc_network(configuration: &Value) -> Result<Network, String> {
+    let wifi:&Value = &configuration["WiFi"];
+    let ssid:String = match (wifi["HexSSID"].as_str(), wifi["SSID"].as_str()) {
+        (Some(hex), _) => hex_ssid(hex)?,
+        (None, Some(ssid)) => String::from(ssid),
+        (None, None) => return Err(format!("{}: the SSID is missing", configuration["Name"].as_str().unwrap_or("a network")))
+    };
+    let name = |err: &str| format!("{}: {}", ssid, err);
+    let passphrase:Option<&str> = wifi["Passphrase"].as_str();
+    let (security, password) = match wifi["Security"].as_str().unwrap_or("None") {
+        "None" => (None, None),
+        "WEP-PSK" => secured("wep", passphrase).map_err(|err| name(&err))?,
+        "WPA-PSK" => secured("wpa2", passphrase).map_err(|err| name(&err))?,
+        "WPA-EAP" | "WEP-8021X" => return Err(name("enterprise networks can't be converted yet")),
+        other => return Err(name(&format!("unknown security {}", other)))
+    };
+    Ok(Network{ ssid, password, security, hidden: wifi["HiddenSSID"].as_bool().unwrap_or_default() })
+}
+
+/// The ONC network configuration of the network. The GUID is derived from the SSID, so importing the
+/// configuration again updates the network instead of adding it twice.
+pub fn onc_configuration(args: &Args) -> Value {
+    // ONC has no personal WPA3 type; Android 10 and later use SAE on WPA-PSK networks that offer it.
+    let security:&str = match resolve_security(args) {
+        None => "None",
+        Some(Security::Wep) => "WEP-PSK",
+        Some(Security::Wpa | Security::Wpa2 | Security::Wpa3 | Security::Wpa2Wpa3) => "WPA-PSK"
+    };
+    let mut wifi:Value = json!({
+        "SSID": args.ssid,
+        "Security": security,
+        "HiddenSSID": args.hidden,
+        "AutoConnect": true
+    });
+    if let Some(psw) = args.psw.as_ref().filter(|_| security != "None") {
+        wifi["Passphrase"] = json!(psw);
+    }
+    json!({
+        "GUID": format!("wifiqr-{}", args.ssid),
+        "Name": args.ssid,
+        "Type": "WiFi",
+        "WiFi": wifi
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::batch;
+    fn args() -> Args {
+        crate::tests::network("", None, None)
+    }
+    fn networks() -> Vec<Network> {
+        let network = |ssid: &str, password: Option<&str>, security: Option<&str>, hidden: bool| Network{
+            ssid: String::from(ssid), password: password.map(String::from), security: security.map(String::from), hidden
+        };
+        vec![
+            network("Home & Garden", Some("hunter2222"), Some("wpa2"), false),
+            network("Café", Some("pass word\\"), Some("wpa3"), true),
+            network("Lobby", None, None, false),
+            network("Legacy", Some("abcde"), Some("wep"), false),
+            network("Old", Some("tkip-rules"), Some("wpa"), false),
+            network("Key", Some(&"0123456789abcdef".repeat(4)), Some("wpa2"), false)
+        ]
+    }
+    /// Writes the networks and reads them back.
+    fn round_trip(format: ProfileFormat, networks: &[Network]) -> Vec<Network> {
+        let networks:Vec<Args> = networks.iter().map(|x| batch::network_args(&args(), x, 0).unwrap()).collect();
+        write(format, &networks).unwrap().into_iter().flat_map(|(name, contents)| {
+            let (networks, skipped) = read(format, Path::new(&name), &contents).unwrap();
+            assert_eq!(skipped, Vec::<String>::new());
+            networks
+        }).collect()
+    }
+    #[test]
+    fn test_wep_key_index() {
+        let mut global:Args = args();
+        global.wep.wep_key_index = Some(3);
+        let networks:Vec<Args> = vec![batch::network_args(&global, &networks()[3], 0).unwrap()];
+        assert!(write_wpa_supplicant(&networks[0]).contains("\twep_key2=\"abcde\"\n\twep_tx_keyidx=2\n"));
+        assert!(write_netsh(&networks[0]).contains("</sharedKey>\n\t\t\t<keyIndex>2</keyIndex>"));
+        for format in [ProfileFormat::Nmconnection, ProfileFormat::WpaSupplicant] {
+            let (name, contents) = &write(format, &networks).unwrap()[0];
+            assert_eq!(read(format, Path::new(name), contents).unwrap().0, vec![self::networks()[3].clone()], "{:?}", format);
+        }
+    }
+    #[test]
+    fn test_write_netsh() {
+        let security = |name: &str| {
+            let network = Network{ ssid: String::from("Home"), password: Some(String::from("hunter22")), security: Some(String::from(name)), hidden: false };
+            write_netsh(&batch::network_args(&args(), &network, 0).unwrap())
+        };
+        assert!(security("wpa3").contains("<authentication>WPA3SAE</authentication>\n\t\t\t\t<encryption>AES</encryption>"));
+        assert!(security("wpa2-wpa3").contains("<authentication>WPA2PSK</authentication>\n\t\t\t\t<encryption>AES</encryption>"));
+        assert!(security("wpa").contains("<authentication>WPAPSK</authentication>\n\t\t\t\t<encryption>TKIP</encryption>"));
+        assert!(security("wpa2").contains("<keyType>passPhrase</keyType>\n\t\t\t\t<protected>false</protected>\n\t\t\t\t<keyMaterial>hunter22</keyMaterial>"));
+        let open:String = write_netsh(&batch::network_args(&args(), &Network{ ssid: String::from("Cafe"), password: None, security: None, hidden: true }, 0).unwrap());
+        assert!(open.contains("<hex>43616665</hex>") && open.contains("<nonBroadcast>true</nonBroadcast>") && !open.contains("<sharedKey>"));
+    }
+    #[test]
+    fn test_round_trip() {
+        for format in [ProfileFormat::Netsh, ProfileFormat::Nmconnection, ProfileFormat::WpaSupplicant] {
+            assert_eq!(round_trip(format, &networks()), networks(), "{:?}", format);
+        }
+        // WPA3, WPA and WPA2 are all one type in the others, and iwd has no WEP.
+        let mut networks:Vec<Network> = networks();
+        networks.remove(3);
+        let wpa2 = |mut networks: Vec<Network>| {
+            networks.iter_mut().filter(|x| x.security.is_some()).for_each(|x| x.security = Some(String::from("wpa2")));
+            networks
+        };
+        assert_eq!(round_trip(ProfileFormat::Iwd, &networks), wpa2(networks.clone()));
+        assert_eq!(round_trip(ProfileFormat::Onc, &networks), wpa2(networks.clone()));
+        // Payloads tell WPA3 apart from the others.
+        let mut expected:Vec<Network> = wpa2(networks.clone());
+        expected[1].security = Some(String::from("wpa3"));
+        assert_eq!(round_trip(ProfileFormat::Wifi, &networks), expected);
+    }
+    #[test]
+    fn test_read_netsh() {
+        let profile = r#"<?xml version="1.0"?>
+<WLANProfile xmlns="http://www.microsoft.com/networking/WLAN/profile/v1">
+	<name>Office</name>
+	<SSIDConfig><SSID><name>Office</name></SSID></SSIDConfig>
+	<MSM><security>
+		<authEncryption><authentication>WPA2PSK</authentication><encryption>AES</encryption><useOneX>false</useOneX></authEncryption>
+		<sharedKey><keyType>passPhrase</keyType><protected>true</protected><keyMaterial>01000000D08C9DDF</keyMaterial></sharedKey>
+	</security></MSM>
+</WLANProfile>"#;
+        assert_eq!(read_netsh(profile).unwrap_err().to_string(), "Office: the key is encrypted, export the profile with key=clear");
+        let (networks, skipped) = read(ProfileFormat::Netsh, Path::new("Office.xml"), &profile.replace("true", "false")).unwrap();
+        assert_eq!((networks[0].password.as_deref(), networks[0].security.as_deref(), skipped.len()), (Some("01000000D08C9DDF"), Some("wpa2"), 0));
+    }
+    #[test]
+    fn test_read_wpa_supplicant() {
+        let conf = "ctrl_interface=/run/wpa_supplicant\n\n# Home\nnetwork={\n  ssid=486F6D65\n  psk=\"password\"\n}\nnetwork={\n  ssid=\"Office\"\n  key_mgmt=WPA-EAP\n  eap=PEAP\n}\n";
+        let (networks, skipped) = read(ProfileFormat::WpaSupplicant, Path::new("wpa_supplicant.conf"), conf).unwrap();
+        assert_eq!(networks, vec![Network{ ssid: String::from("Home"), password: Some(String::from("password")), security: Some(String::from("wpa2")), hidden: false }]);
+        assert_eq!(skipped, vec!["Office: enterprise networks can't be converted yet"]);
+    }
+    #[test]
+    fn test_detect() {
+        assert_eq!(ProfileFormat::detect(Path::new("=486f6d65.psk"), ""), Some(ProfileFormat::Iwd));
+        assert_eq!(ProfileFormat::detect(Path::new("WifiConfigStore.xml"), "<WifiConfigStoreData>"), Some(ProfileFormat::Android));
+        assert_eq!(ProfileFormat::detect(Path::new("codes.txt"), "WIFI:S:Home;;\n"), Some(ProfileFormat::Wifi));
+        assert_eq!(ProfileFormat::detect(Path::new("wpa.txt"), "network={\n}\n"), Some(ProfileFormat::WpaSupplicant));
+        assert_eq!(ProfileFormat::detect(Path::new("notes.txt"), "Home"), None);
+        assert_eq!(iwd_file_name("Home Net-2_4"), "Home Net-2_4");
+        assert_eq!(iwd_file_name("Café"), "=436166c3a9");
+    }
+}
@@ -0,0 +1,166 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Asking for the network on the terminal, so the password doesn't end up in the shell history or the process list.
+//! The password isn't echoed while it's typed. The prompts go to stderr, leaving stdout to the QR-Code.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::Args;
+
+/// Whether the network needs a password, by its security standard or EAP method.
+fn needs_password(args: &Args) -> bool {
+    args.sec.is_some() || args.enterprise.eap.is_some_and(|x| x.tunneled())
+}
+
+/// Whether to ask for the network: with `--interactive`, or if it needs a password that wasn't given and someone
+/// is at the terminal to type it.
+pub fn wanted(args: &Args) -> bool {
+    args.interactive || (args.psw.is_none() && needs_password(args) && io::stdin().is_terminal())
+}
+
+/// Asks for the SSID and the password, each unless given. An empty password leaves the network open.
+pub fn fill(args: &mut Args) -> io::Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(io::Error::other("stdin is not a terminal"));
+    }
+    if args.ssid.is_empty() && args.networks.is_empty() {
+        args.ssid = read_line("SSID: ")?;
+    }
+    if args.psw.is_none() {
+        let prompt:&str = if needs_password(args) {"Password: "}else{"Password (empty for an open network): "};
+        args.psw = Some(read_hidden(prompt)?).filter(|x| !x.is_empty());
+    }
+    Ok(())
+}
+
+/// Asks for one of `count` choices listed by number, until a number between 1 and `count` is entered. Returns the
+/// index of the choice, counted from 0.
+pub fn choose(prompt: &str, count: usize) -> io::Result<usize> {
+    if !io::stdin().is_terminal() {
+        return Err(io::Error::other("stdin is not a terminal"));
+    }
+    loop {
+        match read_line(prompt)?.trim().parse::<usize>() {
+            Ok(choice) if (1..=count).contains(&choice) => return Ok(choice - 1),
+            _ => eprintln!("Enter a number between 1 and {}.", count)
+        }
+    }
+}
+
+/// The next line of the reader without its line break. Fails at the end of the input, so a closed terminal isn't
+/// taken for an empty answer.
+fn line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line:String = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the input ended before the line was entered"));
+    }
+    Ok(String::from(line.trim_end_matches(['\r', '\n'])))
+}
+
+fn read_line(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    line(&mut io::stdin().lock())
+}
+
+/// Reads a line with the echo of the terminal turned off, ending it with the line break that wasn't echoed.
+fn read_hidden(prompt: &str) -> io::Result<String> {
+    let hidden:HiddenInput = HiddenInput::new()?;
+    let line:io::Result<String> = read_line(prompt);
+    drop(hidden);
+    eprintln!();
+    line
+}
+
+/// Turns off the echo of the terminal until it's dropped.
+#[cfg(unix)]
+struct HiddenInput(libc::termios);
+#[cfg(unix)]
+impl HiddenInput {
+    fn new() -> io::Result<Self> {
+        // SAFETY: termios is plain data tcgetattr fills in, and both calls fail for inputs that aren't terminals.
+        unsafe {
+            let mut termios:libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut hidden:libc::termios = termios;
+            hidden.c_lflag &= !libc::ECHO;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &hidden) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(HiddenInput(termios))
+        }
+    }
+}
+#[cfg(unix)]
+impl Drop for HiddenInput {
+    fn drop(&mut self) {
+        // SAFETY: Restores the settings read in `new`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+#[cfg(windows)]
+struct HiddenInput(windows_sys::Win32::Foundation::HANDLE, u32);
+#[cfg(windows)]
+impl HiddenInput {
+    fn new() -> io::Result<Self> {
+        use windows_sys::Win32::System::Console::{GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_ECHO_INPUT, STD_INPUT_HANDLE};
+        // SAFETY: The handle is only passed back to the console functions, which fail gracefully on invalid handles.
+        unsafe {
+            let handle = GetStdHandle(STD_INPUT_HANDLE);
+            let mut mode:u32 = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 || SetConsoleMode(handle, mode & !ENABLE_ECHO_INPUT) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(HiddenInput(handle, mode))
+        }
+    }
+}
+#[cfg(windows)]
+impl Drop for HiddenInput {
+    fn drop(&mut self) {
+        // SAFETY: Restores the mode read in `new` on the same handle.
+        unsafe {
+            windows_sys::Win32::System::Console::SetConsoleMode(self.0, self.1);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+struct HiddenInput;
+#[cfg(not(any(unix, windows)))]
+impl HiddenInput {
+    fn new() -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "the password can't be hidden on this platform, pass it with --psw"))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_line() {
+        let mut reader = io::Cursor::new("Home\r\nsecret\n");
+        assert_eq!(line(&mut reader).unwrap(), "Home");
+        assert_eq!(line(&mut reader).unwrap(), "secret");
+        assert_eq!(line(&mut reader).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,111 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Encoders for the formats print shops and legacy signage players ask for: LZW-compressed TIFF, optionally in
+//! CMYK, and GIF. Every other format is left to `image`, which picks it from the extension.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use image::error::{EncodingError, ParameterError, ParameterErrorKind};
+use image::{ImageError, ImageFormat, Rgba, RgbaImage};
+use tiff::encoder::{colortype, compression::Lzw, TiffEncoder};
+
+/// Trade-off between quality and speed of the GIF palette, from 1 (best) to 30. `image` defaults to 1, which
+/// takes several seconds for a wallpaper without a visible difference for the flat QR-Code.
+const GIF_SPEED: i32 = 10;
+
+/// Composites the pixel onto white, as neither the CMYK TIFFs nor the prints have transparency.
+fn flatten(pixel: Rgba<u8>) -> [u8; 3] {
+    let alpha:u16 = pixel[3] as u16;
+    [0, 1, 2].map(|x| ((pixel[x] as u16 * alpha + 255 * (255 - alpha)) / 255) as u8)
+}
+
+/// Naive conversion to CMYK without a color profile. Gray is set on the K channel only, so the dark modules are
+/// printed in pure black instead of a registration-sensitive mix of all four inks.
+pub fn cmyk(rgb: [u8; 3]) -> [u8; 4] {
+    let max:u8 = rgb.into_iter().max().unwrap();
+    if max == 0 {
+        return [0, 0, 0, 255];
+    }
+    let [c, m, y] = rgb.map(|x| ((max - x) as u16 * 255 / max as u16) as u8);
+    [c, m, y, 255 - max]
+}
+
+fn tiff_error(err: tiff::TiffError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(ImageFormat::Tiff.into(), err))
+}
+
+/// Encodes an LZW-compressed TIFF, in RGB or CMYK.
+pub fn encode_tiff(image: &RgbaImage, cmyk_output: bool) -> image::ImageResult<Vec<u8>> {
+    let mut output:Cursor<Vec<u8>> = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut output).map_err(tiff_error)?;
+    let pixels = image.pixels().map(|x| flatten(*x));
+    if cmyk_output {
+        let data:Vec<u8> = pixels.flat_map(cmyk).collect();
+        encoder.write_image_with_compression::<colortype::CMYK8, _>(image.width(), image.height(), Lzw, &data)
+    }else{
+        let data:Vec<u8> = pixels.flatten().collect();
+        encoder.write_image_with_compression::<colortype::RGB8, _>(image.width(), image.height(), Lzw, &data)
+    }.map_err(tiff_error)?;
+    Ok(output.into_inner())
+}
+
+pub fn encode_gif(image: &RgbaImage) -> image::ImageResult<Vec<u8>> {
+    let mut output:Vec<u8> = Vec::new();
+    image::codecs::gif::GifEncoder::new_with_speed(&mut output, GIF_SPEED).encode(image.as_raw(), image.width(), image.height(), image::ColorType::Rgba8)?;
+    Ok(output)
+}
+
+/// Saves the image, its extension picking the format. `cmyk` is only supported for TIFFs.
+pub fn save(image: &RgbaImage, path: &Path, cmyk_output: bool) -> image::ImageResult<()> {
+    let format:ImageFormat = ImageFormat::from_path(path)?;
+    if cmyk_output && format != ImageFormat::Tiff {
+        return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(String::from("CMYK is only supported for TIFF images")))));
+    }
+    let data:Vec<u8> = match format {
+        ImageFormat::Tiff => encode_tiff(image, cmyk_output)?,
+        ImageFormat::Gif => encode_gif(image)?,
+        _ => return image.save(path)
+    };
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_cmyk() {
+        assert_eq!(cmyk([0, 0, 0]), [0, 0, 0, 255]);
+        assert_eq!(cmyk([255, 255, 255]), [0, 0, 0, 0]);
+        assert_eq!(cmyk([128, 128, 128]), [0, 0, 0, 127]);
+        assert_eq!(cmyk([255, 0, 0]), [0, 255, 255, 0]);
+        assert_eq!(flatten(Rgba([0, 0, 0, 0])), [255, 255, 255]);
+    }
+    #[test]
+    fn test_encode() {
+        let image = RgbaImage::from_fn(16, 8, |x, _| if x < 8 {Rgba([0, 0, 0, 255])}else{Rgba([255, 255, 255, 255])});
+        let mut decoder = tiff::decoder::Decoder::new(Cursor::new(encode_tiff(&image, true).unwrap())).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), tiff::ColorType::CMYK(8));
+        assert_eq!(decoder.get_tag_u32(tiff::tags::Tag::Compression).unwrap(), tiff::tags::CompressionMethod::LZW.to_u16() as u32);
+        let tiff::decoder::DecodingResult::U8(data) = decoder.read_image().unwrap() else { panic!("expected 8 bit samples") };
+        assert_eq!((&data[..4], &data[data.len() - 4..]), (&[0, 0, 0, 255][..], &[0, 0, 0, 0][..]));
+
+        let gif = image::load_from_memory_with_format(&encode_gif(&image).unwrap(), ImageFormat::Gif).unwrap().to_rgba8();
+        assert_eq!((gif.get_pixel(0, 0)[0], gif.get_pixel(15, 0)[0]), (0, 255));
+    }
+}
@@ -0,0 +1,72 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The QR-Code as a JSON document for `--output-format json`, in place of drawing it into the terminal, so scripts
+//! get the payload, version and ECC-Level, and with `--with-matrix` the modules, without parsing block characters.
+
+use serde_json::{json, Value};
+
+use crate::Args;
+
+/// What is printed in place of a file.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format{
+    /// The QR-Code drawn into the terminal.
+    Terminal,
+    /// A JSON document per network, on one line each.
+    Json
+}
+
+/// The document of the code of `payload`, whose modules are `matrix`, made for `args`. The matrix leaves out the
+/// quiet zone and has a row of 1s (dark) and 0s (light) for every line of modules, top to bottom. The version of
+/// Micro QR symbols is a string, e.g. `"M4"`.
+pub fn document(args: &Args, payload: &str, matrix: &[Vec<bool>], with_matrix: bool) -> Value {
+    let mut document:Value = json!({
+        "ssid": args.ssid,
+        "payload": payload,
+        "version": if args.output.micro {json!(format!("M{}", (matrix.len() - 9) / 2))}else{json!((matrix.len() - 17) / 4)},
+        "ecc": format!("{:?}", args.ecc).to_lowercase(),
+        "modules": matrix.len()
+    });
+    if with_matrix {
+        document["matrix"] = matrix.iter().map(|row| row.iter().map(|&x| u8::from(x)).collect::<Vec<u8>>()).collect();
+    }
+    document
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::{assemble_qr_string, ECCLevel};
+    use clap::Parser;
+    #[test]
+    fn test_document() {
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Home", "--psw", "hunter22", "--ecc", "medium"]);
+        let payload:String = assemble_qr_string(&args);
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(&payload, ECCLevel::Medium.into()).unwrap();
+        let document:Value = document(&args, &payload, &matrix, false);
+        assert_eq!(document, json!({"ssid": "Home", "payload": "WIFI:T:WPA;S:Home;P:hunter22;;", "version": 3, "ecc": "medium", "modules": 29}));
+        let document:Value = super::document(&args, &payload, &matrix, true);
+        let rows:&Vec<Value> = document["matrix"].as_array().unwrap();
+        assert_eq!(rows.len(), 29);
+        // The finder pattern in the top-left corner.
+        assert_eq!(rows[0].as_array().unwrap()[..8], [1, 1, 1, 1, 1, 1, 1, 0].map(Value::from));
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Cafe", "--micro"]);
+        let payload:String = assemble_qr_string(&args);
+        let matrix:Vec<Vec<bool>> = crate::micro::encode(&payload, args.ecc).unwrap();
+        assert_eq!(super::document(&args, &payload, &matrix, false)["version"], "M4");
+    }
+}
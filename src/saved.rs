@@ -0,0 +1,275 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Reading a network saved on the machine in place of `--ssid`, `--psw` and `--sec`, so the code of a network the
+//! machine already joins can be made without typing the password again. NetworkManager connections are read
+//! through `nmcli`, which asks for authorization where the secrets of a connection are only readable by root.
+//! Windows WLAN profiles are read through the WLAN API, which only hands out their keys to administrators, in the
+//! XML `netsh wlan export profile key=clear` writes, so the password never shows on screen. On macOS, only the
+//! password of `--ssid` is looked up in the keychain.
+
+use std::fmt;
+#[cfg(not(windows))]
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::batch::Network;
+use crate::profile::{self, ProfileError};
+#[cfg(not(windows))]
+use crate::private_dir::PrivateDir;
+
+/// Properties of a connection read by `nmcli`, in the order it prints them.
+const NM_FIELDS: [&str; 11] = [
+    "connection.type", "802-11-wireless.ssid", "802-11-wireless.hidden", "802-11-wireless-security.key-mgmt",
+    "802-11-wireless-security.proto", "802-11-wireless-security.psk", "802-11-wireless-security.wep-tx-keyidx",
+    "802-11-wireless-security.wep-key0", "802-11-wireless-security.wep-key1", "802-11-wireless-security.wep-key2",
+    "802-11-wireless-security.wep-key3"
+];
+
+/// Where to read the network from. At most one of them is given, and not together with the network.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Source{
+    /// Read the SSID, security type and password of the NetworkManager connection of the name or UUID.
+    #[clap(long, value_parser, value_name = "NAME", conflicts_with_all = &["ssid", "psw", "sec", "networks", "interactive", "psw-stdin", "psw-file", "psw-env", "generate-psw"])]
+    pub from_connection: Option<String>,
+
+    /// Read the network of the Wi-Fi connection NetworkManager has active.
+    #[clap(long, action, conflicts_with_all = &["ssid", "psw", "sec", "networks", "interactive", "psw-stdin", "psw-file", "psw-env", "generate-psw", "from-connection"])]
+    pub current: bool,
+
+    /// Read the network of the saved Windows WLAN profile of the name, as `netsh wlan show profiles` lists them.
+    /// Needs an elevated prompt, as only administrators can read the keys.
+    #[clap(long, value_parser, value_name = "NAME", conflicts_with_all = &["ssid", "psw", "sec", "networks", "interactive", "psw-stdin", "psw-file", "psw-env", "generate-psw", "from-connection", "current"])]
+    pub from_profile: Option<String>,
+
+    /// Look up the password of `--ssid` in the macOS keychain, which keeps those of the networks the Mac joined.
+    /// macOS asks for an administrator's password first.
+    #[clap(long, action, requires = "ssid", conflicts_with_all = &["psw", "networks", "interactive", "psw-stdin", "psw-file", "psw-env", "generate-psw", "from-connection", "current", "from-profile"])]
+    pub from_keychain: bool,
+
+    /// List the networks in range with their signal and security, and ask which one to encode, so the SSID doesn't
+    /// have to be typed. The password is asked for next.
+    #[clap(long, action, conflicts_with_all = &["ssid", "sec", "networks", "from-connection", "current", "from-profile", "from-keychain"])]
+    pub scan: bool
+}
+impl Source {
+    /// The network of the source given, if any. The keychain only has the password of the network of `ssid`, so its
+    /// security standard is left open.
+    pub fn read(&self, ssid: &str) -> Result<Option<Network>, SavedError> {
+        if self.from_keychain {
+            let password:String = keychain_password(ssid)?;
+            return Ok(Some(Network{ ssid: String::from(ssid), password: Some(password), security: None, hidden: false }));
+        }
+        if let Some(name) = &self.from_profile {
+            let xml:String = wlan_profile(name)?;
+            // The WLAN API encrypts the key instead of failing for users who aren't administrators.
+            if xml.contains("<protected>true</protected>") {
+                return Err(SavedError::Encrypted(name.clone()));
+            }
+            return profile::read_netsh(&xml).map(Some).map_err(|err| SavedError::Profile(name.clone(), err));
+        }
+        let connection:String = if let Some(name) = &self.from_connection {
+            name.clone()
+        }else if self.current {
+            active_connection()?
+        }else{
+            return Ok(None);
+        };
+        let output:String = nmcli(&["--show-secrets", "--escape", "no", "--get-values", &NM_FIELDS.join(","), "connection", "show", &connection])?;
+        read_nmcli(&output).map(Some).map_err(|err| SavedError::Profile(connection, err))
+    }
+}
+
+/// Runs `nmcli` and returns its output.
+pub fn nmcli(args: &[&str]) -> Result<String, SavedError> {
+    let output = Command::new("nmcli").args(args).output().map_err(|err| SavedError::Io(String::from("nmcli"), err))?;
+    if !output.status.success() {
+        return Err(SavedError::CommandFailed(String::from("nmcli"), String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Brings up the NetworkManager connection, handing `nmcli` the `secrets` of it in a `passwd-file`, as lines of
+/// `<setting>.<property>:<secret>`. The file is only readable by the current user, unlike the arguments of a process,
+/// and NetworkManager saves the secrets with the connection once they were handed over.
+#[cfg(not(windows))]
+pub fn nmcli_up(connection: &str, secrets: Option<&str>, shred: bool) -> Result<(), SavedError> {
+    let Some(secrets) = secrets else {
+        return nmcli(&["connection", "up", "id", connection]).map(drop);
+    };
+    let io_err = |err| SavedError::Io(String::from("nmcli"), err);
+    let mut directory:PrivateDir = PrivateDir::create(shred).map_err(io_err)?;
+    let path:PathBuf = directory.write("secrets", secrets.as_bytes()).map_err(io_err)?;
+    nmcli(&["connection", "up", "id", connection, "passwd-file", &path.to_string_lossy()]).map(drop)
+}
+
+/// The UUID of the first active Wi-Fi connection. UUIDs and types have no colons, which separate the fields.
+fn active_connection() -> Result<String, SavedError> {
+    let output:String = nmcli(&["--get-values", "UUID,TYPE", "connection", "show", "--active"])?;
+    output.lines().filter_map(|x| x.split_once(':')).find(|(_, x)| *x == "802-11-wireless").map(|(x, _)| String::from(x)).ok_or(SavedError::NotConnected)
+}
+
+/// The network of the properties `nmcli --get-values` printed for [`NM_FIELDS`], one per line.
+fn read_nmcli(output: &str) -> Result<Network, ProfileError> {
+    let values:Vec<&str> = output.lines().collect();
+    profile::read_nm_settings(|section, key| {
+        let index:usize = NM_FIELDS.iter().position(|x| *x == format!("{}.{}", section, key))?;
+        values.get(index).copied().filter(|x| !x.is_empty())
+    })
+}
+
+/// A session of the WLAN API with the Wi-Fi adapters of the machine, closed when it's dropped.
+#[cfg(windows)]
+pub struct Wlan{
+    pub handle: windows_sys::Win32::Foundation::HANDLE,
+    interfaces: *mut windows_sys::Win32::NetworkManagement::WiFi::WLAN_INTERFACE_INFO_LIST
+}
+#[cfg(windows)]
+impl Wlan {
+    pub fn open() -> Result<Self, SavedError> {
+        use std::ptr::{null, null_mut};
+        use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+        use windows_sys::Win32::NetworkManagement::WiFi::{WlanCloseHandle, WlanEnumInterfaces, WlanOpenHandle};
+        // SAFETY: The handle is closed again if the adapters can't be listed, and by `drop` otherwise.
+        unsafe {
+            let mut version:u32 = 0;
+            let mut handle = null_mut();
+            let code:u32 = WlanOpenHandle(2, null(), &mut version, &mut handle);
+            if code != ERROR_SUCCESS {
+                return Err(SavedError::Wlan("WlanOpenHandle", code));
+            }
+            let mut interfaces = null_mut();
+            let code:u32 = WlanEnumInterfaces(handle, null(), &mut interfaces);
+            if code != ERROR_SUCCESS {
+                WlanCloseHandle(handle, null());
+                return Err(SavedError::Wlan("WlanEnumInterfaces", code));
+            }
+            Ok(Wlan{ handle, interfaces })
+        }
+    }
+    /// The Wi-Fi adapters of the machine.
+    pub fn interfaces(&self) -> &[windows_sys::Win32::NetworkManagement::WiFi::WLAN_INTERFACE_INFO] {
+        // SAFETY: The list stays allocated until `drop`, and holds the number of adapters it gives.
+        unsafe {
+            let list = &*self.interfaces;
+            std::slice::from_raw_parts(list.InterfaceInfo.as_ptr(), list.dwNumberOfItems as usize)
+        }
+    }
+}
+#[cfg(windows)]
+impl Drop for Wlan {
+    fn drop(&mut self) {
+        use windows_sys::Win32::NetworkManagement::WiFi::{WlanCloseHandle, WlanFreeMemory};
+        // SAFETY: Frees the list and closes the handle `open` got, which nothing refers to anymore.
+        unsafe {
+            WlanFreeMemory(self.interfaces as *const std::ffi::c_void);
+            WlanCloseHandle(self.handle, std::ptr::null());
+        }
+    }
+}
+
+/// The XML of the WLAN profile of the name, with the key in plain text if the process may read it. Profiles belong
+/// to a Wi-Fi adapter, so the first adapter having one of the name is taken.
+#[cfg(windows)]
+fn wlan_profile(name: &str) -> Result<String, SavedError> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Foundation::{ERROR_NOT_FOUND, ERROR_SUCCESS};
+    use windows_sys::Win32::NetworkManagement::WiFi::{WlanFreeMemory, WlanGetProfile, WLAN_PROFILE_GET_PLAINTEXT_KEY};
+    let wlan:Wlan = Wlan::open()?;
+    let wide_name:Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut result:Result<String, SavedError> = Err(SavedError::Wlan("WlanGetProfile", ERROR_NOT_FOUND));
+    for interface in wlan.interfaces() {
+        let mut xml:*mut u16 = null_mut();
+        let mut flags:u32 = WLAN_PROFILE_GET_PLAINTEXT_KEY;
+        let mut access:u32 = 0;
+        // SAFETY: The XML is a null-terminated string the API allocates, which is copied before it is freed.
+        unsafe {
+            let code:u32 = WlanGetProfile(wlan.handle, &interface.InterfaceGuid, wide_name.as_ptr(), null(), &mut xml, &mut flags, &mut access);
+            if code != ERROR_SUCCESS {
+                result = Err(SavedError::Wlan("WlanGetProfile", code));
+                continue;
+            }
+            let length:usize = (0..).take_while(|x| *xml.add(*x) != 0).count();
+            result = Ok(String::from_utf16_lossy(std::slice::from_raw_parts(xml, length)));
+            WlanFreeMemory(xml as *const std::ffi::c_void);
+        }
+        break;
+    }
+    result
+}
+#[cfg(not(windows))]
+fn wlan_profile(_name: &str) -> Result<String, SavedError> {
+    Err(SavedError::Unsupported("WLAN profiles", "Windows"))
+}
+
+#[cfg(target_os = "macos")]
+fn keychain_password(ssid: &str) -> Result<String, SavedError> {
+    crate::keychain::password(ssid).map_err(|status| SavedError::Keychain(String::from(ssid), status))
+}
+#[cfg(not(target_os = "macos"))]
+fn keychain_password(_ssid: &str) -> Result<String, SavedError> {
+    Err(SavedError::Unsupported("the keychain", "macOS"))
+}
+
+#[derive(Debug)]
+pub enum SavedError{
+    Io(String, std::io::Error),
+    CommandFailed(String, String),
+    /// No Wi-Fi connection is active.
+    NotConnected,
+    Profile(String, ProfileError),
+    /// The key of the WLAN profile was handed out encrypted.
+    Encrypted(String),
+    /// A function of the WLAN API failed with the error code.
+    #[cfg(windows)]
+    Wlan(&'static str, u32),
+    /// The Security framework failed to look up the password of the SSID with the status.
+    #[cfg(target_os = "macos")]
+    Keychain(String, i32),
+    /// What is read, and the only platform it is saved on.
+    Unsupported(&'static str, &'static str)
+}
+impl fmt::Display for SavedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SavedError::Io(program, err) => write!(f, "failed to run {}: {}", program, err),
+            SavedError::CommandFailed(program, stderr) => write!(f, "{} failed: {}", program, stderr.trim()),
+            SavedError::NotConnected => write!(f, "the machine isn't connected to a Wi-Fi network"),
+            SavedError::Profile(connection, err) => write!(f, "the connection {} can't be read: {}", connection, err),
+            SavedError::Encrypted(name) => write!(f, "the key of {} is only readable from an elevated prompt", name),
+            #[cfg(windows)]
+            SavedError::Wlan(function, code) => write!(f, "{} failed: {}", function, std::io::Error::from_raw_os_error(*code as i32)),
+            #[cfg(target_os = "macos")]
+            SavedError::Keychain(ssid, crate::keychain::ERR_SEC_ITEM_NOT_FOUND) => write!(f, "the keychain has no password of {}", ssid),
+            #[cfg(target_os = "macos")]
+            SavedError::Keychain(ssid, status) => write!(f, "the password of {} can't be read from the keychain: OSStatus {}", ssid, status),
+            SavedError::Unsupported(what, platform) => write!(f, "{} can only be read on {}", what, platform)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_read_nmcli() {
+        let network:Network = read_nmcli("802-11-wireless\nCaf\\e:1\nyes\nwpa-psk\n\npass:word\n0\n\n\n\n\n").unwrap();
+        assert_eq!(network, Network{ ssid: String::from("Caf\\e:1"), password: Some(String::from("pass:word")), security: Some(String::from("wpa2")), hidden: true });
+        let network:Network = read_nmcli("802-11-wireless\nLegacy\nno\nnone\n\n\n1\n\n1234567890\n\n\n").unwrap();
+        assert_eq!((network.security.as_deref(), network.password.as_deref()), (Some("wep"), Some("1234567890")));
+        assert!(read_nmcli("802-3-ethernet\n\n\n\n\n\n\n\n\n\n\n").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Listing the networks in range for `--scan`, so the SSID is picked from them instead of typed, along with the
+//! security standard they announce. NetworkManager scans through `nmcli`, Windows through the WLAN API.
+
+use std::fmt;
+
+use crate::saved::SavedError;
+use crate::{prompt, Args, Security};
+
+/// A network in range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Visible{
+    pub ssid: String,
+    /// Signal quality in percent.
+    pub signal: u8,
+    pub security: Announced
+}
+
+/// The security a network announces.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Announced{
+    Open,
+    Personal(Security),
+    Enterprise
+}
+impl fmt::Display for Announced {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Announced::Open => write!(f, "Open"),
+            Announced::Personal(security) => write!(f, "{}", security),
+            Announced::Enterprise => write!(f, "Enterprise")
+        }
+    }
+}
+
+/// Lists the networks in range and sets the SSID and security standard of the one picked on the terminal.
+/// Enterprise networks are only picked with `--eap`, which gives the rest of what they need.
+pub fn pick(args: &mut Args) -> Result<(), ScanError> {
+    let mut networks:Vec<Visible> = visible()?;
+    if networks.is_empty() {
+        return Err(ScanError::NoneVisible);
+    }
+    for (index, network) in networks.iter().enumerate() {
+        eprintln!("{:>3}  {:<32}  {:>3}%  {}", index + 1, network.ssid, network.signal, network.security);
+    }
+    let network:Visible = networks.swap_remove(prompt::choose("Network: ", networks.len()).map_err(ScanError::Io)?);
+    match network.security {
+        Announced::Enterprise if args.enterprise.eap.is_none() => return Err(ScanError::Enterprise(network.ssid)),
+        Announced::Enterprise | Announced::Open => {},
+        Announced::Personal(security) => args.sec = Some(security)
+    }
+    args.ssid = network.ssid;
+    Ok(())
+}
+
+/// The networks in range with an SSID, each once with the strongest signal any of its access points has, strongest
+/// first.
+fn merged(networks: Vec<Visible>) -> Vec<Visible> {
+    let mut merged:Vec<Visible> = Vec::new();
+    for network in networks.into_iter().filter(|x| !x.ssid.is_empty()) {
+        match merged.iter_mut().find(|x| x.ssid == network.ssid) {
+            Some(known) if known.signal < network.signal => *known = network,
+            Some(_) => {},
+            None => merged.push(network)
+        }
+    }
+    merged.sort_by_key(|x| std::cmp::Reverse(x.signal));
+    merged
+}
+
+#[cfg(not(windows))]
+fn visible() -> Result<Vec<Visible>, ScanError> {
+    let output:String = crate::saved::nmcli(&["--terse", "--escape", "no", "--fields", "SIGNAL,SECURITY,SSID", "device", "wifi", "list"]).map_err(ScanError::Saved)?;
+    Ok(merged(read_nmcli(&output)))
+}
+/// The networks `nmcli --terse --escape no --fields SIGNAL,SECURITY,SSID` lists. The SSID comes last, as only it may
+/// contain the colons separating the fields.
+#[cfg(not(windows))]
+fn read_nmcli(output: &str) -> Vec<Visible> {
+    output.lines().filter_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let signal:u8 = fields.next()?.parse().ok()?;
+        let security:Vec<&str> = fields.next()?.split_whitespace().collect();
+        let security:Announced = if security.contains(&"802.1X") {
+            Announced::Enterprise
+        }else if security.contains(&"WPA3") {
+            Announced::Personal(if security.contains(&"WPA2") {Security::Wpa2Wpa3}else{Security::Wpa3})
+        }else if security.contains(&"WPA2") {
+            Announced::Personal(Security::Wpa2)
+        }else if security.contains(&"WPA1") {
+            Announced::Personal(Security::Wpa)
+        }else if security.contains(&"WEP") {
+            Announced::Personal(Security::Wep)
+        }else{
+            // Enhanced open (OWE) networks are joined without a password too.
+            Announced::Open
+        };
+        Some(Visible{ ssid: String::from(fields.next()?), signal, security })
+    }).collect()
+}
+
+#[cfg(windows)]
+fn visible() -> Result<Vec<Visible>, ScanError> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::NetworkManagement::WiFi::*;
+    let wlan:crate::saved::Wlan = crate::saved::Wlan::open().map_err(ScanError::Saved)?;
+    let mut networks:Vec<Visible> = Vec::new();
+    for interface in wlan.interfaces() {
+        let mut list:*mut WLAN_AVAILABLE_NETWORK_LIST = null_mut();
+        // SAFETY: The list is allocated by the API, read within the number of networks it gives and freed after.
+        unsafe {
+            let code:u32 = WlanGetAvailableNetworkList(wlan.handle, &interface.InterfaceGuid, 0, null(), &mut list);
+            if code != ERROR_SUCCESS {
+                return Err(ScanError::Saved(SavedError::Wlan("WlanGetAvailableNetworkList", code)));
+            }
+            for network in std::slice::from_raw_parts((*list).Network.as_ptr(), (*list).dwNumberOfItems as usize) {
+                let ssid:&[u8] = &network.dot11Ssid.ucSSID[..(network.dot11Ssid.uSSIDLength as usize).min(32)];
+                let security:Announced = match network.dot11DefaultAuthAlgorithm {
+                    DOT11_AUTH_ALGO_80211_OPEN | DOT11_AUTH_ALGO_80211_SHARED_KEY if network.bSecurityEnabled != 0 => Announced::Personal(Security::Wep),
+                    DOT11_AUTH_ALGO_WPA_PSK => Announced::Personal(Security::Wpa),
+                    DOT11_AUTH_ALGO_RSNA_PSK => Announced::Personal(Security::Wpa2),
+                    DOT11_AUTH_ALGO_WPA3_SAE => Announced::Personal(Security::Wpa3),
+                    DOT11_AUTH_ALGO_WPA | DOT11_AUTH_ALGO_RSNA | DOT11_AUTH_ALGO_WPA3 | DOT11_AUTH_ALGO_WPA3_ENT => Announced::Enterprise,
+                    _ => Announced::Open
+                };
+                networks.push(Visible{ ssid: String::from_utf8_lossy(ssid).into_owned(), signal: network.wlanSignalQuality.min(100) as u8, security });
+            }
+            WlanFreeMemory(list as *const std::ffi::c_void);
+        }
+    }
+    Ok(merged(networks))
+}
+
+#[derive(Debug)]
+pub enum ScanError{
+    Saved(SavedError),
+    Io(std::io::Error),
+    NoneVisible,
+    /// The Enterprise network of the SSID was picked without `--eap`.
+    Enterprise(String)
+}
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScanError::Saved(err) => write!(f, "{}", err),
+            ScanError::Io(err) => write!(f, "{}", err),
+            ScanError::NoneVisible => write!(f, "no networks are in range"),
+            ScanError::Enterprise(ssid) => write!(f, "{} is an Enterprise network, pass --eap and the identity", ssid)
+        }
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_read_nmcli() {
+        let networks:Vec<Visible> = merged(read_nmcli("40:WPA2:Home\n82:WPA2:Home\n70:WPA2 WPA3:Caf:e\n65::Guest\n30:WPA2 802.1X:Office\n20:WPA2:\n"));
+        let listed:Vec<(&str, u8, Announced)> = networks.iter().map(|x| (x.ssid.as_str(), x.signal, x.security)).collect();
+        assert_eq!(listed, vec![
+            ("Home", 82, Announced::Personal(Security::Wpa2)), ("Caf:e", 70, Announced::Personal(Security::Wpa2Wpa3)),
+            ("Guest", 65, Announced::Open), ("Office", 30, Announced::Enterprise)
+        ]);
+    }
+}
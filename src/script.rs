@@ -0,0 +1,179 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! [Rhai](https://rhai.rs) scripts hooking into batches, for naming and branding rules that don't fit the output
+//! templates. A script defines any of these functions, each getting the network as a map with `ssid`, `password`,
+//! `security`, `hidden` and `index`:
+//! - `payload(network, payload)` returns the payload encoded in the QR-Codes.
+//! - `caption(network)` returns the footer line of the layouts, or `()` to keep the one of the branding.
+//! - `filename(network, path)` returns the path of an output, given the expanded `--output`.
+//!
+//! Scripts can't access files or run programs, and their operations are limited so a runaway loop ends with an error.
+
+use std::fmt;
+#[cfg(feature = "scripting")]
+use std::path::Path;
+
+use crate::Args;
+
+/// Operations a hook may run before it is stopped.
+#[cfg(feature = "scripting")]
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+#[derive(Debug)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub enum ScriptError{
+    #[cfg(not(feature = "scripting"))]
+    Unsupported,
+    Io(std::path::PathBuf, std::io::Error),
+    #[cfg(feature = "scripting")]
+    Parse(Box<rhai::ParseError>),
+    #[cfg(feature = "scripting")]
+    Hook(&'static str, Box<rhai::EvalAltResult>)
+}
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "scripting"))]
+            ScriptError::Unsupported => write!(f, "wifiqr was built without scripting, rebuild it with `--features scripting`"),
+            ScriptError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            #[cfg(feature = "scripting")]
+            ScriptError::Parse(err) => write!(f, "{}", err),
+            #[cfg(feature = "scripting")]
+            ScriptError::Hook(hook, err) => write!(f, "{} failed: {}", hook, err)
+        }
+    }
+}
+
+/// A compiled script and the hooks it defines.
+#[cfg(feature = "scripting")]
+pub struct Hooks{
+    engine: rhai::Engine,
+    ast: rhai::AST
+}
+#[cfg(feature = "scripting")]
+impl Hooks {
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        let source:String = std::fs::read_to_string(path).map_err(|err| ScriptError::Io(path.to_path_buf(), err))?;
+        Self::compile(&source)
+    }
+    fn compile(source: &str) -> Result<Self, ScriptError> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast:rhai::AST = engine.compile(source).map_err(|err| ScriptError::Parse(Box::new(err)))?;
+        Ok(Hooks{ engine, ast })
+    }
+    fn defines(&self, hook: &str) -> bool {
+        self.ast.iter_functions().any(|x| x.name == hook)
+    }
+    fn call(&self, hook: &'static str, args: impl rhai::FuncArgs) -> Result<rhai::Dynamic, ScriptError> {
+        self.engine.call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &self.ast, hook, args).map_err(|err| ScriptError::Hook(hook, err))
+    }
+    /// Calls a hook that has to return a string.
+    fn call_string(&self, hook: &'static str, args: impl rhai::FuncArgs) -> Result<String, ScriptError> {
+        expect_string(hook, self.call(hook, args)?)
+    }
+    pub fn payload(&self, network: &Args, index: usize, payload: String) -> Result<String, ScriptError> {
+        if !self.defines("payload") {
+            return Ok(payload);
+        }
+        self.call_string("payload", (network_map(network, index), payload))
+    }
+    pub fn caption(&self, network: &Args, index: usize) -> Result<Option<String>, ScriptError> {
+        if !self.defines("caption") {
+            return Ok(None);
+        }
+        let caption:rhai::Dynamic = self.call("caption", (network_map(network, index),))?;
+        if caption.is_unit() {
+            return Ok(None);
+        }
+        expect_string("caption", caption).map(Some)
+    }
+    pub fn filename(&self, network: &Args, index: usize, path: String) -> Result<String, ScriptError> {
+        if !self.defines("filename") {
+            return Ok(path);
+        }
+        self.call_string("filename", (network_map(network, index), path))
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn expect_string(hook: &'static str, result: rhai::Dynamic) -> Result<String, ScriptError> {
+    let type_name:String = String::from(result.type_name());
+    result.into_string().map_err(|_| ScriptError::Hook(hook, Box::new(rhai::EvalAltResult::ErrorMismatchOutputType(
+        String::from("string"), type_name, rhai::Position::NONE))))
+}
+
+#[cfg(feature = "scripting")]
+fn network_map(network: &Args, index: usize) -> rhai::Map {
+    let security:String = crate::resolve_security(network).map_or(String::new(), |x| x.to_string().to_uppercase());
+    let mut map = rhai::Map::new();
+    map.insert("ssid".into(), network.ssid.clone().into());
+    map.insert("password".into(), network.psw.clone().unwrap_or_default().into());
+    map.insert("security".into(), security.into());
+    map.insert("hidden".into(), network.hidden.into());
+    map.insert("index".into(), (index as rhai::INT).into());
+    map
+}
+
+/// Without the `scripting` feature no hooks can be loaded, so there are none to call.
+#[cfg(not(feature = "scripting"))]
+pub enum Hooks{}
+#[cfg(not(feature = "scripting"))]
+impl Hooks {
+    pub fn load(_path: &std::path::Path) -> Result<Self, ScriptError> {
+        Err(ScriptError::Unsupported)
+    }
+    pub fn payload(&self, _network: &Args, _index: usize, _payload: String) -> Result<String, ScriptError> {
+        match *self {}
+    }
+    pub fn caption(&self, _network: &Args, _index: usize) -> Result<Option<String>, ScriptError> {
+        match *self {}
+    }
+    pub fn filename(&self, _network: &Args, _index: usize, _path: String) -> Result<String, ScriptError> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "scripting"))]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn network() -> Args {
+        crate::tests::network("Guest", Some("password"), Some(Security::Wpa))
+    }
+    #[test]
+    fn test_hooks() {
+        let hooks = Hooks::compile(r#"
+            fn payload(network, payload) { payload.replace("T:Wpa;", "T:WPA;"); payload }
+            fn caption(network) { if network.index == 1 { () } else { `Network ${network.index}: ${network.ssid}` } }
+            fn filename(network, path) { network.security.to_lower() + "/" + path }
+        "#).unwrap();
+        assert_eq!(hooks.payload(&network(), 2, String::from("WIFI:T:Wpa;S:Guest;;")).unwrap(), "WIFI:T:WPA;S:Guest;;");
+        assert_eq!(hooks.caption(&network(), 1).unwrap(), None);
+        assert_eq!(hooks.caption(&network(), 2).unwrap(), Some(String::from("Network 2: Guest")));
+        assert_eq!(hooks.filename(&network(), 2, String::from("Guest/qr.png")).unwrap(), "wpa/Guest/qr.png");
+    }
+    #[test]
+    fn test_missing_hooks() {
+        let hooks = Hooks::compile("fn filename(network, path) { 42 }").unwrap();
+        assert_eq!(hooks.payload(&network(), 1, String::from("WIFI:S:Guest;;")).unwrap(), "WIFI:S:Guest;;");
+        assert_eq!(hooks.caption(&network(), 1).unwrap(), None);
+        assert!(matches!(hooks.filename(&network(), 1, String::from("qr.png")), Err(ScriptError::Hook("filename", _))));
+        assert!(matches!(Hooks::compile("fn filename(network, path) { loop {} }").unwrap().filename(&network(), 1, String::new()), Err(ScriptError::Hook(..))));
+        assert!(matches!(Hooks::compile("fn payload("), Err(ScriptError::Parse(_))));
+    }
+}
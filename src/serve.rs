@@ -0,0 +1,267 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A small HTTP server for `serve`, so the code can be made from a browser: `/` is a form for the network, and
+//! `/qr?ssid=...&psw=...&sec=...&hidden=1&format=svg|png` returns the code, encoded and checked like on the command
+//! line. Requests are answered one at a time and the connection is closed after each, which is plenty for a
+//! front desk. Passwords travel in the URL, so the server is meant for `127.0.0.1` or a trusted network.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::batch::Network;
+use crate::branding::Branding;
+use crate::error::{Context, Error, ErrorKind};
+use crate::output::{self, OutputFormat};
+use crate::{assemble_qr_string, checked_network, diagnostics, svg, Args};
+
+/// Longest request read, of the request line and the headers.
+const MAX_REQUEST: usize = 16 * 1024;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An answer to a request.
+#[derive(Debug, PartialEq, Eq)]
+struct Response{
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>
+}
+impl Response {
+    fn new(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response{ status, content_type, body: body.into() }
+    }
+    fn text(status: u16, text: &str) -> Self {
+        Response::new(status, "text/plain; charset=utf-8", format!("{}\n", text))
+    }
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error"
+        }
+    }
+}
+
+/// Decodes `%XX` escapes and `+` as a space, as forms encode their values.
+fn percent_decode(value: &str) -> String {
+    let bytes:&[u8] = value.as_bytes();
+    let mut decoded:Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i:usize = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value.get(i + 1..i + 3).filter(|x| x.chars().all(|x| x.is_ascii_hexdigit())) {
+                Some(hex) => {
+                    decoded.push(u8::from_str_radix(hex, 16).expect("the digits are hex digits"));
+                    i += 2;
+                },
+                None => decoded.push(b'%')
+            },
+            byte => decoded.push(byte)
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The pairs of a query string, decoded.
+fn query(query: &str) -> Vec<(String, String)> {
+    query.split('&').filter(|x| !x.is_empty()).map(|pair| match pair.split_once('=') {
+        Some((key, value)) => (percent_decode(key), percent_decode(value)),
+        None => (percent_decode(pair), String::new())
+    }).collect()
+}
+
+/// The network of the query, with the other options of the command line, checked as on the command line.
+fn network(args: &Args, pairs: &[(String, String)]) -> Result<Args, Error> {
+    let value = |key: &str| pairs.iter().rev().find(|(x, _)| x == key).map(|(_, x)| x.clone());
+    let ssid:String = value("ssid").filter(|x| !x.is_empty()).ok_or_else(|| Error::new(ErrorKind::Usage, "The SSID has to be provided with ssid=<SSID>"))?;
+    let hidden:bool = value("hidden").is_some_and(|x| !matches!(x.as_str(), "" | "0" | "false" | "off"));
+    let mut network:Args = checked_network(args, &Network{ ssid, password: value("psw").filter(|x| !x.is_empty()), security: value("sec"), hidden })?;
+    if let Some(size) = value("size") {
+        network.output.size = size.parse().ok().filter(|x| (1..=4096).contains(x)).ok_or_else(|| Error::new(ErrorKind::Usage, "size has to be a number of pixels from 1 to 4096"))?;
+    }
+    Ok(network)
+}
+
+/// The form, with the code of the network below it once one was entered.
+fn form(pairs: &[(String, String)], raw_query: &str) -> String {
+    let value = |key: &str| svg::escape(pairs.iter().rev().find(|(x, _)| x == key).map_or("", |(_, x)| x.as_str()));
+    let selected = |name: &str| if pairs.iter().any(|(x, y)| x == "sec" && y == name) {" selected"}else{""};
+    let options:String = [("", "WPA2 with a password, open without"), ("wpa", "WPA"), ("wpa3", "WPA3"), ("wpa2-wpa3", "WPA2/WPA3"), ("wep", "WEP")].iter()
+        .map(|(name, label)| format!("<option value=\"{}\"{}>{}</option>", name, selected(name), label)).collect();
+    let hidden:&str = if pairs.iter().any(|(x, y)| x == "hidden" && !y.is_empty()) {" checked"}else{""};
+    let code:String = match pairs.iter().any(|(x, y)| x == "ssid" && !y.is_empty()) {
+        true => {
+            let query:String = svg::escape(raw_query);
+            format!("<p><img class=\"code\" alt=\"QR-Code of {ssid}\" src=\"/qr?{query}&amp;format=svg\"></p>\n<p><a href=\"/qr?{query}&amp;format=png\" download=\"{ssid}.png\">Download PNG</a></p>\n", ssid = value("ssid"), query = query)
+        },
+        false => String::new()
+    };
+    format!("<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>Wi-Fi QR-Code</title>
+<style>
+body {{ font-family: Helvetica, Arial, sans-serif; max-width: 32em; margin: 2em auto; padding: 0 1em; }}
+label {{ display: block; margin: 0.8em 0 0.2em; }}
+input[type=text], select {{ width: 100%; box-sizing: border-box; padding: 0.4em; font-size: 1em; }}
+.code {{ width: 100%; max-width: 24em; }}
+</style>
+</head>
+<body>
+<h1>Wi-Fi QR-Code</h1>
+<form action=\"/\" method=\"get\">
+<label for=\"ssid\">Network name (SSID)</label>
+<input type=\"text\" id=\"ssid\" name=\"ssid\" value=\"{ssid}\" required>
+<label for=\"psw\">Password</label>
+<input type=\"text\" id=\"psw\" name=\"psw\" value=\"{psw}\" autocomplete=\"off\">
+<label for=\"sec\">Security</label>
+<select id=\"sec\" name=\"sec\">{options}</select>
+<label><input type=\"checkbox\" name=\"hidden\" value=\"1\"{hidden}> Hidden network</label>
+<p><button type=\"submit\">Make the QR-Code</button></p>
+</form>
+{code}</body>
+</html>
+", ssid = value("ssid"), psw = value("psw"), options = options, hidden = hidden, code = code)
+}
+
+/// The answer to a request for the target, the path and query of the request line.
+fn respond(args: &Args, branding: &Branding, target: &str) -> Response {
+    let (path, raw_query) = target.split_once('?').unwrap_or((target, ""));
+    let pairs:Vec<(String, String)> = query(raw_query);
+    match path {
+        "/" => Response::new(200, "text/html; charset=utf-8", form(&pairs, raw_query)),
+        "/qr" => {
+            let format:OutputFormat = match pairs.iter().rev().find(|(x, _)| x == "format").map(|(_, x)| x.as_str()) {
+                None | Some("svg") => OutputFormat::Svg,
+                Some("png") => OutputFormat::Png,
+                Some(_) => return Response::text(400, "format has to be svg or png")
+            };
+            let network:Args = match network(args, &pairs) {
+                Ok(network) => network,
+                Err(err) => return Response::text(400, &err.to_string())
+            };
+            let payload:String = assemble_qr_string(&network);
+            diagnostics::network(&network, &payload, || crate::matrix(&network, &payload));
+            match output::contents(&network, branding, format, Path::new("the response")) {
+                Ok(body) => Response::new(200, if format == OutputFormat::Svg {"image/svg+xml"}else{"image/png"}, body),
+                Err(err) => Response::text(if err.kind() == ErrorKind::Output {500}else{400}, &format!("Failed to draw the QR-Code: {}", err))
+            }
+        },
+        _ => Response::text(404, "Not found, the form is at /")
+    }
+}
+
+/// Reads the request from the connection and answers it. Returns the request line and the status, for the log.
+fn handle(stream: &mut TcpStream, args: &Args, branding: &Branding) -> std::io::Result<(String, u16)> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request:Vec<u8> = Vec::new();
+    let mut buffer:[u8; 4096] = [0; 4096];
+    while !request.windows(4).any(|x| x == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        let read:usize = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request:String = String::from_utf8_lossy(&request).into_owned();
+    let line:&str = request.lines().next().unwrap_or_default();
+    let mut parts = line.split(' ');
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let response:Response = match method {
+        _ if request.len() >= MAX_REQUEST => Response::text(431, "The request line and headers are too large"),
+        "GET" | "HEAD" => respond(args, branding, target),
+        _ => Response::text(405, "Only GET requests are answered")
+    };
+    let head:String = format!("HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status, response.reason(), response.content_type, response.body.len());
+    stream.write_all(head.as_bytes())?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    // The query, with the password in it, is left out of the log.
+    Ok((format!("{} {}", method, target.split('?').next().unwrap_or_default()), response.status))
+}
+
+/// Answers requests on the address until interrupted.
+pub fn serve(args: &Args, branding: &Branding, bind: SocketAddr) -> Result<(), Error> {
+    let listener:TcpListener = TcpListener::bind(bind).or_fail(ErrorKind::External, |err| format!("Failed to listen on {}: {}", bind, err))?;
+    println!("Serving the form on http://{}/, press Ctrl+C to stop.", listener.local_addr().unwrap_or(bind));
+    for stream in listener.incoming() {
+        let result = stream.and_then(|mut stream| handle(&mut stream, args, branding));
+        match result {
+            Ok((request, status)) => diagnostics::request(&request, status),
+            Err(err) => diagnostics::warn("request-failed", format!("failed to answer a request: {}", err))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    #[test]
+    fn test_query() {
+        assert_eq!(percent_decode("My+Caf%C3%A9%3B%2"), "My Café;%2");
+        assert_eq!(query("ssid=Home&psw=a%26b&hidden"), [(String::from("ssid"), String::from("Home")), (String::from("psw"), String::from("a&b")), (String::from("hidden"), String::new())]);
+    }
+    #[test]
+    fn test_respond() {
+        let args:Args = Args::parse_from(["wifiqr", "serve"]);
+        let branding:Branding = Branding::default();
+        let response:Response = respond(&args, &branding, "/qr?ssid=Home&psw=hunter22");
+        assert_eq!((response.status, response.content_type), (200, "image/svg+xml"));
+        assert!(String::from_utf8(response.body).unwrap().contains("<svg"));
+        let response:Response = respond(&args, &branding, "/qr?ssid=Home&psw=hunter22&format=png&size=200");
+        assert_eq!(image::load_from_memory(&response.body).unwrap().width(), 200);
+        assert_eq!(respond(&args, &branding, "/qr?psw=hunter22").status, 400);
+        assert_eq!(respond(&args, &branding, "/qr?ssid=Home&sec=wpa3").status, 400);
+        assert_eq!(respond(&args, &branding, "/qr?ssid=Home&format=gif").status, 400);
+        assert_eq!(respond(&args, &branding, "/favicon.ico").status, 404);
+        let page:String = String::from_utf8(respond(&args, &branding, "/?ssid=%3Cb%3E&sec=wpa3").body).unwrap();
+        assert!(page.contains("value=\"&lt;b&gt;\"") && page.contains("<option value=\"wpa3\" selected>"));
+        assert!(page.contains("src=\"/qr?ssid=%3Cb%3E&amp;sec=wpa3&amp;format=svg\""));
+    }
+    #[test]
+    fn test_oversized_request() {
+        let listener:TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address:SocketAddr = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream:TcpStream = TcpStream::connect(address).unwrap();
+            // Exactly the limit, so the server reads all of it and closes without resetting the connection.
+            let mut request:Vec<u8> = b"GET /?ssid=".to_vec();
+            request.resize(MAX_REQUEST, b'x');
+            stream.write_all(&request).unwrap();
+            let mut response:String = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(handle(&mut stream, &Args::parse_from(["wifiqr", "serve"]), &Branding::default()).unwrap().1, 431);
+        drop(stream);
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    }
+}
@@ -0,0 +1,123 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! SVG markup of the QR-Code, scaling to whatever size it is embedded at.
+
+use std::fmt::Write;
+
+use base64::Engine;
+use image::RgbaImage;
+
+use crate::color::Color;
+use crate::logo::{self, Area, Logo};
+use crate::{with_border, with_quiet_zone};
+
+/// Escapes text for use in XML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped:String = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character)
+        }
+    }
+    escaped
+}
+
+/// An `<svg>` element with the symbol and its quiet zone, one unit per module. The dark modules are a single path
+/// of horizontal runs. `description` becomes the `<desc>` screen readers announce for the image.
+pub fn qr_code(matrix: &[Vec<bool>], description: &str) -> String {
+    element(&with_quiet_zone(matrix), description, "", Color::BLACK, Color::WHITE)
+}
+
+/// A standalone SVG file of the symbol with a border of `border` modules, in the colors of the dark and light
+/// modules. With a `module_size` in pixels, the image has a fixed size, otherwise it scales to wherever it's placed.
+/// A logo is placed into its area, whose modules are expected to be cleared: SVG logos are inlined, others embedded as
+/// a PNG.
+pub fn document(matrix: &[Vec<bool>], description: &str, border: usize, module_size: Option<u32>, dark: Color, light: Color, logo: Option<(&Logo, Area)>) -> String {
+    let matrix:Vec<Vec<bool>> = with_border(matrix, border);
+    let dimensions:String = module_size.map_or(String::new(), |x| format!(" width=\"{size}\" height=\"{size}\"", size = x as usize * matrix.len()));
+    let mut svg:String = element(&matrix, description, &dimensions, dark, light);
+    if let Some((logo, area)) = logo {
+        // The margin of the area stays light.
+        let (start, side) = (border + area.start + 1, area.side - 2);
+        let tag:String = match logo {
+            Logo::Vector(logo) => logo.element(start, side),
+            Logo::Raster(image) => {
+                let image:RgbaImage = if image.width().max(image.height()) > logo::MAX_EMBEDDED_SIZE {logo::fitted(image, logo::MAX_EMBEDDED_SIZE)}else{image.clone()};
+                let mut png:Vec<u8> = Vec::new();
+                image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+                format!("<image x=\"{start}\" y=\"{start}\" width=\"{side}\" height=\"{side}\" href=\"data:image/png;base64,{}\"/>", base64::engine::general_purpose::STANDARD.encode(&png))
+            }
+        };
+        svg.insert_str(svg.len() - "</svg>".len(), &tag);
+    }
+    format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}\n", svg)
+}
+
+fn element(matrix: &[Vec<bool>], description: &str, dimensions: &str, dark: Color, light: Color) -> String {
+    let mut path:String = String::new();
+    for (y, row) in matrix.iter().enumerate() {
+        let mut x:usize = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let start:usize = x;
+            while x < row.len() && row[x] {
+                x += 1;
+            }
+            let _ = write!(path, "M{},{}h{}v1h-{}z", start, y, x - start, x - start);
+        }
+    }
+    let size:usize = matrix.len();
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\"{dimensions} viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\" role=\"img\" aria-labelledby=\"wifiqr-desc\">\
+<desc id=\"wifiqr-desc\">{}</desc><rect width=\"{size}\" height=\"{size}\" fill=\"{}\"/><path d=\"{}\" fill=\"{}\"/></svg>", escape(description), light, path, dark)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_qr_code() {
+        let svg = qr_code(&[vec![true, true], vec![false, true]], "Wi-Fi \"Home\" & <more>");
+        assert!(svg.contains("viewBox=\"0 0 10 10\""));
+        assert!(svg.contains("<desc id=\"wifiqr-desc\">Wi-Fi &quot;Home&quot; &amp; &lt;more&gt;</desc>"));
+        // The first row is one run of two modules, the second a single module.
+        assert!(svg.contains("d=\"M4,4h2v1h-2zM5,5h1v1h-1z\""));
+    }
+    #[test]
+    fn test_document() {
+        let svg = document(&[vec![true, true], vec![false, true]], "Wi-Fi", 1, Some(10), Color::BLACK, Color::WHITE, None);
+        assert!(svg.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"40\" viewBox=\"0 0 4 4\""));
+        assert!(svg.contains("d=\"M1,1h2v1h-2zM2,2h1v1h-1z\""));
+        assert!(svg.contains("<rect width=\"4\" height=\"4\" fill=\"#ffffff\"/><path d=\"M1,1h2v1h-2zM2,2h1v1h-1z\" fill=\"#000000\"/>"));
+        assert!(document(&[vec![true]], "Wi-Fi", 4, None, Color::BLACK, Color::WHITE, None).contains("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 9 9\""));
+        let svg = document(&[vec![true]], "Wi-Fi", 0, None, Color::new(0x1e, 0x3c, 0x8c), Color::new(0xff, 0xf8, 0xe7), None);
+        assert!(svg.contains("fill=\"#fff8e7\"/><path d=\"M0,0h1v1h-1z\" fill=\"#1e3c8c\"/>"));
+        let svg = document(&vec![vec![false; 9]; 9], "Wi-Fi", 4, None, Color::BLACK, Color::WHITE, Some((&Logo::Raster(RgbaImage::new(4, 4)), Area{ start: 3, side: 3 })));
+        assert!(svg.contains("<image x=\"8\" y=\"8\" width=\"1\" height=\"1\" href=\"data:image/png;base64,"));
+        assert!(svg.ends_with("\"/></svg>\n"));
+        let logo:Logo = Logo::Vector(logo::VectorLogo::parse("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 2 2\"><rect width=\"2\" height=\"2\"/></svg>").unwrap());
+        let svg = document(&vec![vec![false; 9]; 9], "Wi-Fi", 4, None, Color::BLACK, Color::WHITE, Some((&logo, Area{ start: 3, side: 3 })));
+        assert!(svg.ends_with("<svg x=\"8\" y=\"8\" width=\"1\" height=\"1\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 2 2\"><rect width=\"2\" height=\"2\"/></svg></svg>\n"));
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The version and mask of the QR-Code, pinned with `--qr-version`, `--min-version` and `--mask` instead of being
+//! chosen for the payload, e.g. so every code of a batch prints the same size.
+
+use std::fmt;
+
+use image::{GrayImage, Luma};
+use qrcode_generator::qrcodegen::{DataTooLong, Mask, QrCode, QrSegment, Version};
+
+use crate::ECCLevel;
+
+/// The options of the symbol.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options{
+    /// Version of the QR-Code, from 1 (21 x 21 modules) to 40 (177 x 177), e.g. to print all codes of a batch the
+    /// same size. Fails if the payload doesn't fit. The smallest version holding the payload if omitted.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=40), conflicts_with = "min-version")]
+    pub qr_version: Option<u8>,
+
+    /// Smallest version of the QR-Code, which payloads too long for it still grow beyond.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=40))]
+    pub min_version: Option<u8>,
+
+    /// Mask pattern of the QR-Code, from 0 to 7, which decides how the modules are mixed up. If omitted, the one
+    /// leaving the fewest patterns that confuse readers is chosen.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(0..=7))]
+    pub mask: Option<u8>
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SymbolError{
+    /// The payload of this many bytes doesn't fit into the version at the ECC-Level, which holds the bytes given,
+    /// if known.
+    TooLong{ length: usize, version: u8, ecc: ECCLevel, capacity: Option<usize> }
+}
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let SymbolError::TooLong{ length, version, ecc, capacity } = self;
+        match capacity {
+            Some(capacity) => write!(f, "the payload has {} bytes, but version {} holds at most {} at ECC-Level {:?}", length, version, capacity, ecc)?,
+            None => write!(f, "the payload has {} bytes, too many for version {} at ECC-Level {:?}", length, version, ecc)?
+        }
+        match ecc {
+            ECCLevel::Low => write!(f, ", pass a larger --qr-version"),
+            _ => write!(f, ", pass a larger --qr-version or a lower --ecc")
+        }
+    }
+}
+
+/// The modules of the QR-Code of `payload`, without the quiet zone. Without options this is the code
+/// `qrcode_generator::to_matrix` makes, raising the ECC-Level where that doesn't take a larger version.
+pub fn encode(payload: &str, ecc: ECCLevel, options: &Options) -> Result<Vec<Vec<bool>>, SymbolError> {
+    let segments:Vec<QrSegment> = QrSegment::make_segments(payload);
+    let min:Version = Version::new(options.qr_version.or(options.min_version).unwrap_or(Version::MIN.value()));
+    let max:Version = options.qr_version.map_or(Version::MAX, Version::new);
+    let code:QrCode = QrCode::encode_segments_advanced(&segments, ecc.into(), min, max, options.mask.map(Mask::new), true).map_err(|err| SymbolError::TooLong{
+        length: payload.len(),
+        version: max.value(),
+        ecc,
+        // The mode indicator and the character count take up the rest of the data bits.
+        capacity: match err {
+            DataTooLong::DataOverCapacity(_, bits) => Some((bits - 4 - if max.value() < 10 {8}else{16}) / 8),
+            DataTooLong::SegmentTooLong => None
+        }
+    })?;
+    Ok((0..code.size()).map(|y| (0..code.size()).map(|x| code.get_module(x, y)).collect()).collect())
+}
+
+/// A grayscale PNG of the modules `size` pixels wide with a quiet zone of at least one module, drawn like
+/// `qrcode_generator::to_png_to_vec`.
+pub fn png(matrix: &[Vec<bool>], size: u32) -> Vec<u8> {
+    let module_pixels:u32 = (size / (matrix.len() as u32 + 2)).max(1);
+    let margin:u32 = size.saturating_sub(module_pixels * matrix.len() as u32) / 2;
+    let image:GrayImage = GrayImage::from_fn(size, size, |x, y| {
+        let module = |pixel: u32| pixel.checked_sub(margin).map(|x| (x / module_pixels) as usize).filter(|&x| x < matrix.len());
+        match (module(x), module(y)) {
+            (Some(x), Some(y)) if matrix[y][x] => Luma([0]),
+            _ => Luma([255])
+        }
+    });
+    let mut png:Vec<u8> = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).expect("PNGs are written to memory");
+    png
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    const PAYLOAD: &str = "WIFI:T:WPA;S:Home;P:hunter22;;";
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(PAYLOAD, ECCLevel::Low, &Options::default()).unwrap(), qrcode_generator::to_matrix(PAYLOAD, ECCLevel::Low.into()).unwrap());
+        let pinned:Vec<Vec<bool>> = encode(PAYLOAD, ECCLevel::Low, &Options{ qr_version: Some(5), ..Options::default() }).unwrap();
+        assert_eq!(pinned.len(), 37);
+        assert_eq!(encode(PAYLOAD, ECCLevel::Low, &Options{ min_version: Some(5), ..Options::default() }).unwrap(), pinned);
+        assert_eq!(encode(&"x".repeat(100), ECCLevel::Low, &Options{ min_version: Some(2), ..Options::default() }).unwrap().len(), 37);
+        assert_ne!(encode(PAYLOAD, ECCLevel::Low, &Options{ mask: Some(0), ..Options::default() }).unwrap(), encode(PAYLOAD, ECCLevel::Low, &Options{ mask: Some(1), ..Options::default() }).unwrap());
+    }
+    #[test]
+    fn test_too_long() {
+        assert_eq!(encode(PAYLOAD, ECCLevel::Low, &Options{ qr_version: Some(1), ..Options::default() }),
+            Err(SymbolError::TooLong{ length: 30, version: 1, ecc: ECCLevel::Low, capacity: Some(17) }));
+        assert!(matches!(encode(&"x".repeat(300), ECCLevel::Low, &Options{ qr_version: Some(9), ..Options::default() }), Err(SymbolError::TooLong{ capacity: None, .. })));
+    }
+    #[test]
+    fn test_png() {
+        let matrix:Vec<Vec<bool>> = qrcode_generator::to_matrix(PAYLOAD, ECCLevel::Low.into()).unwrap();
+        let drawn = image::load_from_memory(&png(&matrix, 512)).unwrap().to_luma8();
+        let expected = image::load_from_memory(&qrcode_generator::to_png_to_vec(PAYLOAD, ECCLevel::Low.into(), 512).unwrap()).unwrap().to_luma8();
+        assert_eq!(drawn, expected);
+    }
+}
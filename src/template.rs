@@ -0,0 +1,119 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! User-supplied HTML and SVG templates, rendered with [Tera](https://keats.github.io/tera/), for fully custom
+//! signage.
+
+use std::path::Path;
+
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::branding::Branding;
+use crate::i18n::Texts;
+use crate::{alt_text, assemble_qr_string, resolve_security, svg, Args};
+
+/// Extensions of templates whose values are escaped for HTML/XML.
+const ESCAPED_EXTENSIONS: [&str; 5] = ["html", "htm", "svg", "xml", "xhtml"];
+
+/// A PNG as a `data:` URI, which templates can use as the source of an image.
+fn png_data_uri(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// The values templates can use: the network, the QR-Code as inline SVG and PNG, and the branding.
+pub fn context(args: &Args, matrix: &[Vec<bool>], branding: &Branding) -> Value {
+    let description:String = alt_text::describe(args);
+    let logo:Option<String> = branding.logo_image.as_ref().map(|logo| {
+        let mut png:Vec<u8> = Vec::new();
+        logo.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+        png_data_uri(&png)
+    });
+    let texts:&Texts = args.output.lang.texts();
+    json!({
+        "ssid": args.ssid,
+        "password": args.psw.clone().unwrap_or_default(),
+        "security": resolve_security(args).map(|x| x.to_string().to_uppercase()),
+        "hidden": args.hidden,
+        "payload": assemble_qr_string(args),
+        "description": description,
+        "qr_svg": svg::qr_code(matrix, &description),
+        "qr_png": png_data_uri(&crate::symbol::png(matrix, 1024)),
+        "qr_modules": matrix.len(),
+        "info_url": args.info_url,
+        "texts": {
+            "wifi": texts.wifi,
+            "network": texts.network,
+            "password": texts.password,
+            "no_password": texts.no_password,
+            "scan_to_join": texts.scan_to_join,
+            "cant_scan": texts.cant_scan,
+            "join": texts.join,
+            "steps": texts.steps
+        },
+        "vars": {},
+        "branding": {
+            "text": branding.palette.text.to_string(),
+            "accent": branding.palette.accent.to_string(),
+            "background": branding.palette.background.to_string(),
+            "footer": branding.footer,
+            "logo": logo,
+            "frame_text": branding.frame.as_ref().map(|x| x.text().to_string())
+        }
+    })
+}
+
+/// Sets the `--var`s of the command line, as `vars.<KEY>`, for the texts of the design such as a welcome line.
+pub fn set_vars(context: &mut Value, vars: &[(String, String)]) {
+    context["vars"] = Value::Object(vars.iter().map(|(key, value)| (key.clone(), Value::String(value.clone()))).collect());
+}
+
+/// Renders the template read from `path`. Values are escaped if it's an HTML or SVG file; the markup in `qr_svg`
+/// has to be inserted with the `safe` filter then.
+pub fn render(source: &str, path: &Path, context: &Value) -> tera::TeraResult<String> {
+    let escape:bool = path.extension().is_some_and(|x| ESCAPED_EXTENSIONS.iter().any(|y| x.eq_ignore_ascii_case(y)));
+    tera::Tera::one_off(source, &tera::Context::from_serialize(context)?, escape)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Security;
+    fn test_args() -> Args {
+        crate::tests::network("Martin <Router> King", Some("password"), Some(Security::Wpa2))
+    }
+    #[test]
+    fn test_render_html() {
+        let args = test_args();
+        let context = context(&args, &[vec![true]], &Branding::default());
+        let source = "<h1 style=\"color: {{ branding.accent }}\">{{ ssid }}</h1>{{ qr_svg | safe }}";
+        let html = render(source, Path::new("sign.html"), &context).unwrap();
+        assert!(html.starts_with("<h1 style=\"color: #000000\">Martin &lt;Router&gt; King</h1><svg "));
+        // Plain text templates are left unescaped.
+        assert_eq!(render("{{ ssid }} {{ security }}", Path::new("sign.txt"), &context).unwrap(), "Martin <Router> King WPA2");
+        assert!(render("{{ ssid", Path::new("sign.html"), &context).is_err());
+    }
+    #[test]
+    fn test_texts_and_vars() {
+        let mut args = test_args();
+        args.output.lang = crate::i18n::Language::Fr;
+        let mut context = context(&args, &[vec![true]], &Branding::default());
+        set_vars(&mut context, &[(String::from("welcome"), String::from("Bienvenue à l'hôtel")), (String::from("room"), String::from("<12>"))]);
+        let source = "{{ texts.password }}: {{ password }} | {{ vars.welcome }} {{ vars.room }} | {{ texts.steps | first }}";
+        assert_eq!(render(source, Path::new("card.txt"), &context).unwrap(), "Mot de passe: password | Bienvenue à l'hôtel <12> | 1. Ouvrez l'appareil photo de votre téléphone.");
+        assert!(render("{{ vars.room }}", Path::new("card.svg"), &context).unwrap().starts_with("&lt;12"));
+    }
+}
@@ -0,0 +1,361 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A form in the terminal for `tui`: the SSID, password and security standard are entered in fields, and the
+//! QR-Code beneath them is redrawn with half blocks on every key, or what devices won't accept is shown in its place,
+//! so typos show up before the code is printed. The terminal is switched to raw mode and its alternate screen while
+//! the form is open, and drawn with plain escape sequences.
+
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+
+use crate::batch::{self, Network};
+use crate::branding::Branding;
+use crate::console::{self, Invert};
+use crate::error::{Error, ErrorKind};
+use crate::output::{self, OutputFormat};
+use crate::{assemble_qr_string, checked_network, half_blocks, matrix, with_border, Args, Security};
+
+/// The security standards to pick from, by the names of `--sec`, and how they are shown.
+const SECURITY: [(Option<&str>, &str); 6] = [(None, "WPA2, or open without a password"), (Some("wpa"), "WPA"), (Some("wpa2"), "WPA2"),
+    (Some("wpa3"), "WPA3"), (Some("wpa2-wpa3"), "WPA2/WPA3"), (Some("wep"), "WEP")];
+
+/// The keys the form reacts to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key{
+    Char(char),
+    Backspace,
+    Enter,
+    Tab,
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+    /// A letter pressed with Ctrl.
+    Ctrl(char)
+}
+
+/// The keys of the input read from the terminal. Escape sequences of other keys are skipped.
+fn keys(input: &[u8]) -> Vec<Key> {
+    let mut keys:Vec<Key> = Vec::new();
+    let mut i:usize = 0;
+    while i < input.len() {
+        let byte:u8 = input[i];
+        i += 1;
+        match byte {
+            0x1b if matches!(input.get(i), Some(b'[' | b'O')) => {
+                // The final byte of a control sequence is in `@` to `~`, after any parameters.
+                let end:usize = input[i + 1..].iter().position(|x| (0x40..=0x7e).contains(x)).map_or(input.len(), |x| i + 1 + x);
+                match input.get(end) {
+                    Some(b'A') => keys.push(Key::Up),
+                    Some(b'B') => keys.push(Key::Down),
+                    Some(b'C') => keys.push(Key::Right),
+                    Some(b'D') => keys.push(Key::Left),
+                    Some(b'Z') => keys.push(Key::BackTab),
+                    _ => {}
+                }
+                i = end + 1;
+            },
+            0x1b => keys.push(Key::Escape),
+            0x7f | 0x08 => keys.push(Key::Backspace),
+            b'\r' | b'\n' => keys.push(Key::Enter),
+            b'\t' => keys.push(Key::Tab),
+            0x01..=0x1a => keys.push(Key::Ctrl((b'a' + byte - 1) as char)),
+            0x00..=0x1f => {},
+            _ => {
+                let length:usize = match byte {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1
+                };
+                let end:usize = (i - 1 + length).min(input.len());
+                if let Ok(text) = std::str::from_utf8(&input[i - 1..end]) {
+                    keys.extend(text.chars().map(Key::Char));
+                }
+                i = end;
+            }
+        }
+    }
+    keys
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field{
+    Ssid,
+    Password,
+    Security,
+    Hidden
+}
+const FIELDS: [Field; 4] = [Field::Ssid, Field::Password, Field::Security, Field::Hidden];
+
+/// What a key asks for, besides changing the form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action{
+    None,
+    Save(OutputFormat),
+    Quit
+}
+
+/// The network being entered.
+struct Form{
+    ssid: String,
+    psw: String,
+    /// The index into [`SECURITY`].
+    security: usize,
+    hidden: bool,
+    focus: usize,
+    /// What was saved, or why it wasn't.
+    message: String
+}
+impl Form {
+    /// The form, with the network of the command line filled in.
+    fn new(args: &Args) -> Self {
+        let name:Option<&str> = args.sec.map(|x| match x {
+            Security::Wep => "wep",
+            Security::Wpa => "wpa",
+            Security::Wpa2 => "wpa2",
+            Security::Wpa3 => "wpa3",
+            Security::Wpa2Wpa3 => "wpa2-wpa3"
+        });
+        let security:usize = SECURITY.iter().position(|(x, _)| *x == name).unwrap_or(0);
+        Form{ ssid: args.ssid.clone(), psw: args.psw.clone().unwrap_or_default(), security, hidden: args.hidden, focus: 0, message: String::new() }
+    }
+    fn network(&self) -> Network {
+        Network{ ssid: self.ssid.clone(), password: Some(self.psw.clone()).filter(|x| !x.is_empty()), security: SECURITY[self.security].0.map(String::from), hidden: self.hidden }
+    }
+    /// Changes the form for the key.
+    fn handle(&mut self, key: Key) -> Action {
+        let field:Field = FIELDS[self.focus];
+        match key {
+            Key::Escape | Key::Ctrl('c' | 'q') => return Action::Quit,
+            Key::Ctrl('s') => return Action::Save(OutputFormat::Png),
+            Key::Ctrl('e') => return Action::Save(OutputFormat::Svg),
+            Key::Tab | Key::Down | Key::Enter => self.focus = (self.focus + 1) % FIELDS.len(),
+            Key::BackTab | Key::Up => self.focus = (self.focus + FIELDS.len() - 1) % FIELDS.len(),
+            Key::Right | Key::Char(' ') if field == Field::Security => self.security = (self.security + 1) % SECURITY.len(),
+            Key::Left if field == Field::Security => self.security = (self.security + SECURITY.len() - 1) % SECURITY.len(),
+            Key::Left | Key::Right | Key::Char(' ') if field == Field::Hidden => self.hidden = !self.hidden,
+            Key::Char(character) if field == Field::Ssid => self.ssid.push(character),
+            Key::Char(character) if field == Field::Password => self.psw.push(character),
+            Key::Backspace if field == Field::Ssid => {self.ssid.pop();},
+            Key::Backspace if field == Field::Password => {self.psw.pop();},
+            _ => return Action::None
+        }
+        self.message.clear();
+        Action::None
+    }
+}
+
+/// The screen of the form, the code being drawn with half blocks if it fits into `columns`.
+fn draw(form: &Form, args: &Args, columns: Option<usize>, inverted: bool) -> String {
+    let marker = |field: Field| if FIELDS[form.focus] == field {"> "}else{"  "};
+    let caret = |field: Field| if FIELDS[form.focus] == field {"_"}else{""};
+    let mut screen:String = String::from("\x1b[H\x1b[2J");
+    screen += "Wi-Fi QR-Code\r\nTab: next field  Space: change  Ctrl+S: save PNG  Ctrl+E: save SVG  Esc: quit\r\n\r\n";
+    screen += &format!("{}SSID:      {}{}\r\n", marker(Field::Ssid), form.ssid, caret(Field::Ssid));
+    screen += &format!("{}Password:  {}{}\r\n", marker(Field::Password), form.psw, caret(Field::Password));
+    screen += &format!("{}Security:  < {} >\r\n", marker(Field::Security), SECURITY[form.security].1);
+    screen += &format!("{}Hidden:    [{}]\r\n\r\n", marker(Field::Hidden), if form.hidden {"x"}else{" "});
+    if !form.message.is_empty() {
+        screen += &format!("{}\r\n\r\n", form.message);
+    }
+    if form.ssid.is_empty() {
+        return screen + "Enter the SSID of the network.\r\n";
+    }
+    let network:Args = match checked_network(args, &form.network()) {
+        Ok(network) => network,
+        Err(err) => return format!("{}{}\r\n", screen, err.to_string().replace('\n', "\r\n"))
+    };
+    let payload:String = assemble_qr_string(&network);
+    screen += &format!("Payload: {}\r\n\r\n", payload);
+    let code:Vec<Vec<bool>> = match matrix(&network, &payload) {
+        Ok(matrix) => with_border(&matrix, network.output.border),
+        Err(err) => return format!("{}{}\r\n", screen, err.message)
+    };
+    if columns.is_some_and(|x| x < code.len()) {
+        return screen + "Widen the terminal to see the QR-Code.\r\n";
+    }
+    let code:Vec<Vec<bool>> = if inverted {code.into_iter().map(|row| row.into_iter().map(|x| !x).collect()).collect()}else{code};
+    screen + half_blocks(&code).replace('\n', "\r\n").as_str()
+}
+
+/// Saves the code of the form into the working directory, named after the SSID, and says what happened.
+fn save(form: &Form, args: &Args, branding: &Branding, format: OutputFormat) -> String {
+    let network:Args = match checked_network(args, &form.network()) {
+        Ok(network) => network,
+        Err(err) => return format!("Not saved: {}", err.to_string().lines().next().unwrap_or_default())
+    };
+    let path:PathBuf = PathBuf::from(format!("{}.{}", batch::sanitize(&network.ssid), if format == OutputFormat::Svg {"svg"}else{"png"}));
+    match output::contents(&network, branding, format, &path).map_err(|err| err.to_string()).and_then(|x| std::fs::write(&path, x).map_err(|err| format!("{}: {}", path.display(), err))) {
+        Ok(()) => format!("Wrote {}.", path.display()),
+        Err(err) => format!("Failed to save the QR-Code: {}", err)
+    }
+}
+
+/// The alternate screen in raw mode, restored when dropped, and then the mode of the terminal.
+struct Screen{
+    _raw_mode: RawMode
+}
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        let raw:RawMode = RawMode::new()?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(Screen{ _raw_mode: raw })
+    }
+}
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Turns off the line editing, echo and signals of the terminal until it's dropped, so every key is read.
+#[cfg(unix)]
+struct RawMode(libc::termios);
+#[cfg(unix)]
+impl RawMode {
+    fn new() -> io::Result<Self> {
+        // SAFETY: termios is plain data tcgetattr fills in, and both calls fail for inputs that aren't terminals.
+        unsafe {
+            let mut termios:libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw:libc::termios = termios;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            // Ctrl+S would otherwise stop the output, and Enter arrive as a line feed.
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(RawMode(termios))
+        }
+    }
+}
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: Restores the settings read in `new`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+/// Turns off the line editing, echo and Ctrl+C of the console until it's dropped, and has it take and send escape
+/// sequences.
+#[cfg(windows)]
+struct RawMode(windows_sys::Win32::Foundation::HANDLE, u32, windows_sys::Win32::Foundation::HANDLE, u32);
+#[cfg(windows)]
+impl RawMode {
+    fn new() -> io::Result<Self> {
+        use windows_sys::Win32::System::Console::*;
+        // SAFETY: The handles are only passed back to the console functions, which fail gracefully on invalid handles.
+        unsafe {
+            let (input, output) = (GetStdHandle(STD_INPUT_HANDLE), GetStdHandle(STD_OUTPUT_HANDLE));
+            let (mut input_mode, mut output_mode):(u32, u32) = (0, 0);
+            if GetConsoleMode(input, &mut input_mode) == 0 || GetConsoleMode(output, &mut output_mode) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let raw:u32 = (input_mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT)) | ENABLE_VIRTUAL_TERMINAL_INPUT;
+            if SetConsoleMode(input, raw) == 0 || SetConsoleMode(output, output_mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0 {
+                let err = io::Error::last_os_error();
+                SetConsoleMode(input, input_mode);
+                return Err(err);
+            }
+            Ok(RawMode(input, input_mode, output, output_mode))
+        }
+    }
+}
+#[cfg(windows)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        use windows_sys::Win32::System::Console::SetConsoleMode;
+        // SAFETY: Restores the modes read in `new`.
+        unsafe {
+            SetConsoleMode(self.0, self.1);
+            SetConsoleMode(self.2, self.3);
+        }
+    }
+}
+
+/// Shows the form until it's left with Esc or Ctrl+C.
+pub fn run(args: &Args, branding: &Branding) -> Result<(), Error> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(Error::new(ErrorKind::Usage, "tui needs a terminal, pass the network as options to encode it without one!"));
+    }
+    let inverted:bool = match args.output.invert {
+        Invert::Auto => console::dark_background() == Some(true),
+        Invert::Always => true,
+        Invert::Never => false
+    };
+    let mut form:Form = Form::new(args);
+    let failed = |err: io::Error| Error::new(ErrorKind::External, format!("Failed to draw the form in the terminal: {}", err));
+    let _screen:Screen = Screen::enter().map_err(failed)?;
+    let mut input:[u8; 64] = [0; 64];
+    loop {
+        print!("{}", draw(&form, args, console::width(), inverted));
+        io::stdout().flush().map_err(failed)?;
+        let read:usize = io::stdin().read(&mut input).map_err(failed)?;
+        if read == 0 {
+            return Ok(());
+        }
+        for key in keys(&input[..read]) {
+            match form.handle(key) {
+                Action::Quit => return Ok(()),
+                Action::Save(format) => form.message = save(&form, args, branding, format),
+                Action::None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use clap::Parser;
+    #[test]
+    fn test_keys() {
+        assert_eq!(keys(b"a\x1b[A\x1b[1;5C\x1b[Z\x7f\r\t\x13\x1b"), [Key::Char('a'), Key::Up, Key::Right, Key::BackTab, Key::Backspace, Key::Enter, Key::Tab, Key::Ctrl('s'), Key::Escape]);
+        // Delete is skipped as a whole, and UTF-8 read as characters.
+        assert_eq!(keys("\x1b[3~é".as_bytes()), [Key::Char('é')]);
+    }
+    #[test]
+    fn test_form() {
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "Hom", "tui"]);
+        let mut form:Form = Form::new(&args);
+        for key in [Key::Char('e'), Key::Tab, Key::Char('p'), Key::Char('w'), Key::Backspace, Key::Tab, Key::Right, Key::Right, Key::Right, Key::Tab, Key::Char(' ')] {
+            assert_eq!(form.handle(key), Action::None);
+        }
+        assert_eq!((form.ssid.as_str(), form.psw.as_str(), SECURITY[form.security].1, form.hidden), ("Home", "p", "WPA3", true));
+        assert_eq!(form.handle(Key::Ctrl('e')), Action::Save(OutputFormat::Svg));
+        assert_eq!(form.handle(Key::Escape), Action::Quit);
+        // The password is too short for WPA3, which is shown in place of the code.
+        let screen:String = draw(&form, &args, Some(80), false);
+        assert!(screen.contains("> Hidden:    [x]") && !screen.contains("Payload:"));
+        form.psw = String::from("hunter22");
+        let screen:String = draw(&form, &args, Some(80), false);
+        assert!(screen.contains("Payload: WIFI:T:SAE;S:Home;P:hunter22;H:true;;") && screen.contains('▀'));
+        assert!(draw(&form, &args, Some(20), false).contains("Widen the terminal"));
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Checking a QR-Code by scanning it, for `--verify`: the modules are drawn into an image, decoded like a photo of
+//! the code would be, and the network read back from the payload has to be the one given.
+
+use std::fmt;
+
+use image::GrayImage;
+
+use crate::color::Color;
+use crate::inspect::{self, InspectError};
+use crate::{hex_ssid, logo, output, resolve_security, Args, Security, QUIET_ZONE};
+
+/// Pixels per module of the image that is decoded.
+const MODULE_PIXELS: u32 = 4;
+
+#[derive(Debug)]
+pub enum VerifyError{
+    Decode(InspectError),
+    /// The code decoded to this payload instead of the one encoded.
+    Payload(String),
+    /// The field of the payload, as decoded, and what was given.
+    Field(&'static str, String, String)
+}
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Decode(err) => write!(f, "{}", err),
+            VerifyError::Payload(payload) => write!(f, "the QR-Code decodes to {}, not to the payload encoded", payload),
+            // Passwords are left out of errors, which end up in logs.
+            VerifyError::Field("password", ..) => write!(f, "the password reads back differently from the one given"),
+            VerifyError::Field(name, decoded, given) => write!(f, "the {} reads back as \"{}\" instead of \"{}\"", name, decoded, given)
+        }
+    }
+}
+
+/// The `T:` field readers should read for the network.
+fn security_token(args: &Args) -> &'static str {
+    if args.enterprise.eap.is_some() {
+        return "WPA2-EAP";
+    }
+    resolve_security(args).map_or("nopass", Security::payload_token)
+}
+
+/// The modules as drawn, with those beneath the `--logo` left light.
+fn drawn(args: &Args, matrix: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    match args.output.logo.as_ref().and_then(|_| logo::area(matrix, args.output.logo_size, args.ecc).ok()) {
+        Some(area) => logo::cleared(matrix, area),
+        None => matrix.to_vec()
+    }
+}
+
+/// Decodes `matrix`, the code of `payload`, and checks that it holds the network of `args`. The payload is
+/// compared too as the fields readers don't know, e.g. those of Enterprise networks, aren't read back.
+pub fn verify(args: &Args, payload: &str, matrix: &[Vec<bool>]) -> Result<(), VerifyError> {
+    let image:GrayImage = image::DynamicImage::ImageRgba8(output::image(&drawn(args, matrix), QUIET_ZONE, MODULE_PIXELS, Color::BLACK, Color::WHITE)).to_luma8();
+    let symbols:Vec<inspect::Symbol> = inspect::decode(&image).map_err(VerifyError::Decode)?;
+    let decoded:&str = &symbols.first().ok_or(VerifyError::Decode(InspectError::NotFound))?.payload;
+    if decoded != payload {
+        return Err(VerifyError::Payload(String::from(decoded)));
+    }
+    let values:Vec<(String, String)> = inspect::fields(decoded, &mut |_, _| {}).unwrap_or_default();
+    let value = |key: &str| values.iter().find(|(x, _)| x == key).map(|(_, x)| x.as_str());
+    let security:&str = security_token(args);
+    let mut expected:Vec<(&'static str, &str, String)> = vec![
+        ("SSID", value("S").unwrap_or_default(), hex_ssid::written(args).unwrap_or_else(|| args.ssid.clone())),
+        ("security", value("T").unwrap_or("nopass"), String::from(security)),
+        ("hidden flag", if value("H") == Some("true") {"true"}else{"false"}, args.hidden.to_string())
+    ];
+    if security != "nopass" {
+        expected.push(("password", value("P").unwrap_or_default(), args.psw.clone().unwrap_or_default()));
+    }
+    match expected.into_iter().find(|(_, decoded, given)| decoded != given) {
+        Some((name, decoded, given)) => Err(VerifyError::Field(name, String::from(decoded), given)),
+        None => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::assemble_qr_string;
+    use clap::Parser;
+    #[test]
+    fn test_verify() {
+        let args:Args = Args::parse_from(["wifiqr", "--ssid", "My;Home", "--psw", "hunter22", "--hidden"]);
+        let payload:String = assemble_qr_string(&args);
+        let matrix:Vec<Vec<bool>> = crate::matrix(&args, &payload).unwrap();
+        assert!(verify(&args, &payload, &matrix).is_ok());
+        let other:Args = Args::parse_from(["wifiqr", "--ssid", "My;Home", "--psw", "hunter23", "--hidden"]);
+        assert!(matches!(verify(&other, &payload, &matrix), Err(VerifyError::Field("password", _, _))));
+        assert!(matches!(verify(&args, "WIFI:S:x;;", &matrix), Err(VerifyError::Payload(_))));
+        let mut damaged:Vec<Vec<bool>> = matrix.clone();
+        for row in &mut damaged[..9] {
+            row[..9].fill(false);
+        }
+        assert!(matches!(verify(&args, &payload, &damaged), Err(VerifyError::Decode(_))));
+    }
+    #[test]
+    fn test_non_ascii() {
+        // The UTF-8 bytes are encoded in byte mode and decoded back as they were.
+        for ssid in ["Café Müller", "🏠 Home", "東京の家"] {
+            let args:Args = Args::parse_from(["wifiqr", "--ssid", ssid, "--psw", "hunter22"]);
+            let payload:String = assemble_qr_string(&args);
+            assert!(verify(&args, &payload, &crate::matrix(&args, &payload).unwrap()).is_ok(), "{}", ssid);
+        }
+        let mut args:Args = Args::parse_from(["wifiqr", "--ssid-hex", "4361FE", "--psw", "hunter22"]);
+        crate::hex_ssid::prepare(&mut args);
+        let payload:String = assemble_qr_string(&args);
+        assert_eq!(payload, "WIFI:T:WPA;S:4361FE;P:hunter22;;");
+        assert!(verify(&args, &payload, &crate::matrix(&args, &payload).unwrap()).is_ok());
+    }
+}
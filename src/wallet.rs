@@ -0,0 +1,279 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Apple Wallet passes (`.pkpass`) carrying the QR-Code and the name of the network, for guests to keep on their
+//! phones. A pass is a ZIP archive of `pass.json`, its images, a manifest of their SHA-1 hashes and a detached
+//! PKCS#7 signature of the manifest, which is made by `openssl` with the certificate of a pass type ID.
+
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use image::{imageops, Rgba, RgbaImage};
+use serde_json::{json, Value};
+
+use crate::branding::Branding;
+use crate::color::Color;
+use crate::private_dir::PrivateDir;
+use crate::{assemble_qr_string, resolve_security, Args};
+
+/// What identifies the pass to Apple, from the developer account that registered the pass type ID.
+pub struct PassInfo<'a>{
+    /// The pass type ID, e.g. `pass.com.example.wifi`.
+    pub pass_type_id: &'a str,
+    pub team_id: &'a str,
+    /// Shown on the lock screen and at the top of the pass.
+    pub organization: &'a str
+}
+
+/// The signing assets: the certificate of the pass type ID with its private key, and Apple's WWDR intermediate
+/// certificate, all in PEM.
+pub struct Signing<'a>{
+    pub certificate: &'a Path,
+    pub key: &'a Path,
+    pub wwdr: &'a Path
+}
+
+#[derive(Debug)]
+pub enum WalletError{
+    Io(std::io::Error),
+    Signing(String)
+}
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalletError::Io(err) => write!(f, "failed to run openssl: {}", err),
+            WalletError::Signing(stderr) => write!(f, "openssl failed to sign the manifest: {}", stderr.trim())
+        }
+    }
+}
+impl From<std::io::Error> for WalletError {
+    fn from(err: std::io::Error) -> Self {
+        WalletError::Io(err)
+    }
+}
+
+fn css_color(color: Color) -> String {
+    format!("rgb({}, {}, {})", color.r, color.g, color.b)
+}
+
+/// The `pass.json` of a generic pass, with the network on its front and the QR-Code as its barcode.
+pub fn pass_json(args: &Args, branding: &Branding, pass: &PassInfo) -> Value {
+    let password:&str = args.psw.as_deref().filter(|x| !x.is_empty()).unwrap_or("none (open network)");
+    let security:String = resolve_security(args).map_or(String::from("Open"), |x| x.to_string());
+    json!({
+        "formatVersion": 1,
+        "passTypeIdentifier": pass.pass_type_id,
+        // Passes with the same serial number replace each other, so a new password updates the pass.
+        "serialNumber": format!("wifi-{}", args.ssid),
+        "teamIdentifier": pass.team_id,
+        "organizationName": pass.organization,
+        "description": format!("Wi-Fi {}", args.ssid),
+        "backgroundColor": css_color(branding.palette.background),
+        "foregroundColor": css_color(branding.palette.text),
+        "labelColor": css_color(branding.palette.accent),
+        "barcodes": [{
+            "format": "PKBarcodeFormatQR",
+            "message": assemble_qr_string(args),
+            "messageEncoding": "iso-8859-1",
+            "altText": args.ssid
+        }],
+        "generic": {
+            "primaryFields": [{"key": "ssid", "label": "Network", "value": args.ssid}],
+            "secondaryFields": [{"key": "password", "label": "Password", "value": password}],
+            "auxiliaryFields": [{"key": "security", "label": "Security", "value": security}],
+            "backFields": [{"key": "join", "label": "Joining", "value": "Scan the code with the camera of another device, or enter the network and password in the Wi-Fi settings."}]
+        }
+    })
+}
+
+/// Fits the image into a square of `size` pixels, keeping its aspect ratio.
+fn fitted(image: &RgbaImage, size: u32) -> RgbaImage {
+    let scale:f32 = size as f32 / image.width().max(image.height()) as f32;
+    let resized:RgbaImage = imageops::resize(image, ((image.width() as f32 * scale) as u32).clamp(1, size), ((image.height() as f32 * scale) as u32).clamp(1, size), imageops::FilterType::Lanczos3);
+    let mut square = RgbaImage::new(size, size);
+    imageops::overlay(&mut square, &resized, ((size - resized.width()) / 2) as i64, ((size - resized.height()) / 2) as i64);
+    square
+}
+
+fn png(image: &RgbaImage) -> Vec<u8> {
+    let mut png:Vec<u8> = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+    png
+}
+
+/// The icon, which Wallet requires and shows in notifications, and the logo at the top of the pass, at 1x, 2x and
+/// 3x. Both are the logo of the branding, or a square in its accent color without one.
+fn images(branding: &Branding) -> Vec<(String, Vec<u8>)> {
+    let accent:Color = branding.palette.accent;
+    let mut images:Vec<(String, Vec<u8>)> = Vec::new();
+    for (scale, suffix) in [(1, ""), (2, "@2x"), (3, "@3x")] {
+        let size:u32 = 29 * scale;
+        let icon:RgbaImage = match &branding.logo_image {
+            Some(logo) => fitted(logo, size),
+            None => RgbaImage::from_pixel(size, size, Rgba([accent.r, accent.g, accent.b, 255]))
+        };
+        images.push((format!("icon{}.png", suffix), png(&icon)));
+        if let Some(logo) = &branding.logo_image {
+            images.push((format!("logo{}.png", suffix), png(&fitted(logo, 50 * scale))));
+        }
+    }
+    images
+}
+
+/// The SHA-1 hash of the data, which the manifest lists and configuration profiles derive their UUIDs from.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state:[u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message:Vec<u8> = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut words:[u32; 80] = [0; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k):(u32, u32) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6)
+            };
+            let temp:u32 = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, temp);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+    let mut digest:[u8; 20] = [0; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// The `manifest.json`, with the SHA-1 hash of every file.
+fn manifest(files: &[(String, Vec<u8>)]) -> Value {
+    Value::Object(files.iter().map(|(name, contents)| {
+        (name.clone(), Value::String(sha1(contents).iter().map(|x| format!("{:02x}", x)).collect()))
+    }).collect())
+}
+
+/// The detached signature of the manifest, in DER. openssl asks for the passphrase of the key if it has one.
+fn sign(manifest: &[u8], signing: &Signing, shred: bool) -> Result<Vec<u8>, WalletError> {
+    let mut directory = PrivateDir::create(shred)?;
+    let manifest:std::path::PathBuf = directory.write("manifest.json", manifest)?;
+    let output = Command::new("openssl")
+        .args(["smime", "-binary", "-sign", "-outform", "DER", "-certfile"]).arg(signing.wwdr)
+        .arg("-signer").arg(signing.certificate)
+        .arg("-inkey").arg(signing.key)
+        .arg("-in").arg(&manifest)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        return Err(WalletError::Signing(String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(output.stdout)
+}
+
+/// A ZIP archive of the files, stored without compression, as the images already are compressed.
+fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive:Vec<u8> = Vec::new();
+    let mut directory:Vec<u8> = Vec::new();
+    for (name, contents) in files {
+        let mut crc = flate2::Crc::new();
+        crc.update(contents);
+        // Version 2.0, UTF-8 names, stored, modified on 1980-01-01.
+        let mut header:Vec<u8> = Vec::new();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0x0800u16.to_le_bytes());
+        header.extend_from_slice(&[0, 0, 0, 0, 0x21, 0]);
+        header.extend_from_slice(&crc.sum().to_le_bytes());
+        header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&[0, 0]);
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&header);
+        // No comment, disk 0, no attributes, then the offset of the local header.
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&(archive.len() as u32).to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(contents);
+    }
+    let offset:usize = archive.len();
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0, 0, 0, 0]);
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&(offset as u32).to_le_bytes());
+    archive.extend_from_slice(&[0, 0]);
+    archive
+}
+
+/// Builds and signs the pass.
+pub fn pkpass(args: &Args, branding: &Branding, pass: &PassInfo, signing: &Signing) -> Result<Vec<u8>, WalletError> {
+    let mut files:Vec<(String, Vec<u8>)> = vec![(String::from("pass.json"), serde_json::to_vec_pretty(&pass_json(args, branding, pass)).unwrap())];
+    files.extend(images(branding));
+    let manifest:Vec<u8> = serde_json::to_vec_pretty(&manifest(&files)).unwrap();
+    let signature:Vec<u8> = sign(&manifest, signing, args.shred)?;
+    files.push((String::from("manifest.json"), manifest));
+    files.push((String::from("signature"), signature));
+    Ok(zip(&files))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_sha1() {
+        let hex = |data: &[u8]| sha1(data).iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        assert_eq!(hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex(&[b'a'; 1000]), "291e9a6c66994949b57ba5e650361e98fc36b1ba");
+    }
+    #[test]
+    fn test_pass() {
+        let args = crate::tests::network("Lobby", Some("hunter22"), None);
+        let pass = pass_json(&args, &Branding::default(), &PassInfo{ pass_type_id: "pass.com.example.wifi", team_id: "ABCDE12345", organization: "Hotel" });
+        assert_eq!(pass["barcodes"][0]["message"], "WIFI:T:WPA;S:Lobby;P:hunter22;;");
+        assert_eq!(pass["generic"]["secondaryFields"][0]["value"], "hunter22");
+        assert_eq!(pass["serialNumber"], "wifi-Lobby");
+        let files = vec![(String::from("pass.json"), b"{}".to_vec()), (String::from("icon.png"), Vec::new())];
+        assert_eq!(manifest(&files)["icon.png"], "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        let archive = zip(&files);
+        assert!(archive.starts_with(b"PK\x03\x04"));
+        // The end of central directory record counts both files, with 46 bytes for each entry and its name.
+        let end:&[u8] = &archive[archive.len() - 22..];
+        assert_eq!(&end[..12], b"PK\x05\x06\0\0\0\0\x02\0\x02\0");
+        assert_eq!(u32::from_le_bytes([end[12], end[13], end[14], end[15]]), 46 * 2 + 17);
+    }
+}
@@ -0,0 +1,258 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Wallpapers and lock-screen images with the QR-Code composited onto them, for screens that should always show the join code.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use image::{imageops, RgbaImage};
+
+use crate::branding::Align;
+use crate::color::Color;
+use crate::font::EmbeddedFont;
+use crate::{with_quiet_zone, QUIET_ZONE};
+
+/// Either a solid color (`#RRGGBB`) or the path of an image.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Background{
+    Color(Color),
+    Image(PathBuf)
+}
+impl FromStr for Background {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('#') {
+            Ok(Background::Color(s.parse()?))
+        }else{
+            Ok(Background::Image(PathBuf::from(s)))
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum Corner{
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center
+}
+
+/// Devices whose lock screen the image is made for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum LockScreen{
+    /// Clock and date centered in the upper half.
+    Windows11,
+    /// Clock and date in the bottom left corner.
+    Windows10,
+    /// Clock at the top, user picker at the bottom center.
+    Macos,
+    Iphone,
+    Ipad,
+    Android
+}
+impl LockScreen {
+    /// Resolution in pixels.
+    pub fn resolution(&self) -> (u32, u32) {
+        match self {
+            LockScreen::Windows11 | LockScreen::Windows10 => (1920, 1080),
+            LockScreen::Macos => (2880, 1800),
+            LockScreen::Iphone => (1179, 2556),
+            LockScreen::Ipad => (2048, 2732),
+            LockScreen::Android => (1080, 2400)
+        }
+    }
+    /// Area that stays clear of the clock, notifications and system controls, as fractions of the
+    /// resolution: left, top, right and bottom.
+    pub fn safe_zone(&self) -> (f32, f32, f32, f32) {
+        match self {
+            LockScreen::Windows11 => (0.38, 0.52, 0.62, 0.88),
+            LockScreen::Windows10 => (0.68, 0.12, 0.94, 0.72),
+            LockScreen::Macos => (0.74, 0.3, 0.94, 0.7),
+            // Below the clock and widgets, above the flashlight and camera buttons.
+            LockScreen::Iphone => (0.2, 0.45, 0.8, 0.78),
+            LockScreen::Ipad => (0.3, 0.45, 0.7, 0.78),
+            LockScreen::Android => (0.2, 0.45, 0.8, 0.78)
+        }
+    }
+}
+
+/// Renders the symbol with its quiet zone as dark modules on white, using the largest whole number of pixels
+/// per module for which it still fits into `max_size`, so the modules stay crisp.
+pub fn qr_image(matrix: &[Vec<bool>], max_size: u32) -> RgbaImage {
+    let matrix:Vec<Vec<bool>> = with_quiet_zone(matrix);
+    let module_size:u32 = (max_size / matrix.len() as u32).max(1);
+    RgbaImage::from_fn(matrix.len() as u32 * module_size, matrix.len() as u32 * module_size, |x, y| {
+        if matrix[(y / module_size) as usize][(x / module_size) as usize] {Color::BLACK.to_rgba()}else{Color::WHITE.to_rgba()}
+    })
+}
+
+/// Text set below the QR-Code, usually the SSID.
+#[derive(Copy, Clone, Debug)]
+pub struct Caption<'a>{
+    pub font: &'a EmbeddedFont,
+    pub text: &'a str,
+    pub color: Color,
+    pub align: Align
+}
+
+/// Renders the QR-Code no larger than `max_size` and the caption below it onto one white panel. The caption
+/// starts in the quiet zone and is shrunk to the width of the symbol if necessary.
+pub fn qr_panel(matrix: &[Vec<bool>], max_size: u32, caption: Option<Caption>) -> RgbaImage {
+    let qr:RgbaImage = qr_image(matrix, max_size);
+    let Some(caption) = caption else { return qr; };
+    let module_size:u32 = qr.width() / (matrix.len() + 2 * QUIET_ZONE) as u32;
+    let available:f32 = (qr.width() - 2 * QUIET_ZONE as u32 * module_size) as f32;
+    let width:f32 = caption.font.text_width(caption.text) / 1000.0;
+    let size:f32 = (qr.width() as f32 / 10.0).min(available / width.max(0.001));
+    let mask = caption.font.render(caption.text, size);
+    let mut panel = RgbaImage::from_pixel(qr.width(), qr.height() + mask.height(), Color::WHITE.to_rgba());
+    imageops::overlay(&mut panel, &qr, 0, 0);
+    let margin:f32 = (QUIET_ZONE as u32 * module_size) as f32;
+    let x:u32 = caption.align.resolve(Align::Center, caption.text).position(margin, available, mask.width() as f32).max(0.0) as u32;
+    let y:u32 = qr.height() - 2 * module_size;
+    for (mask_x, mask_y, coverage) in mask.enumerate_pixels() {
+        if let Some(pixel) = panel.get_pixel_mut_checked(x + mask_x, y + mask_y) {
+            let alpha:u16 = coverage[0] as u16;
+            for (channel, color) in pixel.0.iter_mut().zip([caption.color.r, caption.color.g, caption.color.b]) {
+                *channel = ((*channel as u16 * (255 - alpha) + color as u16 * alpha) / 255) as u8;
+            }
+        }
+    }
+    panel
+}
+
+/// Creates the canvas. Images are scaled to cover the whole canvas and cropped around their center.
+pub fn background_image(background: &Background, width: u32, height: u32) -> image::ImageResult<RgbaImage> {
+    match background {
+        Background::Color(color) => Ok(RgbaImage::from_pixel(width, height, color.to_rgba())),
+        Background::Image(path) => {
+            let image = image::open(path)?;
+            Ok(image.resize_to_fill(width, height, imageops::FilterType::Lanczos3).to_rgba8())
+        }
+    }
+}
+
+/// Position of the top left corner of an overlay of `size` placed at `corner` of the canvas, `margin` pixels
+/// away from the edges.
+pub fn overlay_position(canvas: (u32, u32), size: (u32, u32), corner: Corner, margin: u32) -> (i64, i64) {
+    let right:i64 = canvas.0 as i64 - size.0 as i64 - margin as i64;
+    let bottom:i64 = canvas.1 as i64 - size.1 as i64 - margin as i64;
+    match corner {
+        Corner::TopLeft => (margin as i64, margin as i64),
+        Corner::TopRight => (right, margin as i64),
+        Corner::BottomLeft => (margin as i64, bottom),
+        Corner::BottomRight => (right, bottom),
+        Corner::Center => ((canvas.0 as i64 - size.0 as i64) / 2, (canvas.1 as i64 - size.1 as i64) / 2)
+    }
+}
+
+/// Composites the QR-Code of `qr_size` pixels, and the caption below it, into the background.
+pub fn wallpaper(matrix: &[Vec<bool>], caption: Option<Caption>, background: &Background, (width, height): (u32, u32), corner: Corner, qr_size: u32, margin: u32) -> image::ImageResult<RgbaImage> {
+    let mut canvas:RgbaImage = background_image(background, width, height)?;
+    let qr:RgbaImage = qr_panel(matrix, qr_size, caption);
+    let (x, y) = overlay_position(canvas.dimensions(), qr.dimensions(), corner, margin);
+    imageops::overlay(&mut canvas, &qr, x, y);
+    Ok(canvas)
+}
+
+/// Places the QR-Code and caption as large as possible in the center of the lock screen's safe zone.
+pub fn lock_screen(matrix: &[Vec<bool>], caption: Option<Caption>, background: &Background, device: LockScreen) -> image::ImageResult<RgbaImage> {
+    let (width, height) = device.resolution();
+    let (left, top, right, bottom) = device.safe_zone();
+    let mut canvas:RgbaImage = background_image(background, width, height)?;
+    let zone_width:u32 = ((right - left) * width as f32) as u32;
+    let zone_height:u32 = ((bottom - top) * height as f32) as u32;
+    // The caption adds at most a fifth of the width of the QR-Code below it.
+    let max_size:u32 = if caption.is_some() {zone_width.min(zone_height * 5 / 6)}else{zone_width.min(zone_height)};
+    let qr:RgbaImage = qr_panel(matrix, max_size, caption);
+    let x:i64 = (left * width as f32) as i64 + (zone_width - qr.width()) as i64 / 2;
+    let y:i64 = (top * height as f32) as i64 + (zone_height - qr.height()) as i64 / 2;
+    imageops::overlay(&mut canvas, &qr, x, y);
+    Ok(canvas)
+}
+
+/// Places the QR-Code and caption as large as possible in the center of a white label of the size in pixels.
+pub fn label(matrix: &[Vec<bool>], caption: Option<Caption>, (width, height): (u32, u32)) -> RgbaImage {
+    let max_size:u32 = if caption.is_some() {width.min(height * 5 / 6)}else{width.min(height)};
+    let mut canvas:RgbaImage = RgbaImage::from_pixel(width, height, Color::WHITE.to_rgba());
+    let qr:RgbaImage = qr_panel(matrix, max_size, caption);
+    let (x, y) = overlay_position(canvas.dimensions(), qr.dimensions(), Corner::Center, 0);
+    imageops::overlay(&mut canvas, &qr, x, y);
+    canvas
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_parse_background() {
+        assert_eq!("#000000".parse::<Background>().unwrap(), Background::Color(Color::BLACK));
+        assert_eq!("beach.jpg".parse::<Background>().unwrap(), Background::Image(PathBuf::from("beach.jpg")));
+        assert!("#nope".parse::<Background>().is_err());
+    }
+    #[test]
+    fn test_qr_image_uses_whole_pixels() {
+        // One module plus the quiet zone is 9 modules, so at most 11 pixels each fit into 100 pixels.
+        let image = qr_image(&[vec![true]], 100);
+        assert_eq!(image.dimensions(), (99, 99));
+        assert_eq!(image.get_pixel(44, 44), &Color::BLACK.to_rgba());
+        assert_eq!(image.get_pixel(43, 43), &Color::WHITE.to_rgba());
+    }
+    #[test]
+    fn test_overlay_position() {
+        assert_eq!(overlay_position((1920, 1080), (200, 200), Corner::TopLeft, 50), (50, 50));
+        assert_eq!(overlay_position((1920, 1080), (200, 200), Corner::BottomRight, 50), (1670, 830));
+        assert_eq!(overlay_position((1920, 1080), (200, 200), Corner::Center, 50), (860, 440));
+    }
+    #[test]
+    fn test_wallpaper_on_color() {
+        let image = wallpaper(&[vec![true]], None, &Background::Color(Color::new(0, 0, 255)), (320, 200), Corner::BottomRight, 90, 10).unwrap();
+        assert_eq!(image.dimensions(), (320, 200));
+        assert_eq!(image.get_pixel(0, 0), &Color::new(0, 0, 255).to_rgba());
+        assert_eq!(image.get_pixel(319 - 10 - 45, 199 - 10 - 45), &Color::BLACK.to_rgba());
+    }
+    #[test]
+    fn test_qr_panel_caption() {
+        let Ok(font) = EmbeddedFont::load(std::path::Path::new("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")) else { return; };
+        let caption = Caption{ font, text: "Guest", color: Color::new(255, 0, 0), align: Align::Auto };
+        let matrix = qrcode_generator::to_matrix("WIFI:T:WPA;S:Guest;P:password;;", qrcode_generator::QrCodeEcc::Low).unwrap();
+        let panel = qr_panel(&matrix, 400, Some(caption));
+        let size = qr_image(&matrix, 400).width();
+        assert_eq!(panel.width(), size);
+        assert!(panel.height() > size);
+        // The caption is drawn in its color below the symbol.
+        assert!(panel.enumerate_pixels().any(|(_, y, x)| y >= size && x[0] > 200 && x[1] < 50));
+    }
+    #[test]
+    fn test_lock_screen_stays_in_safe_zone() {
+        let matrix = qrcode_generator::to_matrix("WIFI:T:WPA;S:Guest;P:password;;", qrcode_generator::QrCodeEcc::Low).unwrap();
+        let background = Background::Color(Color::new(0, 0, 255));
+        for device in [LockScreen::Windows11, LockScreen::Windows10, LockScreen::Macos, LockScreen::Iphone, LockScreen::Ipad, LockScreen::Android] {
+            let image = lock_screen(&matrix, None, &background, device).unwrap();
+            let (width, height) = device.resolution();
+            assert_eq!(image.dimensions(), (width, height));
+            let (left, top, right, bottom) = device.safe_zone();
+            for (x, y, pixel) in image.enumerate_pixels() {
+                if pixel != &Color::new(0, 0, 255).to_rgba() {
+                    assert!(x as f32 >= left * width as f32 && (x as f32) < right * width as f32, "{:?}", device);
+                    assert!(y as f32 >= top * height as f32 && (y as f32) < bottom * height as f32, "{:?}", device);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! WEP keys, which are 5 or 13 ASCII characters or 10 or 26 hex digits for 64 and 128 bit WEP. Readers take 10 or
+//! 26 hex digits in the payload for a hex key and anything else for ASCII, so hex keys have to be one run of digits,
+//! whereas routers often show them in groups, e.g. `12:34:56:78:90`.
+
+use crate::{Args, Security};
+
+/// The options of WEP networks.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options{
+    /// Insist on the WEP key being hex digits, failing for ASCII keys, e.g. to catch a mistyped key.
+    #[clap(long, action)]
+    pub wep_hex: bool,
+
+    /// Which of the 4 keys of the WEP network to send with. Only written to the profiles of `convert`, as the
+    /// payload has no field for it and devices joining through the QR-Code use the first key.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+    pub wep_key_index: Option<u8>
+}
+impl Options {
+    /// The index of the key counted from 0, as the profiles write it.
+    pub fn key_index(&self) -> u8 {
+        self.wep_key_index.map_or(0, |x| x - 1)
+    }
+}
+
+/// Whether the key is a hex key of 64 or 128 bit WEP.
+pub fn is_hex(key: &str) -> bool {
+    [10, 26].contains(&key.len()) && key.chars().all(|x| x.is_ascii_hexdigit())
+}
+
+/// The key as devices take it: hex keys written in groups joined into one run of digits, other keys as they are.
+pub fn normalize(key: &str) -> String {
+    let digits:String = key.chars().filter(|x| ![':', '-', ' '].contains(x)).collect();
+    if digits.len() < key.len() && is_hex(&digits) {digits}else{String::from(key)}
+}
+
+/// Normalizes the key of a WEP network and checks the WEP options against it. Warns if the key index can't be
+/// carried by the QR-Code.
+pub fn prepare(args: &mut Args) -> Result<(), String> {
+    if crate::resolve_security(args) != Some(Security::Wep) {
+        if args.wep != Options::default() {
+            return Err(String::from("--wep-hex and --wep-key-index are only for WEP networks, pass --sec wep"));
+        }
+        return Ok(());
+    }
+    let key:String = normalize(args.psw.as_deref().unwrap_or_default());
+    if args.wep.wep_hex && !is_hex(&key) {
+        return Err(format!("the WEP key isn't 10 or 26 hex digits, but {} characters", key.chars().count()));
+    }
+    if args.wep.key_index() > 0 {
+        crate::diagnostics::warn("wep-key-index", format!("the QR-Code can't carry the key index of {}, devices joining through it use the first key", args.ssid));
+    }
+    args.psw = Some(key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("12:34:56:78:9A"), "123456789A");
+        assert_eq!(normalize("1234-5678-9012-3456-7890-1234-56"), "12345678901234567890123456");
+        // ASCII keys may contain the separators.
+        assert_eq!(normalize("ab:cd"), "ab:cd");
+        assert!(is_hex("123456789a") && !is_hex("12345") && !is_hex("123456789g"));
+    }
+}
@@ -0,0 +1,116 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! WPS PINs, for devices and apps that join through Wi-Fi Protected Setup instead of taking a passphrase. A PIN has
+//! 8 digits, the last being a checksum of the other 7, or 4 digits without one, as shown on some displays. The
+//! `WIFI:` payload has no field for them, so the code of `wifiqr wps` holds the digits alone, which onboarding apps
+//! scanning a PIN enter as they are.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WpsError{
+    /// The PIN has other characters than digits, or neither 4 nor 8 of them.
+    Format,
+    /// The last digit isn't the checksum of the others, with the one it should be.
+    Checksum(u32),
+    Random(String)
+}
+impl fmt::Display for WpsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WpsError::Format => write!(f, "WPS PINs have 8 digits, or 4 on devices with a display"),
+            WpsError::Checksum(expected) => write!(f, "the last digit of the PIN is its checksum, which should be {}, the PIN may be mistyped", expected),
+            WpsError::Random(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+/// The checksum digit of the first 7 digits of a PIN, whose digits are weighted 3 and 1 in turn.
+pub fn checksum(digits: u32) -> u32 {
+    let mut sum:u32 = 0;
+    let mut digits:u32 = digits;
+    while digits > 0 {
+        sum += 3 * (digits % 10);
+        digits /= 10;
+        sum += digits % 10;
+        digits /= 10;
+    }
+    (10 - sum % 10) % 10
+}
+
+/// The digits of the PIN, which may be written in groups such as `1234-5670`, if they are a valid PIN.
+pub fn validate(pin: &str) -> Result<String, WpsError> {
+    let digits:String = pin.chars().filter(|x| ![' ', '-'].contains(x)).collect();
+    if ![4, 8].contains(&digits.len()) || !digits.chars().all(|x| x.is_ascii_digit()) {
+        return Err(WpsError::Format);
+    }
+    if digits.len() == 8 {
+        let number:u32 = digits.parse().expect("the PIN is 8 digits");
+        if checksum(number / 10) != number % 10 {
+            return Err(WpsError::Checksum(checksum(number / 10)));
+        }
+    }
+    Ok(digits)
+}
+
+/// A random 8-digit PIN, for the registrar to be configured with.
+pub fn generate() -> Result<String, WpsError> {
+    // Numbers from this limit up would favour the first PINs, so they are drawn again.
+    let limit:u32 = u32::MAX - u32::MAX % 10_000_000;
+    loop {
+        let mut bytes:[u8; 4] = [0; 4];
+        getrandom::getrandom(&mut bytes).map_err(|err| WpsError::Random(format!("the system has no randomness to offer: {}", err)))?;
+        let number:u32 = u32::from_be_bytes(bytes);
+        if number < limit {
+            let digits:u32 = number % 10_000_000;
+            return Ok(format!("{:07}{}", digits, checksum(digits)));
+        }
+    }
+}
+
+/// The PIN as devices show it, in groups of 4 digits, e.g. `1234-5670`.
+pub fn display(pin: &str) -> String {
+    match pin.len() {
+        8 => format!("{}-{}", &pin[..4], &pin[4..]),
+        _ => String::from(pin)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(1234567), 0);
+        assert_eq!(checksum(0), 0);
+        assert_eq!(checksum(4935785), 1);
+    }
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("1234-5670"), Ok(String::from("12345670")));
+        assert_eq!(validate("1234"), Ok(String::from("1234")));
+        assert_eq!(validate("12345671"), Err(WpsError::Checksum(0)));
+        assert_eq!(validate("123456"), Err(WpsError::Format));
+        assert_eq!(validate("1234567a"), Err(WpsError::Format));
+    }
+    #[test]
+    fn test_generate() {
+        let pin:String = generate().unwrap();
+        assert_eq!(validate(&pin), Ok(pin.clone()));
+        assert_eq!(display(&pin).len(), 9);
+    }
+}
@@ -0,0 +1,185 @@
+/*
+Copyright 2022 Frostie314159
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! JavaScript bindings of `wifi-qr-core`, for pages such as guest portals to show the code of a network with the
+//! payload and escaping of `wifiqr`:
+//!
+//! ```js
+//! import init, { generateWifiQr } from "./pkg/wifi_qr_wasm.js";
+//!
+//! await init();
+//! document.body.innerHTML = generateWifiQr("Home", "hunter22", { security: "wpa2", ecc: "medium" });
+//! const modules = generateWifiQr("Home", "hunter22", { format: "matrix", border: 0 });
+//! ```
+//!
+//! The options, all optional, are named like those of `wifiqr`: `security` (`wep`, `wpa`, `wpa2`, `wpa3` or
+//! `wpa2-wpa3`), `hidden`, `ecc` (`low`, `medium`, `quartile` or `high`), `border` in modules (4 by default) and
+//! `format`, `svg` for an SVG document or `matrix` for the modules as a `Uint8Array`, one byte per module and row
+//! after row, 1 being dark, whose width is the square root of its length.
+
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+use wifi_qr_core::render::{with_border, QUIET_ZONE};
+use wifi_qr_core::{ecc_level, QrCodeEcc, Security, WifiQr, WifiQrBuilder};
+
+/// What `generateWifiQr` returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format{
+    Svg,
+    Matrix
+}
+
+/// The options of `generateWifiQr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options{
+    pub security: Option<Security>,
+    pub hidden: bool,
+    pub ecc: QrCodeEcc,
+    pub border: usize,
+    pub format: Format
+}
+impl Default for Options {
+    fn default() -> Self {
+        Options{ security: None, hidden: false, ecc: QrCodeEcc::Low, border: QUIET_ZONE, format: Format::Svg }
+    }
+}
+
+/// The format of the name.
+fn format(name: &str) -> Result<Format, String> {
+    match name.to_lowercase().as_str() {
+        "svg" => Ok(Format::Svg),
+        "matrix" => Ok(Format::Matrix),
+        _ => Err(format!("unknown format {}, expected svg or matrix", name))
+    }
+}
+
+/// An SVG document of the modules, one path of a rectangle per horizontal run of dark modules.
+pub fn svg(matrix: &[Vec<bool>]) -> String {
+    let size:usize = matrix.len();
+    let mut path:String = String::new();
+    for (y, row) in matrix.iter().enumerate() {
+        let mut x:usize = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let start:usize = x;
+            while x < row.len() && row[x] {
+                x += 1;
+            }
+            let _ = write!(path, "M{},{}h{}v1h-{}z", start, y, x - start, x - start);
+        }
+    }
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\"><rect width=\"{size}\" height=\"{size}\" fill=\"#fff\"/><path d=\"{}\" fill=\"#000\"/></svg>", path)
+}
+
+/// The modules one byte each, row after row, 1 being dark.
+pub fn bytes(matrix: &[Vec<bool>]) -> Vec<u8> {
+    matrix.iter().flatten().map(|x| *x as u8).collect()
+}
+
+/// Encodes the network as `wifiqr` does, with the border of the options.
+pub fn generate(ssid: &str, psw: Option<&str>, options: &Options) -> Result<Vec<Vec<bool>>, String> {
+    let mut builder:WifiQrBuilder = WifiQrBuilder::new(ssid).hidden(options.hidden).ecc(options.ecc);
+    if let Some(psw) = psw.filter(|x| !x.is_empty()) {
+        builder = builder.password(psw);
+    }
+    if let Some(security) = options.security {
+        builder = builder.security(security);
+    }
+    let code:WifiQr = builder.build().map_err(|err| err.to_string())?;
+    Ok(with_border(code.matrix(), options.border))
+}
+
+/// A property of the options object, `undefined` if the object or the property is missing.
+fn property(options: &JsValue, name: &str) -> JsValue {
+    if !options.is_object() {
+        return JsValue::UNDEFINED;
+    }
+    js_sys::Reflect::get(options, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+}
+/// A string property of the options object, if set.
+fn string_property(options: &JsValue, name: &str) -> Result<Option<String>, String> {
+    match property(options, name) {
+        value if value.is_undefined() || value.is_null() => Ok(None),
+        value => value.as_string().map(Some).ok_or_else(|| format!("{} has to be a string", name))
+    }
+}
+
+/// Reads the options object of `generateWifiQr`.
+fn options(options: &JsValue) -> Result<Options, String> {
+    let mut parsed:Options = Options::default();
+    if let Some(name) = string_property(options, "security")? {
+        parsed.security = Some(name.parse()?);
+    }
+    if let Some(name) = string_property(options, "ecc")? {
+        parsed.ecc = ecc_level(&name)?;
+    }
+    if let Some(name) = string_property(options, "format")? {
+        parsed.format = format(&name)?;
+    }
+    parsed.hidden = property(options, "hidden").is_truthy();
+    if let Some(border) = property(options, "border").as_f64() {
+        if !(0.0..=100.0).contains(&border) || border.fract() != 0.0 {
+            return Err(String::from("border has to be a whole number of modules from 0 to 100"));
+        }
+        parsed.border = border as usize;
+    }
+    Ok(parsed)
+}
+
+/// The QR-Code of the network as an SVG document or the matrix of its modules, as set with `format`.
+#[wasm_bindgen(js_name = generateWifiQr)]
+pub fn generate_wifi_qr(ssid: &str, psw: Option<String>, options: JsValue) -> Result<JsValue, JsError> {
+    let options:Options = self::options(&options).map_err(|err| JsError::new(&err))?;
+    let matrix:Vec<Vec<bool>> = generate(ssid, psw.as_deref(), &options).map_err(|err| JsError::new(&err))?;
+    Ok(match options.format {
+        Format::Svg => JsValue::from_str(&svg(&matrix)),
+        Format::Matrix => js_sys::Uint8Array::from(bytes(&matrix).as_slice()).into()
+    })
+}
+
+/// The `WIFI:` payload of the network, e.g. to show it as text next to the code.
+#[wasm_bindgen(js_name = wifiPayload)]
+pub fn wifi_payload(ssid: &str, psw: Option<String>, options: JsValue) -> Result<String, JsError> {
+    let options:Options = self::options(&options).map_err(|err| JsError::new(&err))?;
+    let credentials = wifi_qr_core::WifiCredentials{ ssid: String::from(ssid), password: psw.filter(|x| !x.is_empty()), security: options.security, hidden: options.hidden, ..Default::default() };
+    Ok(credentials.payload())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    #[test]
+    fn test_generate() {
+        let matrix:Vec<Vec<bool>> = generate("My;Home", Some("hunter22"), &Options::default()).unwrap();
+        // 35 bytes need version 3 at ECC-Level low, 29 modules wide.
+        assert_eq!(matrix.len(), 29 + 2 * QUIET_ZONE);
+        let options = Options{ border: 0, security: Some(Security::Wpa3), ..Options::default() };
+        assert_eq!(generate("Home", Some("hunter22"), &options).unwrap().len(), 25);
+        assert!(generate("Home", None, &options).unwrap_err().contains("need a password"));
+        assert_eq!(format("Matrix"), Ok(Format::Matrix));
+        assert!(format("png").is_err());
+    }
+    #[test]
+    fn test_svg_and_bytes() {
+        let matrix:Vec<Vec<bool>> = vec![vec![true, true], vec![false, true]];
+        assert!(svg(&matrix).contains("viewBox=\"0 0 2 2\"") && svg(&matrix).contains("d=\"M0,0h2v1h-2zM1,1h1v1h-1z\""));
+        assert_eq!(bytes(&matrix), [1, 1, 0, 1]);
+    }
+}